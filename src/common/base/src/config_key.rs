@@ -1,4 +1,6 @@
 pub const AWS_GLUE_ACCESS_KEY: &str = "aws-glue-access-key";
 pub const AWS_GLUE_SECRET_KEY: &str = "aws-glue-secret-key";
 pub const AWS_GLUE_REGION: &str = "aws-glue-region";
-pub const ICEBERG_METADATA_LOCATION: &str = "metadata_location";
\ No newline at end of file
+pub const ICEBERG_METADATA_LOCATION: &str = "metadata_location";
+pub const ICEBERG_PREVIOUS_METADATA_LOCATION: &str = "previous_metadata_location";
+pub const CACHE_TTL_SECONDS: &str = "cache-ttl-seconds";
\ No newline at end of file
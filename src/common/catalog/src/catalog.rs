@@ -1,49 +1,202 @@
-use crate::catalog_config::GlueCatalogConfig;
+use crate::catalog_config::{
+    FilesystemCatalogConfig, GlueCatalogConfig, HmsCatalogConfig, RestCatalogConfig,
+    SqlCatalogConfig,
+};
+use crate::filesystem_catalog::FilesystemCatalog;
 use crate::glue_catalog::GlueCatalog;
-use datafusion::catalog::{CatalogProvider, CatalogProviderList};
+use crate::hms_catalog::HmsCatalog;
+use crate::rest_catalog::RestCatalog;
+use crate::sql_catalog::SqlCatalog;
+use async_trait::async_trait;
+use datafusion::catalog::information_schema::INFORMATION_SCHEMA;
+use datafusion::catalog::{CatalogProvider, CatalogProviderList, MemoryCatalogProvider};
+use datafusion::common::ResolvedTableReference;
 use datafusion::error::DataFusionError;
+use futures::future::join_all;
 use serde::Deserialize;
 use std::any::Any;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::sync::Arc;
 
 #[derive(Debug, Deserialize)]
 struct DobbyCatalogConfigs {
+    #[serde(default)]
     glue: Vec<GlueCatalogConfig>,
+    #[serde(default)]
+    rest: Vec<RestCatalogConfig>,
+    #[serde(default)]
+    hms: Vec<HmsCatalogConfig>,
+    #[serde(default)]
+    filesystem: Vec<FilesystemCatalogConfig>,
+    #[serde(default)]
+    sql: Vec<SqlCatalogConfig>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogType {
+    Glue,
+    Rest,
+    Hms,
+    Filesystem,
+    Sql,
+}
+
+impl Display for CatalogType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogType::Glue => write!(f, "glue"),
+            CatalogType::Rest => write!(f, "rest"),
+            CatalogType::Hms => write!(f, "hms"),
+            CatalogType::Filesystem => write!(f, "filesystem"),
+            CatalogType::Sql => write!(f, "sql"),
+        }
+    }
+}
+
+/// A catalog whose sync [`CatalogProvider`] methods, and those of its schemas, only read
+/// cached names. They are refreshed with the async methods here before planning, see
+/// [`DobbyCatalogManager::refresh_table_references`].
+#[async_trait]
+pub trait DobbyCatalogProvider: CatalogProvider {
+    /// Refreshes the schema names once they expired, and with `with_table_names` the
+    /// table names of all schemas.
+    async fn refresh(&self, _with_table_names: bool) {}
+
+    /// Refreshes whether the schema `name` exists once that expired, and with
+    /// `with_table_names` its table names.
+    async fn refresh_schema(&self, _name: &str, _with_table_names: bool) {}
+}
+
+/// In-memory catalogs have nothing to refresh.
+impl DobbyCatalogProvider for MemoryCatalogProvider {}
+
 #[derive(Debug)]
 pub struct DobbyCatalogManager {
-    catalogs: HashMap<String, Arc<dyn CatalogProvider>>,
+    catalogs: HashMap<String, Arc<dyn DobbyCatalogProvider>>,
+    catalog_types: HashMap<String, CatalogType>,
+}
+
+impl Default for DobbyCatalogManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DobbyCatalogManager {
     pub fn new() -> Self {
         DobbyCatalogManager {
             catalogs: HashMap::new(),
+            catalog_types: HashMap::new(),
         }
     }
+
+    /// Returns `(catalog name, catalog type)` of all loaded catalogs, sorted by name.
+    pub fn catalog_definitions(&self) -> Vec<(String, CatalogType)> {
+        let mut definitions: Vec<(String, CatalogType)> = self
+            .catalog_types
+            .iter()
+            .map(|(name, catalog_type)| (name.clone(), *catalog_type))
+            .collect();
+        definitions.sort_by(|a, b| a.0.cmp(&b.0));
+        definitions
+    }
+
     pub async fn init_from_path(&mut self, config_path: &str) -> Result<(), DataFusionError> {
         let toml_str = fs::read_to_string(config_path)?;
         let catalog_configs: DobbyCatalogConfigs =
             toml::from_str(&toml_str).map_err(|e| DataFusionError::External(Box::new(e)))?;
-        let mut name_set: HashSet<String> = HashSet::new();
 
+        // configs hold credentials, so only the catalog names are logged
         for glue_config in &catalog_configs.glue {
-            println!("start to load catalog config: {:?}", glue_config);
-            if name_set.contains(&glue_config.name) {
-                return Err(DataFusionError::Configuration(
-                    "duplicate catalog name".to_string(),
-                ));
-            }
-            let glue_catalog = GlueCatalog::try_new(&glue_config).await?;
-            self.catalogs
-                .insert(glue_config.name.clone(), Arc::new(glue_catalog));
-            name_set.insert(glue_config.name.clone());
+            println!("start to load glue catalog {}", glue_config.name);
+            self.check_catalog_name(&glue_config.name)?;
+            let glue_catalog = GlueCatalog::try_new(glue_config).await?;
+            self.add_catalog(&glue_config.name, CatalogType::Glue, Arc::new(glue_catalog));
+        }
+        for rest_config in &catalog_configs.rest {
+            println!("start to load rest catalog {}", rest_config.name);
+            self.check_catalog_name(&rest_config.name)?;
+            let rest_catalog = RestCatalog::try_new(rest_config).await?;
+            self.add_catalog(&rest_config.name, CatalogType::Rest, Arc::new(rest_catalog));
+        }
+        for hms_config in &catalog_configs.hms {
+            println!("start to load hms catalog {}", hms_config.name);
+            self.check_catalog_name(&hms_config.name)?;
+            let hms_catalog = HmsCatalog::try_new(hms_config).await?;
+            self.add_catalog(&hms_config.name, CatalogType::Hms, Arc::new(hms_catalog));
+        }
+        for filesystem_config in &catalog_configs.filesystem {
+            println!("start to load filesystem catalog {}", filesystem_config.name);
+            self.check_catalog_name(&filesystem_config.name)?;
+            let filesystem_catalog = FilesystemCatalog::try_new(filesystem_config).await?;
+            self.add_catalog(
+                &filesystem_config.name,
+                CatalogType::Filesystem,
+                Arc::new(filesystem_catalog),
+            );
+        }
+        for sql_config in &catalog_configs.sql {
+            println!(
+                "start to load sql catalog {} from {}",
+                sql_config.name,
+                sql_config.redacted_uri()
+            );
+            self.check_catalog_name(&sql_config.name)?;
+            let sql_catalog = SqlCatalog::try_new(sql_config).await?;
+            self.add_catalog(&sql_config.name, CatalogType::Sql, Arc::new(sql_catalog));
+        }
+        Ok(())
+    }
+
+    fn check_catalog_name(&self, name: &str) -> Result<(), DataFusionError> {
+        if self.catalogs.contains_key(name) {
+            return Err(DataFusionError::Configuration(format!(
+                "duplicate catalog name: {name}"
+            )));
         }
         Ok(())
     }
+
+    /// Adds a catalog loaded outside of a catalog config, replacing one with the same name.
+    pub fn add_catalog(&mut self, name: &str, catalog_type: CatalogType, catalog: Arc<dyn DobbyCatalogProvider>) {
+        self.catalogs.insert(name.to_string(), catalog);
+        self.catalog_types.insert(name.to_string(), catalog_type);
+    }
+
+    /// Refreshes the schema names of the catalog `name`, and with `with_table_names` the
+    /// table names of all its schemas.
+    pub async fn refresh_catalog(&self, name: &str, with_table_names: bool) {
+        if let Some(catalog) = self.catalogs.get(name) {
+            catalog.refresh(with_table_names).await;
+        }
+    }
+
+    /// Refreshes the schema `schema_name` of the catalog `name`, and with `with_table_names`
+    /// its table names.
+    pub async fn refresh_schema(&self, name: &str, schema_name: &str, with_table_names: bool) {
+        if let Some(catalog) = self.catalogs.get(name) {
+            catalog.refresh_schema(schema_name, with_table_names).await;
+        }
+    }
+
+    /// Refreshes the schemas `table_refs` are resolved in, before planning a statement
+    /// reading them. `information_schema` lists the tables of all catalogs, so all table
+    /// names are refreshed for it.
+    pub async fn refresh_table_references(&self, table_refs: &[ResolvedTableReference]) {
+        if table_refs
+            .iter()
+            .any(|table_ref| table_ref.schema.as_ref() == INFORMATION_SCHEMA)
+        {
+            join_all(self.catalogs.values().map(|catalog| catalog.refresh(true))).await;
+        }
+        let schemas = table_refs.iter().filter_map(|table_ref| {
+            let catalog = self.catalogs.get(table_ref.catalog.as_ref())?;
+            Some(catalog.refresh_schema(&table_ref.schema, false))
+        });
+        join_all(schemas).await;
+    }
 }
 
 impl CatalogProviderList for DobbyCatalogManager {
@@ -53,18 +206,139 @@ impl CatalogProviderList for DobbyCatalogManager {
 
     fn register_catalog(
         &self,
-        name: String,
-        catalog: Arc<dyn CatalogProvider>,
+        _name: String,
+        _catalog: Arc<dyn CatalogProvider>,
     ) -> Option<Arc<dyn CatalogProvider>> {
         None
     }
 
+    /// Names of all loaded catalogs, sorted.
     fn catalog_names(&self) -> Vec<String> {
-        self.catalogs.keys().cloned().collect()
+        let mut names: Vec<String> = self.catalogs.keys().cloned().collect();
+        names.sort();
+        names
     }
 
     fn catalog(&self, name: &str) -> Option<Arc<dyn CatalogProvider>> {
         let catalog = self.catalogs.get(name)?;
-        Some(catalog.clone())
+        Some(catalog.clone() as Arc<dyn CatalogProvider>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_format::iceberg_commit::testing::TestDir;
+    use mockito::Server;
+    use sqlx::any::install_default_drivers;
+    use sqlx::AnyPool;
+
+    /// Loads a manager from `config` written to `catalog.toml` in `dir`.
+    async fn init_from_config(dir: &TestDir, config: &str) -> Result<DobbyCatalogManager, DataFusionError> {
+        fs::create_dir_all(dir.path())?;
+        let config_path = dir.path().join("catalog.toml");
+        fs::write(&config_path, config)?;
+        let mut manager = DobbyCatalogManager::new();
+        manager.init_from_path(config_path.to_str().unwrap()).await?;
+        Ok(manager)
+    }
+
+    #[tokio::test]
+    async fn test_init_from_path() -> Result<(), DataFusionError> {
+        let dir = TestDir::new("catalog-manager");
+        let manager = init_from_config(&dir, "").await?;
+        assert!(manager.catalog_names().is_empty());
+        assert!(manager.catalog_definitions().is_empty());
+        assert!(manager.catalog("missing").is_none());
+
+        let error = init_from_config(&dir, "[[glue]]\nregion = ").await.unwrap_err();
+        assert!(matches!(error, DataFusionError::External(_)), "{error}");
+        let mut manager = DobbyCatalogManager::new();
+        let missing = dir.path().join("missing.toml");
+        assert!(manager.init_from_path(missing.to_str().unwrap()).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_init_rest_catalog() -> Result<(), DataFusionError> {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/v1/config")
+            .with_body(r#"{"defaults": {}, "overrides": {}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/namespaces")
+            .with_body(r#"{"namespaces": [["sales"]]}"#)
+            .create_async()
+            .await;
+        let dir = TestDir::new("catalog-manager-rest");
+        let config = format!("[[rest]]\nname = \"lake\"\nuri = \"{}\"\n", server.url());
+        let manager = init_from_config(&dir, &config).await?;
+        assert_eq!(manager.catalog_definitions(), vec![("lake".to_string(), CatalogType::Rest)]);
+        let catalog = manager.catalog("lake").expect("catalog lake");
+        assert_eq!(catalog.schema_names(), vec!["sales".to_string()]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_init_filesystem_catalog() -> Result<(), DataFusionError> {
+        let dir = TestDir::new("catalog-manager-filesystem");
+        let warehouse = dir.path().join("warehouse");
+        fs::create_dir_all(warehouse.join("hr"))?;
+        let config = format!(
+            "[[filesystem]]\nname = \"b_lake\"\nwarehouse = \"{0}\"\n\n\
+             [[filesystem]]\nname = \"a_lake\"\nwarehouse = \"{0}\"\n",
+            warehouse.display()
+        );
+        let manager = init_from_config(&dir, &config).await?;
+        assert_eq!(manager.catalog_names(), vec!["a_lake", "b_lake"]);
+        assert_eq!(
+            manager.catalog_definitions(),
+            vec![
+                ("a_lake".to_string(), CatalogType::Filesystem),
+                ("b_lake".to_string(), CatalogType::Filesystem),
+            ]
+        );
+        let catalog = manager.catalog("a_lake").expect("catalog a_lake");
+        assert_eq!(catalog.schema_names(), vec!["hr".to_string()]);
+
+        // catalog names are unique
+        let error = init_from_config(&dir, &config.replace("a_lake", "b_lake")).await.unwrap_err();
+        assert!(error.to_string().contains("duplicate catalog name: b_lake"), "{error}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_init_sql_catalog() -> Result<(), DataFusionError> {
+        let dir = TestDir::new("catalog-manager-sql");
+        let warehouse = dir.path().join("warehouse");
+        fs::create_dir_all(&warehouse)?;
+        let db_path = dir.path().join("catalog.db");
+        install_default_drivers();
+        let pool = AnyPool::connect(&format!("sqlite://{}?mode=rwc", db_path.display()))
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        for sql in [
+            "CREATE TABLE iceberg_tables (catalog_name VARCHAR(255), table_namespace VARCHAR(255), \
+             table_name VARCHAR(255), metadata_location VARCHAR(1000), previous_metadata_location VARCHAR(1000))",
+            "INSERT INTO iceberg_tables VALUES ('lake', 'finance', 'ledger', 's3://warehouse/ledger.json', NULL)",
+        ] {
+            sqlx::query(sql)
+                .execute(&pool)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        }
+        let config = format!("[[sql]]\nname = \"lake\"\nuri = \"sqlite://{}?mode=rwc\"\n", db_path.display());
+        let manager = init_from_config(&dir, &config).await?;
+        assert_eq!(manager.catalog_definitions(), vec![("lake".to_string(), CatalogType::Sql)]);
+        let catalog = manager.catalog("lake").expect("catalog lake");
+        assert_eq!(catalog.schema_names(), vec!["finance".to_string()]);
+
+        // catalog names are unique across catalog types
+        let config = format!("{config}\n[[filesystem]]\nname = \"lake\"\nwarehouse = \"{}\"\n", warehouse.display());
+        let error = init_from_config(&dir, &config).await.unwrap_err();
+        assert!(error.to_string().contains("duplicate catalog name: lake"), "{error}");
+        Ok(())
     }
 }
@@ -0,0 +1,229 @@
+use crate::catalog::DobbyCatalogProvider;
+use crate::catalog_config::{DobbyCatalogConfig, FilesystemCatalogConfig};
+use crate::filesystem_schema::FilesystemNamespace;
+use crate::lazy_schema::LazySchemas;
+use async_trait::async_trait;
+use datafusion::catalog::{CatalogProvider, SchemaProvider};
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::error::DataFusionError;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Hadoop catalogs point at the current metadata file with this file in `metadata/`.
+const VERSION_HINT_FILE: &str = "version-hint.text";
+const METADATA_FILE_SUFFIX: &str = ".metadata.json";
+
+/// A warehouse directory laid out as `<namespace>/<table>/metadata/vN.metadata.json`.
+#[derive(Debug)]
+pub struct FilesystemWarehouse {
+    object_store: Arc<dyn ObjectStore>,
+    /// `s3://bucket/` or `file:///`, prefixed to object store paths to get file io locations
+    store_url: String,
+    root: Path,
+}
+
+impl FilesystemWarehouse {
+    pub fn try_new(config: &FilesystemCatalogConfig) -> Result<Self, DataFusionError> {
+        let url = ListingTableUrl::parse(&config.warehouse)?;
+        Ok(FilesystemWarehouse {
+            object_store: config.build_object_store(url.as_ref())?,
+            store_url: url.object_store().as_str().to_string(),
+            root: url.prefix().clone(),
+        })
+    }
+
+    pub async fn list_namespaces(&self) -> Result<Vec<String>, DataFusionError> {
+        self.list_directories(&self.root).await
+    }
+
+    pub async fn list_tables(&self, namespace: &str) -> Result<Vec<String>, DataFusionError> {
+        self.list_directories(&self.root.child(namespace)).await
+    }
+
+    /// Returns the location of the current metadata file of a table, `None` if the
+    /// table has no metadata. The version hint wins, otherwise the highest version.
+    pub async fn current_metadata_location(
+        &self,
+        namespace: &str,
+        table: &str,
+    ) -> Result<Option<String>, DataFusionError> {
+        let metadata_dir = self.root.child(namespace).child(table).child("metadata");
+        let metadata_files: Vec<String> = self
+            .object_store
+            .list_with_delimiter(Some(&metadata_dir))
+            .await?
+            .objects
+            .into_iter()
+            .filter_map(|meta| meta.location.filename().map(|name| name.to_string()))
+            .collect();
+
+        let hinted_file = match self
+            .object_store
+            .get(&metadata_dir.child(VERSION_HINT_FILE))
+            .await
+        {
+            Ok(hint) => {
+                let hint = hint.bytes().await?;
+                String::from_utf8_lossy(&hint)
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .map(|version| format!("v{version}{METADATA_FILE_SUFFIX}"))
+                    .filter(|file| metadata_files.contains(file))
+            }
+            Err(object_store::Error::NotFound { .. }) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let current_file = hinted_file.or_else(|| {
+            metadata_files
+                .iter()
+                .filter_map(|file| parse_metadata_version(file).map(|version| (version, file)))
+                .max_by_key(|(version, _)| *version)
+                .map(|(_, file)| file.clone())
+        });
+        Ok(current_file.map(|file| format!("{}{}", self.store_url, metadata_dir.child(file))))
+    }
+
+    async fn list_directories(&self, prefix: &Path) -> Result<Vec<String>, DataFusionError> {
+        let listing = self.object_store.list_with_delimiter(Some(prefix)).await?;
+        Ok(listing
+            .common_prefixes
+            .iter()
+            .filter_map(|dir| dir.filename())
+            .filter(|name| !name.starts_with(['.', '_']))
+            .map(|name| name.to_string())
+            .collect())
+    }
+}
+
+/// Parses the version of `v12.metadata.json` (Hadoop tables) or
+/// `00012-<uuid>.metadata.json` (catalog managed tables).
+fn parse_metadata_version(file_name: &str) -> Option<i64> {
+    let name = file_name.strip_suffix(METADATA_FILE_SUFFIX)?;
+    let name = name.strip_prefix('v').unwrap_or(name);
+    let version = name.split('-').next()?;
+    version.parse::<i64>().ok()
+}
+
+#[derive(Debug)]
+pub struct FilesystemCatalog {
+    config: FilesystemCatalogConfig,
+    warehouse: Arc<FilesystemWarehouse>,
+    /// Namespace names and namespaces, they keep their own table caches
+    namespaces: LazySchemas<FilesystemNamespace>,
+}
+
+impl FilesystemCatalog {
+    pub async fn try_new(catalog_config: &FilesystemCatalogConfig) -> Result<Self, DataFusionError> {
+        let warehouse = Arc::new(FilesystemWarehouse::try_new(catalog_config)?);
+        let catalog = FilesystemCatalog {
+            config: catalog_config.clone(),
+            warehouse,
+            namespaces: LazySchemas::new(catalog_config.cache.ttl()),
+        };
+        let names = catalog.warehouse.list_namespaces().await?;
+        catalog.namespaces.set_names(names, |name| Ok(catalog.new_namespace(name)))?;
+        Ok(catalog)
+    }
+
+    fn new_namespace(&self, name: &str) -> FilesystemNamespace {
+        FilesystemNamespace::new(&self.warehouse, name, &self.config)
+    }
+}
+
+impl CatalogProvider for FilesystemCatalog {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        self.namespaces.names()
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        Some(self.namespaces.schema(name)?)
+    }
+}
+
+#[async_trait]
+impl DobbyCatalogProvider for FilesystemCatalog {
+    async fn refresh(&self, with_table_names: bool) {
+        self.namespaces
+            .refresh_names(
+                format!("filesystem catalog {}", self.config.name),
+                self.warehouse.list_namespaces(),
+                |name| Ok(self.new_namespace(name)),
+            )
+            .await;
+        if with_table_names {
+            self.namespaces.refresh_table_names().await;
+        }
+    }
+
+    /// Namespaces are only listed, so a namespace is looked up in that list.
+    async fn refresh_schema(&self, name: &str, with_table_names: bool) {
+        let exists = async {
+            let names = self.warehouse.list_namespaces().await?;
+            Ok::<_, DataFusionError>(names.iter().any(|n| n == name))
+        };
+        self.namespaces
+            .refresh_schema(
+                format!("filesystem catalog {}", self.config.name),
+                name,
+                exists,
+                |name| Ok(self.new_namespace(name)),
+                with_table_names,
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_metadata_version() {
+        assert_eq!(parse_metadata_version("v3.metadata.json"), Some(3));
+        assert_eq!(
+            parse_metadata_version("00012-6d5f0c4e-9b1a-4c1e-8f1a-2b3c4d5e6f70.metadata.json"),
+            Some(12)
+        );
+        assert_eq!(parse_metadata_version("snap-1-1-abc.avro"), None);
+        assert_eq!(parse_metadata_version(VERSION_HINT_FILE), None);
+    }
+
+    #[tokio::test]
+    async fn test_discover_warehouse() -> Result<(), DataFusionError> {
+        let warehouse_dir = std::env::temp_dir().join(format!("dobbydb-warehouse-{}", std::process::id()));
+        let metadata_dir = warehouse_dir.join("sales").join("orders").join("metadata");
+        fs::create_dir_all(&metadata_dir)?;
+        fs::write(metadata_dir.join("v1.metadata.json"), "{}")?;
+        fs::write(metadata_dir.join("v2.metadata.json"), "{}")?;
+        fs::create_dir_all(warehouse_dir.join("sales").join("empty"))?;
+
+        let config: FilesystemCatalogConfig = toml::from_str(&format!(
+            "name = \"fs\"\nwarehouse = \"{}\"",
+            warehouse_dir.display()
+        ))
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let warehouse = FilesystemWarehouse::try_new(&config)?;
+        assert_eq!(warehouse.list_namespaces().await?, vec!["sales".to_string()]);
+        let mut tables = warehouse.list_tables("sales").await?;
+        tables.sort();
+        assert_eq!(tables, vec!["empty".to_string(), "orders".to_string()]);
+
+        let location = warehouse.current_metadata_location("sales", "orders").await?;
+        assert!(location.is_some_and(|l| l.ends_with("sales/orders/metadata/v2.metadata.json")));
+        fs::write(metadata_dir.join(VERSION_HINT_FILE), "1\n")?;
+        let location = warehouse.current_metadata_location("sales", "orders").await?;
+        assert!(location.is_some_and(|l| l.ends_with("sales/orders/metadata/v1.metadata.json")));
+        assert_eq!(warehouse.current_metadata_location("sales", "empty").await?, None);
+
+        fs::remove_dir_all(&warehouse_dir)?;
+        Ok(())
+    }
+}
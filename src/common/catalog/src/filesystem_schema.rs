@@ -0,0 +1,167 @@
+use crate::catalog_config::{DobbyCatalogConfig, FilesystemCatalogConfig};
+use crate::filesystem_catalog::FilesystemWarehouse;
+use crate::lazy_schema::{LazySchema, LazyTables};
+use crate::table_format::external_table::ExternalIcebergTable;
+use crate::table_format::iceberg_table::IcebergTable;
+use crate::table_format::table::{lookup_table, TableIdentifier};
+use async_trait::async_trait;
+use datafusion::catalog::{SchemaProvider, TableProvider};
+use datafusion::error::DataFusionError;
+use std::any::Any;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct FilesystemNamespace {
+    warehouse: Arc<FilesystemWarehouse>,
+    namespace_name: String,
+    config: FilesystemCatalogConfig,
+    /// Table names and tables, listed and loaded on demand
+    tables: LazyTables<Arc<IcebergTable>>,
+}
+
+impl FilesystemNamespace {
+    /// Creates the namespace without listing the warehouse, tables are listed and loaded
+    /// on demand.
+    pub fn new(
+        warehouse: &Arc<FilesystemWarehouse>,
+        namespace_name: &str,
+        config: &FilesystemCatalogConfig,
+    ) -> Self {
+        FilesystemNamespace {
+            warehouse: warehouse.clone(),
+            namespace_name: namespace_name.to_string(),
+            config: config.clone(),
+            tables: LazyTables::new(config.cache.ttl()),
+        }
+    }
+
+    async fn load_table(&self, name: &str) -> Result<Option<IcebergTable>, DataFusionError> {
+        let Some(metadata_location) = self
+            .warehouse
+            .current_metadata_location(&self.namespace_name, name)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let table = ExternalIcebergTable::try_new(
+            &TableIdentifier::new(&self.namespace_name, name),
+            &metadata_location,
+            self.config.build_iceberg_file_io_parameters(),
+        )
+        .await?;
+        Ok(Some(IcebergTable::try_from_external(table)?))
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for FilesystemNamespace {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.tables.table_names()
+    }
+
+    async fn table(
+        &self,
+        name: &str,
+    ) -> datafusion::common::Result<Option<Arc<dyn TableProvider>>, DataFusionError> {
+        lookup_table(name, |table_name| async move {
+            let load = async { Ok(self.load_table(table_name).await?.map(Arc::new)) };
+            let table = self.tables.table(table_name, load).await?;
+            Ok(table.map(|table| table as Arc<dyn TableProvider>))
+        })
+        .await
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.table_names().iter().any(|n| n == name)
+    }
+}
+
+#[async_trait]
+impl LazySchema for FilesystemNamespace {
+    async fn refresh_table_names(&self) {
+        self.tables
+            .refresh_names(
+                format!("namespace {}", self.namespace_name),
+                self.warehouse.list_tables(&self.namespace_name),
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_format::iceberg_commit::testing::{table_metadata, TestDir};
+    use crate::table_format::iceberg_commit::to_datafusion_error;
+    use iceberg::spec::{NestedField, PrimitiveType, Type, UnboundPartitionSpec};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+
+    /// Writes the metadata of a table with the given columns to `metadata/<file_name>`.
+    fn write_metadata(table_dir: &Path, file_name: &str, columns: &[&str]) -> Result<(), DataFusionError> {
+        let fields = columns
+            .iter()
+            .enumerate()
+            .map(|(i, name)| NestedField::optional(i as i32 + 1, *name, Type::Primitive(PrimitiveType::Long)).into())
+            .collect::<Vec<_>>();
+        let schema = iceberg::spec::Schema::builder()
+            .with_fields(fields)
+            .build()
+            .map_err(to_datafusion_error)?;
+        let metadata = table_metadata(table_dir, schema, UnboundPartitionSpec::builder().build(), HashMap::new())?;
+        let metadata_dir = table_dir.join("metadata");
+        fs::create_dir_all(&metadata_dir)?;
+        let metadata = serde_json::to_vec(&metadata).map_err(|e| DataFusionError::External(Box::new(e)))?;
+        fs::write(metadata_dir.join(file_name), metadata)?;
+        Ok(())
+    }
+
+    async fn column_names(namespace: &FilesystemNamespace, table: &str) -> Result<Vec<String>, DataFusionError> {
+        let table = namespace.table(table).await?.expect("table");
+        Ok(table.schema().fields().iter().map(|f| f.name().clone()).collect())
+    }
+
+    #[tokio::test]
+    async fn test_load_tables() -> Result<(), DataFusionError> {
+        let dir = TestDir::new("fs-namespace");
+        let warehouse_dir = dir.path();
+        let table_dir = warehouse_dir.join("sales").join("orders");
+        write_metadata(&table_dir, "v1.metadata.json", &["id"])?;
+        write_metadata(&table_dir, "v2.metadata.json", &["id", "amount"])?;
+        fs::create_dir_all(warehouse_dir.join("sales").join("empty"))?;
+
+        let config: FilesystemCatalogConfig = toml::from_str(&format!(
+            "name = \"fs\"\nwarehouse = \"{}\"",
+            warehouse_dir.display()
+        ))
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let warehouse = Arc::new(FilesystemWarehouse::try_new(&config)?);
+
+        // without a version hint the highest version is loaded
+        let namespace = FilesystemNamespace::new(&warehouse, "sales", &config);
+        assert!(namespace.table_names().is_empty());
+        namespace.refresh_table_names().await;
+        let mut table_names = namespace.table_names();
+        table_names.sort();
+        assert_eq!(table_names, vec!["empty", "orders"]);
+        assert_eq!(column_names(&namespace, "orders").await?, vec!["id", "amount"]);
+        assert!(namespace.table("empty").await?.is_none());
+        assert!(namespace.table("missing").await?.is_none());
+
+        // the version hint wins over the highest version
+        fs::write(table_dir.join("metadata").join("version-hint.text"), "1\n")?;
+        let namespace = FilesystemNamespace::new(&warehouse, "sales", &config);
+        assert_eq!(column_names(&namespace, "orders").await?, vec!["id"]);
+
+        // hints pointing at a missing version are ignored
+        fs::write(table_dir.join("metadata").join("version-hint.text"), "7\n")?;
+        let namespace = FilesystemNamespace::new(&warehouse, "sales", &config);
+        assert_eq!(column_names(&namespace, "orders").await?, vec!["id", "amount"]);
+        Ok(())
+    }
+}
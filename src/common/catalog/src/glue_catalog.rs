@@ -1,8 +1,13 @@
+use crate::catalog::DobbyCatalogProvider;
 use crate::catalog_config::GlueCatalogConfig;
 use crate::glue_schema::GlueDatabase;
+use crate::lazy_schema::LazySchemas;
+use async_trait::async_trait;
 use aws_config::Region;
 use aws_sdk_glue::config::Credentials;
+use aws_sdk_glue::types::DatabaseInput;
 use datafusion::catalog::{CatalogProvider, SchemaProvider};
+use datafusion::common::plan_err;
 use datafusion::error::DataFusionError;
 use std::any::Any;
 use std::collections::HashMap;
@@ -11,7 +16,9 @@ use std::sync::Arc;
 #[derive(Debug)]
 pub struct GlueCatalog {
     config: GlueCatalogConfig,
-    databases: HashMap<String, Arc<dyn SchemaProvider>>,
+    glue_client: aws_sdk_glue::Client,
+    /// Database names and databases, they keep their own table caches
+    databases: LazySchemas<GlueDatabase>,
 }
 
 impl GlueCatalog {
@@ -30,21 +37,129 @@ impl GlueCatalog {
         }
         let aws_config = aws_config.load().await;
         let glue_client = aws_sdk_glue::Client::new(&aws_config);
-        let mut total_databases: HashMap<String, Arc<dyn SchemaProvider>> = HashMap::new();
-        let dbs = glue_client
+        Self::try_new_with_client(catalog_config, glue_client).await
+    }
+
+    pub async fn try_new_with_client(
+        catalog_config: &GlueCatalogConfig,
+        glue_client: aws_sdk_glue::Client,
+    ) -> Result<Self, DataFusionError> {
+        // only list database names up front, tables are resolved on demand
+        let catalog = GlueCatalog {
+            config: catalog_config.clone(),
+            glue_client,
+            databases: LazySchemas::new(catalog_config.cache.ttl()),
+        };
+        let names = list_database_names(&catalog.glue_client).await?;
+        catalog.databases.set_names(names, |name| Ok(catalog.new_database(name)))?;
+        Ok(catalog)
+    }
+
+    fn new_database(&self, name: &str) -> GlueDatabase {
+        GlueDatabase::new(&self.glue_client, name, &self.config)
+    }
+
+    /// Creates a database in Glue, tables created in it without a location go under
+    /// `location`. Returns false without creating anything if the database exists and
+    /// `if_not_exists` is set.
+    pub async fn create_database(
+        &self,
+        name: &str,
+        location: Option<String>,
+        properties: HashMap<String, String>,
+        if_not_exists: bool,
+    ) -> Result<bool, DataFusionError> {
+        let database_input = DatabaseInput::builder()
+            .name(name)
+            .set_location_uri(location)
+            .set_parameters(Some(properties))
+            .build()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let result = self
+            .glue_client
+            .create_database()
+            .database_input(database_input)
+            .send()
+            .await;
+        self.databases.invalidate(name);
+        match result {
+            Ok(_) => {
+                println!("created glue database {}", name);
+                Ok(true)
+            }
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_already_exists_exception()) => {
+                if if_not_exists {
+                    return Ok(false);
+                }
+                plan_err!("database {} already exists", name)
+            }
+            Err(e) => Err(DataFusionError::External(Box::new(e))),
+        }
+    }
+
+    /// Deletes a database from Glue. Glue deletes the tables of a database with it, so a
+    /// database with tables is only dropped with `cascade`; the table files are kept.
+    /// Returns false if the database doesn't exist.
+    pub async fn drop_database(&self, name: &str, cascade: bool) -> Result<bool, DataFusionError> {
+        if !database_exists(&self.glue_client, name).await? {
+            return Ok(false);
+        }
+        let table_names = self.new_database(name).list_table_names().await?;
+        if !table_names.is_empty() && !cascade {
+            return plan_err!(
+                "database {} has {} tables, drop it with CASCADE to drop them too",
+                name,
+                table_names.len()
+            );
+        }
+        let result = self.glue_client.delete_database().name(name).send().await;
+        self.databases.remove(name);
+        match result {
+            Ok(_) => {
+                println!("dropped glue database {} with {} tables", name, table_names.len());
+                Ok(true)
+            }
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_entity_not_found_exception()) => {
+                Ok(false)
+            }
+            Err(e) => Err(DataFusionError::External(Box::new(e))),
+        }
+    }
+}
+
+/// Lists all database names in Glue, following pagination.
+async fn list_database_names(
+    glue_client: &aws_sdk_glue::Client,
+) -> Result<Vec<String>, DataFusionError> {
+    let mut names: Vec<String> = Vec::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        let resp = glue_client
             .get_databases()
+            .set_next_token(next_token)
             .send()
             .await
             .map_err(|e| DataFusionError::External(Box::new(e)))?;
-        for database in dbs.database_list {
-            let glue_schema =
-                GlueDatabase::try_new(&glue_client, &database.name, catalog_config).await?;
-            total_databases.insert(database.name.clone(), Arc::new(glue_schema));
+        names.extend(resp.database_list.into_iter().map(|database| database.name));
+        next_token = resp.next_token;
+        if next_token.is_none() {
+            break;
         }
-        Ok(GlueCatalog {
-            config: catalog_config.clone(),
-            databases: total_databases,
-        })
+    }
+    Ok(names)
+}
+
+/// Looks up a single database instead of listing them all.
+async fn database_exists(
+    glue_client: &aws_sdk_glue::Client,
+    name: &str,
+) -> Result<bool, DataFusionError> {
+    match glue_client.get_database().name(name).send().await {
+        Ok(_) => Ok(true),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_entity_not_found_exception()) => {
+            Ok(false)
+        }
+        Err(e) => Err(DataFusionError::External(Box::new(e))),
     }
 }
 
@@ -54,10 +169,109 @@ impl CatalogProvider for GlueCatalog {
     }
 
     fn schema_names(&self) -> Vec<String> {
-        self.databases.keys().cloned().collect()
+        self.databases.names()
     }
 
     fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
-        self.databases.get(name).cloned()
+        Some(self.databases.schema(name)?)
+    }
+}
+
+#[async_trait]
+impl DobbyCatalogProvider for GlueCatalog {
+    async fn refresh(&self, with_table_names: bool) {
+        self.databases
+            .refresh_names(
+                format!("glue catalog {}", self.config.name),
+                list_database_names(&self.glue_client),
+                |name| Ok(self.new_database(name)),
+            )
+            .await;
+        if with_table_names {
+            self.databases.refresh_table_names().await;
+        }
+    }
+
+    async fn refresh_schema(&self, name: &str, with_table_names: bool) {
+        self.databases
+            .refresh_schema(
+                format!("glue catalog {}", self.config.name),
+                name,
+                database_exists(&self.glue_client, name),
+                |name| Ok(self.new_database(name)),
+                with_table_names,
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_config::BehaviorVersion;
+    use mockito::{Matcher, Mock, Server, ServerGuard};
+
+    fn glue_client(server: &ServerGuard) -> aws_sdk_glue::Client {
+        let config = aws_sdk_glue::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("access", "secret", None, None, "test"))
+            .endpoint_url(server.url())
+            .build();
+        aws_sdk_glue::Client::from_conf(config)
+    }
+
+    /// Serves the Glue operation `target` for requests whose body matches `body`.
+    async fn mock_glue(server: &mut ServerGuard, target: &str, body: Matcher, status: usize, response: &str) -> Mock {
+        server
+            .mock("POST", "/")
+            .match_header("x-amz-target", format!("AWSGlue.{target}").as_str())
+            .match_body(body)
+            .with_status(status)
+            .with_header("content-type", "application/x-amz-json-1.1")
+            .with_body(response)
+            .create_async()
+            .await
+    }
+
+    fn json(body: &str) -> Matcher {
+        Matcher::PartialJsonString(body.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_list_and_look_up_databases() -> Result<(), DataFusionError> {
+        let mut server = Server::new_async().await;
+        // databases and tables are listed in two pages each
+        mock_glue(&mut server, "GetDatabases", Matcher::Any, 200,
+            r#"{"DatabaseList": [{"Name": "sales"}], "NextToken": "databases-2"}"#).await;
+        mock_glue(&mut server, "GetDatabases", json(r#"{"NextToken": "databases-2"}"#), 200,
+            r#"{"DatabaseList": [{"Name": "hr"}]}"#).await;
+        mock_glue(&mut server, "GetTables", json(r#"{"DatabaseName": "finance"}"#), 200,
+            r#"{"TableList": [{"Name": "ledger"}], "NextToken": "tables-2"}"#).await;
+        mock_glue(&mut server, "GetTables", json(r#"{"DatabaseName": "finance", "NextToken": "tables-2"}"#), 200,
+            r#"{"TableList": [{"Name": "budget"}]}"#).await;
+        // databases which aren't listed yet are looked up one by one
+        let get_finance = mock_glue(&mut server, "GetDatabase", json(r#"{"Name": "finance"}"#), 200,
+            r#"{"Database": {"Name": "finance"}}"#).await.expect(1);
+        mock_glue(&mut server, "GetDatabase", json(r#"{"Name": "missing"}"#), 400,
+            r#"{"__type": "EntityNotFoundException", "Message": "database missing not found"}"#).await;
+
+        let config: GlueCatalogConfig = toml::from_str("name = \"glue\"")
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let catalog = GlueCatalog::try_new_with_client(&config, glue_client(&server)).await?;
+        assert_eq!(catalog.schema_names(), vec!["sales", "hr"]);
+        assert!(catalog.schema("hr").is_some());
+
+        assert!(catalog.schema("finance").is_none());
+        catalog.refresh_schema("finance", true).await;
+        let finance = catalog.schema("finance").expect("database finance");
+        assert_eq!(finance.table_names(), vec!["ledger", "budget"]);
+        // the lookup is cached for the cache ttl
+        catalog.refresh_schema("finance", false).await;
+        get_finance.assert_async().await;
+
+        catalog.refresh_schema("missing", false).await;
+        assert!(catalog.schema("missing").is_none());
+        Ok(())
     }
 }
@@ -0,0 +1,142 @@
+use crate::table_format::external_table::ExternalIcebergTable;
+use crate::table_format::iceberg_commit::IcebergTableCommitter;
+use crate::table_format::table::TableIdentifier;
+use async_trait::async_trait;
+use aws_sdk_glue::types::TableInput;
+use datafusion::error::DataFusionError;
+use dobbydb_common_base::config_key::{ICEBERG_METADATA_LOCATION, ICEBERG_PREVIOUS_METADATA_LOCATION};
+use iceberg::table::Table;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Commits Iceberg tables registered in Glue by swapping their `metadata_location`
+/// parameter. The update is conditional on the Glue table version read before, so a
+/// concurrent commit makes Glue reject it and the commit is retried.
+#[derive(Debug)]
+pub struct GlueIcebergCommitter {
+    glue_client: aws_sdk_glue::Client,
+    table_identifier: TableIdentifier,
+    file_io_config: HashMap<String, String>,
+    /// Set once a commit succeeds, so the cached table is reloaded
+    stale: Arc<AtomicBool>,
+}
+
+impl GlueIcebergCommitter {
+    pub fn new(
+        glue_client: &aws_sdk_glue::Client,
+        table_identifier: &TableIdentifier,
+        file_io_config: HashMap<String, String>,
+        stale: Arc<AtomicBool>,
+    ) -> Self {
+        GlueIcebergCommitter {
+            glue_client: glue_client.clone(),
+            table_identifier: table_identifier.clone(),
+            file_io_config,
+            stale,
+        }
+    }
+
+    async fn get_glue_table(&self) -> Result<aws_sdk_glue::types::Table, DataFusionError> {
+        self.glue_client
+            .get_table()
+            .database_name(&self.table_identifier.namespace[0])
+            .name(&self.table_identifier.name)
+            .send()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .table
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!("glue table {} not found", self.table_identifier.name))
+            })
+    }
+}
+
+fn metadata_location(glue_table: &aws_sdk_glue::types::Table) -> Option<&String> {
+    glue_table
+        .parameters()
+        .and_then(|parameters| parameters.get(ICEBERG_METADATA_LOCATION))
+}
+
+#[async_trait]
+impl IcebergTableCommitter for GlueIcebergCommitter {
+    async fn load_table(&self) -> Result<Table, DataFusionError> {
+        let glue_table = self.get_glue_table().await?;
+        let location = metadata_location(&glue_table).ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "glue table {} has no {}, it is not an iceberg table",
+                glue_table.name, ICEBERG_METADATA_LOCATION
+            ))
+        })?;
+        let table = ExternalIcebergTable::try_new(
+            &self.table_identifier,
+            location,
+            self.file_io_config.clone(),
+        )
+        .await?
+        .table();
+        Table::builder()
+            .metadata(table.metadata_ref())
+            .metadata_location(location)
+            .identifier(table.identifier().clone())
+            .file_io(table.file_io().clone())
+            .build()
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+
+    async fn swap_metadata_location(
+        &self,
+        base: &Table,
+        metadata_location: &str,
+    ) -> Result<bool, DataFusionError> {
+        let glue_table = self.get_glue_table().await?;
+        let current_location = self::metadata_location(&glue_table).map(|location| location.as_str());
+        if current_location != base.metadata_location() {
+            return Ok(false);
+        }
+
+        let mut parameters = glue_table.parameters().cloned().unwrap_or_default();
+        if let Some(previous_location) = current_location {
+            parameters.insert(
+                ICEBERG_PREVIOUS_METADATA_LOCATION.to_string(),
+                previous_location.to_string(),
+            );
+        }
+        parameters.insert(
+            ICEBERG_METADATA_LOCATION.to_string(),
+            metadata_location.to_string(),
+        );
+        let table_input = TableInput::builder()
+            .name(&glue_table.name)
+            .set_description(glue_table.description.clone())
+            .set_owner(glue_table.owner.clone())
+            .set_storage_descriptor(glue_table.storage_descriptor.clone())
+            .set_partition_keys(glue_table.partition_keys.clone())
+            .set_table_type(glue_table.table_type.clone())
+            .set_parameters(Some(parameters))
+            .build()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let result = self
+            .glue_client
+            .update_table()
+            .database_name(&self.table_identifier.namespace[0])
+            .table_input(table_input)
+            .set_version_id(glue_table.version_id.clone())
+            .skip_archive(true)
+            .send()
+            .await;
+        match result {
+            Ok(_) => {
+                self.stale.store(true, Ordering::Release);
+                Ok(true)
+            }
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_concurrent_modification_exception()) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(DataFusionError::External(Box::new(e))),
+        }
+    }
+}
@@ -1,47 +1,260 @@
-use crate::catalog_config::GlueCatalogConfig;
+use crate::catalog_config::{DobbyCatalogConfig, GlueCatalogConfig};
+use crate::glue_commit::GlueIcebergCommitter;
 use crate::glue_table::GlueTable;
-use crate::table_format::table::TableIdentifier;
+use crate::lazy_schema::{LazySchema, LazyTables};
+use crate::table_format::hive_type::iceberg_type_to_hive;
+use crate::table_format::iceberg_commit::{
+    purge_table_files, write_metadata, IcebergTableCommitter,
+};
+use crate::table_format::iceberg_ddl::{new_table_metadata, PartitionTransform};
+use crate::table_format::table::{lookup_table, TableIdentifier};
 use async_trait::async_trait;
+use aws_sdk_glue::types::{Column, StorageDescriptor, Table, TableInput};
+use datafusion::arrow::datatypes::Schema;
 use datafusion::catalog::{SchemaProvider, TableProvider};
+use datafusion::common::plan_err;
 use datafusion::error::DataFusionError;
+use dobbydb_common_base::config_key::ICEBERG_METADATA_LOCATION;
+use iceberg::io::FileIO;
 use std::any::Any;
 use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct GlueDatabase {
+    glue_client: aws_sdk_glue::Client,
     database_name: String,
-    tables: HashMap<String, Arc<dyn TableProvider>>,
+    glue_config: GlueCatalogConfig,
+    /// Table names and tables, listed and loaded on demand
+    tables: LazyTables<Arc<GlueTable>>,
 }
 
 impl GlueDatabase {
-    pub async fn try_new(
+    /// Creates the database without talking to Glue, tables are listed and loaded on demand.
+    pub fn new(
         glue_client: &aws_sdk_glue::Client,
         database_name: &str,
         glue_config: &GlueCatalogConfig,
-    ) -> Result<Self, DataFusionError> {
-        let mut hash_tables: HashMap<String, Arc<dyn TableProvider>> = HashMap::new();
-        let resp = glue_client
-            .get_tables()
-            .database_name(database_name)
+    ) -> Self {
+        GlueDatabase {
+            glue_client: glue_client.clone(),
+            database_name: database_name.to_string(),
+            glue_config: glue_config.clone(),
+            tables: LazyTables::new(glue_config.cache.ttl()).with_is_valid(|table| !table.is_stale()),
+        }
+    }
+
+    /// Lists all table names of the database, following pagination.
+    pub(crate) async fn list_table_names(&self) -> Result<Vec<String>, DataFusionError> {
+        let mut names: Vec<String> = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let resp = self
+                .glue_client
+                .get_tables()
+                .database_name(&self.database_name)
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            if let Some(tables) = resp.table_list {
+                names.extend(tables.into_iter().map(|table| table.name));
+            }
+            next_token = resp.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(names)
+    }
+
+    async fn get_glue_table(&self, name: &str) -> Result<Option<Table>, DataFusionError> {
+        match self
+            .glue_client
+            .get_table()
+            .database_name(&self.database_name)
+            .name(name)
             .send()
             .await
+        {
+            Ok(resp) => Ok(resp.table),
+            Err(e) => {
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_entity_not_found_exception())
+                {
+                    return Ok(None);
+                }
+                Err(DataFusionError::External(Box::new(e)))
+            }
+        }
+    }
+
+    async fn load_table(&self, name: &str) -> Result<Option<GlueTable>, DataFusionError> {
+        let Some(table) = self.get_glue_table(name).await? else {
+            return Ok(None);
+        };
+        let table = GlueTable::try_new(
+            &self.glue_client,
+            TableIdentifier::new(&self.database_name, &table.name),
+            &table,
+            &self.glue_config,
+        )
+        .await?;
+        Ok(Some(table))
+    }
+
+    /// Creates an empty Iceberg table: writes its first metadata file under `location`,
+    /// `<database location>/<name>` by default, and registers the table in Glue.
+    ///
+    /// Returns false without creating anything if the table exists and `if_not_exists`
+    /// is set.
+    pub async fn create_iceberg_table(
+        &self,
+        name: &str,
+        schema: &Schema,
+        partition_by: &[PartitionTransform],
+        properties: HashMap<String, String>,
+        location: Option<String>,
+        if_not_exists: bool,
+    ) -> Result<bool, DataFusionError> {
+        if self.get_glue_table(name).await?.is_some() {
+            if if_not_exists {
+                return Ok(false);
+            }
+            return plan_err!("table {}.{} already exists", self.database_name, name);
+        }
+        let location = match location {
+            Some(location) => location,
+            None => format!("{}/{}", self.database_location().await?, name),
+        };
+        let metadata = new_table_metadata(schema, partition_by, properties, &location)?;
+        let file_io = FileIO::from_path(metadata.location())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .with_props(self.glue_config.build_iceberg_file_io_parameters())
+            .build()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let metadata_location = write_metadata(&file_io, None, &metadata).await?;
+
+        let columns = metadata
+            .current_schema()
+            .as_struct()
+            .fields()
+            .iter()
+            .map(|field| {
+                Column::builder()
+                    .name(&field.name)
+                    .r#type(iceberg_type_to_hive(&field.field_type))
+                    .set_comment(field.doc.clone())
+                    .build()
+            })
+            .collect::<Result<Vec<Column>, _>>()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let table_input = TableInput::builder()
+            .name(name)
+            .table_type("EXTERNAL_TABLE")
+            .parameters("table_type", "ICEBERG")
+            .parameters(ICEBERG_METADATA_LOCATION, &metadata_location)
+            .storage_descriptor(
+                StorageDescriptor::builder()
+                    .location(metadata.location())
+                    .set_columns(Some(columns))
+                    .build(),
+            )
+            .build()
             .map_err(|e| DataFusionError::External(Box::new(e)))?;
-        if let Some(tables) = resp.table_list {
-            for table in tables {
-                let build_table = GlueTable::try_new(
-                    TableIdentifier::new(database_name, &table.name),
-                    &table,
-                    glue_config,
-                )
-                .await?;
-                hash_tables.insert(table.name.clone().clone(), Arc::new(build_table));
+        let result = self
+            .glue_client
+            .create_table()
+            .database_name(&self.database_name)
+            .table_input(table_input)
+            .send()
+            .await;
+        self.tables.invalidate_names();
+        match result {
+            Ok(_) => {
+                println!(
+                    "created iceberg table {}.{} at {}",
+                    self.database_name, name, metadata_location
+                );
+                Ok(true)
+            }
+            Err(e) => {
+                // the metadata file isn't referenced by any table, don't leave it behind
+                if let Err(delete_error) = file_io.delete(&metadata_location).await {
+                    println!("failed to delete {}: {}", metadata_location, delete_error);
+                }
+                if if_not_exists
+                    && e.as_service_error()
+                        .is_some_and(|e| e.is_already_exists_exception())
+                {
+                    return Ok(false);
+                }
+                Err(DataFusionError::External(Box::new(e)))
             }
         }
-        Ok(GlueDatabase {
-            database_name: database_name.to_string(),
-            tables: hash_tables,
-        })
+    }
+
+    /// Removes a table from Glue, with `purge` the files of the Iceberg table are deleted
+    /// as well. Returns false if the table doesn't exist.
+    pub async fn drop_table(&self, name: &str, purge: bool) -> Result<bool, DataFusionError> {
+        if self.get_glue_table(name).await?.is_none() {
+            return Ok(false);
+        }
+        // load the table before dropping it, its metadata is needed to find the files
+        let purged_table = if purge {
+            let committer = GlueIcebergCommitter::new(
+                &self.glue_client,
+                &TableIdentifier::new(&self.database_name, name),
+                self.glue_config.build_iceberg_file_io_parameters(),
+                Arc::new(AtomicBool::new(false)),
+            );
+            Some(committer.load_table().await?)
+        } else {
+            None
+        };
+        let result = self
+            .glue_client
+            .delete_table()
+            .database_name(&self.database_name)
+            .name(name)
+            .send()
+            .await;
+        self.tables.invalidate_names();
+        self.tables.invalidate(name);
+        match result {
+            Ok(_) => {}
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_entity_not_found_exception()) =>
+            {
+                return Ok(false);
+            }
+            Err(e) => return Err(DataFusionError::External(Box::new(e))),
+        }
+        if let Some(table) = purged_table {
+            purge_table_files(&table).await?;
+        }
+        Ok(true)
+    }
+
+    /// Location of the database in Glue, new tables are created under it.
+    async fn database_location(&self) -> Result<String, DataFusionError> {
+        let database = self
+            .glue_client
+            .get_database()
+            .name(&self.database_name)
+            .send()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .database;
+        match database.as_ref().and_then(|database| database.location_uri()) {
+            Some(location) => Ok(location.trim_end_matches('/').to_string()),
+            None => plan_err!(
+                "glue database {} has no location, set the table LOCATION",
+                self.database_name
+            ),
+        }
     }
 }
 
@@ -52,17 +265,31 @@ impl SchemaProvider for GlueDatabase {
     }
 
     fn table_names(&self) -> Vec<String> {
-        self.tables.keys().cloned().collect()
+        self.tables.table_names()
     }
 
     async fn table(
         &self,
         name: &str,
     ) -> datafusion::common::Result<Option<Arc<dyn TableProvider>>, DataFusionError> {
-        Ok(self.tables.get(name).cloned())
+        lookup_table(name, |table_name| async move {
+            let load = async { Ok(self.load_table(table_name).await?.map(Arc::new)) };
+            let table = self.tables.table(table_name, load).await?;
+            Ok(table.map(|table| table as Arc<dyn TableProvider>))
+        })
+        .await
     }
 
     fn table_exist(&self, name: &str) -> bool {
-        self.tables.contains_key(name)
+        self.table_names().iter().any(|n| n == name)
+    }
+}
+
+#[async_trait]
+impl LazySchema for GlueDatabase {
+    async fn refresh_table_names(&self) {
+        self.tables
+            .refresh_names(format!("glue database {}", self.database_name), self.list_table_names())
+            .await;
     }
 }
@@ -1,41 +1,55 @@
 use crate::catalog_config::{DobbyCatalogConfig, GlueCatalogConfig};
-use crate::table_format::external_table::{ExternalIcebergTable, ExternalTable, ExternalTableFormat, IcebergTableScan};
-use crate::table_format::table::TableIdentifier;
+use crate::glue_commit::GlueIcebergCommitter;
+use crate::table_format::external_table::{deduce_table_format, ExternalHiveTable, ExternalIcebergTable, ExternalTable, ExternalTableFormat, HivePartition, HiveStorageDescriptor};
+use crate::table_format::delta_table::ExternalDeltaTable;
+use crate::table_format::hudi_table::ExternalHudiTable;
+use crate::table_format::partition_pruning::{convert_filters_to_glue_expression, is_partition_filter};
+use crate::table_format::iceberg_metadata_table::{IcebergMetadataTable, IcebergMetadataTableType};
+use crate::table_format::iceberg_table::IcebergTable;
+use crate::table_format::iceberg_commit::IcebergTableCommitter;
+use crate::table_format::iceberg_procedures::{call_procedure, IcebergProcedure};
+use crate::table_format::iceberg_analyze::analyze_table;
+use crate::table_format::iceberg_sink::plan_insert;
+use crate::table_format::table::{TableIdentifier, TableVersion};
 use async_trait::async_trait;
-use aws_sdk_glue::types::Table;
+use aws_sdk_glue::types::{Column, Table};
+use datafusion::arrow::array::RecordBatch;
 use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::catalog::{Session, TableProvider};
-use datafusion::common::not_impl_err;
+use datafusion::common::{not_impl_err, plan_err, Statistics};
 use datafusion::datasource::TableType;
 use datafusion::error::DataFusionError;
-use datafusion::logical_expr::Expr;
+use datafusion::logical_expr::dml::InsertOp;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
 use datafusion::physical_plan::ExecutionPlan;
-use dobbydb_common_base::config_key::ICEBERG_METADATA_LOCATION;
-use iceberg::arrow::schema_to_arrow_schema;
 use std::any::Any;
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use iceberg::Error;
+use url::Url;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GlueTable {
+    glue_client: aws_sdk_glue::Client,
     table_identifier: TableIdentifier,
     table_location: String,
     schema: SchemaRef,
     table_type: TableType,
     catalog_config: GlueCatalogConfig,
     external_table: ExternalTable,
+    /// Set once this process commits to the table, the cached table is outdated then
+    stale: Arc<AtomicBool>,
 }
 
 impl GlueTable {
     pub async fn try_new(
+        glue_client: &aws_sdk_glue::Client,
         table_identifier: TableIdentifier,
         glue_table: &Table,
         catalog_config: &GlueCatalogConfig,
     ) -> Result<Self, DataFusionError> {
-        let (table_format, table_location) = deduce_table_format(&glue_table.parameters)?;
+        let (table_format, table_location) = deduce_glue_table_format(glue_table)?;
 
-        return match table_format {
+        match table_format {
             ExternalTableFormat::Iceberg => {
                 let file_io_properties = catalog_config.build_iceberg_file_io_parameters();
                 let table = ExternalIcebergTable::try_new(
@@ -44,40 +58,247 @@ impl GlueTable {
                     file_io_properties,
                 )
                 .await?;
-                let schema = schema_to_arrow_schema(table.static_table.metadata().current_schema())
+                let table = IcebergTable::try_from_external(table)?;
+                Ok(GlueTable {
+                    glue_client: glue_client.clone(),
+                    table_identifier,
+                    table_location,
+                    schema: table.schema(),
+                    table_type: TableType::Base,
+                    catalog_config: catalog_config.clone(),
+                    stale: Arc::new(AtomicBool::new(false)),
+                    external_table: ExternalTable::Iceberg(table),
+                })
+            }
+            ExternalTableFormat::Delta => {
+                let url = Url::parse(&table_location)
                     .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                let object_store = catalog_config.build_object_store(&url)?;
+                let table = ExternalDeltaTable::try_new(url, object_store).await?;
                 Ok(GlueTable {
+                    glue_client: glue_client.clone(),
                     table_identifier,
                     table_location,
-                    schema: Arc::new(schema),
+                    schema: table.schema(),
                     table_type: TableType::Base,
                     catalog_config: catalog_config.clone(),
+                    stale: Arc::new(AtomicBool::new(false)),
+                    external_table: ExternalTable::Delta(Box::new(table)),
+                })
+            }
+            ExternalTableFormat::Hudi => {
+                let url = Url::parse(&table_location)
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                let object_store = catalog_config.build_object_store(&url)?;
+                let table = ExternalHudiTable::try_new(
+                    &build_hive_storage_descriptor(glue_table, &table_location),
+                    object_store,
+                )
+                .await?;
+                Ok(GlueTable {
+                    glue_client: glue_client.clone(),
+                    table_identifier,
+                    table_location,
+                    schema: table.schema(),
+                    table_type: TableType::Base,
+                    catalog_config: catalog_config.clone(),
+                    stale: Arc::new(AtomicBool::new(false)),
+                    external_table: ExternalTable::Hudi(table),
+                })
+            }
+            ExternalTableFormat::Hive => {
+                let url = Url::parse(&table_location)
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                let object_store = catalog_config.build_object_store(&url)?;
+                let table = ExternalHiveTable::try_new(
+                    build_hive_storage_descriptor(glue_table, &table_location),
+                    object_store,
+                )?;
+                Ok(GlueTable {
+                    glue_client: glue_client.clone(),
+                    table_identifier,
+                    table_location,
+                    schema: table.schema(),
+                    table_type: TableType::Base,
+                    catalog_config: catalog_config.clone(),
+                    stale: Arc::new(AtomicBool::new(false)),
+                    external_table: ExternalTable::Hive(table),
+                })
+            }
+        }
+    }
+
+    /// Lists the partitions of the table in Glue matching `expression`, all of them if it
+    /// is `None`, following pagination.
+    async fn list_partitions(
+        &self,
+        expression: Option<String>,
+    ) -> Result<Vec<HivePartition>, DataFusionError> {
+        let mut partitions: Vec<HivePartition> = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let resp = self
+                .glue_client
+                .get_partitions()
+                .database_name(&self.table_identifier.namespace[0])
+                .table_name(&self.table_identifier.name)
+                .set_expression(expression.clone())
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            for partition in resp.partitions() {
+                let Some(location) = partition
+                    .storage_descriptor()
+                    .and_then(|sd| sd.location())
+                else {
+                    continue;
+                };
+                partitions.push(HivePartition {
+                    values: partition.values().to_vec(),
+                    location: location.to_string(),
+                });
+            }
+            next_token = resp.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(partitions)
+    }
+
+    /// Location of the table data as registered in Glue.
+    pub fn table_location(&self) -> &str {
+        &self.table_location
+    }
+
+    /// Whether the table was committed to since it was loaded.
+    pub fn is_stale(&self) -> bool {
+        self.stale.load(Ordering::Acquire)
+    }
+
+    /// Returns this table as of a historical `version`, with the schema of that version.
+    pub async fn with_version(&self, version: &TableVersion) -> Result<GlueTable, DataFusionError> {
+        match &self.external_table {
+            ExternalTable::Iceberg(table) => {
+                let table = table.with_version(version)?;
+                Ok(GlueTable {
+                    schema: table.schema(),
                     external_table: ExternalTable::Iceberg(table),
+                    ..self.clone()
+                })
+            }
+            ExternalTable::Delta(table) => {
+                let table = table.with_version(version).await?;
+                Ok(GlueTable {
+                    schema: table.schema(),
+                    external_table: ExternalTable::Delta(Box::new(table)),
+                    ..self.clone()
                 })
             }
-            _ => Err(DataFusionError::NotImplemented(
-                "not yet implemented".to_string(),
-            )),
+            ExternalTable::Hudi(table) => Ok(GlueTable {
+                external_table: ExternalTable::Hudi(table.with_version(version)?),
+                ..self.clone()
+            }),
+            _ => not_impl_err!("time travel is only supported for iceberg, delta and hudi tables"),
+        }
+    }
+
+    /// Returns the metadata table of this table, e.g. `orders$snapshots`.
+    pub fn metadata_table(
+        &self,
+        metadata_table_type: IcebergMetadataTableType,
+    ) -> Result<IcebergMetadataTable, DataFusionError> {
+        match &self.external_table {
+            ExternalTable::Iceberg(table) => table.metadata_table(metadata_table_type),
+            _ => not_impl_err!("metadata tables are only supported for iceberg tables"),
+        }
+    }
+
+    /// Returns the current Iceberg table and a committer of new snapshots to it.
+    pub fn iceberg_writer(
+        &self,
+    ) -> Result<(iceberg::table::Table, Arc<dyn IcebergTableCommitter>), DataFusionError> {
+        let ExternalTable::Iceberg(table) = &self.external_table else {
+            return not_impl_err!("writes are only supported for iceberg tables");
         };
+        if !table.is_current_version() {
+            return plan_err!("cannot write to a historical version of {}", self.table_identifier.name);
+        }
+        let committer = GlueIcebergCommitter::new(
+            &self.glue_client,
+            &self.table_identifier,
+            self.catalog_config.build_iceberg_file_io_parameters(),
+            self.stale.clone(),
+        );
+        Ok((table.table(), Arc::new(committer)))
+    }
+
+    /// Runs a maintenance procedure on the Iceberg table, see [`call_procedure`].
+    pub async fn call_procedure(
+        &self,
+        state: &dyn Session,
+        procedure: &IcebergProcedure,
+        dry_run: bool,
+    ) -> Result<RecordBatch, DataFusionError> {
+        let (table, committer) = self.iceberg_writer()?;
+        let url = Url::parse(table.metadata().location())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let object_store = self.catalog_config.build_object_store(&url)?;
+        call_procedure(state, procedure, dry_run, committer, object_store).await
+    }
+
+    /// Computes NDV statistics of `columns` of the Iceberg table, see [`analyze_table`].
+    pub async fn analyze(
+        &self,
+        state: &dyn Session,
+        columns: &[String],
+    ) -> Result<RecordBatch, DataFusionError> {
+        let (_, committer) = self.iceberg_writer()?;
+        analyze_table(state, committer, columns).await
     }
 }
 
-fn deduce_table_format(
-    parameters: &Option<HashMap<String, String>>,
+fn deduce_glue_table_format(
+    glue_table: &Table,
 ) -> Result<(ExternalTableFormat, String), DataFusionError> {
-    if let Some(parameters) = parameters {
-        let metadata = parameters.get(ICEBERG_METADATA_LOCATION).map(|v| v.clone());
-        if let Some(metadata) = metadata {
-            Ok((ExternalTableFormat::Iceberg, metadata))
-        } else {
-            Err(DataFusionError::Configuration(
-                "invalid table parameters".to_string(),
-            ))
-        }
-    } else {
-        Err(DataFusionError::Configuration(
-            "invalid table parameters".to_string(),
-        ))
+    let storage_descriptor = glue_table.storage_descriptor();
+    // tables registered by Spark data sources (e.g. delta) keep their real location in
+    // the `path` serde parameter, the storage location is only a placeholder
+    let location = storage_descriptor
+        .and_then(|sd| sd.serde_info())
+        .and_then(|serde| serde.parameters())
+        .and_then(|parameters| parameters.get("path"))
+        .map(|path| path.as_str())
+        .or_else(|| storage_descriptor.and_then(|sd| sd.location()));
+    let input_format = storage_descriptor.and_then(|sd| sd.input_format());
+    deduce_table_format(&glue_table.name, glue_table.parameters(), input_format, location)
+}
+
+fn build_hive_storage_descriptor(glue_table: &Table, location: &str) -> HiveStorageDescriptor {
+    let to_columns = |columns: &[Column]| {
+        columns
+            .iter()
+            .map(|c| (c.name.clone(), c.r#type().unwrap_or_default().to_string()))
+            .collect::<Vec<(String, String)>>()
+    };
+    let storage_descriptor = glue_table.storage_descriptor();
+    let serde_info = storage_descriptor.and_then(|sd| sd.serde_info());
+    HiveStorageDescriptor {
+        location: location.to_string(),
+        input_format: storage_descriptor
+            .and_then(|sd| sd.input_format())
+            .map(|f| f.to_string()),
+        serde_library: serde_info
+            .and_then(|serde| serde.serialization_library())
+            .map(|l| l.to_string()),
+        serde_parameters: serde_info
+            .and_then(|serde| serde.parameters())
+            .cloned()
+            .unwrap_or_default(),
+        table_parameters: glue_table.parameters().cloned().unwrap_or_default(),
+        columns: to_columns(storage_descriptor.map(|sd| sd.columns()).unwrap_or_default()),
+        partition_keys: to_columns(glue_table.partition_keys()),
     }
 }
 
@@ -95,6 +316,38 @@ impl TableProvider for GlueTable {
         self.table_type
     }
 
+    fn statistics(&self) -> Option<Statistics> {
+        match &self.external_table {
+            ExternalTable::Iceberg(table) => table.statistics(),
+            ExternalTable::Delta(table) => table.delta_table.statistics(),
+            _ => None,
+        }
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> datafusion::common::Result<Vec<TableProviderFilterPushDown>> {
+        match &self.external_table {
+            ExternalTable::Iceberg(table) => return table.supports_filters_pushdown(filters),
+            ExternalTable::Delta(table) => return table.delta_table.supports_filters_pushdown(filters),
+            _ => {}
+        }
+        Ok(filters
+            .iter()
+            .map(|filter| match &self.external_table {
+                // partitions are pruned with the filter, but not every filter can be
+                // evaluated on partition values, so DataFusion filters the rows again
+                ExternalTable::Hive(table)
+                    if is_partition_filter(filter, &table.partition_columns) =>
+                {
+                    TableProviderFilterPushDown::Inexact
+                }
+                _ => TableProviderFilterPushDown::Unsupported,
+            })
+            .collect())
+    }
+
     async fn scan(
         &self,
         state: &dyn Session,
@@ -103,20 +356,45 @@ impl TableProvider for GlueTable {
         limit: Option<usize>,
     ) -> datafusion::common::Result<Arc<dyn ExecutionPlan>> {
         return match &self.external_table {
-            ExternalTable::Iceberg(table) => {
-                Ok(Arc::new(IcebergTableScan::new(
-                    table.static_table.clone().into_table(),
-                    table.static_table.metadata().current_snapshot_id(),
-                    self.schema.clone(),
-                    projection,
-                    filters,
-                )))
-            },
+            ExternalTable::Iceberg(table) => table.scan(state, projection, filters, limit).await,
+            ExternalTable::Delta(table) => {
+                table.delta_table.scan(state, projection, filters, limit).await
+            }
+            ExternalTable::Hudi(table) => table.scan(state, projection, limit).await,
+            ExternalTable::Hive(table) => {
+                let partitions = if table.partition_columns.is_empty() {
+                    vec![]
+                } else {
+                    let partition_filters = filters
+                        .iter()
+                        .filter(|filter| is_partition_filter(filter, &table.partition_columns))
+                        .cloned()
+                        .collect::<Vec<Expr>>();
+                    // let Glue do the bulk of the pruning, then evaluate the filters Glue
+                    // could not on the remaining partitions
+                    let expression = convert_filters_to_glue_expression(
+                        &partition_filters,
+                        &table.partition_columns,
+                    );
+                    let partitions = self.list_partitions(expression).await?;
+                    table.prune_partitions(partitions, &partition_filters)?
+                };
+                table.scan(state, &partitions, projection, limit).await
+            }
             _ => {
                 Err(DataFusionError::NotImplemented("not yet implemented".to_string()))
             }
         }
 
     }
-}
 
+    async fn insert_into(
+        &self,
+        _state: &dyn Session,
+        input: Arc<dyn ExecutionPlan>,
+        insert_op: InsertOp,
+    ) -> datafusion::common::Result<Arc<dyn ExecutionPlan>> {
+        let (table, committer) = self.iceberg_writer()?;
+        plan_insert(table, committer, input, insert_op)
+    }
+}
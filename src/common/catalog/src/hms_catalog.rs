@@ -0,0 +1,184 @@
+use crate::catalog::DobbyCatalogProvider;
+use crate::catalog_config::HmsCatalogConfig;
+use crate::hms_client::{HiveMetastoreClient, ThriftHmsClient};
+use crate::hms_schema::HmsDatabase;
+use crate::lazy_schema::LazySchemas;
+use async_trait::async_trait;
+use datafusion::catalog::{CatalogProvider, SchemaProvider};
+use datafusion::error::DataFusionError;
+use std::any::Any;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct HmsCatalog {
+    config: HmsCatalogConfig,
+    client: Arc<dyn HiveMetastoreClient>,
+    /// Database names and databases, they keep their own table caches
+    databases: LazySchemas<HmsDatabase>,
+}
+
+impl HmsCatalog {
+    pub async fn try_new(catalog_config: &HmsCatalogConfig) -> Result<Self, DataFusionError> {
+        let client = ThriftHmsClient::try_new(catalog_config.metastore_address())?;
+        Self::try_new_with_client(catalog_config, Arc::new(client)).await
+    }
+
+    pub async fn try_new_with_client(
+        catalog_config: &HmsCatalogConfig,
+        client: Arc<dyn HiveMetastoreClient>,
+    ) -> Result<Self, DataFusionError> {
+        // only list database names up front, tables are resolved on demand
+        let catalog = HmsCatalog {
+            config: catalog_config.clone(),
+            client,
+            databases: LazySchemas::new(catalog_config.cache.ttl()),
+        };
+        let names = catalog.client.get_all_databases().await?;
+        catalog.databases.set_names(names, |name| Ok(catalog.new_database(name)))?;
+        Ok(catalog)
+    }
+
+    fn new_database(&self, name: &str) -> HmsDatabase {
+        HmsDatabase::new(&self.client, name, &self.config)
+    }
+
+    /// The metastore only lists databases, so a database is looked up in that list.
+    async fn database_exists(&self, name: &str) -> Result<bool, DataFusionError> {
+        let names = self.client.get_all_databases().await?;
+        Ok(names.iter().any(|n| n == name))
+    }
+}
+
+impl CatalogProvider for HmsCatalog {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        self.databases.names()
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        Some(self.databases.schema(name)?)
+    }
+}
+
+#[async_trait]
+impl DobbyCatalogProvider for HmsCatalog {
+    async fn refresh(&self, with_table_names: bool) {
+        self.databases
+            .refresh_names(
+                format!("hms catalog {}", self.config.name),
+                self.client.get_all_databases(),
+                |name| Ok(self.new_database(name)),
+            )
+            .await;
+        if with_table_names {
+            self.databases.refresh_table_names().await;
+        }
+    }
+
+    async fn refresh_schema(&self, name: &str, with_table_names: bool) {
+        self.databases
+            .refresh_schema(
+                format!("hms catalog {}", self.config.name),
+                name,
+                self.database_exists(name),
+                |name| Ok(self.new_database(name)),
+                with_table_names,
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hms_client::HmsTable;
+    use crate::table_format::external_table::{HivePartition, HiveStorageDescriptor};
+    use datafusion::arrow::datatypes::DataType;
+    use std::collections::HashMap;
+
+    /// In-process stand-in for the metastore with one partitioned parquet table.
+    #[derive(Debug)]
+    struct StubHmsClient;
+
+    #[async_trait]
+    impl HiveMetastoreClient for StubHmsClient {
+        async fn get_all_databases(&self) -> Result<Vec<String>, DataFusionError> {
+            Ok(vec!["sales".to_string()])
+        }
+
+        async fn get_all_tables(&self, _database_name: &str) -> Result<Vec<String>, DataFusionError> {
+            Ok(vec!["orders".to_string()])
+        }
+
+        async fn get_table(
+            &self,
+            _database_name: &str,
+            table_name: &str,
+        ) -> Result<Option<HmsTable>, DataFusionError> {
+            if table_name != "orders" {
+                return Ok(None);
+            }
+            Ok(Some(HmsTable {
+                name: table_name.to_string(),
+                parameters: HashMap::new(),
+                storage_descriptor: HiveStorageDescriptor {
+                    location: "file:///tmp/warehouse/sales/orders".to_string(),
+                    input_format: Some(
+                        "org.apache.hadoop.hive.ql.io.parquet.MapredParquetInputFormat".to_string(),
+                    ),
+                    columns: vec![
+                        ("id".to_string(), "bigint".to_string()),
+                        ("amount".to_string(), "decimal(10,2)".to_string()),
+                    ],
+                    partition_keys: vec![("dt".to_string(), "string".to_string())],
+                    ..Default::default()
+                },
+            }))
+        }
+
+        async fn get_partitions(
+            &self,
+            _database_name: &str,
+            _table_name: &str,
+            _filter: Option<String>,
+        ) -> Result<Vec<HivePartition>, DataFusionError> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hms_catalog() -> Result<(), DataFusionError> {
+        let config: HmsCatalogConfig =
+            toml::from_str("name = \"hms\"\nmetastore-uri = \"thrift://localhost:9083\"")
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        assert_eq!(config.metastore_address(), "localhost:9083");
+
+        let catalog = HmsCatalog::try_new_with_client(&config, Arc::new(StubHmsClient)).await?;
+        assert_eq!(catalog.schema_names(), vec!["sales".to_string()]);
+        assert!(catalog.schema("missing").is_none());
+
+        let schema = catalog.schema("sales").expect("database sales");
+        catalog.refresh(true).await;
+        assert_eq!(schema.table_names(), vec!["orders".to_string()]);
+        let table = schema.table("orders").await?.expect("table orders");
+        let table_schema = table.schema();
+        let columns: Vec<(&str, &DataType)> = table_schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("id", &DataType::Int64),
+                ("amount", &DataType::Decimal128(10, 2)),
+                ("dt", &DataType::Utf8),
+            ]
+        );
+        assert!(schema.table("missing").await?.is_none());
+        Ok(())
+    }
+}
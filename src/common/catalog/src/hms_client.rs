@@ -0,0 +1,397 @@
+use crate::table_format::external_table::{HivePartition, HiveStorageDescriptor};
+use async_trait::async_trait;
+use datafusion::error::DataFusionError;
+use hive_metastore::{
+    FieldSchema, Partition, StorageDescriptor, Table, ThriftHiveMetastoreClient,
+    ThriftHiveMetastoreClientBuilder, ThriftHiveMetastoreGetTableException,
+};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::net::ToSocketAddrs;
+use volo_thrift::codec::default::DefaultMakeCodec;
+use volo_thrift::MaybeException;
+
+/// A table as stored in the Hive Metastore.
+#[derive(Debug, Clone)]
+pub struct HmsTable {
+    pub name: String,
+    pub parameters: HashMap<String, String>,
+    /// Storage of the table, its `location` is empty if the metastore has none
+    pub storage_descriptor: HiveStorageDescriptor,
+}
+
+/// The Hive Metastore calls DobbyDB needs, so the catalog can be tested without a
+/// running metastore.
+#[async_trait]
+pub trait HiveMetastoreClient: Debug + Send + Sync {
+    async fn get_all_databases(&self) -> Result<Vec<String>, DataFusionError>;
+
+    async fn get_all_tables(&self, database_name: &str) -> Result<Vec<String>, DataFusionError>;
+
+    /// Returns `None` if the table does not exist.
+    async fn get_table(
+        &self,
+        database_name: &str,
+        table_name: &str,
+    ) -> Result<Option<HmsTable>, DataFusionError>;
+
+    /// Lists the partitions matching `filter`, all of them if it is `None`. The filter
+    /// uses the metastore's filter syntax, e.g. `dt >= '2026-10-01'`.
+    async fn get_partitions(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        filter: Option<String>,
+    ) -> Result<Vec<HivePartition>, DataFusionError>;
+}
+
+/// Talks Thrift to a Hive Metastore.
+pub struct ThriftHmsClient {
+    address: String,
+    client: ThriftHiveMetastoreClient,
+}
+
+impl Debug for ThriftHmsClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThriftHmsClient")
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+impl ThriftHmsClient {
+    /// Connects lazily to the metastore at `host:port`, using the buffered transport HMS
+    /// serves by default.
+    pub fn try_new(address: &str) -> Result<Self, DataFusionError> {
+        let socket_address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| {
+                DataFusionError::Configuration(format!("invalid metastore address: {address}"))
+            })?;
+        let client = ThriftHiveMetastoreClientBuilder::new("hms")
+            .address(socket_address)
+            .make_codec(DefaultMakeCodec::buffered())
+            .build();
+        Ok(ThriftHmsClient {
+            address: address.to_string(),
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl HiveMetastoreClient for ThriftHmsClient {
+    async fn get_all_databases(&self) -> Result<Vec<String>, DataFusionError> {
+        let databases = self
+            .client
+            .get_all_databases()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(from_thrift_exception(databases)?
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect())
+    }
+
+    async fn get_all_tables(&self, database_name: &str) -> Result<Vec<String>, DataFusionError> {
+        let tables = self
+            .client
+            .get_all_tables(database_name.to_string().into())
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(from_thrift_exception(tables)?
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect())
+    }
+
+    async fn get_table(
+        &self,
+        database_name: &str,
+        table_name: &str,
+    ) -> Result<Option<HmsTable>, DataFusionError> {
+        let table = self
+            .client
+            .get_table(database_name.to_string().into(), table_name.to_string().into())
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        match table {
+            MaybeException::Ok(table) => Ok(Some(to_hms_table(table_name, table))),
+            MaybeException::Exception(ThriftHiveMetastoreGetTableException::O2(_)) => Ok(None),
+            MaybeException::Exception(e) => Err(DataFusionError::Execution(format!(
+                "failed to get table {database_name}.{table_name} from hive metastore: {e:?}"
+            ))),
+        }
+    }
+
+    async fn get_partitions(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        filter: Option<String>,
+    ) -> Result<Vec<HivePartition>, DataFusionError> {
+        // -1 lists all partitions
+        let partitions = match filter {
+            Some(filter) => from_thrift_exception(
+                self.client
+                    .get_partitions_by_filter(
+                        database_name.to_string().into(),
+                        table_name.to_string().into(),
+                        filter.into(),
+                        -1,
+                    )
+                    .await
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?,
+            )?,
+            None => from_thrift_exception(
+                self.client
+                    .get_partitions(
+                        database_name.to_string().into(),
+                        table_name.to_string().into(),
+                        -1,
+                    )
+                    .await
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?,
+            )?,
+        };
+        Ok(partitions.into_iter().filter_map(to_hive_partition).collect())
+    }
+}
+
+fn from_thrift_exception<T, E: Debug>(value: MaybeException<T, E>) -> Result<T, DataFusionError> {
+    match value {
+        MaybeException::Ok(value) => Ok(value),
+        MaybeException::Exception(e) => Err(DataFusionError::Execution(format!(
+            "hive metastore exception: {e:?}"
+        ))),
+    }
+}
+
+fn to_hms_table(table_name: &str, table: Table) -> HmsTable {
+    let parameters: HashMap<String, String> = table
+        .parameters
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let storage_descriptor = to_hive_storage_descriptor(
+        table.sd.unwrap_or_default(),
+        table.partition_keys.unwrap_or_default(),
+        parameters.clone(),
+    );
+    HmsTable {
+        name: table_name.to_string(),
+        parameters,
+        storage_descriptor,
+    }
+}
+
+fn to_hive_storage_descriptor(
+    sd: StorageDescriptor,
+    partition_keys: Vec<FieldSchema>,
+    table_parameters: HashMap<String, String>,
+) -> HiveStorageDescriptor {
+    let to_columns = |columns: Vec<FieldSchema>| {
+        columns
+            .into_iter()
+            .map(|c| {
+                (
+                    c.name.map(|n| n.to_string()).unwrap_or_default(),
+                    c.r#type.map(|t| t.to_string()).unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<(String, String)>>()
+    };
+    let serde_info = sd.serde_info.unwrap_or_default();
+    HiveStorageDescriptor {
+        location: sd.location.map(|l| l.to_string()).unwrap_or_default(),
+        input_format: sd.input_format.map(|f| f.to_string()),
+        serde_library: serde_info.serialization_lib.map(|l| l.to_string()),
+        serde_parameters: serde_info
+            .parameters
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        table_parameters,
+        columns: to_columns(sd.cols.unwrap_or_default()),
+        partition_keys: to_columns(partition_keys),
+    }
+}
+
+fn to_hive_partition(partition: Partition) -> Option<HivePartition> {
+    let location = partition.sd.and_then(|sd| sd.location)?;
+    Some(HivePartition {
+        values: partition
+            .values
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| v.to_string())
+            .collect(),
+        location: location.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hive_metastore::{
+        NoSuchObjectException, SerDeInfo, ThriftHiveMetastoreGetAllDatabasesResultSend,
+        ThriftHiveMetastoreGetAllTablesResultSend,
+        ThriftHiveMetastoreGetPartitionsByFilterResultSend,
+        ThriftHiveMetastoreGetPartitionsResultSend, ThriftHiveMetastoreGetTableResultSend,
+        ThriftHiveMetastoreRequestRecv, ThriftHiveMetastoreResponseSend,
+    };
+    use volo::net::incoming::DefaultIncoming;
+    use volo_thrift::context::ServerContext;
+    use volo_thrift::{ApplicationException, ApplicationExceptionKind, ServerError};
+
+    /// Serves the few metastore calls the client makes: database `sales` with the table
+    /// `orders`, partitioned by `dt` with the partitions 2026-10-01 and 2026-10-02.
+    #[derive(Clone)]
+    struct StubMetastore;
+
+    fn field(name: &str, r#type: &str) -> FieldSchema {
+        FieldSchema {
+            name: Some(name.to_string().into()),
+            r#type: Some(r#type.to_string().into()),
+            ..Default::default()
+        }
+    }
+
+    fn partition(dt: &str) -> Partition {
+        Partition {
+            values: Some(vec![dt.to_string().into()]),
+            sd: Some(StorageDescriptor {
+                location: Some(format!("s3://warehouse/sales/orders/dt={dt}").into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    impl volo::Service<ServerContext, ThriftHiveMetastoreRequestRecv> for StubMetastore {
+        type Response = ThriftHiveMetastoreResponseSend;
+        type Error = ServerError;
+
+        async fn call(
+            &self,
+            _cx: &mut ServerContext,
+            req: ThriftHiveMetastoreRequestRecv,
+        ) -> Result<Self::Response, Self::Error> {
+            match req {
+                ThriftHiveMetastoreRequestRecv::GetAllDatabases(_) => {
+                    Ok(ThriftHiveMetastoreResponseSend::GetAllDatabases(
+                        ThriftHiveMetastoreGetAllDatabasesResultSend::Ok(vec!["sales".into()]),
+                    ))
+                }
+                ThriftHiveMetastoreRequestRecv::GetAllTables(args) => {
+                    let tables = if args.db_name == "sales" { vec!["orders".into()] } else { vec![] };
+                    Ok(ThriftHiveMetastoreResponseSend::GetAllTables(
+                        ThriftHiveMetastoreGetAllTablesResultSend::Ok(tables),
+                    ))
+                }
+                ThriftHiveMetastoreRequestRecv::GetTable(args) => {
+                    let result = if args.dbname == "sales" && args.tbl_name == "orders" {
+                        ThriftHiveMetastoreGetTableResultSend::Ok(Table {
+                            table_name: Some("orders".into()),
+                            db_name: Some("sales".into()),
+                            sd: Some(StorageDescriptor {
+                                cols: Some(vec![field("id", "bigint"), field("amount", "decimal(10,2)")]),
+                                location: Some("s3://warehouse/sales/orders".into()),
+                                input_format: Some(
+                                    "org.apache.hadoop.hive.ql.io.parquet.MapredParquetInputFormat".into(),
+                                ),
+                                serde_info: Some(SerDeInfo {
+                                    serialization_lib: Some(
+                                        "org.apache.hadoop.hive.ql.io.parquet.serde.ParquetHiveSerDe".into(),
+                                    ),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            }),
+                            partition_keys: Some(vec![field("dt", "string")]),
+                            parameters: Some(
+                                [("EXTERNAL".into(), "TRUE".into())].into_iter().collect(),
+                            ),
+                            ..Default::default()
+                        })
+                    } else {
+                        ThriftHiveMetastoreGetTableResultSend::O2(NoSuchObjectException {
+                            message: Some(format!("{}.{} not found", args.dbname, args.tbl_name).into()),
+                        })
+                    };
+                    Ok(ThriftHiveMetastoreResponseSend::GetTable(result))
+                }
+                ThriftHiveMetastoreRequestRecv::GetPartitions(_) => {
+                    Ok(ThriftHiveMetastoreResponseSend::GetPartitions(
+                        ThriftHiveMetastoreGetPartitionsResultSend::Ok(vec![
+                            partition("2026-10-01"),
+                            partition("2026-10-02"),
+                        ]),
+                    ))
+                }
+                ThriftHiveMetastoreRequestRecv::GetPartitionsByFilter(args) => {
+                    assert_eq!(args.filter, "dt >= '2026-10-02'");
+                    Ok(ThriftHiveMetastoreResponseSend::GetPartitionsByFilter(
+                        ThriftHiveMetastoreGetPartitionsByFilterResultSend::Ok(vec![partition(
+                            "2026-10-02",
+                        )]),
+                    ))
+                }
+                _ => Err(ApplicationException::new(
+                    ApplicationExceptionKind::UNKNOWN_METHOD,
+                    "not served by the stub metastore",
+                )
+                .into()),
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_thrift_hms_client() -> Result<(), DataFusionError> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        tokio::spawn(
+            volo_thrift::server::Server::new(StubMetastore)
+                .make_codec(DefaultMakeCodec::buffered())
+                .run(DefaultIncoming::from(listener)),
+        );
+
+        let client = ThriftHmsClient::try_new(&address.to_string())?;
+        assert_eq!(client.get_all_databases().await?, vec!["sales"]);
+        assert_eq!(client.get_all_tables("sales").await?, vec!["orders"]);
+
+        let table = client.get_table("sales", "orders").await?.expect("table orders");
+        assert_eq!(table.name, "orders");
+        assert_eq!(table.parameters.get("EXTERNAL").map(String::as_str), Some("TRUE"));
+        let sd = &table.storage_descriptor;
+        assert_eq!(sd.location, "s3://warehouse/sales/orders");
+        assert_eq!(
+            sd.serde_library.as_deref(),
+            Some("org.apache.hadoop.hive.ql.io.parquet.serde.ParquetHiveSerDe")
+        );
+        assert_eq!(
+            sd.columns,
+            vec![
+                ("id".to_string(), "bigint".to_string()),
+                ("amount".to_string(), "decimal(10,2)".to_string()),
+            ]
+        );
+        assert_eq!(sd.partition_keys, vec![("dt".to_string(), "string".to_string())]);
+        assert_eq!(sd.table_parameters, table.parameters);
+        assert!(client.get_table("sales", "missing").await?.is_none());
+
+        let partitions = client.get_partitions("sales", "orders", None).await?;
+        let values: Vec<&[String]> = partitions.iter().map(|p| p.values.as_slice()).collect();
+        assert_eq!(values, vec![["2026-10-01".to_string()], ["2026-10-02".to_string()]]);
+        assert_eq!(partitions[0].location, "s3://warehouse/sales/orders/dt=2026-10-01");
+        let partitions = client
+            .get_partitions("sales", "orders", Some("dt >= '2026-10-02'".to_string()))
+            .await?;
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].values, vec!["2026-10-02"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::catalog_config::HmsCatalogConfig;
+use crate::hms_client::HiveMetastoreClient;
+use crate::hms_table::HiveMetastoreTable;
+use crate::lazy_schema::{LazySchema, LazyTables};
+use crate::table_format::table::{lookup_table, TableIdentifier};
+use async_trait::async_trait;
+use datafusion::catalog::{SchemaProvider, TableProvider};
+use datafusion::error::DataFusionError;
+use std::any::Any;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct HmsDatabase {
+    client: Arc<dyn HiveMetastoreClient>,
+    database_name: String,
+    hms_config: HmsCatalogConfig,
+    /// Table names and tables, listed and loaded on demand
+    tables: LazyTables<HiveMetastoreTable>,
+}
+
+impl HmsDatabase {
+    /// Creates the database without talking to the metastore, tables are listed and
+    /// loaded on demand.
+    pub fn new(
+        client: &Arc<dyn HiveMetastoreClient>,
+        database_name: &str,
+        hms_config: &HmsCatalogConfig,
+    ) -> Self {
+        HmsDatabase {
+            client: client.clone(),
+            database_name: database_name.to_string(),
+            hms_config: hms_config.clone(),
+            tables: LazyTables::new(hms_config.cache.ttl()),
+        }
+    }
+
+    async fn load_table(&self, name: &str) -> Result<Option<HiveMetastoreTable>, DataFusionError> {
+        let Some(table) = self.client.get_table(&self.database_name, name).await? else {
+            return Ok(None);
+        };
+        let table = HiveMetastoreTable::try_new(
+            &self.client,
+            TableIdentifier::new(&self.database_name, name),
+            table,
+            &self.hms_config,
+        )
+        .await?;
+        Ok(Some(table))
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for HmsDatabase {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.tables.table_names()
+    }
+
+    async fn table(
+        &self,
+        name: &str,
+    ) -> datafusion::common::Result<Option<Arc<dyn TableProvider>>, DataFusionError> {
+        lookup_table(name, |table_name| async move {
+            let table = self.tables.table(table_name, self.load_table(table_name)).await?;
+            Ok(table.map(HiveMetastoreTable::provider))
+        })
+        .await
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.table_names().iter().any(|n| n == name)
+    }
+}
+
+#[async_trait]
+impl LazySchema for HmsDatabase {
+    async fn refresh_table_names(&self) {
+        self.tables
+            .refresh_names(
+                format!("hive database {}", self.database_name),
+                self.client.get_all_tables(&self.database_name),
+            )
+            .await;
+    }
+}
@@ -0,0 +1,168 @@
+use crate::catalog_config::{DobbyCatalogConfig, HmsCatalogConfig};
+use crate::hms_client::{HiveMetastoreClient, HmsTable};
+use crate::table_format::external_table::{
+    deduce_table_format, ExternalHiveTable, ExternalIcebergTable, ExternalTableFormat,
+};
+use crate::table_format::iceberg_table::IcebergTable;
+use crate::table_format::partition_pruning::{convert_filters_to_glue_expression, is_partition_filter};
+use crate::table_format::table::TableIdentifier;
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::not_impl_err;
+use datafusion::datasource::TableType;
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::ExecutionPlan;
+use std::any::Any;
+use std::sync::Arc;
+use url::Url;
+
+/// A table of the Hive Metastore, resolved the same way as a [`GlueTable`]: Iceberg if
+/// its parameters have a `metadata_location`, a Hive-format table otherwise.
+///
+/// [`GlueTable`]: crate::glue_table::GlueTable
+#[derive(Debug, Clone)]
+pub enum HiveMetastoreTable {
+    Iceberg(Arc<IcebergTable>),
+    Hive(Arc<HiveTable>),
+}
+
+impl HiveMetastoreTable {
+    pub async fn try_new(
+        client: &Arc<dyn HiveMetastoreClient>,
+        table_identifier: TableIdentifier,
+        hms_table: HmsTable,
+        catalog_config: &HmsCatalogConfig,
+    ) -> Result<Self, DataFusionError> {
+        let (table_format, table_location) = deduce_table_format(
+            &hms_table.name,
+            Some(&hms_table.parameters),
+            hms_table.storage_descriptor.input_format.as_deref(),
+            Some(&hms_table.storage_descriptor.location),
+        )?;
+        match table_format {
+            ExternalTableFormat::Iceberg => {
+                let table = ExternalIcebergTable::try_new(
+                    &table_identifier,
+                    &table_location,
+                    catalog_config.build_iceberg_file_io_parameters(),
+                )
+                .await?;
+                Ok(HiveMetastoreTable::Iceberg(Arc::new(
+                    IcebergTable::try_from_external(table)?,
+                )))
+            }
+            ExternalTableFormat::Delta => {
+                not_impl_err!("delta tables are not supported in hive metastore catalogs yet")
+            }
+            ExternalTableFormat::Hudi => {
+                not_impl_err!("hudi tables are not supported in hive metastore catalogs yet")
+            }
+            ExternalTableFormat::Hive => {
+                let url = Url::parse(&table_location)
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                let object_store = catalog_config.build_object_store(&url)?;
+                let table = ExternalHiveTable::try_new(hms_table.storage_descriptor, object_store)?;
+                Ok(HiveMetastoreTable::Hive(Arc::new(HiveTable {
+                    client: client.clone(),
+                    table_identifier,
+                    table,
+                })))
+            }
+        }
+    }
+
+    /// Returns the table provider, whatever the format of the table.
+    pub fn provider(self) -> Arc<dyn TableProvider> {
+        match self {
+            HiveMetastoreTable::Iceberg(table) => table,
+            HiveMetastoreTable::Hive(table) => table,
+        }
+    }
+}
+
+/// A Hive-format table of the Hive Metastore.
+#[derive(Debug)]
+pub struct HiveTable {
+    client: Arc<dyn HiveMetastoreClient>,
+    table_identifier: TableIdentifier,
+    table: ExternalHiveTable,
+}
+
+#[async_trait]
+impl TableProvider for HiveTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.table.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> datafusion::common::Result<Vec<TableProviderFilterPushDown>> {
+        // partitions are pruned with the filter, DataFusion still filters the rows
+        Ok(filters
+            .iter()
+            .map(|filter| {
+                if is_partition_filter(filter, &self.table.partition_columns) {
+                    TableProviderFilterPushDown::Inexact
+                } else {
+                    TableProviderFilterPushDown::Unsupported
+                }
+            })
+            .collect())
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> datafusion::common::Result<Arc<dyn ExecutionPlan>> {
+        let partitions = if self.table.partition_columns.is_empty() {
+            vec![]
+        } else {
+            let partition_filters = filters
+                .iter()
+                .filter(|filter| is_partition_filter(filter, &self.table.partition_columns))
+                .cloned()
+                .collect::<Vec<Expr>>();
+            let database_name = &self.table_identifier.namespace[0];
+            let table_name = &self.table_identifier.name;
+            // the metastore filter syntax matches Glue's expressions, but older metastores
+            // reject some of it (e.g. non-string keys), so fall back to listing everything
+            let filter = convert_filters_to_glue_expression(
+                &partition_filters,
+                &self.table.partition_columns,
+            );
+            let partitions = match filter {
+                Some(filter) => match self
+                    .client
+                    .get_partitions(database_name, table_name, Some(filter.clone()))
+                    .await
+                {
+                    Ok(partitions) => partitions,
+                    Err(e) => {
+                        println!(
+                            "failed to list partitions of {} by filter {}, list all partitions: {}",
+                            table_name, filter, e
+                        );
+                        self.client.get_partitions(database_name, table_name, None).await?
+                    }
+                },
+                None => self.client.get_partitions(database_name, table_name, None).await?,
+            };
+            self.table.prune_partitions(partitions, &partition_filters)?
+        };
+        self.table.scan(state, &partitions, projection, limit).await
+    }
+}
@@ -0,0 +1,313 @@
+use crate::ttl_cache::{TtlCache, TtlCell};
+use async_trait::async_trait;
+use datafusion::error::DataFusionError;
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A schema whose table names are cached, [`datafusion::catalog::SchemaProvider::table_names`]
+/// only reads them.
+#[async_trait]
+pub trait LazySchema: Send + Sync {
+    /// Refreshes the table names once the cached ones expired.
+    async fn refresh_table_names(&self);
+}
+
+/// Schema names and schemas of a catalog. The sync
+/// [`datafusion::catalog::CatalogProvider`] methods only read them, they are refreshed
+/// before planning by [`crate::catalog::DobbyCatalogProvider`].
+#[derive(Debug)]
+pub struct LazySchemas<S> {
+    /// Schema names of the catalog, refreshed once the cache ttl expires
+    names: TtlCell<Vec<String>>,
+    /// Schemas known to exist, they keep their own table caches
+    schemas: Mutex<HashMap<String, Arc<S>>>,
+    /// Whether a schema looked up by name exists, checked again once the cache ttl expires
+    exists: TtlCache<String, bool>,
+}
+
+impl<S: LazySchema> LazySchemas<S> {
+    pub fn new(ttl: Duration) -> Self {
+        LazySchemas {
+            names: TtlCell::new(ttl),
+            schemas: Mutex::new(HashMap::new()),
+            exists: TtlCache::new(ttl),
+        }
+    }
+
+    /// Returns the last listed schema names, even if they have expired.
+    pub fn names(&self) -> Vec<String> {
+        self.names.get_stale().unwrap_or_default()
+    }
+
+    /// Returns the schema `name` if it is known to exist.
+    pub fn schema(&self, name: &str) -> Option<Arc<S>> {
+        self.schemas.lock().unwrap().get(name).cloned()
+    }
+
+    /// Sets the listed schema names, the schemas are created with `new_schema`.
+    pub fn set_names(
+        &self,
+        names: Vec<String>,
+        new_schema: impl Fn(&str) -> Result<S, DataFusionError>,
+    ) -> Result<(), DataFusionError> {
+        for name in &names {
+            self.add(name, &new_schema)?;
+        }
+        self.names.set(names);
+        Ok(())
+    }
+
+    /// Lists the schema names with `list` once the cached ones expired. Keeps the expired
+    /// names when listing fails, `catalog` names the catalog in the log.
+    pub async fn refresh_names<E: Display>(
+        &self,
+        catalog: impl Display,
+        list: impl Future<Output = Result<Vec<String>, E>>,
+        new_schema: impl Fn(&str) -> Result<S, DataFusionError>,
+    ) {
+        if self.names.get().is_some() {
+            return;
+        }
+        let result = match list.await {
+            Ok(names) => self.set_names(names, new_schema),
+            Err(e) => Err(DataFusionError::Execution(e.to_string())),
+        };
+        if let Err(e) = result {
+            println!("failed to refresh schemas of {}, use cached ones: {}", catalog, e);
+        }
+    }
+
+    /// Checks with `exists` whether the schema `name` exists once the last check expired,
+    /// and refreshes its table names with `with_table_names`. Keeps the last result when
+    /// checking fails, `catalog` names the catalog in the log.
+    pub async fn refresh_schema<E: Display>(
+        &self,
+        catalog: impl Display,
+        name: &str,
+        exists: impl Future<Output = Result<bool, E>>,
+        new_schema: impl Fn(&str) -> Result<S, DataFusionError>,
+        with_table_names: bool,
+    ) {
+        if self.exists.get(&name.to_string()).is_none() {
+            let result = match exists.await {
+                Ok(true) => self.add(name, &new_schema),
+                Ok(false) => {
+                    self.schemas.lock().unwrap().remove(name);
+                    self.exists.insert(name.to_string(), false);
+                    Ok(())
+                }
+                Err(e) => Err(DataFusionError::Execution(e.to_string())),
+            };
+            if let Err(e) = result {
+                println!("failed to check schema {} of {}: {}", name, catalog, e);
+            }
+        }
+        if let Some(schema) = self.schema(name).filter(|_| with_table_names) {
+            schema.refresh_table_names().await;
+        }
+    }
+
+    /// Refreshes the table names of all schemas known to exist.
+    pub async fn refresh_table_names(&self) {
+        let schemas: Vec<Arc<S>> = self.schemas.lock().unwrap().values().cloned().collect();
+        join_all(schemas.iter().map(|schema| schema.refresh_table_names())).await;
+    }
+
+    fn add(
+        &self,
+        name: &str,
+        new_schema: &impl Fn(&str) -> Result<S, DataFusionError>,
+    ) -> Result<(), DataFusionError> {
+        let mut schemas = self.schemas.lock().unwrap();
+        if !schemas.contains_key(name) {
+            schemas.insert(name.to_string(), Arc::new(new_schema(name)?));
+        }
+        self.exists.insert(name.to_string(), true);
+        Ok(())
+    }
+
+    /// Forgets the schema names and whether the schema `name` exists, e.g. after it was
+    /// created.
+    pub fn invalidate(&self, name: &str) {
+        self.names.invalidate();
+        self.exists.invalidate(&name.to_string());
+    }
+
+    /// Forgets the schema `name`, e.g. after it was dropped.
+    pub fn remove(&self, name: &str) {
+        self.invalidate(name);
+        self.schemas.lock().unwrap().remove(name);
+    }
+}
+
+/// Table names and tables of a schema, listed and loaded on demand and cached for the
+/// cache ttl of the catalog.
+#[derive(Debug)]
+pub struct LazyTables<T> {
+    /// Table names of the schema, refreshed once the cache ttl expires
+    names: TtlCell<Vec<String>>,
+    /// Tables loaded so far, keyed by table name
+    tables: TtlCache<String, T>,
+    /// Whether a cached table can still be used, e.g. it hasn't been committed to since
+    is_valid: fn(&T) -> bool,
+}
+
+impl<T: Clone> LazyTables<T> {
+    pub fn new(ttl: Duration) -> Self {
+        LazyTables {
+            names: TtlCell::new(ttl),
+            tables: TtlCache::new(ttl),
+            is_valid: |_| true,
+        }
+    }
+
+    /// Reloads cached tables for which `is_valid` returns false.
+    pub fn with_is_valid(mut self, is_valid: fn(&T) -> bool) -> Self {
+        self.is_valid = is_valid;
+        self
+    }
+
+    /// Returns the last listed table names, even if they have expired.
+    pub fn table_names(&self) -> Vec<String> {
+        self.names.get_stale().unwrap_or_default()
+    }
+
+    /// Lists the table names with `list` once the cached ones expired. Keeps the expired
+    /// names when listing fails, `schema` names the schema in the log.
+    pub async fn refresh_names<E: Display>(
+        &self,
+        schema: impl Display,
+        list: impl Future<Output = Result<Vec<String>, E>>,
+    ) {
+        if self.names.get().is_some() {
+            return;
+        }
+        match list.await {
+            Ok(names) => self.names.set(names),
+            Err(e) => println!("failed to refresh tables of {}, use cached ones: {}", schema, e),
+        }
+    }
+
+    /// Returns the table `name`, loaded with `load` unless it is cached.
+    pub async fn table(
+        &self,
+        name: &str,
+        load: impl Future<Output = Result<Option<T>, DataFusionError>>,
+    ) -> Result<Option<T>, DataFusionError> {
+        if let Some(table) = self.tables.get(&name.to_string())
+            && (self.is_valid)(&table)
+        {
+            return Ok(Some(table));
+        }
+        let table = load.await?;
+        if let Some(table) = &table {
+            self.tables.insert(name.to_string(), table.clone());
+        }
+        Ok(table)
+    }
+
+    /// Forgets the table names, e.g. after a table was created or dropped.
+    pub fn invalidate_names(&self) {
+        self.names.invalidate();
+    }
+
+    /// Forgets the table `name`, e.g. after it was dropped.
+    pub fn invalidate(&self, name: &str) {
+        self.tables.invalidate(&name.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_lazy_tables() -> Result<(), DataFusionError> {
+        let tables: LazyTables<i32> = LazyTables::new(Duration::from_secs(60)).with_is_valid(|t| *t > 0);
+        let loads = AtomicUsize::new(0);
+        let load = |value: Option<i32>| {
+            let loads = &loads;
+            async move {
+                loads.fetch_add(1, Ordering::SeqCst);
+                Ok(value)
+            }
+        };
+        assert_eq!(tables.table("a", load(Some(1))).await?, Some(1));
+        assert_eq!(tables.table("a", load(Some(2))).await?, Some(1));
+        assert_eq!(tables.table("missing", load(None)).await?, None);
+        // invalid tables are reloaded
+        assert_eq!(tables.table("b", load(Some(-1))).await?, Some(-1));
+        assert_eq!(tables.table("b", load(Some(3))).await?, Some(3));
+        tables.invalidate("a");
+        assert_eq!(tables.table("a", load(Some(4))).await?, Some(4));
+        assert_eq!(loads.load(Ordering::SeqCst), 5);
+
+        assert!(tables.table_names().is_empty());
+        tables.refresh_names("db", async { Ok::<_, DataFusionError>(vec!["a".to_string()]) }).await;
+        assert_eq!(tables.table_names(), vec!["a"]);
+        tables.invalidate_names();
+        assert!(tables.table_names().is_empty());
+
+        // expired names are kept when listing fails
+        let expired: LazyTables<i32> = LazyTables::new(Duration::ZERO);
+        expired.refresh_names("db", async { Ok::<_, DataFusionError>(vec!["a".to_string()]) }).await;
+        expired.refresh_names("db", async { Err(DataFusionError::Plan("listing failed".to_string())) }).await;
+        assert_eq!(expired.table_names(), vec!["a"]);
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct CountingSchema {
+        refreshes: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LazySchema for CountingSchema {
+        async fn refresh_table_names(&self) {
+            self.refreshes.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lazy_schemas() -> Result<(), DataFusionError> {
+        let schemas: LazySchemas<CountingSchema> = LazySchemas::new(Duration::from_secs(60));
+        let new_schema = |_: &str| {
+            Ok(CountingSchema {
+                refreshes: AtomicUsize::new(0),
+            })
+        };
+        schemas.set_names(vec!["a".to_string()], new_schema)?;
+        assert_eq!(schemas.names(), vec!["a"]);
+        // names are only listed again once they expired
+        schemas
+            .refresh_names("catalog", async { Ok::<_, DataFusionError>(vec!["b".to_string()]) }, new_schema)
+            .await;
+        assert_eq!(schemas.names(), vec!["a"]);
+
+        // schemas which aren't listed are checked by name
+        assert!(schemas.schema("nested").is_none());
+        schemas
+            .refresh_schema("catalog", "nested", async { Ok::<_, DataFusionError>(true) }, new_schema, true)
+            .await;
+        let nested = schemas.schema("nested").expect("schema nested");
+        assert_eq!(nested.refreshes.load(Ordering::SeqCst), 1);
+        schemas
+            .refresh_schema("catalog", "nested", async { Ok::<_, DataFusionError>(false) }, new_schema, false)
+            .await;
+        assert!(schemas.schema("nested").is_some());
+        schemas.remove("nested");
+        schemas
+            .refresh_schema("catalog", "nested", async { Ok::<_, DataFusionError>(false) }, new_schema, false)
+            .await;
+        assert!(schemas.schema("nested").is_none());
+
+        schemas.refresh_table_names().await;
+        assert_eq!(schemas.schema("a").expect("schema a").refreshes.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+}
@@ -1,6 +1,19 @@
 pub mod catalog;
 pub mod glue_catalog;
 pub mod glue_table;
+pub mod glue_commit;
 pub mod glue_schema;
+pub mod rest_catalog;
+pub mod rest_schema;
+pub mod hms_catalog;
+pub mod hms_client;
+pub mod hms_schema;
+pub mod hms_table;
+pub mod filesystem_catalog;
+pub mod filesystem_schema;
+pub mod sql_catalog;
+pub mod sql_schema;
 pub mod catalog_config;
-pub mod table_format;
\ No newline at end of file
+pub mod table_format;
+pub mod ttl_cache;
+pub mod lazy_schema;
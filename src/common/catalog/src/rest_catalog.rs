@@ -0,0 +1,156 @@
+use crate::catalog::DobbyCatalogProvider;
+use crate::catalog_config::RestCatalogConfig;
+use crate::lazy_schema::LazySchemas;
+use crate::rest_schema::RestNamespace;
+use async_trait::async_trait;
+use datafusion::catalog::{CatalogProvider, SchemaProvider};
+use datafusion::error::DataFusionError;
+use iceberg::Catalog;
+use iceberg_catalog_rest::RestCatalogConfig as IcebergRestCatalogConfig;
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// Separates the levels of a nested namespace in its schema name, e.g. `sales.eu`.
+pub(crate) const NAMESPACE_SEPARATOR: &str = ".";
+
+pub struct RestCatalog {
+    config: RestCatalogConfig,
+    rest_client: Arc<iceberg_catalog_rest::RestCatalog>,
+    /// Top level namespace names and namespaces resolved so far, nested ones included.
+    /// They keep their own table caches
+    namespaces: LazySchemas<RestNamespace>,
+}
+
+impl Debug for RestCatalog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RestCatalog")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl RestCatalog {
+    pub async fn try_new(catalog_config: &RestCatalogConfig) -> Result<Self, DataFusionError> {
+        let rest_config = match &catalog_config.warehouse {
+            Some(warehouse) => IcebergRestCatalogConfig::builder()
+                .uri(catalog_config.uri.clone())
+                .warehouse(warehouse.clone())
+                .props(catalog_config.build_rest_properties())
+                .build(),
+            None => IcebergRestCatalogConfig::builder()
+                .uri(catalog_config.uri.clone())
+                .props(catalog_config.build_rest_properties())
+                .build(),
+        };
+        let rest_client = Arc::new(iceberg_catalog_rest::RestCatalog::new(rest_config));
+
+        // only list namespace names up front, tables are resolved on demand
+        let catalog = RestCatalog {
+            config: catalog_config.clone(),
+            rest_client,
+            namespaces: LazySchemas::new(catalog_config.cache.ttl()),
+        };
+        let names = list_namespace_names(&catalog.rest_client).await?;
+        catalog.namespaces.set_names(names, |name| catalog.new_namespace(name))?;
+        Ok(catalog)
+    }
+
+    fn new_namespace(&self, name: &str) -> Result<RestNamespace, DataFusionError> {
+        RestNamespace::try_new(&self.rest_client, name, &self.config)
+    }
+}
+
+/// Lists the top level namespaces, nested ones are still reachable by their full name.
+async fn list_namespace_names(
+    rest_client: &iceberg_catalog_rest::RestCatalog,
+) -> Result<Vec<String>, DataFusionError> {
+    let namespaces = rest_client
+        .list_namespaces(None)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(namespaces
+        .iter()
+        .map(|namespace| namespace.join(NAMESPACE_SEPARATOR))
+        .collect())
+}
+
+impl CatalogProvider for RestCatalog {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        self.namespaces.names()
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        Some(self.namespaces.schema(name)?)
+    }
+}
+
+#[async_trait]
+impl DobbyCatalogProvider for RestCatalog {
+    async fn refresh(&self, with_table_names: bool) {
+        self.namespaces
+            .refresh_names(
+                format!("rest catalog {}", self.config.name),
+                list_namespace_names(&self.rest_client),
+                |name| self.new_namespace(name),
+            )
+            .await;
+        if with_table_names {
+            self.namespaces.refresh_table_names().await;
+        }
+    }
+
+    /// Nested namespaces are not listed, so they are looked up by name.
+    async fn refresh_schema(&self, name: &str, with_table_names: bool) {
+        self.namespaces
+            .refresh_schema(
+                format!("rest catalog {}", self.config.name),
+                name,
+                RestNamespace::exists(&self.rest_client, name),
+                |name| self.new_namespace(name),
+                with_table_names,
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+
+    #[tokio::test]
+    async fn test_list_namespaces_and_tables() -> Result<(), DataFusionError> {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/v1/config")
+            .with_body(r#"{"defaults": {}, "overrides": {}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/namespaces")
+            .with_body(r#"{"namespaces": [["sales"]]}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/v1/namespaces/sales/tables")
+            .with_body(r#"{"identifiers": [{"namespace": ["sales"], "name": "orders"}]}"#)
+            .create_async()
+            .await;
+
+        let config: RestCatalogConfig =
+            toml::from_str(&format!("name = \"rest\"\nuri = \"{}\"", server.url()))
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let catalog = RestCatalog::try_new(&config).await?;
+        assert_eq!(catalog.schema_names(), vec!["sales".to_string()]);
+        let schema = catalog.schema("sales").expect("namespace sales");
+        catalog.refresh(true).await;
+        assert_eq!(schema.table_names(), vec!["orders".to_string()]);
+        assert!(catalog.schema("missing").is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::catalog_config::RestCatalogConfig;
+use crate::rest_catalog::NAMESPACE_SEPARATOR;
+use crate::lazy_schema::{LazySchema, LazyTables};
+use crate::table_format::iceberg_table::IcebergTable;
+use crate::table_format::table::lookup_table;
+use async_trait::async_trait;
+use datafusion::catalog::{SchemaProvider, TableProvider};
+use datafusion::error::DataFusionError;
+use iceberg::{Catalog, NamespaceIdent, TableIdent};
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+pub struct RestNamespace {
+    rest_client: Arc<iceberg_catalog_rest::RestCatalog>,
+    namespace: NamespaceIdent,
+    /// Table names and tables, listed and loaded on demand
+    tables: LazyTables<Arc<IcebergTable>>,
+}
+
+impl Debug for RestNamespace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RestNamespace")
+            .field("namespace", &self.namespace)
+            .finish()
+    }
+}
+
+impl RestNamespace {
+    /// Creates the namespace without talking to the catalog, tables are listed and
+    /// loaded on demand.
+    pub fn try_new(
+        rest_client: &Arc<iceberg_catalog_rest::RestCatalog>,
+        namespace_name: &str,
+        rest_config: &RestCatalogConfig,
+    ) -> Result<Self, DataFusionError> {
+        Ok(RestNamespace {
+            rest_client: rest_client.clone(),
+            namespace: to_namespace_ident(namespace_name)?,
+            tables: LazyTables::new(rest_config.cache.ttl()),
+        })
+    }
+
+    pub async fn exists(
+        rest_client: &iceberg_catalog_rest::RestCatalog,
+        namespace_name: &str,
+    ) -> Result<bool, DataFusionError> {
+        rest_client
+            .namespace_exists(&to_namespace_ident(namespace_name)?)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+
+    async fn list_table_names(&self) -> Result<Vec<String>, DataFusionError> {
+        let tables = self
+            .rest_client
+            .list_tables(&self.namespace)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(tables.into_iter().map(|table| table.name).collect())
+    }
+
+    async fn load_table(&self, name: &str) -> Result<Option<IcebergTable>, DataFusionError> {
+        let table_ident = TableIdent::new(self.namespace.clone(), name.to_string());
+        let exists = self
+            .rest_client
+            .table_exists(&table_ident)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        if !exists {
+            return Ok(None);
+        }
+        let table = self
+            .rest_client
+            .load_table(&table_ident)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(Some(IcebergTable::try_new(table).await?))
+    }
+}
+
+fn to_namespace_ident(namespace_name: &str) -> Result<NamespaceIdent, DataFusionError> {
+    NamespaceIdent::from_strs(namespace_name.split(NAMESPACE_SEPARATOR))
+        .map_err(|e| DataFusionError::External(Box::new(e)))
+}
+
+#[async_trait]
+impl SchemaProvider for RestNamespace {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.tables.table_names()
+    }
+
+    async fn table(
+        &self,
+        name: &str,
+    ) -> datafusion::common::Result<Option<Arc<dyn TableProvider>>, DataFusionError> {
+        lookup_table(name, |table_name| async move {
+            let load = async { Ok(self.load_table(table_name).await?.map(Arc::new)) };
+            let table = self.tables.table(table_name, load).await?;
+            Ok(table.map(|table| table as Arc<dyn TableProvider>))
+        })
+        .await
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.table_names().iter().any(|n| n == name)
+    }
+}
+
+#[async_trait]
+impl LazySchema for RestNamespace {
+    async fn refresh_table_names(&self) {
+        self.tables
+            .refresh_names(format!("rest namespace {}", self.namespace), self.list_table_names())
+            .await;
+    }
+}
@@ -0,0 +1,93 @@
+use crate::catalog_config::{DobbyCatalogConfig, SqlCatalogConfig};
+use crate::lazy_schema::{LazySchema, LazyTables};
+use crate::sql_catalog::SqlCatalogStore;
+use crate::table_format::external_table::ExternalIcebergTable;
+use crate::table_format::iceberg_table::IcebergTable;
+use crate::table_format::table::{lookup_table, TableIdentifier};
+use async_trait::async_trait;
+use datafusion::catalog::{SchemaProvider, TableProvider};
+use datafusion::error::DataFusionError;
+use std::any::Any;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct SqlNamespace {
+    store: Arc<SqlCatalogStore>,
+    namespace_name: String,
+    config: SqlCatalogConfig,
+    /// Table names and tables, listed and loaded on demand
+    tables: LazyTables<Arc<IcebergTable>>,
+}
+
+impl SqlNamespace {
+    /// Creates the namespace without querying the catalog database, tables are listed
+    /// and loaded on demand.
+    pub fn new(
+        store: &Arc<SqlCatalogStore>,
+        namespace_name: &str,
+        config: &SqlCatalogConfig,
+    ) -> Self {
+        SqlNamespace {
+            store: store.clone(),
+            namespace_name: namespace_name.to_string(),
+            config: config.clone(),
+            tables: LazyTables::new(config.cache.ttl()),
+        }
+    }
+
+    async fn load_table(&self, name: &str) -> Result<Option<IcebergTable>, DataFusionError> {
+        let Some(metadata_location) = self
+            .store
+            .metadata_location(&self.namespace_name, name)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let table = ExternalIcebergTable::try_new(
+            &TableIdentifier::new(&self.namespace_name, name),
+            &metadata_location,
+            self.config.build_iceberg_file_io_parameters(),
+        )
+        .await?;
+        Ok(Some(IcebergTable::try_from_external(table)?))
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for SqlNamespace {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.tables.table_names()
+    }
+
+    async fn table(
+        &self,
+        name: &str,
+    ) -> datafusion::common::Result<Option<Arc<dyn TableProvider>>, DataFusionError> {
+        lookup_table(name, |table_name| async move {
+            let load = async { Ok(self.load_table(table_name).await?.map(Arc::new)) };
+            let table = self.tables.table(table_name, load).await?;
+            Ok(table.map(|table| table as Arc<dyn TableProvider>))
+        })
+        .await
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.table_names().iter().any(|n| n == name)
+    }
+}
+
+#[async_trait]
+impl LazySchema for SqlNamespace {
+    async fn refresh_table_names(&self) {
+        self.tables
+            .refresh_names(
+                format!("namespace {}", self.namespace_name),
+                self.store.list_tables(&self.namespace_name),
+            )
+            .await;
+    }
+}
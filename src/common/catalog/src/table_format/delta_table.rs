@@ -0,0 +1,199 @@
+use crate::table_format::table::TableVersion;
+use chrono::DateTime;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::catalog::TableProvider;
+use datafusion::common::not_impl_err;
+use datafusion::error::DataFusionError;
+use deltalake::{DeltaTable, DeltaTableBuilder};
+use object_store::ObjectStore;
+use std::sync::Arc;
+use url::Url;
+
+/// Table parameter Spark sets to the data source of tables it registers, `delta` for
+/// Delta Lake tables.
+pub const SPARK_SOURCES_PROVIDER: &str = "spark.sql.sources.provider";
+
+/// A Delta Lake table, read through delta-rs: it replays the log from the last
+/// checkpoint plus the JSON commits after it, and prunes files by partition values and
+/// file statistics. Tables whose protocol needs reader features delta-rs lacks, e.g.
+/// deletion vectors on older delta-rs, fail to load with a protocol error.
+#[derive(Debug, Clone)]
+pub struct ExternalDeltaTable {
+    pub delta_table: DeltaTable,
+    location: Url,
+    /// Store rooted at the bucket of the table, shared with the other table formats
+    object_store: Arc<dyn ObjectStore>,
+}
+
+impl ExternalDeltaTable {
+    /// Loads the latest version of the table at `location`, reading its files through
+    /// `object_store`.
+    pub async fn try_new(
+        location: Url,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> Result<Self, DataFusionError> {
+        Self::load(location, object_store, None).await
+    }
+
+    /// Loads the table as of `version`, a Delta version number or a timestamp.
+    pub async fn with_version(&self, version: &TableVersion) -> Result<Self, DataFusionError> {
+        Self::load(
+            self.location.clone(),
+            self.object_store.clone(),
+            Some(version),
+        )
+        .await
+    }
+
+    async fn load(
+        location: Url,
+        object_store: Arc<dyn ObjectStore>,
+        version: Option<&TableVersion>,
+    ) -> Result<Self, DataFusionError> {
+        let builder = DeltaTableBuilder::from_uri(location.as_str())
+            .with_storage_backend(object_store.clone(), location.clone());
+        let builder = match version {
+            None => builder,
+            Some(TableVersion::SnapshotId(version)) => builder.with_version(*version),
+            Some(TableVersion::Timestamp(timestamp_ms)) => {
+                let timestamp =
+                    DateTime::from_timestamp_millis(*timestamp_ms).ok_or_else(|| {
+                        DataFusionError::Plan(format!("invalid timestamp {timestamp_ms}"))
+                    })?;
+                builder.with_timestamp(timestamp)
+            }
+            Some(TableVersion::Ref(name)) => {
+                return not_impl_err!("delta tables have no branches or tags, got {name}");
+            }
+            Some(TableVersion::Incremental(_, _)) => {
+                return not_impl_err!("incremental reads are not supported for delta tables");
+            }
+        };
+        let delta_table = builder
+            .load()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(ExternalDeltaTable {
+            delta_table,
+            location,
+            object_store,
+        })
+    }
+
+    /// Data columns followed by partition columns.
+    pub fn schema(&self) -> SchemaRef {
+        TableProvider::schema(&self.delta_table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{AsArray, Int64Array, RecordBatch, StringArray};
+    use datafusion::arrow::datatypes::{DataType as ArrowDataType, Field, Int64Type, Schema};
+    use datafusion::common::Result as DFResult;
+    use datafusion::physical_plan::{collect, displayable};
+    use datafusion::prelude::{SessionContext, col, lit};
+    use deltalake::DeltaOps;
+    use deltalake::checkpoints::create_checkpoint;
+    use deltalake::kernel::{DataType, StructField};
+    use object_store::local::LocalFileSystem;
+    use std::fs;
+
+    fn to_datafusion_error(e: deltalake::DeltaTableError) -> DataFusionError {
+        DataFusionError::External(Box::new(e))
+    }
+
+    async fn write(table: DeltaTable, rows: Vec<(i64, &str)>) -> DFResult<DeltaTable> {
+        let schema = Schema::new(vec![
+            Field::new("id", ArrowDataType::Int64, false),
+            Field::new("region", ArrowDataType::Utf8, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from_iter_values(rows.iter().map(|(id, _)| *id))),
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|(_, region)| *region),
+                )),
+            ],
+        )?;
+        DeltaOps(table)
+            .write(vec![batch])
+            .await
+            .map_err(to_datafusion_error)
+    }
+
+    /// Scans `table` with `filters`, returns the plan and the sorted ids read.
+    async fn scan(
+        table: &ExternalDeltaTable,
+        filters: &[datafusion::prelude::Expr],
+    ) -> DFResult<(String, Vec<i64>)> {
+        let ctx = SessionContext::new();
+        let plan = table
+            .delta_table
+            .scan(&ctx.state(), None, filters, None)
+            .await?;
+        let display = displayable(plan.as_ref()).indent(true).to_string();
+        let mut ids = collect(plan, ctx.task_ctx())
+            .await?
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<i64>>();
+        ids.sort();
+        Ok((display, ids))
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_pruning_and_time_travel() -> DFResult<()> {
+        let table_dir = std::env::temp_dir().join(format!("dobbydb-delta-{}", std::process::id()));
+        fs::create_dir_all(&table_dir)?;
+        let location = Url::from_directory_path(&table_dir)
+            .map_err(|_| DataFusionError::Plan(format!("invalid table directory {table_dir:?}")))?;
+
+        let table = DeltaOps::try_from_uri(location.as_str())
+            .await
+            .map_err(to_datafusion_error)?
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DataType::LONG, false),
+                StructField::new("region", DataType::STRING, true),
+            ])
+            .with_partition_columns(["region"])
+            .await
+            .map_err(to_datafusion_error)?;
+        let table = write(table, vec![(1, "eu"), (2, "us")]).await?;
+        create_checkpoint(&table, None)
+            .await
+            .map_err(to_datafusion_error)?;
+        write(table, vec![(3, "eu")]).await?;
+        // commits before a checkpoint may be cleaned up, the log is replayed from it
+        let log_dir = table_dir.join("_delta_log");
+        assert!(
+            log_dir
+                .join("00000000000000000001.checkpoint.parquet")
+                .exists()
+        );
+        fs::remove_file(log_dir.join("00000000000000000000.json"))?;
+
+        let table = ExternalDeltaTable::try_new(location, Arc::new(LocalFileSystem::new())).await?;
+        assert_eq!(table.delta_table.version(), Some(2));
+        let (plan, ids) = scan(&table, &[col("region").eq(lit("eu"))]).await?;
+        assert!(plan.contains("region=eu"), "{plan}");
+        assert!(!plan.contains("region=us"), "{plan}");
+        assert_eq!(ids, vec![1, 3]);
+
+        let as_of = table.with_version(&TableVersion::SnapshotId(1)).await?;
+        assert_eq!(as_of.delta_table.version(), Some(1));
+        assert_eq!(scan(&as_of, &[]).await?.1, vec![1, 2]);
+
+        fs::remove_dir_all(&table_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+use datafusion::arrow::datatypes::{DataType, Schema};
+use datafusion::common::{Column, ScalarValue};
+use datafusion::logical_expr::expr::InList;
+use datafusion::logical_expr::{Between, BinaryExpr, Expr, Like, Operator};
+use iceberg::expr::{Predicate, Reference};
+use iceberg::spec::Datum;
+
+/// Converts DataFusion filters into a single Iceberg [`Predicate`] by `AND`ing
+/// every filter that can be (at least partially) translated.
+///
+/// Returns `None` if none of the filters can be translated.
+pub fn convert_filters_to_predicate(filters: &[Expr], schema: &Schema) -> Option<Predicate> {
+    filters
+        .iter()
+        .filter_map(|filter| convert_filter_to_predicate(filter, schema))
+        .reduce(Predicate::and)
+}
+
+/// Converts one DataFusion filter into an Iceberg [`Predicate`].
+///
+/// The returned predicate never filters out more rows than `filter` does, but it
+/// may filter out fewer: untranslatable conjuncts of an `AND` are dropped. Callers
+/// must therefore still evaluate `filter` on the scanned rows.
+pub fn convert_filter_to_predicate(filter: &Expr, schema: &Schema) -> Option<Predicate> {
+    to_predicate(filter, schema, true)
+}
+
+/// `allow_partial` controls whether untranslatable conjuncts may be dropped. This is
+/// not allowed below a `NOT`, as negating a weaker predicate produces a stronger one.
+fn to_predicate(expr: &Expr, schema: &Schema, allow_partial: bool) -> Option<Predicate> {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
+            Operator::And => {
+                let left = to_predicate(left, schema, allow_partial);
+                let right = to_predicate(right, schema, allow_partial);
+                match (left, right) {
+                    (Some(left), Some(right)) => Some(left.and(right)),
+                    (Some(left), None) if allow_partial => Some(left),
+                    (None, Some(right)) if allow_partial => Some(right),
+                    _ => None,
+                }
+            }
+            Operator::Or => {
+                let left = to_predicate(left, schema, allow_partial)?;
+                let right = to_predicate(right, schema, allow_partial)?;
+                Some(left.or(right))
+            }
+            _ => comparison_to_predicate(left, *op, right, schema),
+        },
+        Expr::Not(inner) => to_predicate(inner, schema, false).map(|p| p.negate()),
+        Expr::IsNull(inner) => {
+            let (reference, _) = to_reference(inner, schema)?;
+            Some(reference.is_null())
+        }
+        Expr::IsNotNull(inner) => {
+            let (reference, _) = to_reference(inner, schema)?;
+            Some(reference.is_not_null())
+        }
+        Expr::InList(InList {
+            expr,
+            list,
+            negated,
+        }) => {
+            let (reference, data_type) = to_reference(expr, schema)?;
+            let datums = list
+                .iter()
+                .map(|e| to_datum(e, &data_type))
+                .collect::<Option<Vec<Datum>>>()?;
+            if *negated {
+                Some(reference.is_not_in(datums))
+            } else {
+                Some(reference.is_in(datums))
+            }
+        }
+        Expr::Between(Between {
+            expr,
+            negated,
+            low,
+            high,
+        }) => {
+            let (reference, data_type) = to_reference(expr, schema)?;
+            let low = to_datum(low, &data_type)?;
+            let high = to_datum(high, &data_type)?;
+            let predicate = reference
+                .clone()
+                .greater_than_or_equal_to(low)
+                .and(reference.less_than_or_equal_to(high));
+            if *negated {
+                Some(predicate.negate())
+            } else {
+                Some(predicate)
+            }
+        }
+        Expr::Like(like) => like_to_predicate(like, schema),
+        Expr::Column(_) => {
+            // boolean column used directly as a filter
+            let (reference, data_type) = to_reference(expr, schema)?;
+            if data_type == DataType::Boolean {
+                Some(reference.equal_to(Datum::bool(true)))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn comparison_to_predicate(
+    left: &Expr,
+    op: Operator,
+    right: &Expr,
+    schema: &Schema,
+) -> Option<Predicate> {
+    // normalize to `column op literal`
+    let (column, op, literal) = match (to_reference(left, schema), to_reference(right, schema)) {
+        (Some(column), None) => (column, op, right),
+        (None, Some(column)) => (column, op.swap()?, left),
+        _ => return None,
+    };
+    let (reference, data_type) = column;
+    let datum = to_datum(literal, &data_type)?;
+    match op {
+        Operator::Eq => Some(reference.equal_to(datum)),
+        Operator::NotEq => Some(reference.not_equal_to(datum)),
+        Operator::Lt => Some(reference.less_than(datum)),
+        Operator::LtEq => Some(reference.less_than_or_equal_to(datum)),
+        Operator::Gt => Some(reference.greater_than(datum)),
+        Operator::GtEq => Some(reference.greater_than_or_equal_to(datum)),
+        _ => None,
+    }
+}
+
+/// Only `LIKE 'prefix%'` and `LIKE 'literal'` are translated, everything else has no
+/// Iceberg equivalent.
+fn like_to_predicate(like: &Like, schema: &Schema) -> Option<Predicate> {
+    if like.case_insensitive || like.escape_char.is_some() {
+        return None;
+    }
+    let (reference, data_type) = to_reference(&like.expr, schema)?;
+    if !matches!(
+        data_type,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+    ) {
+        return None;
+    }
+    let pattern = match like.pattern.as_ref() {
+        Expr::Literal(ScalarValue::Utf8(Some(pattern)), _)
+        | Expr::Literal(ScalarValue::LargeUtf8(Some(pattern)), _)
+        | Expr::Literal(ScalarValue::Utf8View(Some(pattern)), _) => pattern,
+        _ => return None,
+    };
+    let has_wildcard = |s: &str| s.contains(['%', '_', '\\']);
+    let predicate = if !has_wildcard(pattern) {
+        reference.equal_to(Datum::string(pattern))
+    } else {
+        let prefix = pattern.strip_suffix('%')?;
+        if prefix.is_empty() || has_wildcard(prefix) {
+            return None;
+        }
+        reference.starts_with(Datum::string(prefix))
+    };
+    if like.negated {
+        Some(predicate.negate())
+    } else {
+        Some(predicate)
+    }
+}
+
+fn to_reference(expr: &Expr, schema: &Schema) -> Option<(Reference, DataType)> {
+    match expr {
+        Expr::Column(Column { name, .. }) => {
+            let field = schema.field_with_name(name).ok()?;
+            Some((Reference::new(name.clone()), field.data_type().clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Converts a literal into a [`Datum`] matching the column type, `None` if the
+/// literal can't be represented losslessly.
+fn to_datum(expr: &Expr, data_type: &DataType) -> Option<Datum> {
+    let value = match expr {
+        Expr::Literal(value, _) => value,
+        _ => return None,
+    };
+    if value.is_null() {
+        return None;
+    }
+    let value = if &value.data_type() == data_type {
+        value.clone()
+    } else {
+        let casted = value.cast_to(data_type).ok()?;
+        // reject lossy casts, e.g. 1.5 -> 1
+        if casted.cast_to(&value.data_type()).ok()? != *value {
+            return None;
+        }
+        casted
+    };
+    match value {
+        ScalarValue::Boolean(Some(v)) => Some(Datum::bool(v)),
+        ScalarValue::Int8(Some(v)) => Some(Datum::int(v as i32)),
+        ScalarValue::Int16(Some(v)) => Some(Datum::int(v as i32)),
+        ScalarValue::Int32(Some(v)) => Some(Datum::int(v)),
+        ScalarValue::Int64(Some(v)) => Some(Datum::long(v)),
+        ScalarValue::Float32(Some(v)) => Some(Datum::float(v)),
+        ScalarValue::Float64(Some(v)) => Some(Datum::double(v)),
+        ScalarValue::Utf8(Some(v))
+        | ScalarValue::LargeUtf8(Some(v))
+        | ScalarValue::Utf8View(Some(v)) => Some(Datum::string(v)),
+        ScalarValue::Date32(Some(v)) => Some(Datum::date(v)),
+        ScalarValue::TimestampMicrosecond(Some(v), tz) => match tz {
+            Some(_) => Some(Datum::timestamptz_micros(v)),
+            None => Some(Datum::timestamp_micros(v)),
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::Field;
+    use datafusion::prelude::{col, lit};
+
+    fn test_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ])
+    }
+
+    #[test]
+    fn test_convert_comparison() {
+        let schema = test_schema();
+        let predicate = convert_filter_to_predicate(&col("id").gt(lit(10i64)), &schema).unwrap();
+        assert_eq!(predicate, Reference::new("id").greater_than(Datum::long(10)));
+
+        // literal on the left side
+        let predicate = convert_filter_to_predicate(&lit(10i64).lt(col("id")), &schema).unwrap();
+        assert_eq!(predicate, Reference::new("id").greater_than(Datum::long(10)));
+    }
+
+    #[test]
+    fn test_convert_partial_and() {
+        let schema = test_schema();
+        let filter = col("id")
+            .eq(lit(1i64))
+            .and(col("name").ilike(lit("a%")));
+        let predicate = convert_filter_to_predicate(&filter, &schema).unwrap();
+        assert_eq!(predicate, Reference::new("id").equal_to(Datum::long(1)));
+
+        // partial conversion isn't allowed below NOT
+        let filter = Expr::Not(Box::new(filter));
+        assert!(convert_filter_to_predicate(&filter, &schema).is_none());
+    }
+
+    #[test]
+    fn test_convert_like_prefix() {
+        let schema = test_schema();
+        let predicate =
+            convert_filter_to_predicate(&col("name").like(lit("abc%")), &schema).unwrap();
+        assert_eq!(
+            predicate,
+            Reference::new("name").starts_with(Datum::string("abc"))
+        );
+        assert!(convert_filter_to_predicate(&col("name").like(lit("a%c")), &schema).is_none());
+    }
+}
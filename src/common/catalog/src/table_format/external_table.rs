@@ -1,42 +1,436 @@
 use std::any::Any;
-use crate::table_format::table::TableIdentifier;
+use crate::table_format::table::{TableIdentifier, TableVersion};
+use iceberg::arrow::schema_to_arrow_schema;
 use iceberg::io::FileIO;
+use iceberg::scan::{FileScanTask, FileScanTaskStream};
 use iceberg::table::{StaticTable, Table};
 use iceberg::{Error, NamespaceIdent, TableIdent};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::pin::Pin;
 use std::sync::Arc;
-use datafusion::arrow::datatypes::Schema;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::common::{Column, DFSchema, ScalarValue, Statistics};
+use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::json::JsonFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{ListingTableUrl, PartitionedFile};
+use datafusion::datasource::physical_plan::{FileGroup, FileScanConfigBuilder};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::DataFusionError;
 use datafusion::execution::TaskContext;
-use datafusion::logical_expr::Expr;
+use datafusion::logical_expr::{Expr, lit, try_cast, when};
 use datafusion::physical_expr::{EquivalenceProperties, Partitioning};
 use datafusion::physical_plan::{DisplayAs, ExecutionPlan, PlanProperties, SendableRecordBatchStream};
 use datafusion::physical_plan::execution_plan::{Boundedness, EmissionType};
+use datafusion::physical_plan::limit::LimitStream;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
-use futures::{Stream, TryStreamExt};
+use futures::{Stream, StreamExt, TryStreamExt};
 use iceberg::expr::Predicate;
+use iceberg::spec::ManifestContentType;
 use datafusion::error::Result as DFResult;
+use crate::table_format::expr_to_predicate::convert_filters_to_predicate;
+use crate::table_format::hive_type::hive_type_to_arrow;
+use crate::table_format::partition_pruning::prune_partitions;
+use object_store::{ObjectMeta, ObjectStore};
+use dobbydb_common_base::config_key::ICEBERG_METADATA_LOCATION;
+use crate::table_format::delta_table::{ExternalDeltaTable, SPARK_SOURCES_PROVIDER};
+use crate::table_format::hudi_table::ExternalHudiTable;
+use crate::table_format::iceberg_table::IcebergTable;
+use crate::table_format::orc_format::OrcFormat;
+use crate::table_format::iceberg_deletes::{resolve_deletes, ScanDeletes, MANIFEST_CONCURRENCY};
+use crate::table_format::iceberg_dml::{FILE_PATH_COLUMN, POSITION_COLUMN};
+use crate::table_format::iceberg_statistics::ScanStatistics;
 
 #[derive(Eq, Debug, Clone, PartialEq)]
 pub enum ExternalTableFormat {
     Hive,
-    Iceberg
+    Iceberg,
+    Delta,
+    Hudi,
 }
-#[derive(Debug)]
+/// Deduces the table format from the table parameters, input format and storage location
+/// of a metastore (Glue, HMS) table, returning it with the location to load the table
+/// from: the metadata file for Iceberg, the table directory for Delta, Hudi and Hive.
+pub fn deduce_table_format(
+    table_name: &str,
+    parameters: Option<&HashMap<String, String>>,
+    input_format: Option<&str>,
+    location: Option<&str>,
+) -> Result<(ExternalTableFormat, String), DataFusionError> {
+    if let Some(metadata) = parameters.and_then(|parameters| parameters.get(ICEBERG_METADATA_LOCATION)) {
+        return Ok((ExternalTableFormat::Iceberg, metadata.clone()));
+    }
+    let Some(location) = location.filter(|location| !location.is_empty()) else {
+        return Err(DataFusionError::Configuration(format!(
+            "invalid table parameters of {}, neither {} nor a storage location is set",
+            table_name, ICEBERG_METADATA_LOCATION
+        )));
+    };
+    let is_delta = parameters
+        .and_then(|parameters| parameters.get(SPARK_SOURCES_PROVIDER))
+        .is_some_and(|provider| provider.eq_ignore_ascii_case("delta"));
+    if is_delta {
+        return Ok((ExternalTableFormat::Delta, location.to_string()));
+    }
+    // Spark registers hudi tables with the `hudi` provider, Hudi's hive sync with its own
+    // input formats (`org.apache.hudi.hadoop.HoodieParquetInputFormat`)
+    let is_hudi = parameters
+        .and_then(|parameters| parameters.get(SPARK_SOURCES_PROVIDER))
+        .is_some_and(|provider| provider.eq_ignore_ascii_case("hudi"))
+        || input_format.is_some_and(|input_format| {
+            let input_format = input_format.to_ascii_lowercase();
+            input_format.contains("hudi") || input_format.contains("hoodie")
+        });
+    if is_hudi {
+        return Ok((ExternalTableFormat::Hudi, location.to_string()));
+    }
+    Ok((ExternalTableFormat::Hive, location.to_string()))
+}
+
+#[derive(Debug, Clone)]
 pub enum ExternalTable {
     Hive(ExternalHiveTable),
-    Iceberg(ExternalIcebergTable),
+    Iceberg(IcebergTable),
+    Delta(Box<ExternalDeltaTable>),
+    Hudi(ExternalHudiTable),
     Invalid,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HiveFileFormat {
+    Parquet,
+    Orc,
+    Csv,
+    Json,
+}
+
+impl HiveFileFormat {
+    /// Deduces the file format from the input format and serde library of a Hive table.
+    pub fn detect(
+        input_format: Option<&str>,
+        serde_library: Option<&str>,
+    ) -> Result<Self, DataFusionError> {
+        let input_format = input_format.unwrap_or_default().to_ascii_lowercase();
+        let serde_library = serde_library.unwrap_or_default().to_ascii_lowercase();
+        if input_format.contains("parquet") || serde_library.contains("parquet") {
+            Ok(HiveFileFormat::Parquet)
+        } else if input_format.contains("orc") || serde_library.contains("orc") {
+            Ok(HiveFileFormat::Orc)
+        } else if serde_library.contains("json") {
+            Ok(HiveFileFormat::Json)
+        } else if input_format.contains("textinputformat")
+            || serde_library.contains("lazysimpleserde")
+            || serde_library.contains("opencsvserde")
+        {
+            Ok(HiveFileFormat::Csv)
+        } else {
+            Err(DataFusionError::NotImplemented(format!(
+                "unsupported hive table format, input format: {input_format}, serde: {serde_library}"
+            )))
+        }
+    }
+}
+
+/// Catalog independent description of how a Hive table is stored.
+#[derive(Debug, Clone, Default)]
+pub struct HiveStorageDescriptor {
+    pub location: String,
+    pub input_format: Option<String>,
+    pub serde_library: Option<String>,
+    pub serde_parameters: HashMap<String, String>,
+    pub table_parameters: HashMap<String, String>,
+    /// `(name, hive type)` of the columns stored in the data files
+    pub columns: Vec<(String, String)>,
+    /// `(name, hive type)` of the partition columns
+    pub partition_keys: Vec<(String, String)>,
+}
+
+/// A partition of a Hive table.
+#[derive(Debug, Clone)]
+pub struct HivePartition {
+    /// Partition values, in the order of the table's partition columns
+    pub values: Vec<String>,
+    pub location: String,
+}
+
+/// Value Hive uses for null partition values.
+const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+#[derive(Debug, Clone)]
 pub struct ExternalHiveTable {
+    pub location: String,
+    pub file_format: HiveFileFormat,
+    /// Field delimiter of text tables
+    field_delimiter: u8,
+    /// Quote character of text tables
+    quote: u8,
+    /// Escape character of text tables, if any
+    escape: Option<u8>,
+    /// Text read as null in the files of text tables, `\N` unless the table sets
+    /// `serialization.null.format`; only LazySimpleSerDe tables have one
+    null_format: Option<String>,
+    /// Whether text files start with a header line
+    has_header: bool,
+    /// Columns stored in the data files
+    pub data_columns: Vec<Field>,
+    /// Columns derived from the partitions, they follow the data columns in the table schema
+    pub partition_columns: Vec<Field>,
+    object_store: Arc<dyn ObjectStore>,
+}
 
+impl ExternalHiveTable {
+    pub fn try_new(
+        descriptor: HiveStorageDescriptor,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> Result<Self, DataFusionError> {
+        let file_format = HiveFileFormat::detect(
+            descriptor.input_format.as_deref(),
+            descriptor.serde_library.as_deref(),
+        )?;
+        let to_fields = |columns: &[(String, String)]| {
+            columns
+                .iter()
+                .map(|(name, hive_type)| Ok(Field::new(name, hive_type_to_arrow(hive_type)?, true)))
+                .collect::<Result<Vec<Field>, DataFusionError>>()
+        };
+
+        let is_open_csv = descriptor
+            .serde_library
+            .as_deref()
+            .is_some_and(|s| s.to_ascii_lowercase().contains("opencsvserde"));
+        let field_delimiter = descriptor
+            .serde_parameters
+            .get("field.delim")
+            .or_else(|| descriptor.serde_parameters.get("separatorChar"))
+            .and_then(|d| d.bytes().next())
+            .unwrap_or(if is_open_csv { b',' } else { b'\x01' });
+        let serde_char = |key: &str| {
+            descriptor
+                .serde_parameters
+                .get(key)
+                .and_then(|c| c.bytes().next())
+        };
+        // LazySimpleSerDe does not quote fields, NUL never occurs in text data
+        let quote = if is_open_csv { serde_char("quoteChar").unwrap_or(b'"') } else { b'\0' };
+        let escape = if is_open_csv {
+            serde_char("escapeChar").or(Some(b'\\'))
+        } else {
+            serde_char("escape.delim")
+        };
+        let null_format = (!is_open_csv).then(|| {
+            descriptor
+                .serde_parameters
+                .get("serialization.null.format")
+                .cloned()
+                .unwrap_or_else(|| "\\N".to_string())
+        });
+        let has_header = descriptor
+            .table_parameters
+            .get("skip.header.line.count")
+            .is_some_and(|count| count.trim() != "0");
+
+        Ok(ExternalHiveTable {
+            location: descriptor.location,
+            file_format,
+            field_delimiter,
+            quote,
+            escape,
+            null_format,
+            has_header,
+            data_columns: to_fields(&descriptor.columns)?,
+            partition_columns: to_fields(&descriptor.partition_keys)?,
+            object_store,
+        })
+    }
+
+    /// Data columns followed by partition columns.
+    pub fn schema(&self) -> SchemaRef {
+        let mut fields = self.data_columns.clone();
+        fields.extend(self.partition_columns.iter().cloned());
+        Arc::new(Schema::new(fields))
+    }
+
+    /// Scans the data files of the given `partitions`, which are ignored for
+    /// unpartitioned tables.
+    pub async fn scan(
+        &self,
+        state: &dyn Session,
+        partitions: &[HivePartition],
+        projection: Option<&Vec<usize>>,
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let table_url = ListingTableUrl::parse(&self.location)?;
+        let object_store_url = table_url.object_store();
+        state
+            .runtime_env()
+            .register_object_store(object_store_url.as_ref(), self.object_store.clone());
+
+        let mut files: Vec<PartitionedFile> = Vec::new();
+        if self.partition_columns.is_empty() {
+            files.extend(self.list_files(&self.location, vec![]).await?);
+        } else {
+            for partition in partitions {
+                let values = self.partition_values(partition)?;
+                files.extend(self.list_files(&partition.location, values).await?);
+            }
+        }
+
+        let format = self.build_file_format()?;
+        let null_format = self.null_format.as_ref().filter(|_| self.file_format == HiveFileFormat::Csv);
+        // the null format is matched on the raw text, the columns are cast once it is replaced
+        let file_schema = match null_format {
+            Some(_) => Schema::new(
+                self.data_columns
+                    .iter()
+                    .map(|field| Field::new(field.name(), DataType::Utf8, true))
+                    .collect::<Vec<Field>>(),
+            ),
+            None => Schema::new(self.data_columns.clone()),
+        };
+        let config = FileScanConfigBuilder::new(object_store_url, Arc::new(file_schema), format.file_source())
+            .with_file_groups(split_files(files, state.config().target_partitions()))
+            .with_table_partition_cols(self.partition_columns.clone())
+            .with_projection(projection.cloned())
+            .with_limit(limit)
+            .build();
+        let plan = format.create_physical_plan(state, config).await?;
+        match null_format {
+            Some(null_format) => self.read_text_columns(state, plan, null_format),
+            None => Ok(plan),
+        }
+    }
+
+    /// Projects the text data columns of `plan` to their types, reading `null_format` and
+    /// values that do not parse as nulls, as LazySimpleSerDe does.
+    fn read_text_columns(
+        &self,
+        state: &dyn Session,
+        plan: Arc<dyn ExecutionPlan>,
+        null_format: &str,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let input_schema = plan.schema();
+        let df_schema = DFSchema::try_from(input_schema.as_ref().clone())?;
+        let exprs = input_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let column = Expr::Column(Column::new_unqualified(field.name()));
+                let expr = match self.data_columns.iter().find(|data| data.name() == field.name()) {
+                    Some(data) => when(
+                        column.clone().eq(lit(null_format)),
+                        lit(ScalarValue::try_from(data.data_type())?),
+                    )
+                    .otherwise(try_cast(column, data.data_type().clone()))?,
+                    None => column,
+                };
+                Ok((state.create_physical_expr(expr, &df_schema)?, field.name().clone()))
+            })
+            .collect::<DFResult<Vec<_>>>()?;
+        Ok(Arc::new(ProjectionExec::try_new(exprs, plan)?))
+    }
+
+    /// Keeps the partitions whose values may satisfy the partition `filters`.
+    pub fn prune_partitions(
+        &self,
+        partitions: Vec<HivePartition>,
+        filters: &[Expr],
+    ) -> DFResult<Vec<HivePartition>> {
+        let partition_values = partitions
+            .iter()
+            .map(|partition| self.partition_values(partition))
+            .collect::<DFResult<Vec<Vec<ScalarValue>>>>()?;
+        let matches = prune_partitions(&self.partition_columns, &partition_values, filters)?;
+        Ok(partitions
+            .into_iter()
+            .zip(matches)
+            .filter_map(|(partition, matched)| matched.then_some(partition))
+            .collect())
+    }
+
+    fn build_file_format(&self) -> DFResult<Arc<dyn FileFormat>> {
+        match self.file_format {
+            HiveFileFormat::Parquet => Ok(Arc::new(ParquetFormat::default())),
+            HiveFileFormat::Csv => Ok(Arc::new(
+                CsvFormat::default()
+                    .with_delimiter(self.field_delimiter)
+                    .with_quote(self.quote)
+                    .with_escape(self.escape)
+                    .with_has_header(self.has_header),
+            )),
+            HiveFileFormat::Json => Ok(Arc::new(JsonFormat::default())),
+            HiveFileFormat::Orc => Ok(Arc::new(OrcFormat)),
+        }
+    }
+
+    fn partition_values(&self, partition: &HivePartition) -> DFResult<Vec<ScalarValue>> {
+        if partition.values.len() != self.partition_columns.len() {
+            return Err(DataFusionError::Execution(format!(
+                "partition {} has {} values, expected {}",
+                partition.location,
+                partition.values.len(),
+                self.partition_columns.len()
+            )));
+        }
+        self.partition_columns
+            .iter()
+            .zip(&partition.values)
+            .map(|(field, value)| {
+                if value == HIVE_DEFAULT_PARTITION {
+                    ScalarValue::try_from(field.data_type())
+                } else {
+                    ScalarValue::try_from_string(value.clone(), field.data_type())
+                }
+            })
+            .collect()
+    }
+
+    /// Lists the data files under `location`, skipping hidden and temporary files.
+    async fn list_files(
+        &self,
+        location: &str,
+        partition_values: Vec<ScalarValue>,
+    ) -> DFResult<Vec<PartitionedFile>> {
+        let url = ListingTableUrl::parse(location)?;
+        let objects: Vec<ObjectMeta> = self
+            .object_store
+            .list(Some(url.prefix()))
+            .try_collect()
+            .await?;
+        Ok(objects
+            .into_iter()
+            .filter(|meta| !is_hidden_file(meta))
+            .map(|meta| {
+                let mut file = PartitionedFile::from(meta);
+                file.partition_values = partition_values.clone();
+                file
+            })
+            .collect())
+    }
+}
+
+fn is_hidden_file(meta: &ObjectMeta) -> bool {
+    let hidden_name = meta
+        .location
+        .filename()
+        .is_some_and(|name| name.starts_with(['_', '.']));
+    hidden_name || meta.location.as_ref().contains("/_temporary/")
 }
 
-#[derive(Debug)]
+/// Distributes `files` round-robin into at most `target_partitions` groups.
+pub(crate) fn split_files(files: Vec<PartitionedFile>, target_partitions: usize) -> Vec<FileGroup> {
+    let group_count = target_partitions.min(files.len()).max(1);
+    let mut groups: Vec<Vec<PartitionedFile>> = vec![Vec::new(); group_count];
+    for (idx, file) in files.into_iter().enumerate() {
+        groups[idx % group_count].push(file);
+    }
+    groups.into_iter().map(FileGroup::new).collect()
+}
+
+#[derive(Debug, Clone)]
 pub struct ExternalIcebergTable {
     pub static_table: StaticTable,
 }
@@ -68,9 +462,57 @@ impl ExternalIcebergTable {
             static_table: iceberg_table,
         })
     }
+
+    /// Wraps a table loaded by an Iceberg catalog, e.g. the REST catalog.
+    pub async fn from_table(table: Table) -> Result<Self, DataFusionError> {
+        let static_table = StaticTable::from_metadata(
+            table.metadata().clone(),
+            table.identifier().clone(),
+            table.file_io().clone(),
+        )
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(Self { static_table })
+    }
+
+    /// Returns the table, to scan it or read its files.
+    pub fn table(&self) -> Table {
+        self.static_table.clone().into_table()
+    }
+
+    /// Resolves `version` to a snapshot id and the arrow schema of that snapshot.
+    pub fn resolve_version(
+        &self,
+        version: &TableVersion,
+    ) -> Result<(i64, SchemaRef), DataFusionError> {
+        let metadata = self.static_table.metadata();
+        let snapshot = match version {
+            TableVersion::SnapshotId(snapshot_id) => metadata.snapshot_by_id(*snapshot_id),
+            TableVersion::Timestamp(timestamp_ms) => metadata
+                .history()
+                .iter()
+                .rfind(|log| log.timestamp_ms <= *timestamp_ms)
+                .and_then(|log| metadata.snapshot_by_id(log.snapshot_id)),
+            TableVersion::Ref(ref_name) => metadata.snapshot_for_ref(ref_name),
+            TableVersion::Incremental(_, _) => {
+                return Err(DataFusionError::NotImplemented(
+                    "incremental reads are not supported for iceberg tables".to_string(),
+                ));
+            }
+        }
+        .ok_or_else(|| DataFusionError::Plan(format!("no snapshot found for {version}")))?;
+
+        let iceberg_schema = snapshot
+            .schema_id()
+            .and_then(|schema_id| metadata.schema_by_id(schema_id))
+            .unwrap_or_else(|| metadata.current_schema());
+        let schema = schema_to_arrow_schema(iceberg_schema)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok((snapshot.snapshot_id(), Arc::new(schema)))
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IcebergTableScan {
     /// A table in the catalog.
     table: Table,
@@ -83,42 +525,126 @@ pub struct IcebergTableScan {
     projection: Option<Vec<String>>,
     /// Filters to apply to the table scan
     predicates: Option<Predicate>,
+    /// File scan tasks planned up front, one group per output partition
+    file_groups: Vec<Vec<FileScanTask>>,
+    /// Maximum number of groups the tasks are split into
+    target_partitions: usize,
+    /// Deletes of the planned tasks applied while reading them rather than by the reader
+    deletes: Arc<ScanDeletes>,
+    /// Whether the location of every row is appended as `_file` and `_pos` columns
+    row_positions: bool,
+    /// Number of rows in each [`RecordBatch`] produced by the scan
+    batch_size: usize,
+    /// Metrics of the planned data files, unknown statistics are reported without them
+    statistics: Option<Arc<ScanStatistics>>,
+    /// Maximum number of rows produced by each partition, None means all rows
+    limit: Option<usize>,
+    /// Execution metrics of the partitions
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl IcebergTableScan {
-    /// Creates a new [`IcebergTableScan`] object.
-    pub(crate) fn new(
+    /// Creates a new [`IcebergTableScan`] object, planning the files to scan and
+    /// splitting them into at most `target_partitions` groups.
+    ///
+    /// Without `filters`, planning stops once the record counts of the planned files add up
+    /// to the `limit`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn try_new(
         table: Table,
         snapshot_id: Option<i64>,
         schema: Arc<Schema>,
         projection: Option<&Vec<usize>>,
         filters: &[Expr],
-    ) -> Self {
+        limit: Option<usize>,
+        target_partitions: usize,
+        batch_size: usize,
+    ) -> DFResult<Self> {
         let output_schema = match projection {
             None => schema.clone(),
-            Some(projection) => Arc::new(schema.project(projection).unwrap()),
+            Some(projection) => Arc::new(schema.project(projection)?),
         };
-        let plan_properties = Self::compute_properties(output_schema.clone());
         let projection = get_column_names(schema.clone(), projection);
-        // let predicates = convert_filters_to_predicate(filters);
+        let predicates = convert_filters_to_predicate(filters, &schema);
 
-        Self {
+        let mut tasks = plan_file_scan_tasks(
+            &table,
+            snapshot_id,
+            projection.clone(),
+            predicates.clone(),
+            limit.filter(|_| filters.is_empty()),
+        )
+        .await?;
+        let deletes = resolve_deletes(&table, snapshot_id, &mut tasks).await?;
+        let file_groups = bin_pack_file_scan_tasks(tasks, target_partitions);
+        let plan_properties = Self::compute_properties(output_schema, file_groups.len());
+
+        Ok(Self {
             table,
             snapshot_id,
             plan_properties,
             projection,
-            predicates: None,
+            predicates,
+            file_groups,
+            target_partitions,
+            deletes: Arc::new(deletes),
+            row_positions: false,
+            batch_size,
+            statistics: None,
+            limit,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    /// Appends the data file path and position of every row as the [`FILE_PATH_COLUMN`] and
+    /// [`POSITION_COLUMN`] columns, for row-level changes.
+    pub(crate) fn with_row_positions(self) -> Self {
+        let mut fields = self.schema().fields().to_vec();
+        fields.push(Arc::new(Field::new(FILE_PATH_COLUMN, DataType::Utf8, false)));
+        fields.push(Arc::new(Field::new(POSITION_COLUMN, DataType::Int64, false)));
+        let partition_count = self.file_groups.len();
+        Self {
+            plan_properties: Self::compute_properties(Arc::new(Schema::new(fields)), partition_count),
+            row_positions: true,
+            ..self
         }
     }
 
+    /// Only reads the data files at the paths in `files`.
+    pub(crate) fn with_files(self, files: &HashSet<String>) -> Self {
+        let schema = self.schema();
+        let mut tasks = self.file_groups.into_iter().flatten().collect::<Vec<FileScanTask>>();
+        tasks.retain(|task| files.contains(&task.data_file_path));
+        let file_groups = bin_pack_file_scan_tasks(tasks, self.target_partitions);
+        Self {
+            plan_properties: Self::compute_properties(schema, file_groups.len()),
+            file_groups,
+            ..self
+        }
+    }
+
+    /// Reads the metrics of the planned data files from their manifests, to report the
+    /// statistics of the scan.
+    pub(crate) async fn with_statistics(self) -> DFResult<Self> {
+        let statistics = ScanStatistics::try_new(
+            &self.table,
+            self.snapshot_id,
+            &self.schema(),
+            self.file_groups.iter().flatten(),
+            self.predicates.is_some(),
+        )
+        .await?;
+        Ok(Self {
+            statistics: Some(Arc::new(statistics)),
+            ..self
+        })
+    }
+
     /// Computes [`PlanProperties`] used in query optimization.
-    fn compute_properties(schema: Arc<Schema>) -> PlanProperties {
-        // TODO:
-        // This is more or less a placeholder, to be replaced
-        // once we support output-partitioning
+    fn compute_properties(schema: Arc<Schema>, partition_count: usize) -> PlanProperties {
         PlanProperties::new(
             EquivalenceProperties::new(schema),
-            Partitioning::UnknownPartitioning(1),
+            Partitioning::UnknownPartitioning(partition_count),
             EmissionType::Incremental,
             Boundedness::Bounded,
         )
@@ -134,7 +660,7 @@ impl ExecutionPlan for IcebergTableScan {
         self
     }
 
-    fn children(&self) -> Vec<&Arc<(dyn ExecutionPlan + 'static)>> {
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
         vec![]
     }
 
@@ -149,23 +675,71 @@ impl ExecutionPlan for IcebergTableScan {
         &self.plan_properties
     }
 
+    fn partition_statistics(&self, partition: Option<usize>) -> DFResult<Statistics> {
+        let Some(statistics) = &self.statistics else {
+            return Ok(Statistics::new_unknown(&self.schema()));
+        };
+        let (statistics, partition_count) = match partition {
+            None => (
+                statistics.statistics(self.file_groups.iter().flatten(), &self.schema())?,
+                self.file_groups.len(),
+            ),
+            Some(partition) => match self.file_groups.get(partition) {
+                Some(tasks) => (statistics.statistics(tasks, &self.schema())?, 1),
+                None => {
+                    return Err(DataFusionError::Internal(format!(
+                        "IcebergTableScan has {} partitions, got partition {}",
+                        self.file_groups.len(),
+                        partition
+                    )))
+                }
+            },
+        };
+        statistics.with_fetch(self.schema(), self.limit, 0, partition_count)
+    }
+
+    fn fetch(&self) -> Option<usize> {
+        self.limit
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
     fn execute(
         &self,
-        _partition: usize,
+        partition: usize,
         _context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream, DataFusionError> {
+        let tasks = self.file_groups.get(partition).cloned().ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "IcebergTableScan has {} partitions, got partition {}",
+                self.file_groups.len(),
+                partition
+            ))
+        })?;
         let fut = get_batch_stream(
             self.table.clone(),
-            self.snapshot_id,
-            self.projection.clone(),
-            self.predicates.clone(),
+            tasks,
+            self.deletes.clone(),
+            self.row_positions,
+            self.batch_size,
         );
         let stream = futures::stream::once(fut).try_flatten();
-
-        Ok(Box::pin(RecordBatchStreamAdapter::new(
+        let stream: SendableRecordBatchStream = Box::pin(RecordBatchStreamAdapter::new(
             self.schema(),
             stream,
-        )))
+        ));
+        match self.limit {
+            // stops reading the files of the partition once it produced enough rows
+            Some(limit) => Ok(Box::pin(LimitStream::new(
+                stream,
+                0,
+                Some(limit),
+                BaselineMetrics::new(&self.metrics, partition),
+            ))),
+            None => Ok(stream),
+        }
     }
 }
 
@@ -177,28 +751,34 @@ impl DisplayAs for IcebergTableScan {
     ) -> std::fmt::Result {
         write!(
             f,
-            "IcebergTableScan projection:[{}] predicate:[{}]",
+            "IcebergTableScan snapshot:[{}] projection:[{}] predicate:[{}] limit:[{}] file_groups:[{}] files:[{}]",
+            self.snapshot_id
+                .map_or(String::new(), |id| id.to_string()),
             self.projection
                 .clone()
                 .map_or(String::new(), |v| v.join(",")),
             self.predicates
                 .clone()
-                .map_or(String::from(""), |p| format!("{}", p))
+                .map_or(String::from(""), |p| format!("{}", p)),
+            self.limit.map_or(String::new(), |limit| limit.to_string()),
+            self.file_groups.len(),
+            self.file_groups.iter().map(|g| g.len()).sum::<usize>()
         )
     }
 }
 
-/// Asynchronously retrieves a stream of [`RecordBatch`] instances
-/// from a given table.
+/// Plans the [`FileScanTask`]s of a table scan.
 ///
-/// This function initializes a [`TableScan`], builds it,
-/// and then converts it into a stream of Arrow [`RecordBatch`]es.
-async fn get_batch_stream(
-    table: Table,
+/// With a `limit`, only given for unfiltered scans, planning stops once the record counts of
+/// the planned data files add up to it. Files with deletes may have fewer rows so they are
+/// not counted.
+async fn plan_file_scan_tasks(
+    table: &Table,
     snapshot_id: Option<i64>,
     column_names: Option<Vec<String>>,
     predicates: Option<Predicate>,
-) -> DFResult<Pin<Box<dyn Stream<Item = DFResult<RecordBatch>> + Send>>> {
+    limit: Option<usize>,
+) -> DFResult<Vec<FileScanTask>> {
     let scan_builder = match snapshot_id {
         Some(snapshot_id) => table.scan().snapshot_id(snapshot_id),
         None => table.scan(),
@@ -211,14 +791,117 @@ async fn get_batch_stream(
     if let Some(pred) = predicates {
         scan_builder = scan_builder.with_filter(pred);
     }
+    // planning takes no data manifest entries until all delete manifests are read, so the
+    // manifests read at once have to reach the last delete manifest, or the data manifests
+    // before it block on the bounded entry channel
+    let snapshot = match snapshot_id {
+        Some(snapshot_id) => table.metadata().snapshot_by_id(snapshot_id),
+        None => table.metadata().current_snapshot(),
+    };
+    let mut concurrency = MANIFEST_CONCURRENCY;
+    if let Some(snapshot) = snapshot {
+        let manifest_list = snapshot
+            .load_manifest_list(table.file_io(), table.metadata())
+            .await
+            .map_err(to_datafusion_error)?;
+        if let Some(last_delete_manifest) = manifest_list
+            .entries()
+            .iter()
+            .rposition(|manifest| manifest.content == ManifestContentType::Deletes)
+        {
+            concurrency = concurrency.max(last_delete_manifest + 1);
+        }
+    }
+    scan_builder = scan_builder.with_concurrency_limit(concurrency);
     let table_scan = scan_builder.build().map_err(to_datafusion_error)?;
 
-    let stream = table_scan
-        .to_arrow()
+    let mut planned_tasks = table_scan
+        .plan_files()
+        .await
+        .map_err(to_datafusion_error)?;
+    let Some(limit) = limit else {
+        return planned_tasks.try_collect().await.map_err(to_datafusion_error);
+    };
+    let mut tasks = Vec::new();
+    let mut record_count = 0;
+    while record_count < limit {
+        let Some(task) = planned_tasks.try_next().await.map_err(to_datafusion_error)? else {
+            break;
+        };
+        if task.deletes.is_empty() {
+            record_count += task.record_count.unwrap_or_default() as usize;
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// Splits `tasks` into at most `target_partitions` groups of roughly equal byte size,
+/// assigning the largest remaining task to the currently smallest group.
+///
+/// Always returns at least one (possibly empty) group.
+fn bin_pack_file_scan_tasks(
+    mut tasks: Vec<FileScanTask>,
+    target_partitions: usize,
+) -> Vec<Vec<FileScanTask>> {
+    let group_count = target_partitions.min(tasks.len()).max(1);
+    let mut groups: Vec<Vec<FileScanTask>> = vec![Vec::new(); group_count];
+    let mut group_sizes: Vec<u64> = vec![0; group_count];
+
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.length));
+    for task in tasks {
+        let (smallest, _) = group_sizes
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, size)| **size)
+            .unwrap();
+        group_sizes[smallest] += task.length;
+        groups[smallest].push(task);
+    }
+    groups
+}
+
+/// Asynchronously retrieves a stream of [`RecordBatch`] instances
+/// for the given planned [`FileScanTask`]s of a table.
+///
+/// The tasks equality deletes apply to, or all of them when reading row positions, are read
+/// one by one after the others.
+async fn get_batch_stream(
+    table: Table,
+    tasks: Vec<FileScanTask>,
+    deletes: Arc<ScanDeletes>,
+    row_positions: bool,
+    batch_size: usize,
+) -> DFResult<Pin<Box<dyn Stream<Item = DFResult<RecordBatch>> + Send>>> {
+    let reader = table
+        .reader_builder()
+        .with_batch_size(batch_size)
+        .build();
+    let (deleted_tasks, tasks): (Vec<FileScanTask>, Vec<FileScanTask>) = tasks
+        .into_iter()
+        .partition(|task| row_positions || deletes.applies_to(task));
+    let tasks: FileScanTaskStream = Box::pin(futures::stream::iter(tasks.into_iter().map(Ok)));
+
+    let stream = reader
+        .clone()
+        .read(tasks)
         .await
         .map_err(to_datafusion_error)?
         .map_err(to_datafusion_error);
-    Ok(Box::pin(stream))
+    if deleted_tasks.is_empty() {
+        return Ok(Box::pin(stream));
+    }
+
+    let file_io = table.file_io().clone();
+    let deleted_stream = futures::stream::iter(deleted_tasks)
+        .then(move |task| {
+            let reader = reader.clone();
+            let deletes = deletes.clone();
+            let file_io = file_io.clone();
+            async move { deletes.read(reader, task, &file_io, row_positions).await }
+        })
+        .try_flatten();
+    Ok(Box::pin(stream.chain(deleted_stream)))
 }
 
 fn get_column_names(
@@ -234,4 +917,201 @@ fn get_column_names(
 
 fn to_datafusion_error(error: Error) -> datafusion::common::DataFusionError {
     datafusion::common::DataFusionError::External(error.into())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{Array, AsArray, Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{Int32Type, Int64Type};
+    use crate::table_format::iceberg_commit::testing::{empty_table, TestDir};
+    use crate::table_format::iceberg_commit::IcebergTableCommitter;
+    use crate::table_format::iceberg_sink::IcebergDataSink;
+    use datafusion::common::stats::Precision;
+    use datafusion::datasource::sink::DataSink;
+    use datafusion::physical_plan::collect;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use datafusion::prelude::{col, SessionContext};
+    use iceberg::spec::DataFileFormat;
+    use object_store::local::LocalFileSystem;
+    use std::fs;
+
+    fn file_scan_task(path: &str, length: u64) -> FileScanTask {
+        FileScanTask {
+            start: 0,
+            length,
+            record_count: None,
+            data_file_path: path.to_string(),
+            data_file_format: DataFileFormat::Parquet,
+            schema: Arc::new(iceberg::spec::Schema::builder().build().unwrap()),
+            project_field_ids: vec![],
+            predicate: None,
+            deletes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_bin_pack_file_scan_tasks() {
+        let tasks = [("a", 10), ("b", 50), ("c", 30), ("d", 20), ("e", 40), ("f", 30)]
+            .iter()
+            .map(|(path, length)| file_scan_task(path, *length))
+            .collect::<Vec<FileScanTask>>();
+
+        let groups = bin_pack_file_scan_tasks(tasks.clone(), 3);
+        let sizes = groups
+            .iter()
+            .map(|group| group.iter().map(|task| task.length).sum::<u64>())
+            .collect::<Vec<u64>>();
+        assert_eq!(sizes, vec![60, 60, 60]);
+        assert_eq!(groups.iter().map(Vec::len).sum::<usize>(), tasks.len());
+
+        // never more groups than tasks, and no empty groups
+        let groups = bin_pack_file_scan_tasks(tasks[..2].to_vec(), 8);
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.len() == 1));
+        assert_eq!(bin_pack_file_scan_tasks(tasks.clone(), 1)[0].len(), tasks.len());
+        // a scan without files still has a single, empty, partition
+        assert_eq!(bin_pack_file_scan_tasks(vec![], 4).len(), 1);
+    }
+
+    #[test]
+    fn test_deduce_table_format() -> Result<(), DataFusionError> {
+        let location = Some("s3://bucket/orders");
+        let iceberg = HashMap::from([(
+            ICEBERG_METADATA_LOCATION.to_string(),
+            "s3://bucket/orders/metadata/00001.metadata.json".to_string(),
+        )]);
+        assert_eq!(
+            deduce_table_format("orders", Some(&iceberg), None, location)?.0,
+            ExternalTableFormat::Iceberg
+        );
+        let delta = HashMap::from([(SPARK_SOURCES_PROVIDER.to_string(), "DELTA".to_string())]);
+        assert_eq!(
+            deduce_table_format("orders", Some(&delta), None, location)?,
+            (ExternalTableFormat::Delta, "s3://bucket/orders".to_string())
+        );
+        assert_eq!(
+            deduce_table_format("orders", None, None, location)?.0,
+            ExternalTableFormat::Hive
+        );
+        let hudi_input_format = Some("org.apache.hudi.hadoop.HoodieParquetInputFormat");
+        assert_eq!(
+            deduce_table_format("orders", None, hudi_input_format, location)?.0,
+            ExternalTableFormat::Hudi
+        );
+        assert!(deduce_table_format("orders", Some(&delta), None, None).is_err());
+        Ok(())
+    }
+
+    async fn scan_hive_table(descriptor: HiveStorageDescriptor) -> DFResult<Vec<RecordBatch>> {
+        let table = ExternalHiveTable::try_new(descriptor, Arc::new(LocalFileSystem::new()))?;
+        let ctx = SessionContext::new();
+        let plan = table.scan(&ctx.state(), &[], None, None).await?;
+        assert_eq!(plan.schema(), table.schema());
+        collect(plan, ctx.task_ctx()).await
+    }
+
+    #[tokio::test]
+    async fn test_scan_text_and_orc_tables() -> DFResult<()> {
+        let table_dir = std::env::temp_dir().join(format!("dobbydb-hive-{}", std::process::id()));
+        let text_dir = table_dir.join("text");
+        let orc_dir = table_dir.join("orc");
+        fs::create_dir_all(&text_dir)?;
+        fs::create_dir_all(&orc_dir)?;
+
+        // LazySimpleSerDe neither quotes fields nor parses invalid numbers
+        fs::write(text_dir.join("000000_0"), "1|a\n\\N|\\N\nx|\"q\"\n")?;
+        let batches = scan_hive_table(HiveStorageDescriptor {
+            location: format!("file://{}", text_dir.display()),
+            input_format: Some("org.apache.hadoop.mapred.TextInputFormat".to_string()),
+            serde_library: Some("org.apache.hadoop.hive.serde2.lazy.LazySimpleSerDe".to_string()),
+            serde_parameters: HashMap::from([("field.delim".to_string(), "|".to_string())]),
+            columns: vec![
+                ("id".to_string(), "int".to_string()),
+                ("name".to_string(), "string".to_string()),
+            ],
+            ..Default::default()
+        })
+        .await?;
+        let ids = batches[0].column(0).as_primitive::<Int32Type>();
+        let names = batches[0].column(1).as_string::<i32>();
+        assert_eq!(ids.iter().collect::<Vec<_>>(), vec![Some(1), None, None]);
+        assert_eq!(names.iter().collect::<Vec<_>>(), vec![Some("a"), None, Some("\"q\"")]);
+
+        // columns are matched by name, `region` is missing from the file
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, true),
+            Field::new("id", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            file_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(Int64Array::from(vec![1, 2])),
+            ],
+        )?;
+        let mut writer = orc_rust::ArrowWriterBuilder::new(fs::File::create(orc_dir.join("000000_0"))?, file_schema)
+            .try_build()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        writer.write(&batch).map_err(|e| DataFusionError::External(Box::new(e)))?;
+        writer.close().map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let batches = scan_hive_table(HiveStorageDescriptor {
+            location: format!("file://{}", orc_dir.display()),
+            input_format: Some("org.apache.hadoop.hive.ql.io.orc.OrcInputFormat".to_string()),
+            serde_library: Some("org.apache.hadoop.hive.ql.io.orc.OrcSerde".to_string()),
+            columns: vec![
+                ("id".to_string(), "bigint".to_string()),
+                ("name".to_string(), "string".to_string()),
+                ("region".to_string(), "string".to_string()),
+            ],
+            ..Default::default()
+        })
+        .await?;
+        assert_eq!(batches[0].column(0).as_primitive::<Int64Type>().values(), &[1, 2]);
+        assert_eq!(batches[0].column(1).as_string::<i32>().value(1), "b");
+        assert_eq!(batches[0].column(2).null_count(), 2);
+
+        fs::remove_dir_all(&table_dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scan_with_limit() -> DFResult<()> {
+        let table_dir = TestDir::new("iceberg-limit");
+        let (committer, table) = empty_table(&table_dir, HashMap::new()).await?;
+        let mut sink = IcebergDataSink::try_new(table, committer.clone(), false)?;
+        let schema = sink.schema().clone();
+        // three appends of three rows each, one data file per append
+        for ids in [vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]] {
+            let regions = StringArray::from(vec!["eu"; ids.len()]);
+            let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids)), Arc::new(regions)])?;
+            let stream = Box::pin(MemoryStream::try_new(vec![batch], schema.clone(), None)?);
+            sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+            sink = IcebergDataSink::try_new(committer.load_table().await?, committer.clone(), false)?;
+        }
+        let table = committer.load_table().await?;
+        let planned_files = |scan: &IcebergTableScan| scan.file_groups.iter().map(Vec::len).sum::<usize>();
+
+        // the record count of the first planned file covers the limit, only it is planned
+        let scan = IcebergTableScan::try_new(table.clone(), None, schema.clone(), None, &[], Some(2), 4, 1024)
+            .await?
+            .with_statistics()
+            .await?;
+        assert_eq!(planned_files(&scan), 1);
+        assert_eq!(scan.fetch(), Some(2));
+        assert_eq!(scan.partition_statistics(None)?.num_rows, Precision::Exact(2));
+        let batches = collect(Arc::new(scan), Arc::new(TaskContext::default())).await?;
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 2);
+
+        // a limit above the rows of the first files plans the files covering it
+        let scan = IcebergTableScan::try_new(table.clone(), None, schema.clone(), None, &[], Some(4), 4, 1024).await?;
+        assert_eq!(planned_files(&scan), 2);
+
+        // rows of the planned files may not match the filters, so a filtered scan plans them all
+        let filters = [col("id").gt(lit(0i64))];
+        let scan = IcebergTableScan::try_new(table, None, schema, None, &filters, Some(2), 4, 1024).await?;
+        assert_eq!(planned_files(&scan), 3);
+        assert_eq!(scan.fetch(), Some(2));
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+use datafusion::arrow::datatypes::{DataType, Field, Fields, TimeUnit, DECIMAL128_MAX_PRECISION};
+use datafusion::error::DataFusionError;
+use iceberg::spec::{PrimitiveType, Type};
+use std::sync::Arc;
+
+/// Converts a Hive column type as stored in Glue/HMS, e.g. `map<string,array<int>>`,
+/// into an Arrow [`DataType`].
+pub fn hive_type_to_arrow(hive_type: &str) -> Result<DataType, DataFusionError> {
+    let hive_type = hive_type.trim();
+    let lower = hive_type.to_ascii_lowercase();
+    let data_type = match lower.as_str() {
+        "string" => DataType::Utf8,
+        "boolean" => DataType::Boolean,
+        "tinyint" => DataType::Int8,
+        "smallint" => DataType::Int16,
+        "int" | "integer" => DataType::Int32,
+        "bigint" => DataType::Int64,
+        "float" => DataType::Float32,
+        "double" => DataType::Float64,
+        "date" => DataType::Date32,
+        "timestamp" => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        "binary" => DataType::Binary,
+        _ if lower.starts_with("varchar") || lower.starts_with("char") => DataType::Utf8,
+        _ if lower.starts_with("decimal") => parse_decimal(&lower)?,
+        _ if lower.starts_with("array<") => {
+            let inner = strip_type_args(hive_type, "array")?;
+            DataType::List(Arc::new(Field::new("item", hive_type_to_arrow(inner)?, true)))
+        }
+        _ if lower.starts_with("map<") => {
+            let inner = strip_type_args(hive_type, "map")?;
+            let [key, value] = split_top_level(inner, ',')[..] else {
+                return invalid_type(hive_type);
+            };
+            DataType::Map(
+                Arc::new(Field::new(
+                    "key_value",
+                    DataType::Struct(Fields::from(vec![
+                        Field::new("key", hive_type_to_arrow(key)?, false),
+                        Field::new("value", hive_type_to_arrow(value)?, true),
+                    ])),
+                    false,
+                )),
+                false,
+            )
+        }
+        _ if lower.starts_with("struct<") => {
+            let inner = strip_type_args(hive_type, "struct")?;
+            let fields = split_top_level(inner, ',')
+                .into_iter()
+                .map(|field| {
+                    let Some((name, field_type)) = field.split_once(':') else {
+                        return invalid_type(hive_type);
+                    };
+                    Ok(Field::new(name.trim(), hive_type_to_arrow(field_type)?, true))
+                })
+                .collect::<Result<Vec<Field>, DataFusionError>>()?;
+            DataType::Struct(Fields::from(fields))
+        }
+        _ => return invalid_type(hive_type),
+    };
+    Ok(data_type)
+}
+
+/// Converts an Iceberg type into the Hive column type Glue/HMS keep for it, so engines
+/// reading the table as a Hive table (e.g. Athena listing columns) see its schema.
+pub fn iceberg_type_to_hive(iceberg_type: &Type) -> String {
+    match iceberg_type {
+        Type::Primitive(primitive) => match primitive {
+            PrimitiveType::Boolean => "boolean".to_string(),
+            PrimitiveType::Int => "int".to_string(),
+            PrimitiveType::Long => "bigint".to_string(),
+            PrimitiveType::Float => "float".to_string(),
+            PrimitiveType::Double => "double".to_string(),
+            PrimitiveType::Decimal { precision, scale } => format!("decimal({precision},{scale})"),
+            PrimitiveType::Date => "date".to_string(),
+            PrimitiveType::Timestamp
+            | PrimitiveType::Timestamptz
+            | PrimitiveType::TimestampNs
+            | PrimitiveType::TimestamptzNs => "timestamp".to_string(),
+            PrimitiveType::Time | PrimitiveType::String | PrimitiveType::Uuid => "string".to_string(),
+            PrimitiveType::Fixed(_) | PrimitiveType::Binary => "binary".to_string(),
+        },
+        Type::List(list) => format!("array<{}>", iceberg_type_to_hive(&list.element_field.field_type)),
+        Type::Map(map) => format!(
+            "map<{},{}>",
+            iceberg_type_to_hive(&map.key_field.field_type),
+            iceberg_type_to_hive(&map.value_field.field_type)
+        ),
+        Type::Struct(struct_type) => {
+            let fields = struct_type
+                .fields()
+                .iter()
+                .map(|field| format!("{}:{}", field.name, iceberg_type_to_hive(&field.field_type)))
+                .collect::<Vec<String>>();
+            format!("struct<{}>", fields.join(","))
+        }
+    }
+}
+
+fn invalid_type<T>(hive_type: &str) -> Result<T, DataFusionError> {
+    Err(DataFusionError::NotImplemented(format!(
+        "unsupported hive type: {hive_type}"
+    )))
+}
+
+/// `decimal` defaults to `decimal(10,0)` in Hive. Hive decimals have at most 38 digits,
+/// like [`DataType::Decimal128`].
+fn parse_decimal(decimal: &str) -> Result<DataType, DataFusionError> {
+    let args = decimal
+        .strip_prefix("decimal")
+        .map(str::trim)
+        .unwrap_or_default();
+    if args.is_empty() {
+        return Ok(DataType::Decimal128(10, 0));
+    }
+    let args = args
+        .strip_prefix('(')
+        .and_then(|a| a.strip_suffix(')'))
+        .ok_or_else(|| DataFusionError::Plan(format!("invalid hive type: {decimal}")))?;
+    let mut parts = args.split(',').map(|p| p.trim().parse::<i64>());
+    let (precision, scale) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(precision)), Some(Ok(scale)), None) => (precision, scale),
+        (Some(Ok(precision)), None, None) => (precision, 0),
+        _ => return invalid_type(decimal),
+    };
+    if !(1..=DECIMAL128_MAX_PRECISION as i64).contains(&precision) || !(0..=precision).contains(&scale) {
+        return Err(DataFusionError::Plan(format!(
+            "invalid hive type: {decimal}, precision must be between 1 and \
+             {DECIMAL128_MAX_PRECISION} and scale between 0 and the precision"
+        )));
+    }
+    Ok(DataType::Decimal128(precision as u8, scale as i8))
+}
+
+/// Returns `inner` of `name<inner>`.
+fn strip_type_args<'a>(hive_type: &'a str, name: &str) -> Result<&'a str, DataFusionError> {
+    hive_type
+        .get(name.len()..)
+        .and_then(|t| t.trim().strip_prefix('<'))
+        .and_then(|t| t.strip_suffix('>'))
+        .ok_or_else(|| DataFusionError::Plan(format!("invalid hive type: {hive_type}")))
+}
+
+/// Splits on `separator`, ignoring separators nested in `<...>`.
+fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, ch) in s.char_indices() {
+        match ch {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(s[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iceberg::spec::{ListType, NestedField, StructType};
+
+    #[test]
+    fn test_hive_type_to_arrow() -> Result<(), DataFusionError> {
+        assert_eq!(hive_type_to_arrow("bigint")?, DataType::Int64);
+        assert_eq!(hive_type_to_arrow("varchar(20)")?, DataType::Utf8);
+        assert_eq!(hive_type_to_arrow("decimal(38, 2)")?, DataType::Decimal128(38, 2));
+        assert!(hive_type_to_arrow("decimal(39,2)").is_err());
+        assert!(hive_type_to_arrow("decimal(300)").is_err());
+        assert!(hive_type_to_arrow("decimal(5,6)").is_err());
+        assert!(hive_type_to_arrow("decimal(10,-1)").is_err());
+        assert_eq!(
+            hive_type_to_arrow("array<int>")?,
+            DataType::List(Arc::new(Field::new("item", DataType::Int32, true)))
+        );
+        let DataType::Struct(fields) = hive_type_to_arrow("struct<a:int,b:map<string,decimal(10,2)>>")?
+        else {
+            panic!("expected a struct");
+        };
+        assert_eq!(fields.len(), 2);
+        assert!(matches!(fields[1].data_type(), DataType::Map(_, _)));
+        assert!(hive_type_to_arrow("uniontype<int,string>").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_iceberg_type_to_hive() {
+        let struct_type = Type::Struct(StructType::new(vec![
+            NestedField::required(1, "id", Type::Primitive(PrimitiveType::Long)).into(),
+            NestedField::optional(
+                2,
+                "tags",
+                Type::List(ListType::new(
+                    NestedField::list_element(3, Type::Primitive(PrimitiveType::String), false).into(),
+                )),
+            )
+            .into(),
+            NestedField::optional(
+                4,
+                "price",
+                Type::Primitive(PrimitiveType::Decimal { precision: 10, scale: 2 }),
+            )
+            .into(),
+        ]));
+        assert_eq!(
+            iceberg_type_to_hive(&struct_type),
+            "struct<id:bigint,tags:array<string>,price:decimal(10,2)>"
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use crate::table_format::external_table::{split_files, HiveStorageDescriptor};
+use crate::table_format::hive_type::hive_type_to_arrow;
+use crate::table_format::table::TableVersion;
+use chrono::DateTime;
+use datafusion::arrow::datatypes::{Field, Schema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::common::{not_impl_err, DFSchema};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{ListingTableUrl, PartitionedFile};
+use datafusion::datasource::physical_plan::FileScanConfigBuilder;
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::execution::context::ExecutionProps;
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::{create_physical_expr, PhysicalExpr};
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::prelude::{col, lit};
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Meta column Hudi writes with the instant that last changed the row.
+pub const HUDI_COMMIT_TIME_COLUMN: &str = "_hoodie_commit_time";
+
+const HOODIE_DIR: &str = ".hoodie";
+const HOODIE_PROPERTIES: &str = "hoodie.properties";
+const COMMIT_ACTION: &str = "commit";
+const REPLACE_COMMIT_ACTION: &str = "replacecommit";
+
+/// What to read of a Hudi table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HudiQuery {
+    /// The latest committed file slices
+    Latest,
+    /// The file slices committed at or before the instant
+    AsOf(String),
+    /// Rows changed by the commits after the first instant up to and including the second
+    Incremental(String, String),
+}
+
+/// A Hudi copy-on-write table, read from its base Parquet files. The `.hoodie` timeline
+/// (Hudi 0.x layout, JSON commit metadata) tells which file slices are committed.
+#[derive(Debug, Clone)]
+pub struct ExternalHudiTable {
+    location: String,
+    schema: SchemaRef,
+    object_store: Arc<dyn ObjectStore>,
+    query: HudiQuery,
+}
+
+impl ExternalHudiTable {
+    pub async fn try_new(
+        descriptor: &HiveStorageDescriptor,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> DFResult<Self> {
+        let table_url = ListingTableUrl::parse(&descriptor.location)?;
+        let properties_path = table_url.prefix().child(HOODIE_DIR).child(HOODIE_PROPERTIES);
+        let properties = object_store.get(&properties_path).await?.bytes().await?;
+        let properties = String::from_utf8_lossy(&properties);
+        let table_type = properties
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("hoodie.table.type="))
+            .unwrap_or("COPY_ON_WRITE");
+        if !table_type.trim().eq_ignore_ascii_case("COPY_ON_WRITE") {
+            return not_impl_err!("only copy-on-write hudi tables are supported, got {table_type}");
+        }
+
+        // partition columns are also stored in the base files
+        let mut fields: Vec<Field> = Vec::new();
+        for (name, hive_type) in descriptor.columns.iter().chain(&descriptor.partition_keys) {
+            if fields.iter().all(|f| f.name() != name) {
+                fields.push(Field::new(name, hive_type_to_arrow(hive_type)?, true));
+            }
+        }
+        Ok(ExternalHudiTable {
+            location: descriptor.location.clone(),
+            schema: Arc::new(Schema::new(fields)),
+            object_store,
+            query: HudiQuery::Latest,
+        })
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Reads the table as of an instant (`FOR SYSTEM_VERSION AS OF <instant>`) or a
+    /// timestamp, or the changes between two timestamps.
+    pub fn with_version(&self, version: &TableVersion) -> DFResult<Self> {
+        let query = match version {
+            TableVersion::SnapshotId(instant) => HudiQuery::AsOf(normalize_instant(&instant.to_string())),
+            TableVersion::Timestamp(timestamp_ms) => HudiQuery::AsOf(instant_from_millis(*timestamp_ms)?),
+            TableVersion::Incremental(begin, end) => {
+                if !self.schema.fields().iter().any(|f| f.name() == HUDI_COMMIT_TIME_COLUMN) {
+                    return not_impl_err!(
+                        "incremental reads need the {HUDI_COMMIT_TIME_COLUMN} column in the table schema"
+                    );
+                }
+                HudiQuery::Incremental(instant_from_millis(*begin)?, instant_from_millis(*end)?)
+            }
+            TableVersion::Ref(name) => {
+                return not_impl_err!("hudi tables have no branches or tags, got {name}");
+            }
+        };
+        Ok(ExternalHudiTable {
+            query,
+            ..self.clone()
+        })
+    }
+
+    pub async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let table_url = ListingTableUrl::parse(&self.location)?;
+        let object_store_url = table_url.object_store();
+        state
+            .runtime_env()
+            .register_object_store(object_store_url.as_ref(), self.object_store.clone());
+
+        let base = table_url.prefix();
+        let files = match &self.query {
+            HudiQuery::Latest => self.latest_base_files(base, None).await?,
+            HudiQuery::AsOf(instant) => self.latest_base_files(base, Some(instant)).await?,
+            HudiQuery::Incremental(begin, end) => self.incremental_base_files(base, begin, end).await?,
+        };
+        let files = files.into_iter().map(PartitionedFile::from).collect();
+
+        let format = ParquetFormat::default();
+        let file_groups = split_files(files, state.config().target_partitions());
+        let HudiQuery::Incremental(begin, end) = &self.query else {
+            let config = FileScanConfigBuilder::new(object_store_url, self.schema.clone(), format.file_source())
+                .with_file_groups(file_groups)
+                .with_projection(projection.cloned())
+                .with_limit(limit)
+                .build();
+            return format.create_physical_plan(state, config).await;
+        };
+
+        // base files also hold rows of older commits, keep the ones changed in the range
+        let config = FileScanConfigBuilder::new(object_store_url, self.schema.clone(), format.file_source())
+            .with_file_groups(file_groups)
+            .build();
+        let plan = format.create_physical_plan(state, config).await?;
+        let predicate = col(HUDI_COMMIT_TIME_COLUMN)
+            .gt(lit(begin.as_str()))
+            .and(col(HUDI_COMMIT_TIME_COLUMN).lt_eq(lit(end.as_str())));
+        let df_schema = DFSchema::try_from(self.schema.as_ref().clone())?;
+        let predicate = create_physical_expr(&predicate, &df_schema, &ExecutionProps::new())?;
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(FilterExec::try_new(predicate, plan)?);
+        let Some(projection) = projection else {
+            return Ok(plan);
+        };
+        let exprs = projection
+            .iter()
+            .map(|idx| {
+                let name = self.schema.field(*idx).name().clone();
+                (Arc::new(Column::new(&name, *idx)) as Arc<dyn PhysicalExpr>, name)
+            })
+            .collect();
+        Ok(Arc::new(ProjectionExec::try_new(exprs, plan)?))
+    }
+
+    /// Completed `commit`/`replacecommit` instants of the timeline, sorted.
+    async fn completed_instants(&self, base: &Path) -> DFResult<Vec<(String, String)>> {
+        let listing = self
+            .object_store
+            .list_with_delimiter(Some(&base.child(HOODIE_DIR)))
+            .await?;
+        let mut instants: Vec<(String, String)> = listing
+            .objects
+            .iter()
+            .filter_map(|meta| {
+                let (instant, action) = meta.location.filename()?.split_once('.')?;
+                let completed = action == COMMIT_ACTION || action == REPLACE_COMMIT_ACTION;
+                let valid = !instant.is_empty() && instant.chars().all(|c| c.is_ascii_digit());
+                (completed && valid).then(|| (instant.to_string(), action.to_string()))
+            })
+            .collect();
+        instants.sort();
+        Ok(instants)
+    }
+
+    async fn read_commit_metadata(&self, base: &Path, instant: &str, action: &str) -> DFResult<Value> {
+        let path = base.child(HOODIE_DIR).child(format!("{instant}.{action}"));
+        let bytes = self.object_store.get(&path).await?.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+
+    /// The latest base file of every file group committed at or before `as_of`, skipping
+    /// file groups replaced by clustering or insert overwrite. Like Hudi's file system view,
+    /// instants older than the active timeline count as committed, their commit files
+    /// were moved to `.hoodie/archived`.
+    async fn latest_base_files(&self, base: &Path, as_of: Option<&str>) -> DFResult<Vec<ObjectMeta>> {
+        let completed = self.completed_instants(base).await?;
+        let timeline_start = completed.first().map(|(instant, _)| normalize_instant(instant));
+        let instants: Vec<(String, String)> = completed
+            .into_iter()
+            .filter(|(instant, _)| as_of.is_none_or(|as_of| normalize_instant(instant).as_str() <= as_of))
+            .collect();
+        let active: HashSet<&str> = instants.iter().map(|(instant, _)| instant.as_str()).collect();
+        let is_committed = |instant: &str| {
+            let normalized = normalize_instant(instant);
+            let archived = timeline_start.as_ref().is_some_and(|start| normalized < *start)
+                && as_of.is_none_or(|as_of| normalized.as_str() <= as_of);
+            archived || active.contains(instant)
+        };
+
+        let mut replaced: HashSet<(String, String)> = HashSet::new();
+        for (instant, action) in &instants {
+            if action != REPLACE_COMMIT_ACTION {
+                continue;
+            }
+            let metadata = self.read_commit_metadata(base, instant, action).await?;
+            let Some(partitions) = metadata["partitionToReplaceFileIds"].as_object() else {
+                continue;
+            };
+            for (partition, file_ids) in partitions {
+                for file_id in file_ids.as_array().into_iter().flatten().filter_map(Value::as_str) {
+                    replaced.insert((partition.clone(), file_id.to_string()));
+                }
+            }
+        }
+
+        let files: Vec<ObjectMeta> = self.object_store.list(Some(base)).try_collect().await?;
+        let mut latest: HashMap<(String, String), (String, ObjectMeta)> = HashMap::new();
+        for meta in files {
+            let Some(relative) = meta.location.prefix_match(base) else {
+                continue;
+            };
+            let parts: Vec<String> = relative.map(|part| part.as_ref().to_string()).collect();
+            let Some((file_name, partition)) = parts.split_last() else {
+                continue;
+            };
+            if parts.iter().any(|part| part.starts_with('.')) {
+                continue;
+            }
+            let Some((file_id, instant)) = parse_base_file_name(file_name) else {
+                continue;
+            };
+            if !is_committed(&instant) {
+                continue;
+            }
+            let key = (partition.join("/"), file_id);
+            if replaced.contains(&key) {
+                continue;
+            }
+            match latest.get(&key) {
+                Some((latest_instant, _)) if *latest_instant >= instant => {}
+                _ => {
+                    latest.insert(key, (instant, meta));
+                }
+            }
+        }
+        Ok(latest.into_values().map(|(_, meta)| meta).collect())
+    }
+
+    /// The latest base file of every file group written by the commits in `(begin, end]`.
+    async fn incremental_base_files(&self, base: &Path, begin: &str, end: &str) -> DFResult<Vec<ObjectMeta>> {
+        let mut latest: HashMap<(String, String), (String, String)> = HashMap::new();
+        for (instant, action) in self.completed_instants(base).await? {
+            let normalized = normalize_instant(&instant);
+            if normalized.as_str() <= begin || normalized.as_str() > end {
+                continue;
+            }
+            let metadata = self.read_commit_metadata(base, &instant, &action).await?;
+            let Some(partitions) = metadata["partitionToWriteStats"].as_object() else {
+                continue;
+            };
+            for (partition, stats) in partitions {
+                for stat in stats.as_array().into_iter().flatten() {
+                    let (Some(file_id), Some(path)) = (stat["fileId"].as_str(), stat["path"].as_str()) else {
+                        continue;
+                    };
+                    // instants are visited in order, later commits win
+                    latest.insert((partition.clone(), file_id.to_string()), (instant.clone(), path.to_string()));
+                }
+            }
+        }
+
+        let mut files: Vec<ObjectMeta> = Vec::with_capacity(latest.len());
+        for (instant, path) in latest.into_values() {
+            let location = Path::from(format!("{base}/{path}"));
+            match self.object_store.head(&location).await {
+                Ok(meta) => files.push(meta),
+                Err(object_store::Error::NotFound { .. }) => {
+                    return Err(DataFusionError::Execution(format!(
+                        "instant range ({begin}, {end}] was cleaned: base file {location} of instant \
+                         {instant} was removed by the cleaner, read a more recent range"
+                    )));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Splits a base file name `<file id>_<write token>_<instant>.parquet` into its file id
+/// and instant.
+fn parse_base_file_name(file_name: &str) -> Option<(String, String)> {
+    let name = file_name.strip_suffix(".parquet")?;
+    let mut parts = name.rsplitn(3, '_');
+    let instant = parts.next()?;
+    let _write_token = parts.next()?;
+    let file_id = parts.next()?;
+    Some((file_id.to_string(), instant.to_string()))
+}
+
+/// Hudi instants are `yyyyMMddHHmmssSSS`, older ones `yyyyMMddHHmmss` which Hudi
+/// compares as the last millisecond of that second.
+fn normalize_instant(instant: &str) -> String {
+    if instant.len() == 14 {
+        format!("{instant}999")
+    } else {
+        instant.to_string()
+    }
+}
+
+/// Formats a timestamp as an instant, Hudi timelines are assumed to be in UTC.
+fn instant_from_millis(timestamp_ms: i64) -> DFResult<String> {
+    DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|time| time.format("%Y%m%d%H%M%S%3f").to_string())
+        .ok_or_else(|| DataFusionError::Plan(format!("invalid timestamp {timestamp_ms}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::local::LocalFileSystem;
+    use std::fs;
+
+    #[test]
+    fn test_instants() -> DFResult<()> {
+        assert_eq!(
+            parse_base_file_name("a1b2-0_1-2-3_20261001120000123.parquet"),
+            Some(("a1b2-0".to_string(), "20261001120000123".to_string()))
+        );
+        assert_eq!(parse_base_file_name(".hoodie_partition_metadata"), None);
+        assert_eq!(normalize_instant("20261001120000"), "20261001120000999");
+        assert_eq!(instant_from_millis(1000)?, "19700101000001000");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_base_files() -> DFResult<()> {
+        let table_dir = std::env::temp_dir().join(format!("dobbydb-hudi-{}", std::process::id()));
+        let hoodie_dir = table_dir.join(HOODIE_DIR);
+        let partition_dir = table_dir.join("2026-10-01");
+        fs::create_dir_all(&hoodie_dir)?;
+        fs::create_dir_all(&partition_dir)?;
+        fs::write(hoodie_dir.join(HOODIE_PROPERTIES), "hoodie.table.type=COPY_ON_WRITE\n")?;
+        let commit = |path: &str| {
+            format!(r#"{{"partitionToWriteStats": {{"2026-10-01": [{{"fileId": "f1", "path": "{path}"}}]}}}}"#)
+        };
+        // the commit of file group f0 was archived, it is older than the active timeline
+        fs::create_dir_all(hoodie_dir.join("archived"))?;
+        fs::write(partition_dir.join("f0_0-1-0_20260901000000000.parquet"), "")?;
+        fs::write(hoodie_dir.join("20261001000000000.commit"), commit("2026-10-01/f1_0-1-0_20261001000000000.parquet"))?;
+        fs::write(hoodie_dir.join("20261002000000000.commit"), commit("2026-10-01/f1_0-1-0_20261002000000000.parquet"))?;
+        // still inflight, its file must be ignored
+        fs::write(hoodie_dir.join("20261003000000000.commit.requested"), "")?;
+        for instant in ["20261001000000000", "20261002000000000", "20261003000000000"] {
+            fs::write(partition_dir.join(format!("f1_0-1-0_{instant}.parquet")), "")?;
+        }
+
+        let descriptor = HiveStorageDescriptor {
+            location: format!("file://{}", table_dir.display()),
+            columns: vec![(HUDI_COMMIT_TIME_COLUMN.to_string(), "string".to_string())],
+            ..Default::default()
+        };
+        let table = ExternalHudiTable::try_new(&descriptor, Arc::new(LocalFileSystem::new())).await?;
+        let table_url = ListingTableUrl::parse(&descriptor.location)?;
+        let base = table_url.prefix();
+        let file_names = |files: Vec<ObjectMeta>| {
+            files
+                .iter()
+                .filter_map(|meta| meta.location.filename().map(|name| name.to_string()))
+                .collect::<Vec<String>>()
+        };
+
+        let sorted_file_names = |files: Vec<ObjectMeta>| {
+            let mut names = file_names(files);
+            names.sort();
+            names
+        };
+        let latest = table.latest_base_files(base, None).await?;
+        assert_eq!(
+            sorted_file_names(latest),
+            vec!["f0_0-1-0_20260901000000000.parquet", "f1_0-1-0_20261002000000000.parquet"]
+        );
+        let as_of = table.latest_base_files(base, Some("20261001120000000")).await?;
+        assert_eq!(
+            sorted_file_names(as_of),
+            vec!["f0_0-1-0_20260901000000000.parquet", "f1_0-1-0_20261001000000000.parquet"]
+        );
+        let before_archived = table.latest_base_files(base, Some("20260801000000000")).await?;
+        assert!(before_archived.is_empty());
+        let changes = table
+            .incremental_base_files(base, "20261001120000000", "20261002120000000")
+            .await?;
+        assert_eq!(file_names(changes), vec!["f1_0-1-0_20261002000000000.parquet"]);
+        fs::remove_file(partition_dir.join("f1_0-1-0_20261002000000000.parquet"))?;
+        let cleaned = table
+            .incremental_base_files(base, "20261001120000000", "20261002120000000")
+            .await;
+        assert!(cleaned.is_err_and(|e| e.to_string().contains("was cleaned")));
+
+        fs::remove_dir_all(&table_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,533 @@
+use crate::table_format::external_table::IcebergTableScan;
+use crate::table_format::iceberg_commit::{
+    commit_table_change, to_datafusion_error, IcebergTableCommitter, TableChange,
+};
+use crate::table_format::iceberg_statistics::{NDV_PROPERTY, THETA_SKETCH_BLOB_TYPE};
+use async_trait::async_trait;
+use datafusion::arrow::array::{Array, AsArray, RecordBatch, StringArray, UInt64Array};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{
+    DataType, Decimal128Type, Field, Float32Type, Float64Type, Int32Type, Int64Type, Schema,
+    SchemaRef,
+};
+use datafusion::catalog::Session;
+use datafusion::common::plan_err;
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::physical_plan::execute_stream;
+use futures::TryStreamExt;
+use iceberg::arrow::schema_to_arrow_schema;
+use iceberg::puffin::{Blob, CompressionCodec, PuffinWriter, CREATED_BY_PROPERTY};
+use iceberg::spec::{BlobMetadata, StatisticsFile};
+use iceberg::table::Table;
+use iceberg::{TableRequirement, TableUpdate};
+use murmur3::murmur3_x64_128;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Cursor;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Default seed of Apache DataSketches, only sketches with the same seed can be merged.
+const THETA_SEED: u64 = 9001;
+/// Number of hashes a sketch keeps, 2^12 like the NDV statistics of Iceberg's Spark
+/// procedures.
+const THETA_NOMINAL_ENTRIES: usize = 4096;
+
+/// Compact sketch serialization of Apache DataSketches.
+const THETA_SERIAL_VERSION: u8 = 3;
+const THETA_COMPACT_FAMILY: u8 = 3;
+const THETA_READ_ONLY_FLAG: u8 = 1 << 1;
+const THETA_EMPTY_FLAG: u8 = 1 << 2;
+const THETA_COMPACT_FLAG: u8 = 1 << 3;
+const THETA_ORDERED_FLAG: u8 = 1 << 4;
+const THETA_SINGLE_ITEM_FLAG: u8 = 1 << 5;
+
+/// Length of the magic bytes starting a Puffin file, the rest of the file before the
+/// blobs.
+const PUFFIN_MAGIC_LENGTH: u64 = 4;
+
+/// A Theta sketch estimating the number of distinct values of a column from the smallest
+/// hashes of its values. It is serialized as a compact sketch of Apache DataSketches, so
+/// Spark and Trino can read and merge it.
+#[derive(Debug, Default)]
+pub(crate) struct ThetaSketch {
+    /// The smallest hashes added, one more than the nominal entries once there are as many
+    hashes: BTreeSet<u64>,
+}
+
+impl ThetaSketch {
+    /// Adds a value in its Iceberg single-value binary form, as Iceberg's Spark procedures
+    /// do.
+    pub(crate) fn update(&mut self, value: &[u8]) {
+        let Ok(hash) = murmur3_x64_128(&mut Cursor::new(value), THETA_SEED as u32) else {
+            return;
+        };
+        // the first half of the hash, as a positive long
+        let hash = (hash as u64) >> 1;
+        if self.hashes.len() <= THETA_NOMINAL_ENTRIES {
+            self.hashes.insert(hash);
+        } else if self.hashes.last().is_some_and(|max| hash < *max) && self.hashes.insert(hash) {
+            self.hashes.pop_last();
+        }
+    }
+
+    /// Upper bound of the kept hashes, as a fraction of `i64::MAX`.
+    fn theta(&self) -> u64 {
+        match self.hashes.last() {
+            Some(max) if self.hashes.len() > THETA_NOMINAL_ENTRIES => *max,
+            _ => i64::MAX as u64,
+        }
+    }
+
+    fn retained(&self) -> impl Iterator<Item = u64> + '_ {
+        self.hashes.iter().copied().take(THETA_NOMINAL_ENTRIES)
+    }
+
+    pub(crate) fn estimate(&self) -> f64 {
+        let retained = self.hashes.len().min(THETA_NOMINAL_ENTRIES) as f64;
+        retained / (self.theta() as f64 / i64::MAX as f64)
+    }
+
+    /// Serializes the sketch as an ordered compact sketch.
+    pub(crate) fn serialize(&self) -> Vec<u8> {
+        let theta = self.theta();
+        let retained = self.retained().collect::<Vec<u64>>();
+        let estimation = theta < i64::MAX as u64;
+        let preamble_longs: u8 = match (estimation, retained.len()) {
+            (true, _) => 3,
+            (false, 0 | 1) => 1,
+            (false, _) => 2,
+        };
+        let mut flags = THETA_READ_ONLY_FLAG | THETA_COMPACT_FLAG | THETA_ORDERED_FLAG;
+        if retained.is_empty() {
+            flags |= THETA_EMPTY_FLAG;
+        } else if preamble_longs == 1 {
+            flags |= THETA_SINGLE_ITEM_FLAG;
+        }
+
+        let mut bytes = Vec::with_capacity(8 * (preamble_longs as usize + retained.len()));
+        bytes.extend([preamble_longs, THETA_SERIAL_VERSION, THETA_COMPACT_FAMILY, 0, 0, flags]);
+        bytes.extend(theta_seed_hash().to_le_bytes());
+        if preamble_longs > 1 {
+            bytes.extend((retained.len() as u32).to_le_bytes());
+            // sampling probability
+            bytes.extend(1.0f32.to_le_bytes());
+        }
+        if estimation {
+            bytes.extend(theta.to_le_bytes());
+        }
+        for hash in retained {
+            bytes.extend(hash.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// The 16 bit hash of the seed stored in sketches, to check they are merged with sketches
+/// of the same seed.
+fn theta_seed_hash() -> u16 {
+    murmur3_x64_128(&mut Cursor::new(THETA_SEED.to_le_bytes()), 0).unwrap_or_default() as u16
+}
+
+/// Computes Theta sketches of `columns` of the current snapshot, all primitive columns if
+/// empty, writes them to a Puffin file and commits it as the statistics file of the
+/// snapshot. Returns the estimated number of distinct values of every column.
+pub async fn analyze_table(
+    state: &dyn Session,
+    committer: Arc<dyn IcebergTableCommitter>,
+    columns: &[String],
+) -> DFResult<RecordBatch> {
+    let table = committer.load_table().await?;
+    let metadata = table.metadata();
+    let table_name = table.identifier().name();
+    let Some(snapshot) = metadata.current_snapshot() else {
+        return plan_err!("table {table_name} has no data to analyze");
+    };
+    let iceberg_schema = metadata.current_schema();
+    let schema = Arc::new(
+        schema_to_arrow_schema(iceberg_schema).map_err(|e| DataFusionError::External(Box::new(e)))?,
+    );
+
+    let columns = if columns.is_empty() {
+        schema
+            .fields()
+            .iter()
+            .filter(|field| is_sketched(field.data_type()))
+            .map(|field| field.name().clone())
+            .collect()
+    } else {
+        let mut seen = HashSet::new();
+        columns
+            .iter()
+            .filter(|column| seen.insert(column.as_str()))
+            .cloned()
+            .collect::<Vec<String>>()
+    };
+    let mut projection = Vec::new();
+    let mut field_ids = Vec::new();
+    for column in &columns {
+        let (Ok(index), Some(field)) = (schema.index_of(column), iceberg_schema.field_by_name(column)) else {
+            return plan_err!("column {column} not found in {table_name}");
+        };
+        let data_type = schema.field(index).data_type();
+        if !is_sketched(data_type) {
+            return plan_err!("cannot compute statistics of column {column} of type {data_type}");
+        }
+        projection.push(index);
+        field_ids.push(field.id);
+    }
+    if columns.is_empty() {
+        return plan_err!("table {table_name} has no columns to compute statistics of");
+    }
+
+    let config = state.config();
+    let scan = IcebergTableScan::try_new(
+        table.clone(),
+        Some(snapshot.snapshot_id()),
+        schema.clone(),
+        Some(&projection),
+        &[],
+        None,
+        config.target_partitions(),
+        config.batch_size(),
+    )
+    .await?;
+    let mut sketches = columns.iter().map(|_| ThetaSketch::default()).collect::<Vec<_>>();
+    let mut data = execute_stream(Arc::new(scan), state.task_ctx())?;
+    while let Some(batch) = data.try_next().await? {
+        for (sketch, array) in sketches.iter_mut().zip(batch.columns()) {
+            update_sketch(sketch, array.as_ref())?;
+        }
+    }
+
+    let statistics = write_statistics_file(
+        &table,
+        snapshot.snapshot_id(),
+        snapshot.sequence_number(),
+        &sketches,
+        &field_ids,
+    )
+    .await?;
+    println!(
+        "analyzed columns {} of table {}, statistics written to {}",
+        columns.join(", "),
+        table_name,
+        statistics.statistics_path
+    );
+    commit_table_change(committer.as_ref(), &StatisticsUpdate { statistics }).await?;
+
+    let distinct_counts = sketches
+        .iter()
+        .map(|sketch| sketch.estimate().round() as u64)
+        .collect::<Vec<u64>>();
+    Ok(RecordBatch::try_new(
+        analyze_result_schema(),
+        vec![
+            Arc::new(StringArray::from(columns)),
+            Arc::new(UInt64Array::from(distinct_counts)),
+        ],
+    )?)
+}
+
+/// Schema of the rows [`analyze_table`] returns, the distinct count of each column.
+pub fn analyze_result_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("column_name", DataType::Utf8, false),
+        Field::new("distinct_count", DataType::UInt64, false),
+    ]))
+}
+
+/// Writes a Puffin file with a Theta sketch blob per column, returning the statistics
+/// file to commit.
+async fn write_statistics_file(
+    table: &Table,
+    snapshot_id: i64,
+    sequence_number: i64,
+    sketches: &[ThetaSketch],
+    field_ids: &[i32],
+) -> DFResult<StatisticsFile> {
+    let path = format!(
+        "{}/metadata/{}-{}.stats",
+        table.metadata().location(),
+        snapshot_id,
+        Uuid::new_v4()
+    );
+    let output = table.file_io().new_output(&path).map_err(to_datafusion_error)?;
+    let properties = HashMap::from([(CREATED_BY_PROPERTY.to_string(), "DobbyDB".to_string())]);
+    let mut writer = PuffinWriter::new(&output, properties, false)
+        .await
+        .map_err(to_datafusion_error)?;
+    let mut blob_metadata = Vec::new();
+    let mut blobs_length = 0;
+    for (sketch, field_id) in sketches.iter().zip(field_ids) {
+        let data = sketch.serialize();
+        blobs_length += data.len() as u64;
+        let properties = HashMap::from([(
+            NDV_PROPERTY.to_string(),
+            (sketch.estimate().round() as u64).to_string(),
+        )]);
+        blob_metadata.push(BlobMetadata {
+            r#type: THETA_SKETCH_BLOB_TYPE.to_string(),
+            snapshot_id,
+            sequence_number,
+            fields: vec![*field_id],
+            properties: properties.clone(),
+        });
+        let blob = Blob::builder()
+            .r#type(THETA_SKETCH_BLOB_TYPE.to_string())
+            .fields(vec![*field_id])
+            .snapshot_id(snapshot_id)
+            .sequence_number(sequence_number)
+            .data(data)
+            .properties(properties)
+            .build();
+        writer
+            .add(blob, CompressionCodec::None)
+            .await
+            .map_err(to_datafusion_error)?;
+    }
+    writer.close().await.map_err(to_datafusion_error)?;
+
+    let file_size = output
+        .to_input_file()
+        .metadata()
+        .await
+        .map_err(to_datafusion_error)?
+        .size;
+    Ok(StatisticsFile {
+        snapshot_id,
+        statistics_path: path,
+        file_size_in_bytes: file_size as i64,
+        file_footer_size_in_bytes: file_size.saturating_sub(PUFFIN_MAGIC_LENGTH + blobs_length) as i64,
+        key_metadata: None,
+        blob_metadata,
+    })
+}
+
+/// Sets the statistics file of a snapshot, replacing the previous one.
+struct StatisticsUpdate {
+    statistics: StatisticsFile,
+}
+
+#[async_trait]
+impl TableChange for StatisticsUpdate {
+    async fn produce(&self, base: &Table) -> DFResult<(Vec<TableUpdate>, Vec<TableRequirement>)> {
+        let metadata = base.metadata();
+        if metadata.snapshot_by_id(self.statistics.snapshot_id).is_none() {
+            return Err(DataFusionError::Execution(format!(
+                "snapshot {} of {} was expired while computing its statistics",
+                self.statistics.snapshot_id,
+                base.identifier().name()
+            )));
+        }
+        let updates = vec![TableUpdate::SetStatistics {
+            statistics: self.statistics.clone(),
+        }];
+        let requirements = vec![TableRequirement::UuidMatch { uuid: metadata.uuid() }];
+        Ok((updates, requirements))
+    }
+}
+
+/// Whether the values of a column type are added to sketches, as for Iceberg primitive
+/// types.
+fn is_sketched(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Boolean
+            | DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Date32
+            | DataType::Time64(_)
+            | DataType::Timestamp(_, _)
+            | DataType::Decimal128(_, _)
+            | DataType::Utf8
+            | DataType::LargeUtf8
+            | DataType::Utf8View
+            | DataType::Binary
+            | DataType::LargeBinary
+            | DataType::BinaryView
+            | DataType::FixedSizeBinary(_)
+    )
+}
+
+/// Adds the non-null values of `array` to `sketch` in their Iceberg single-value binary
+/// form: little-endian numbers, UTF-8 strings and big-endian unscaled decimals.
+fn update_sketch(sketch: &mut ThetaSketch, array: &dyn Array) -> DFResult<()> {
+    match array.data_type() {
+        DataType::Boolean => {
+            for value in array.as_boolean().iter().flatten() {
+                sketch.update(&[value as u8]);
+            }
+        }
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Date32 => {
+            let array = cast(array, &DataType::Int32)?;
+            for value in array.as_primitive::<Int32Type>().iter().flatten() {
+                sketch.update(&value.to_le_bytes());
+            }
+        }
+        DataType::Int64 | DataType::Time64(_) | DataType::Timestamp(_, _) => {
+            let array = cast(array, &DataType::Int64)?;
+            for value in array.as_primitive::<Int64Type>().iter().flatten() {
+                sketch.update(&value.to_le_bytes());
+            }
+        }
+        DataType::Float32 => {
+            for value in array.as_primitive::<Float32Type>().iter().flatten() {
+                sketch.update(&value.to_le_bytes());
+            }
+        }
+        DataType::Float64 => {
+            for value in array.as_primitive::<Float64Type>().iter().flatten() {
+                sketch.update(&value.to_le_bytes());
+            }
+        }
+        DataType::Decimal128(_, _) => {
+            for value in array.as_primitive::<Decimal128Type>().iter().flatten() {
+                sketch.update(&decimal_bytes(value));
+            }
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+            let array = cast(array, &DataType::Utf8)?;
+            for value in array.as_string::<i32>().iter().flatten() {
+                sketch.update(value.as_bytes());
+            }
+        }
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView | DataType::FixedSizeBinary(_) => {
+            let array = cast(array, &DataType::Binary)?;
+            for value in array.as_binary::<i32>().iter().flatten() {
+                sketch.update(value);
+            }
+        }
+        data_type => return plan_err!("cannot compute statistics of values of type {data_type}"),
+    }
+    Ok(())
+}
+
+/// The unscaled value of a decimal as two's-complement big-endian bytes, using as few
+/// bytes as possible.
+fn decimal_bytes(value: i128) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let sign = if value < 0 { 0xff } else { 0 };
+    let mut start = 0;
+    while start < bytes.len() - 1 && bytes[start] == sign && (bytes[start + 1] & 0x80) == (sign & 0x80) {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_format::iceberg_commit::testing::{empty_table, TestDir};
+    use crate::table_format::iceberg_sink::IcebergDataSink;
+    use crate::table_format::iceberg_statistics::snapshot_statistics;
+    use datafusion::arrow::array::Int64Array;
+    use datafusion::arrow::datatypes::UInt64Type;
+    use datafusion::common::stats::Precision;
+    use datafusion::datasource::sink::DataSink;
+    use datafusion::execution::TaskContext;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use datafusion::prelude::SessionContext;
+    use iceberg::puffin::PuffinReader;
+
+    #[test]
+    fn test_theta_sketch() {
+        assert_eq!(theta_seed_hash(), 0x93cc);
+        assert_eq!(decimal_bytes(12345), vec![0x30, 0x39]);
+        assert_eq!(decimal_bytes(-1), vec![0xff]);
+        assert_eq!(decimal_bytes(128), vec![0x00, 0x80]);
+
+        let empty = ThetaSketch::default();
+        assert_eq!(empty.estimate(), 0.0);
+        assert_eq!(empty.serialize(), vec![1, 3, 3, 0, 0, 0x1e, 0xcc, 0x93]);
+
+        let mut sketch = ThetaSketch::default();
+        for value in 0..1000i64 {
+            sketch.update(&value.to_le_bytes());
+            sketch.update(&value.to_le_bytes());
+        }
+        // exact below the nominal entries
+        assert_eq!(sketch.estimate(), 1000.0);
+        assert_eq!(sketch.serialize().len(), 16 + 1000 * 8);
+
+        for value in 1000..100_000i64 {
+            sketch.update(&value.to_le_bytes());
+        }
+        let estimate = sketch.estimate();
+        assert!((95_000.0..105_000.0).contains(&estimate), "estimate {estimate}");
+        let bytes = sketch.serialize();
+        assert_eq!(bytes[0], 3);
+        assert_eq!(bytes.len(), 24 + THETA_NOMINAL_ENTRIES * 8);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_table() -> DFResult<()> {
+        let table_dir = TestDir::new("iceberg-analyze");
+        let (committer, _) = empty_table(&table_dir, HashMap::new()).await?;
+        let state = SessionContext::new().state();
+        let result = analyze_table(&state, committer.clone(), &[]).await;
+        assert!(result.is_err_and(|e| e.to_string().contains("has no data to analyze")));
+
+        let sink = IcebergDataSink::try_new(committer.load_table().await?, committer.clone(), false)?;
+        let schema = sink.schema().clone();
+        let regions = ["eu", "us", "apac"];
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from_iter_values(0..100)),
+                Arc::new(StringArray::from_iter((0..100).map(|i| (i % 4 < 3).then(|| regions[i % 4])))),
+            ],
+        )?;
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+        sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+
+        let result = analyze_table(&state, committer.clone(), &["region".to_string()]).await?;
+        assert_eq!(result.schema(), analyze_result_schema());
+        assert_eq!(result.column(0).as_string::<i32>().value(0), "region");
+        assert_eq!(result.column(1).as_primitive::<UInt64Type>().values(), &[3]);
+
+        // the Puffin file is registered as the statistics file of the snapshot
+        let table = committer.load_table().await?;
+        let snapshot_id = table.metadata().current_snapshot_id().expect("snapshot");
+        let statistics = table.metadata().statistics_for_snapshot(snapshot_id).expect("statistics file");
+        assert_eq!(statistics.blob_metadata.len(), 1);
+        assert_eq!(statistics.blob_metadata[0].fields, vec![2]);
+        assert_eq!(statistics.blob_metadata[0].properties[NDV_PROPERTY], "3");
+
+        // and holds the sketch of the column
+        let input = table.file_io().new_input(&statistics.statistics_path).map_err(to_datafusion_error)?;
+        assert_eq!(
+            input.metadata().await.map_err(to_datafusion_error)?.size,
+            statistics.file_size_in_bytes as u64
+        );
+        let reader = PuffinReader::new(input);
+        let blobs = reader.file_metadata().await.map_err(to_datafusion_error)?.blobs().to_vec();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].blob_type(), THETA_SKETCH_BLOB_TYPE);
+        assert_eq!(blobs[0].snapshot_id(), snapshot_id);
+        let blob = reader.blob(&blobs[0]).await.map_err(to_datafusion_error)?;
+        let mut sketch = ThetaSketch::default();
+        for region in regions {
+            sketch.update(region.as_bytes());
+        }
+        assert_eq!(blob.data(), sketch.serialize());
+
+        // analyzing again replaces the statistics file, read back as the distinct counts
+        let result = analyze_table(&state, committer.clone(), &[]).await?;
+        assert_eq!(result.num_rows(), 2);
+        let table = committer.load_table().await?;
+        assert_eq!(table.metadata().statistics_iter().count(), 1);
+        let arrow_schema = schema_to_arrow_schema(table.metadata().current_schema()).map_err(to_datafusion_error)?;
+        let statistics = snapshot_statistics(table.metadata(), None, &arrow_schema);
+        assert_eq!(statistics.column_statistics[0].distinct_count, Precision::Inexact(100));
+        assert_eq!(statistics.column_statistics[1].distinct_count, Precision::Inexact(3));
+
+        let result = analyze_table(&state, committer.clone(), &["city".to_string()]).await;
+        assert!(result.is_err_and(|e| e.to_string().contains("column city not found")));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,869 @@
+use async_trait::async_trait;
+use datafusion::error::DataFusionError;
+use iceberg::spec::{
+    DataContentType, DataFile, Datum, FormatVersion, ManifestContentType, ManifestFile,
+    ManifestListWriter, ManifestStatus, ManifestWriter, ManifestWriterBuilder, Operation,
+    PartitionSpecRef, PrimitiveLiteral, Snapshot, SnapshotReference, SnapshotRetention,
+    SnapshotSummaryCollector, Struct, Summary, TableMetadata, MAIN_BRANCH,
+};
+use iceberg::io::FileIO;
+use iceberg::table::Table;
+use iceberg::{TableRequirement, TableUpdate};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Table property with the number of times a commit conflicting with another writer is retried.
+const COMMIT_NUM_RETRIES: &str = "commit.retry.num-retries";
+const COMMIT_NUM_RETRIES_DEFAULT: u32 = 4;
+/// Table property with the wait before the first retry, doubled for every further retry.
+const COMMIT_MIN_RETRY_WAIT_MS: &str = "commit.retry.min-wait-ms";
+const COMMIT_MIN_RETRY_WAIT_MS_DEFAULT: u64 = 100;
+
+/// `(total, added, removed)` snapshot summary properties, the totals are carried over
+/// from the parent snapshot.
+const SUMMARY_TOTALS: [(&str, &str, &str); 6] = [
+    ("total-records", "added-records", "deleted-records"),
+    ("total-data-files", "added-data-files", "deleted-data-files"),
+    ("total-delete-files", "added-delete-files", "removed-delete-files"),
+    ("total-files-size", "added-files-size", "removed-files-size"),
+    ("total-position-deletes", "added-position-deletes", "removed-position-deletes"),
+    ("total-equality-deletes", "added-equality-deletes", "removed-equality-deletes"),
+];
+
+/// Swaps the metadata file an Iceberg table points at in the catalog tracking the table.
+#[async_trait]
+pub trait IcebergTableCommitter: Debug + Send + Sync {
+    /// Loads the table as currently committed in the catalog, with its metadata location.
+    async fn load_table(&self) -> Result<Table, DataFusionError>;
+
+    /// Points the catalog at `metadata_location` if it still points at the metadata `base`
+    /// was loaded from, returns false if another writer committed in between.
+    async fn swap_metadata_location(
+        &self,
+        base: &Table,
+        metadata_location: &str,
+    ) -> Result<bool, DataFusionError>;
+}
+
+/// The live files of the table a snapshot removes.
+#[derive(Debug, Clone)]
+pub enum RemovedFiles {
+    None,
+    /// Every data and delete file, e.g. for INSERT OVERWRITE
+    All,
+    /// The files at these paths, the commit fails if one is no longer live
+    Paths(HashSet<String>),
+}
+
+impl RemovedFiles {
+    fn contains(&self, path: &str) -> bool {
+        match self {
+            RemovedFiles::None => false,
+            RemovedFiles::All => true,
+            RemovedFiles::Paths(paths) => paths.contains(path),
+        }
+    }
+}
+
+/// The changes of a snapshot, applied on top of whatever the current snapshot is at commit
+/// time so they can be retried after a conflict.
+#[derive(Debug, Clone)]
+pub struct SnapshotUpdate {
+    pub operation: Operation,
+    /// Data and delete files written for the snapshot
+    pub added_files: Vec<DataFile>,
+    pub removed_files: RemovedFiles,
+    /// Data files the added delete files reference, the commit fails if one is no longer live
+    pub referenced_files: HashSet<String>,
+    /// Snapshot the removed and referenced files were read from, the commit fails if a later
+    /// snapshot added delete files for them
+    pub base_snapshot_id: Option<i64>,
+    /// Schema the added files were written with
+    pub schema_id: i32,
+    /// Partition spec the added files were written with
+    pub partition_spec_id: i32,
+}
+
+/// Changes to the metadata of a table, produced against the table current at commit time
+/// so they can be retried after a conflict.
+#[async_trait]
+pub trait TableChange: Send + Sync {
+    /// Returns the table updates applying the change to `base`, and the requirements
+    /// `base` has to meet for them.
+    async fn produce(
+        &self,
+        base: &Table,
+    ) -> Result<(Vec<TableUpdate>, Vec<TableRequirement>), DataFusionError>;
+}
+
+#[async_trait]
+impl TableChange for SnapshotUpdate {
+    async fn produce(
+        &self,
+        base: &Table,
+    ) -> Result<(Vec<TableUpdate>, Vec<TableRequirement>), DataFusionError> {
+        let metadata = base.metadata();
+        if metadata.current_schema_id() != self.schema_id
+            || metadata.default_partition_spec_id() != self.partition_spec_id
+        {
+            return Err(DataFusionError::Execution(format!(
+                "schema or partition spec of {} changed while writing it",
+                base.identifier().name()
+            )));
+        }
+        produce_snapshot(base, self).await
+    }
+}
+
+/// Commits `update` as a new snapshot of the main branch, retrying with the refreshed
+/// table while other writers commit concurrently. Returns the committed table.
+pub async fn commit_snapshot(
+    committer: &dyn IcebergTableCommitter,
+    update: &SnapshotUpdate,
+) -> Result<Table, DataFusionError> {
+    commit_table_change(committer, update).await
+}
+
+/// Commits `change`, retrying with the refreshed table while other writers commit
+/// concurrently. Returns the committed table.
+pub async fn commit_table_change(
+    committer: &dyn IcebergTableCommitter,
+    change: &dyn TableChange,
+) -> Result<Table, DataFusionError> {
+    let mut retries = 0;
+    loop {
+        let base = committer.load_table().await?;
+        let (updates, requirements) = change.produce(&base).await?;
+        let metadata = apply_updates(&base, updates, requirements)?;
+        let metadata_location =
+            write_metadata(base.file_io(), base.metadata_location(), &metadata).await?;
+        if committer
+            .swap_metadata_location(&base, &metadata_location)
+            .await?
+        {
+            return Table::builder()
+                .metadata(metadata)
+                .metadata_location(metadata_location)
+                .identifier(base.identifier().clone())
+                .file_io(base.file_io().clone())
+                .build()
+                .map_err(to_datafusion_error);
+        }
+
+        let properties = base.metadata().properties();
+        let num_retries = properties
+            .get(COMMIT_NUM_RETRIES)
+            .and_then(|retries| retries.parse().ok())
+            .unwrap_or(COMMIT_NUM_RETRIES_DEFAULT);
+        if retries >= num_retries {
+            return Err(DataFusionError::Execution(format!(
+                "failed to commit {} after {} retries, other writers keep committing",
+                base.identifier().name(),
+                retries
+            )));
+        }
+        let min_wait_ms = properties
+            .get(COMMIT_MIN_RETRY_WAIT_MS)
+            .and_then(|wait| wait.parse().ok())
+            .unwrap_or(COMMIT_MIN_RETRY_WAIT_MS_DEFAULT);
+        retries += 1;
+        println!(
+            "commit of {} conflicts with another writer, retry {}/{}",
+            base.identifier().name(),
+            retries,
+            num_retries
+        );
+        tokio::time::sleep(Duration::from_millis(min_wait_ms << (retries - 1))).await;
+    }
+}
+
+/// Field id of the `file_path` column of position delete files.
+const POSITION_DELETE_FILE_PATH_ID: i32 = 2147483546;
+
+/// Writes the manifests and manifest list of a snapshot applying `update` to the current
+/// snapshot of `base`, returning the table updates and requirements adding it.
+async fn produce_snapshot(
+    base: &Table,
+    update: &SnapshotUpdate,
+) -> Result<(Vec<TableUpdate>, Vec<TableRequirement>), DataFusionError> {
+    let metadata = base.metadata();
+    let file_io = base.file_io();
+    let mut new_snapshot = NewSnapshot::new(metadata);
+    let snapshot_id = new_snapshot.snapshot_id;
+    let sequence_number = new_snapshot.sequence_number;
+    let schema = metadata.current_schema().clone();
+    let added_spec = partition_spec(metadata, update.partition_spec_id)?;
+
+    let mut summary = SnapshotSummaryCollector::default();
+    let mut manifests: Vec<ManifestFile> = Vec::new();
+    let mut next_manifest_path = || new_snapshot.next_manifest_path(metadata);
+
+    let (added_data_files, added_delete_files): (Vec<&DataFile>, Vec<&DataFile>) = update
+        .added_files
+        .iter()
+        .partition(|file| file.content_type() == DataContentType::Data);
+    for (content, files) in [
+        (ManifestContentType::Data, added_data_files),
+        (ManifestContentType::Deletes, added_delete_files),
+    ] {
+        if files.is_empty() {
+            continue;
+        }
+        let mut writer =
+            new_manifest_writer(base, next_manifest_path(), snapshot_id, content, &added_spec)?;
+        for file in files {
+            writer
+                .add_file(file.clone(), sequence_number)
+                .map_err(to_datafusion_error)?;
+            summary.add_file(file, schema.clone(), added_spec.clone());
+        }
+        manifests.push(writer.write_manifest_file().await.map_err(to_datafusion_error)?);
+    }
+
+    // carry over the manifests of the current snapshot, rewriting the ones with removed
+    // files so those are tracked as deleted
+    let mut removed_paths: HashSet<String> = HashSet::new();
+    let mut referenced_paths: HashSet<String> = HashSet::new();
+    // partition spec and partition of the removed and referenced data files by path
+    let mut changed_files: HashMap<String, (i32, Struct)> = HashMap::new();
+    if let Some(current_snapshot) = metadata.current_snapshot() {
+        let manifest_list = current_snapshot
+            .load_manifest_list(file_io, metadata)
+            .await
+            .map_err(to_datafusion_error)?;
+        for manifest_file in manifest_list.entries() {
+            let has_referenced_files = !update.referenced_files.is_empty()
+                && manifest_file.content == ManifestContentType::Data;
+            if matches!(update.removed_files, RemovedFiles::None) && !has_referenced_files {
+                manifests.push(manifest_file.clone());
+                continue;
+            }
+            let manifest = manifest_file
+                .load_manifest(file_io)
+                .await
+                .map_err(to_datafusion_error)?;
+            referenced_paths.extend(
+                manifest
+                    .entries()
+                    .iter()
+                    .filter(|entry| entry.is_alive() && update.referenced_files.contains(entry.file_path()))
+                    .map(|entry| entry.file_path().to_string()),
+            );
+            if manifest_file.content == ManifestContentType::Data {
+                for entry in manifest.entries().iter().filter(|entry| entry.is_alive()) {
+                    let changed = update.referenced_files.contains(entry.file_path())
+                        || matches!(&update.removed_files, RemovedFiles::Paths(paths) if paths.contains(entry.file_path()));
+                    if changed {
+                        let partition = entry.data_file().partition().clone();
+                        changed_files.insert(entry.file_path().to_string(), (manifest_file.partition_spec_id, partition));
+                    }
+                }
+            }
+            let has_removed_files = manifest
+                .entries()
+                .iter()
+                .any(|entry| entry.is_alive() && update.removed_files.contains(entry.file_path()));
+            if !has_removed_files {
+                manifests.push(manifest_file.clone());
+                continue;
+            }
+
+            let manifest_spec = partition_spec(metadata, manifest_file.partition_spec_id)?;
+            let mut writer = new_manifest_writer(
+                base,
+                next_manifest_path(),
+                snapshot_id,
+                manifest_file.content,
+                &manifest_spec,
+            )?;
+            for entry in manifest.entries().iter().filter(|entry| entry.is_alive()) {
+                let data_sequence_number = entry
+                    .sequence_number()
+                    .unwrap_or(manifest_file.sequence_number);
+                if update.removed_files.contains(entry.file_path()) {
+                    writer
+                        .add_delete_file(
+                            entry.data_file().clone(),
+                            data_sequence_number,
+                            entry.file_sequence_number,
+                        )
+                        .map_err(to_datafusion_error)?;
+                    summary.remove_file(entry.data_file(), schema.clone(), manifest_spec.clone());
+                    removed_paths.insert(entry.file_path().to_string());
+                } else {
+                    writer
+                        .add_existing_file(
+                            entry.data_file().clone(),
+                            entry.snapshot_id().unwrap_or(manifest_file.added_snapshot_id),
+                            data_sequence_number,
+                            entry.file_sequence_number,
+                        )
+                        .map_err(to_datafusion_error)?;
+                }
+            }
+            manifests.push(writer.write_manifest_file().await.map_err(to_datafusion_error)?);
+        }
+    }
+    if let Some(missing) = update
+        .referenced_files
+        .iter()
+        .find(|path| !referenced_paths.contains(*path))
+    {
+        return Err(DataFusionError::Execution(format!(
+            "data file {} of {} was removed by a concurrent commit",
+            missing,
+            base.identifier().name()
+        )));
+    }
+    if let RemovedFiles::Paths(paths) = &update.removed_files
+        && let Some(missing) = paths.iter().find(|path| !removed_paths.contains(*path))
+    {
+        return Err(DataFusionError::Execution(format!(
+            "file {} of {} was removed by a concurrent commit",
+            missing,
+            base.identifier().name()
+        )));
+    }
+    validate_no_conflicting_deletes(base, update.base_snapshot_id, &changed_files).await?;
+
+    let truncated = matches!(update.removed_files, RemovedFiles::All);
+    new_snapshot
+        .add(base, manifests, update.operation.clone(), summary.build(), truncated)
+        .await
+}
+
+/// Fails if a snapshot committed after `base_snapshot_id` added delete files that may delete
+/// rows of `files`, given by path with their partition spec and partition. Rewriting such a
+/// file would restore the deleted rows, and deleting its rows again would change them twice.
+async fn validate_no_conflicting_deletes(
+    base: &Table,
+    base_snapshot_id: Option<i64>,
+    files: &HashMap<String, (i32, Struct)>,
+) -> Result<(), DataFusionError> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let metadata = base.metadata();
+    let file_io = base.file_io();
+    let mut snapshot = metadata.current_snapshot();
+    while let Some(current) = snapshot.filter(|s| Some(s.snapshot_id()) != base_snapshot_id) {
+        let manifest_list = current
+            .load_manifest_list(file_io, metadata)
+            .await
+            .map_err(to_datafusion_error)?;
+        for manifest_file in manifest_list.entries().iter().filter(|manifest_file| {
+            manifest_file.content == ManifestContentType::Deletes
+                && manifest_file.added_snapshot_id == current.snapshot_id()
+        }) {
+            let manifest = manifest_file
+                .load_manifest(file_io)
+                .await
+                .map_err(to_datafusion_error)?;
+            for entry in manifest.entries().iter().filter(|entry| entry.status() == ManifestStatus::Added) {
+                let conflict = files.iter().find(|(path, (spec_id, partition))| {
+                    may_delete_rows(metadata, manifest_file.partition_spec_id, entry.data_file(), path, *spec_id, partition)
+                });
+                if let Some((path, _)) = conflict {
+                    return Err(DataFusionError::Execution(format!(
+                        "rows of data file {} of {} were deleted by a concurrent commit",
+                        path,
+                        base.identifier().name()
+                    )));
+                }
+            }
+        }
+        snapshot = current
+            .parent_snapshot_id()
+            .and_then(|parent_id| metadata.snapshot_by_id(parent_id));
+    }
+    Ok(())
+}
+
+/// Whether `delete_file` of the partition spec `delete_spec_id` may delete rows of the data
+/// file at `path`. Position deletes are matched by the data file they reference or the
+/// bounds of their file paths, equality deletes by partition.
+fn may_delete_rows(
+    metadata: &TableMetadata,
+    delete_spec_id: i32,
+    delete_file: &DataFile,
+    path: &str,
+    spec_id: i32,
+    partition: &Struct,
+) -> bool {
+    let same_partition = delete_spec_id == spec_id && delete_file.partition() == partition;
+    match delete_file.content_type() {
+        DataContentType::PositionDeletes => {
+            if let Some(referenced_path) = delete_file.referenced_data_file() {
+                return referenced_path == path;
+            }
+            let bound = |bounds: &HashMap<i32, Datum>| match bounds
+                .get(&POSITION_DELETE_FILE_PATH_ID)
+                .map(|datum| datum.literal())
+            {
+                Some(PrimitiveLiteral::String(bound)) => Some(bound.clone()),
+                _ => None,
+            };
+            let in_bounds = bound(delete_file.lower_bounds()).is_none_or(|lower| lower.as_str() <= path)
+                && bound(delete_file.upper_bounds()).is_none_or(|upper| path <= upper.as_str());
+            same_partition && in_bounds
+        }
+        DataContentType::EqualityDeletes => {
+            same_partition
+                || metadata
+                    .partition_spec_by_id(delete_spec_id)
+                    .is_some_and(|spec| spec.is_unpartitioned())
+        }
+        DataContentType::Data => false,
+    }
+}
+
+/// Ids and file names of a snapshot being produced.
+pub(crate) struct NewSnapshot {
+    pub(crate) snapshot_id: i64,
+    pub(crate) sequence_number: i64,
+    commit_uuid: Uuid,
+    manifest_count: u32,
+}
+
+impl NewSnapshot {
+    pub(crate) fn new(metadata: &TableMetadata) -> Self {
+        NewSnapshot {
+            snapshot_id: generate_snapshot_id(metadata),
+            sequence_number: metadata.next_sequence_number(),
+            commit_uuid: Uuid::new_v4(),
+            manifest_count: 0,
+        }
+    }
+
+    /// Location of the next manifest written for the snapshot.
+    pub(crate) fn next_manifest_path(&mut self, metadata: &TableMetadata) -> String {
+        let path = format!(
+            "{}/metadata/{}-m{}.avro",
+            metadata.location(),
+            self.commit_uuid,
+            self.manifest_count
+        );
+        self.manifest_count += 1;
+        path
+    }
+
+    /// Writes the manifest list of the snapshot with `manifests` and returns the table
+    /// updates and requirements making it the current snapshot of the main branch.
+    ///
+    /// The totals of the summary are the totals of the current snapshot, or zero if the
+    /// snapshot is `truncated`, plus the added and minus the removed counts of
+    /// `summary_properties`.
+    pub(crate) async fn add(
+        &self,
+        base: &Table,
+        manifests: Vec<ManifestFile>,
+        operation: Operation,
+        mut summary_properties: HashMap<String, String>,
+        truncated: bool,
+    ) -> Result<(Vec<TableUpdate>, Vec<TableRequirement>), DataFusionError> {
+        let metadata = base.metadata();
+        let snapshot_id = self.snapshot_id;
+        let sequence_number = self.sequence_number;
+        let manifest_list_path = format!(
+            "{}/metadata/snap-{}-0-{}.avro",
+            metadata.location(),
+            snapshot_id,
+            self.commit_uuid
+        );
+        let output = base
+            .file_io()
+            .new_output(&manifest_list_path)
+            .map_err(to_datafusion_error)?;
+        let parent_snapshot_id = metadata.current_snapshot_id();
+        let mut manifest_list_writer = match metadata.format_version() {
+            FormatVersion::V1 => ManifestListWriter::v1(output, snapshot_id, parent_snapshot_id),
+            FormatVersion::V2 => {
+                ManifestListWriter::v2(output, snapshot_id, parent_snapshot_id, sequence_number)
+            }
+        };
+        manifest_list_writer
+            .add_manifests(manifests.into_iter())
+            .map_err(to_datafusion_error)?;
+        manifest_list_writer.close().await.map_err(to_datafusion_error)?;
+
+        let previous_summary = metadata
+            .current_snapshot()
+            .map(|snapshot| &snapshot.summary().additional_properties);
+        for (total, added, removed) in SUMMARY_TOTALS {
+            let value = |properties: Option<&HashMap<String, String>>, key: &str| -> i64 {
+                properties
+                    .and_then(|properties| properties.get(key))
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(0)
+            };
+            let previous_total = if truncated { 0 } else { value(previous_summary, total) };
+            let removed_count = if truncated { 0 } else { value(Some(&summary_properties), removed) };
+            let total_count = previous_total + value(Some(&summary_properties), added) - removed_count;
+            summary_properties.insert(total.to_string(), total_count.max(0).to_string());
+        }
+
+        let snapshot = Snapshot::builder()
+            .with_manifest_list(manifest_list_path)
+            .with_snapshot_id(snapshot_id)
+            .with_parent_snapshot_id(parent_snapshot_id)
+            .with_sequence_number(sequence_number)
+            .with_summary(Summary {
+                operation,
+                additional_properties: summary_properties,
+            })
+            .with_schema_id(metadata.current_schema_id())
+            .with_timestamp_ms(chrono::Utc::now().timestamp_millis())
+            .build();
+        let updates = vec![
+            TableUpdate::AddSnapshot { snapshot },
+            TableUpdate::SetSnapshotRef {
+                ref_name: MAIN_BRANCH.to_string(),
+                reference: SnapshotReference::new(
+                    snapshot_id,
+                    SnapshotRetention::branch(None, None, None),
+                ),
+            },
+        ];
+        let requirements = vec![
+            TableRequirement::UuidMatch {
+                uuid: metadata.uuid(),
+            },
+            TableRequirement::RefSnapshotIdMatch {
+                r#ref: MAIN_BRANCH.to_string(),
+                snapshot_id: parent_snapshot_id,
+            },
+        ];
+        Ok((updates, requirements))
+    }
+}
+
+pub(crate) fn new_manifest_writer(
+    base: &Table,
+    path: String,
+    snapshot_id: i64,
+    content: ManifestContentType,
+    partition_spec: &PartitionSpecRef,
+) -> Result<ManifestWriter, DataFusionError> {
+    let output = base.file_io().new_output(path).map_err(to_datafusion_error)?;
+    let builder = ManifestWriterBuilder::new(
+        output,
+        Some(snapshot_id),
+        None,
+        base.metadata().current_schema().clone(),
+        partition_spec.as_ref().clone(),
+    );
+    Ok(match (base.metadata().format_version(), content) {
+        (FormatVersion::V1, _) => builder.build_v1(),
+        (FormatVersion::V2, ManifestContentType::Data) => builder.build_v2_data(),
+        (FormatVersion::V2, ManifestContentType::Deletes) => builder.build_v2_deletes(),
+    })
+}
+
+pub(crate) fn partition_spec(
+    metadata: &TableMetadata,
+    spec_id: i32,
+) -> Result<PartitionSpecRef, DataFusionError> {
+    metadata.partition_spec_by_id(spec_id).cloned().ok_or_else(|| {
+        DataFusionError::Internal(format!("partition spec {spec_id} not found in table metadata"))
+    })
+}
+
+/// A random positive snapshot id not used by the table yet.
+fn generate_snapshot_id(metadata: &TableMetadata) -> i64 {
+    loop {
+        let (high, low) = Uuid::new_v4().as_u64_pair();
+        let snapshot_id = ((high ^ low) as i64).wrapping_abs();
+        if snapshot_id > 0 && metadata.snapshot_by_id(snapshot_id).is_none() {
+            return snapshot_id;
+        }
+    }
+}
+
+/// Checks `requirements` against the metadata of `base` and applies `updates` to it.
+pub fn apply_updates(
+    base: &Table,
+    updates: Vec<TableUpdate>,
+    requirements: Vec<TableRequirement>,
+) -> Result<TableMetadata, DataFusionError> {
+    for requirement in requirements {
+        requirement
+            .check(Some(base.metadata()))
+            .map_err(to_datafusion_error)?;
+    }
+    let mut builder = base
+        .metadata()
+        .clone()
+        .into_builder(base.metadata_location().map(|location| location.to_string()));
+    for update in updates {
+        builder = update.apply(builder).map_err(to_datafusion_error)?;
+    }
+    Ok(builder.build().map_err(to_datafusion_error)?.metadata)
+}
+
+/// Writes `metadata` as the version following the metadata file at `previous_location`,
+/// `<version>-<uuid>.metadata.json` under the table location, and returns its location.
+/// The first version of a new table has no previous location.
+pub async fn write_metadata(
+    file_io: &FileIO,
+    previous_location: Option<&str>,
+    metadata: &TableMetadata,
+) -> Result<String, DataFusionError> {
+    let version = previous_location
+        .and_then(parse_metadata_file_version)
+        .map_or(0, |version| version + 1);
+    let location = format!(
+        "{}/metadata/{:05}-{}.metadata.json",
+        metadata.location(),
+        version,
+        Uuid::new_v4()
+    );
+    let content = serde_json::to_vec(metadata).map_err(|e| DataFusionError::External(Box::new(e)))?;
+    file_io
+        .new_output(&location)
+        .map_err(to_datafusion_error)?
+        .write(content.into())
+        .await
+        .map_err(to_datafusion_error)?;
+    Ok(location)
+}
+
+/// Deletes every file reachable from the metadata of `table`: data and delete files of all
+/// snapshots, their manifests and manifest lists, statistics files and the metadata files
+/// in the metadata log. Files written by failed commits are not referenced and are kept.
+pub async fn purge_table_files(table: &Table) -> Result<(), DataFusionError> {
+    let metadata = table.metadata();
+    let file_io = table.file_io();
+    let mut files: HashSet<String> = HashSet::new();
+    for snapshot in metadata.snapshots() {
+        let manifest_list = snapshot
+            .load_manifest_list(file_io, metadata)
+            .await
+            .map_err(to_datafusion_error)?;
+        for manifest_file in manifest_list.entries() {
+            if !files.insert(manifest_file.manifest_path.clone()) {
+                continue;
+            }
+            let manifest = manifest_file
+                .load_manifest(file_io)
+                .await
+                .map_err(to_datafusion_error)?;
+            files.extend(manifest.entries().iter().map(|entry| entry.file_path().to_string()));
+        }
+        files.insert(snapshot.manifest_list().to_string());
+    }
+    files.extend(
+        metadata
+            .statistics_iter()
+            .map(|statistics| statistics.statistics_path.clone()),
+    );
+    files.extend(
+        metadata
+            .metadata_log()
+            .iter()
+            .map(|log| log.metadata_file.clone()),
+    );
+    files.extend(table.metadata_location().map(|location| location.to_string()));
+
+    println!(
+        "purge {} files of table {}",
+        files.len(),
+        table.identifier().name()
+    );
+    for file in files {
+        file_io.delete(&file).await.map_err(to_datafusion_error)?;
+    }
+    Ok(())
+}
+
+/// Parses the version of a metadata file named `<version>-<uuid>.metadata.json` or
+/// `v<version>.metadata.json`.
+fn parse_metadata_file_version(location: &str) -> Option<u64> {
+    let file_name = location.rsplit('/').next()?;
+    let name = file_name.strip_suffix(".metadata.json")?;
+    let version = match name.split_once('-') {
+        Some((version, _)) => version,
+        None => name.strip_prefix('v')?,
+    };
+    version.parse().ok()
+}
+
+pub(crate) fn to_datafusion_error(error: iceberg::Error) -> DataFusionError {
+    DataFusionError::External(Box::new(error))
+}
+
+/// Committers for tests, also of the crates using this one with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing {
+    use super::*;
+    use iceberg::io::FileIOBuilder;
+    use iceberg::spec::{
+        FormatVersion, NestedField, PrimitiveType, Schema, SortOrder, TableMetadataBuilder, Type,
+        UnboundPartitionSpec,
+    };
+    use iceberg::TableIdent;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    /// A directory under the temp dir of the system, removed again when dropped so a failed
+    /// test doesn't leave it behind.
+    #[derive(Debug)]
+    pub struct TestDir(PathBuf);
+
+    impl TestDir {
+        /// A directory named after `name` and the process, emptied of what an earlier run
+        /// may have left.
+        pub fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("dobbydb-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            TestDir(path)
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// The columns of the tables of [`empty_table`], a required `id` and an optional
+    /// `region`.
+    pub fn orders_schema() -> Schema {
+        Schema::builder()
+            .with_fields(vec![
+                NestedField::required(1, "id", Type::Primitive(PrimitiveType::Long)).into(),
+                NestedField::optional(2, "region", Type::Primitive(PrimitiveType::String)).into(),
+            ])
+            .build()
+            .expect("valid schema")
+    }
+
+    /// Metadata of a format version 2 table at `location` without snapshots.
+    pub fn table_metadata(
+        location: &Path,
+        schema: Schema,
+        partition_spec: UnboundPartitionSpec,
+        properties: HashMap<String, String>,
+    ) -> Result<TableMetadata, DataFusionError> {
+        TableMetadataBuilder::new(
+            schema,
+            partition_spec,
+            SortOrder::unsorted_order(),
+            location.display().to_string(),
+            FormatVersion::V2,
+            properties,
+        )
+        .and_then(|builder| builder.build())
+        .map(|build| build.metadata)
+        .map_err(to_datafusion_error)
+    }
+
+    /// Creates a table without snapshots at `location`, returning its committer and the
+    /// table.
+    pub async fn create_table(
+        location: &Path,
+        schema: Schema,
+        partition_spec: UnboundPartitionSpec,
+        properties: HashMap<String, String>,
+    ) -> Result<(Arc<MemoryCommitter>, Table), DataFusionError> {
+        let metadata = table_metadata(location, schema, partition_spec, properties)?;
+        let committer = Arc::new(MemoryCommitter::create(&metadata).await?);
+        let table = committer.load_table().await?;
+        Ok((committer, table))
+    }
+
+    /// Creates an unpartitioned table of [`orders_schema`] without snapshots at `dir`.
+    pub async fn empty_table(
+        dir: &TestDir,
+        properties: HashMap<String, String>,
+    ) -> Result<(Arc<MemoryCommitter>, Table), DataFusionError> {
+        create_table(dir.path(), orders_schema(), UnboundPartitionSpec::builder().build(), properties).await
+    }
+
+    /// Tracks the metadata location in memory, like a catalog would.
+    #[derive(Debug)]
+    pub struct MemoryCommitter {
+        file_io: FileIO,
+        metadata_location: Mutex<String>,
+    }
+
+    impl MemoryCommitter {
+        /// Creates a table on the local file system by writing its first metadata file
+        /// under the location of `metadata`.
+        pub async fn create(metadata: &TableMetadata) -> Result<Self, DataFusionError> {
+            let file_io = FileIOBuilder::new_fs_io().build().map_err(to_datafusion_error)?;
+            let metadata_location = format!("{}/metadata/00000-init.metadata.json", metadata.location());
+            file_io
+                .new_output(&metadata_location)
+                .map_err(to_datafusion_error)?
+                .write(serde_json::to_vec(metadata).map_err(|e| DataFusionError::External(Box::new(e)))?.into())
+                .await
+                .map_err(to_datafusion_error)?;
+            Ok(MemoryCommitter {
+                file_io,
+                metadata_location: Mutex::new(metadata_location),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl IcebergTableCommitter for MemoryCommitter {
+        async fn load_table(&self) -> Result<Table, DataFusionError> {
+            let location = self.metadata_location.lock().unwrap().clone();
+            let content = self
+                .file_io
+                .new_input(&location)
+                .map_err(to_datafusion_error)?
+                .read()
+                .await
+                .map_err(to_datafusion_error)?;
+            let metadata: TableMetadata =
+                serde_json::from_slice(&content).map_err(|e| DataFusionError::External(Box::new(e)))?;
+            Table::builder()
+                .metadata(metadata)
+                .metadata_location(location)
+                .identifier(TableIdent::from_strs(["sales", "orders"]).map_err(to_datafusion_error)?)
+                .file_io(self.file_io.clone())
+                .build()
+                .map_err(to_datafusion_error)
+        }
+
+        async fn swap_metadata_location(
+            &self,
+            base: &Table,
+            metadata_location: &str,
+        ) -> Result<bool, DataFusionError> {
+            let mut current = self.metadata_location.lock().unwrap();
+            if Some(current.as_str()) != base.metadata_location() {
+                return Ok(false);
+            }
+            *current = metadata_location.to_string();
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metadata_file_version() {
+        assert_eq!(
+            parse_metadata_file_version(
+                "s3://bucket/orders/metadata/00012-8a62c37d-4573-4021-952a-c0baef7d21d0.metadata.json"
+            ),
+            Some(12)
+        );
+        assert_eq!(
+            parse_metadata_file_version("/warehouse/sales/orders/metadata/v3.metadata.json"),
+            Some(3)
+        );
+        assert_eq!(parse_metadata_file_version("s3://bucket/orders/metadata/snap-1.avro"), None);
+    }
+}
@@ -0,0 +1,175 @@
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef, Fields, Schema, TimeUnit};
+use datafusion::common::plan_err;
+use datafusion::error::DataFusionError;
+use datafusion::parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+use iceberg::arrow::arrow_schema_to_schema;
+use iceberg::spec::{
+    FormatVersion, PartitionSpec, SortOrder, TableMetadata, TableMetadataBuilder, Transform,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Property giving the format version of a table being created, it is consumed when
+/// building the metadata instead of being kept as a table property.
+pub const FORMAT_VERSION: &str = "format-version";
+
+/// A `PARTITIONED BY` item of `CREATE TABLE`: a transform applied to a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionTransform {
+    pub column: String,
+    /// Iceberg transform, e.g. `identity`, `bucket[16]`, `truncate[4]` or `day`
+    pub transform: String,
+}
+
+/// Builds the metadata of a new Iceberg table at `location` with `schema`, partitioned
+/// by `partition_by` and no snapshot. Timestamps are stored with microsecond precision,
+/// nanosecond timestamps need format version 3.
+pub fn new_table_metadata(
+    schema: &Schema,
+    partition_by: &[PartitionTransform],
+    mut properties: HashMap<String, String>,
+    location: &str,
+) -> Result<TableMetadata, DataFusionError> {
+    let format_version = match properties.remove(FORMAT_VERSION).as_deref() {
+        None | Some("2") => FormatVersion::V2,
+        Some("1") => FormatVersion::V1,
+        Some(version) => return plan_err!("unsupported iceberg format version {version}"),
+    };
+
+    // the builder assigns fresh field ids, these only have to be unique
+    let mut next_field_id = 0;
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| with_field_ids(field, &mut next_field_id))
+        .collect::<Vec<Field>>();
+    let schema = Arc::new(
+        arrow_schema_to_schema(&Schema::new(fields))
+            .map_err(|e| DataFusionError::External(Box::new(e)))?,
+    );
+
+    let mut spec = PartitionSpec::builder(schema.clone());
+    for partition in partition_by {
+        let transform = Transform::from_str(&partition.transform)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        spec = spec
+            .add_partition_field(
+                &partition.column,
+                partition_field_name(&partition.column, transform),
+                transform,
+            )
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    }
+    let spec = spec
+        .build()
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    let metadata = TableMetadataBuilder::new(
+        schema.as_ref().clone(),
+        spec,
+        SortOrder::unsorted_order(),
+        location.trim_end_matches('/').to_string(),
+        format_version,
+        properties,
+    )
+    .and_then(|builder| builder.build())
+    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(metadata.metadata)
+}
+
+/// Partition field names Spark gives to the transforms, e.g. `id_bucket` for `bucket[16]` of `id`.
+fn partition_field_name(column: &str, transform: Transform) -> String {
+    match transform {
+        Transform::Identity => column.to_string(),
+        Transform::Bucket(_) => format!("{column}_bucket"),
+        Transform::Truncate(_) => format!("{column}_trunc"),
+        Transform::Year => format!("{column}_year"),
+        Transform::Month => format!("{column}_month"),
+        Transform::Day => format!("{column}_day"),
+        Transform::Hour => format!("{column}_hour"),
+        Transform::Void | Transform::Unknown => format!("{column}_null"),
+    }
+}
+
+/// Copies `field`, giving it and its nested fields an id and normalizing timestamps to
+/// microseconds in UTC.
+fn with_field_ids(field: &Field, next_field_id: &mut i32) -> Field {
+    *next_field_id += 1;
+    let field_id = *next_field_id;
+    let mut nested = |field: &FieldRef| Arc::new(with_field_ids(field, next_field_id));
+    let data_type = match field.data_type() {
+        DataType::Timestamp(_, timezone) => DataType::Timestamp(
+            TimeUnit::Microsecond,
+            timezone.as_ref().map(|_| "+00:00".into()),
+        ),
+        DataType::Struct(fields) => DataType::Struct(fields.iter().map(nested).collect::<Fields>()),
+        DataType::List(element) => DataType::List(nested(element)),
+        DataType::LargeList(element) => DataType::LargeList(nested(element)),
+        DataType::FixedSizeList(element, _) => DataType::List(nested(element)),
+        DataType::Map(entries, sorted) => DataType::Map(nested(entries), *sorted),
+        data_type => data_type.clone(),
+    };
+    field
+        .clone()
+        .with_data_type(data_type)
+        .with_metadata(HashMap::from([(
+            PARQUET_FIELD_ID_META_KEY.to_string(),
+            field_id.to_string(),
+        )]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iceberg::spec::{PrimitiveType, Type};
+
+    #[test]
+    fn test_new_table_metadata() -> Result<(), DataFusionError> {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("ts", DataType::Timestamp(TimeUnit::Nanosecond, None), true),
+            Field::new(
+                "tags",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                true,
+            ),
+        ]);
+        let partition_by = vec![
+            PartitionTransform {
+                column: "id".to_string(),
+                transform: "bucket[16]".to_string(),
+            },
+            PartitionTransform {
+                column: "ts".to_string(),
+                transform: "day".to_string(),
+            },
+        ];
+        let properties = HashMap::from([
+            (FORMAT_VERSION.to_string(), "1".to_string()),
+            ("write.parquet.compression-codec".to_string(), "zstd".to_string()),
+        ]);
+        let metadata = new_table_metadata(&schema, &partition_by, properties, "s3://bucket/orders/")?;
+
+        assert_eq!(metadata.format_version(), FormatVersion::V1);
+        assert_eq!(metadata.location(), "s3://bucket/orders");
+        assert!(metadata.current_snapshot().is_none());
+        assert!(!metadata.properties().contains_key(FORMAT_VERSION));
+        let ts = metadata.current_schema().field_by_name("ts").unwrap();
+        assert_eq!(*ts.field_type, Type::Primitive(PrimitiveType::Timestamp));
+        let partition_names = metadata
+            .default_partition_spec()
+            .fields()
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(partition_names, vec!["id_bucket", "ts_day"]);
+
+        let partition_by = vec![PartitionTransform {
+            column: "tags".to_string(),
+            transform: "identity".to_string(),
+        }];
+        assert!(new_table_metadata(&schema, &partition_by, HashMap::new(), "s3://bucket/t").is_err());
+        Ok(())
+    }
+}
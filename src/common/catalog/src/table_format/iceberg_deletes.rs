@@ -0,0 +1,646 @@
+use crate::table_format::iceberg_dml::{FILE_PATH_COLUMN, POSITION_COLUMN};
+use datafusion::arrow::array::{
+    ArrayRef, AsArray, BooleanArray, Int64Array, RecordBatch, RecordBatchOptions, StringArray,
+};
+use datafusion::arrow::compute::{cast, filter_record_batch};
+use datafusion::arrow::datatypes::{DataType, Field, Int64Type, Schema as ArrowSchema};
+use datafusion::arrow::row::{RowConverter, SortField};
+use datafusion::common::not_impl_err;
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use datafusion::parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+use futures::{Stream, StreamExt, TryFutureExt, TryStreamExt};
+use iceberg::arrow::{type_to_arrow_type, ArrowReader};
+use iceberg::io::FileIO;
+use iceberg::scan::{FileScanTask, FileScanTaskDeleteFile, FileScanTaskStream};
+use iceberg::spec::{
+    DataContentType, DataFile, DataFileFormat, ManifestContentType, ManifestFile, Schema, TableMetadata,
+};
+use iceberg::table::Table;
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// Number of manifests read at the same time, also by scan planning.
+pub(crate) const MANIFEST_CONCURRENCY: usize = 16;
+
+/// A live data or delete file of a snapshot, with what decides which deletes apply to it.
+struct TrackedFile {
+    sequence_number: i64,
+    partition_spec_id: i32,
+    data_file: DataFile,
+}
+
+/// Keeps only the delete files of `tasks` that apply to their data file and moves the
+/// equality delete files out of them into the returned [`ScanDeletes`], as the Iceberg
+/// reader only applies position deletes.
+///
+/// Scan planning attaches a superset of the delete files: it ignores partition specs and the
+/// data file a position delete file references, and applies global equality deletes to data
+/// files of the same sequence number. The manifests are read again to tell them apart.
+pub(crate) async fn resolve_deletes(
+    table: &Table,
+    snapshot_id: Option<i64>,
+    tasks: &mut [FileScanTask],
+) -> DFResult<ScanDeletes> {
+    let mut scan_deletes = ScanDeletes::default();
+    if tasks.iter().all(|task| task.deletes.is_empty()) {
+        return Ok(scan_deletes);
+    }
+
+    let metadata = table.metadata();
+    let file_io = table.file_io();
+    let snapshot = match snapshot_id {
+        Some(snapshot_id) => metadata.snapshot_by_id(snapshot_id),
+        None => metadata.current_snapshot(),
+    }
+    .ok_or_else(|| {
+        DataFusionError::Internal(format!(
+            "snapshot of {} with delete files not found",
+            table.identifier().name()
+        ))
+    })?;
+    let manifest_list = snapshot
+        .load_manifest_list(file_io, metadata)
+        .await
+        .map_err(to_datafusion_error)?;
+
+    // the data manifests are only read for the sequence numbers and partitions of the
+    // scanned data files, which the scan tasks don't carry
+    let (delete_manifests, data_manifests): (Vec<&ManifestFile>, Vec<&ManifestFile>) = manifest_list
+        .entries()
+        .iter()
+        .partition(|manifest_file| manifest_file.content == ManifestContentType::Deletes);
+    if delete_manifests.is_empty() {
+        return Ok(scan_deletes);
+    }
+    let scanned_paths: HashSet<&str> = tasks
+        .iter()
+        .filter(|task| !task.deletes.is_empty())
+        .map(|task| task.data_file_path.as_str())
+        .collect();
+    let (delete_files, data_files) = futures::try_join!(
+        load_tracked_files(&delete_manifests, file_io, |_| true),
+        load_tracked_files(&data_manifests, file_io, |path| scanned_paths.contains(path)),
+    )?;
+
+    for task in tasks.iter_mut().filter(|task| !task.deletes.is_empty()) {
+        let data_file = data_files.get(&task.data_file_path).ok_or_else(|| {
+            DataFusionError::Internal(format!("data file {} not found in manifests", task.data_file_path))
+        })?;
+        let mut position_delete_files = Vec::new();
+        let mut equality_delete_files = Vec::new();
+        for delete in std::mem::take(&mut task.deletes) {
+            let delete_file = delete_files.get(&delete.file_path).ok_or_else(|| {
+                DataFusionError::Internal(format!("delete file {} not found in manifests", delete.file_path))
+            })?;
+            if !applies_to(metadata, delete_file, data_file, &task.data_file_path) {
+                continue;
+            }
+            let format = delete_file.data_file.file_format();
+            if format != DataFileFormat::Parquet {
+                return not_impl_err!(
+                    "{} delete file {} of {} is not supported, only parquet delete files are",
+                    format,
+                    delete.file_path,
+                    table.identifier().name()
+                );
+            }
+            match delete.file_type {
+                DataContentType::EqualityDeletes => {
+                    check_equality_ids(&task.schema, &delete.equality_ids)?;
+                    equality_delete_files.push(delete);
+                }
+                _ => position_delete_files.push(delete),
+            }
+        }
+        task.deletes = position_delete_files;
+        if !equality_delete_files.is_empty() {
+            scan_deletes
+                .delete_files
+                .insert(task.data_file_path.clone(), equality_delete_files);
+        }
+    }
+    Ok(scan_deletes)
+}
+
+/// Reads `manifest_files` concurrently, returning their live files accepted by `keep` by path.
+async fn load_tracked_files(
+    manifest_files: &[&ManifestFile],
+    file_io: &FileIO,
+    keep: impl Fn(&str) -> bool,
+) -> DFResult<HashMap<String, TrackedFile>> {
+    // collected first, a lazily mapped iterator makes the scan futures not Send
+    let loads = manifest_files
+        .iter()
+        .map(|manifest_file| {
+            let partition_spec_id = manifest_file.partition_spec_id;
+            manifest_file
+                .load_manifest(file_io)
+                .map_ok(move |manifest| (partition_spec_id, manifest))
+        })
+        .collect::<Vec<_>>();
+    let manifests = futures::stream::iter(loads)
+        .buffer_unordered(MANIFEST_CONCURRENCY)
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(to_datafusion_error)?;
+
+    let mut files = HashMap::new();
+    for (partition_spec_id, manifest) in manifests {
+        for entry in manifest
+            .entries()
+            .iter()
+            .filter(|entry| entry.is_alive() && keep(entry.file_path()))
+        {
+            files.insert(
+                entry.file_path().to_string(),
+                TrackedFile {
+                    sequence_number: entry.sequence_number().unwrap_or_default(),
+                    partition_spec_id,
+                    data_file: entry.data_file().clone(),
+                },
+            );
+        }
+    }
+    Ok(files)
+}
+
+/// Whether the rows of `delete_file` apply to `data_file`. Position deletes apply to data
+/// files of the same partition committed up to the same snapshot, equality deletes to data
+/// files committed before them in the same partition or anywhere if they are unpartitioned.
+fn applies_to(
+    metadata: &TableMetadata,
+    delete_file: &TrackedFile,
+    data_file: &TrackedFile,
+    data_file_path: &str,
+) -> bool {
+    let same_partition = delete_file.partition_spec_id == data_file.partition_spec_id
+        && delete_file.data_file.partition() == data_file.data_file.partition();
+    match delete_file.data_file.content_type() {
+        DataContentType::PositionDeletes => {
+            delete_file.sequence_number >= data_file.sequence_number
+                && same_partition
+                && delete_file
+                    .data_file
+                    .referenced_data_file()
+                    .is_none_or(|path| path == data_file_path)
+        }
+        DataContentType::EqualityDeletes => {
+            delete_file.sequence_number > data_file.sequence_number
+                && (same_partition
+                    || metadata
+                        .partition_spec_by_id(delete_file.partition_spec_id)
+                        .is_some_and(|spec| spec.is_unpartitioned()))
+        }
+        DataContentType::Data => false,
+    }
+}
+
+/// Equality deletes are compared on top-level primitive columns only.
+fn check_equality_ids(schema: &Schema, equality_ids: &[i32]) -> DFResult<()> {
+    for field_id in equality_ids {
+        match schema.as_struct().field_by_id(*field_id) {
+            Some(field) if field.field_type.is_primitive() => {}
+            Some(field) => {
+                return not_impl_err!("equality deletes on column {} are not supported", field.name)
+            }
+            None => {
+                return not_impl_err!(
+                    "equality deletes on field {} are not supported, it is not a top-level column",
+                    field_id
+                )
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Delete files loaded once per scan, by path, and shared by the data files they apply to.
+type LoadedFiles<T> = Mutex<HashMap<String, Arc<OnceCell<Arc<T>>>>>;
+
+/// The deletes of the data files of a scan the Iceberg reader does not apply itself: the
+/// equality deletes, and the position deletes when rows are read with their position.
+#[derive(Debug, Default)]
+pub(crate) struct ScanDeletes {
+    /// Equality delete files applying to each data file
+    delete_files: HashMap<String, Vec<FileScanTaskDeleteFile>>,
+    equality_deletes: LoadedFiles<EqualityDeleteSet>,
+    /// Deleted positions of every data file a position delete file references
+    position_deletes: LoadedFiles<HashMap<String, Vec<i64>>>,
+}
+
+impl ScanDeletes {
+    /// Whether equality deletes apply to `task`, it then has to be read with [`Self::read`].
+    pub(crate) fn applies_to(&self, task: &FileScanTask) -> bool {
+        self.delete_files.contains_key(&task.data_file_path)
+    }
+
+    /// Reads `task` with `reader`, dropping the rows its equality delete files match. With
+    /// `row_positions` its position deletes are applied here as well, so the position of
+    /// every row in the data file is known, and the [`FILE_PATH_COLUMN`] and
+    /// [`POSITION_COLUMN`] columns are appended.
+    pub(crate) async fn read(
+        &self,
+        reader: ArrowReader,
+        mut task: FileScanTask,
+        file_io: &FileIO,
+        row_positions: bool,
+    ) -> DFResult<Pin<Box<dyn Stream<Item = DFResult<RecordBatch>> + Send>>> {
+        let mut delete_sets = Vec::new();
+        for delete_file in self.delete_files.get(&task.data_file_path).into_iter().flatten() {
+            delete_sets.push(self.load_equality_deletes(delete_file, &task.schema, file_io).await?);
+        }
+        let mut deleted_positions: HashSet<i64> = HashSet::new();
+        if row_positions {
+            for delete_file in std::mem::take(&mut task.deletes) {
+                let positions = self.load_position_deletes(&delete_file, file_io).await?;
+                deleted_positions.extend(positions.get(&task.data_file_path).into_iter().flatten());
+            }
+            // rows are counted from the start of the file, the predicate would skip some
+            task.predicate = None;
+        }
+
+        // the equality columns are read along with the projected ones and dropped after
+        let projected_count = task.project_field_ids.len();
+        let key_columns = delete_sets
+            .iter()
+            .map(|delete_set| {
+                delete_set
+                    .field_ids
+                    .iter()
+                    .map(|field_id| {
+                        match task.project_field_ids.iter().position(|id| id == field_id) {
+                            Some(index) => index,
+                            None => {
+                                task.project_field_ids.push(*field_id);
+                                task.project_field_ids.len() - 1
+                            }
+                        }
+                    })
+                    .collect::<Vec<usize>>()
+            })
+            .collect::<Vec<Vec<usize>>>();
+
+        let data_file_path = task.data_file_path.clone();
+        let mut next_position: i64 = 0;
+        let tasks: FileScanTaskStream = Box::pin(futures::stream::iter([Ok(task)]));
+        let stream = reader
+            .read(tasks)
+            .await
+            .map_err(to_datafusion_error)?
+            .map_err(to_datafusion_error)
+            .map(move |batch: DFResult<RecordBatch>| -> DFResult<RecordBatch> {
+                let batch = batch?;
+                let row_count = batch.num_rows();
+                let positions =
+                    Int64Array::from_iter_values(next_position..next_position + row_count as i64);
+                next_position += row_count as i64;
+
+                let mut keep = positions
+                    .values()
+                    .iter()
+                    .map(|position| !deleted_positions.contains(position))
+                    .collect::<Vec<bool>>();
+                for (delete_set, columns) in delete_sets.iter().zip(&key_columns) {
+                    delete_set.mark_deleted(&batch, columns, &mut keep)?;
+                }
+                let mut batch = batch.project(&(0..projected_count).collect::<Vec<usize>>())?;
+                if row_positions {
+                    let mut fields = batch.schema().fields().to_vec();
+                    fields.push(Arc::new(Field::new(FILE_PATH_COLUMN, DataType::Utf8, false)));
+                    fields.push(Arc::new(Field::new(POSITION_COLUMN, DataType::Int64, false)));
+                    let mut columns = batch.columns().to_vec();
+                    columns.push(Arc::new(StringArray::from_iter_values(
+                        std::iter::repeat_n(data_file_path.as_str(), row_count),
+                    )));
+                    columns.push(Arc::new(positions));
+                    batch = RecordBatch::try_new_with_options(
+                        Arc::new(ArrowSchema::new(fields)),
+                        columns,
+                        &RecordBatchOptions::new().with_row_count(Some(row_count)),
+                    )?;
+                }
+                Ok(filter_record_batch(&batch, &BooleanArray::from(keep))?)
+            });
+        Ok(Box::pin(stream))
+    }
+
+    async fn load_equality_deletes(
+        &self,
+        delete_file: &FileScanTaskDeleteFile,
+        schema: &Schema,
+        file_io: &FileIO,
+    ) -> DFResult<Arc<EqualityDeleteSet>> {
+        let cell = self
+            .equality_deletes
+            .lock()
+            .unwrap()
+            .entry(delete_file.file_path.clone())
+            .or_default()
+            .clone();
+        cell.get_or_try_init(|| EqualityDeleteSet::load(delete_file, schema, file_io))
+            .await
+            .cloned()
+    }
+
+    async fn load_position_deletes(
+        &self,
+        delete_file: &FileScanTaskDeleteFile,
+        file_io: &FileIO,
+    ) -> DFResult<Arc<HashMap<String, Vec<i64>>>> {
+        let cell = self
+            .position_deletes
+            .lock()
+            .unwrap()
+            .entry(delete_file.file_path.clone())
+            .or_default()
+            .clone();
+        cell.get_or_try_init(|| async {
+            let mut positions: HashMap<String, Vec<i64>> = HashMap::new();
+            for batch in read_parquet_file(&delete_file.file_path, file_io).await? {
+                let batch = batch?;
+                if batch.num_columns() < 2 {
+                    return Err(DataFusionError::Execution(format!(
+                        "position delete file {} has no file_path and pos columns",
+                        delete_file.file_path
+                    )));
+                }
+                let file_paths = cast(batch.column(0), &DataType::Utf8)?;
+                let file_paths = file_paths.as_string::<i32>();
+                let deleted_positions = cast(batch.column(1), &DataType::Int64)?;
+                let deleted_positions = deleted_positions.as_primitive::<Int64Type>();
+                for (file_path, position) in file_paths.iter().zip(deleted_positions.iter()) {
+                    if let (Some(file_path), Some(position)) = (file_path, position) {
+                        positions.entry(file_path.to_string()).or_default().push(position);
+                    }
+                }
+            }
+            Ok(Arc::new(positions))
+        })
+        .await
+        .cloned()
+    }
+}
+
+/// Reads a whole Parquet delete file into memory and returns a reader of its batches.
+async fn read_parquet_file(path: &str, file_io: &FileIO) -> DFResult<ParquetRecordBatchReader> {
+    let content = file_io
+        .new_input(path)
+        .map_err(to_datafusion_error)?
+        .read()
+        .await
+        .map_err(to_datafusion_error)?;
+    Ok(ParquetRecordBatchReaderBuilder::try_new(content)?.build()?)
+}
+
+/// The keys of the rows an equality delete file deletes, in the row format of the columns.
+#[derive(Debug)]
+struct EqualityDeleteSet {
+    field_ids: Vec<i32>,
+    data_types: Vec<DataType>,
+    converter: RowConverter,
+    keys: HashSet<Box<[u8]>>,
+}
+
+impl EqualityDeleteSet {
+    async fn load(
+        delete_file: &FileScanTaskDeleteFile,
+        schema: &Schema,
+        file_io: &FileIO,
+    ) -> DFResult<Arc<Self>> {
+        let data_types = delete_file
+            .equality_ids
+            .iter()
+            .map(|field_id| {
+                let field = schema.as_struct().field_by_id(*field_id).ok_or_else(|| {
+                    DataFusionError::Internal(format!("equality delete field {field_id} not found"))
+                })?;
+                type_to_arrow_type(&field.field_type).map_err(to_datafusion_error)
+            })
+            .collect::<DFResult<Vec<DataType>>>()?;
+        let converter = RowConverter::new(
+            data_types
+                .iter()
+                .map(|data_type| SortField::new(data_type.clone()))
+                .collect(),
+        )?;
+
+        let mut keys = HashSet::new();
+        for batch in read_parquet_file(&delete_file.file_path, file_io).await? {
+            let batch = batch?;
+            let columns = delete_file
+                .equality_ids
+                .iter()
+                .zip(&data_types)
+                .map(|(field_id, data_type)| {
+                    let field_id = field_id.to_string();
+                    let index = batch
+                        .schema()
+                        .fields()
+                        .iter()
+                        .position(|field| field.metadata().get(PARQUET_FIELD_ID_META_KEY) == Some(&field_id))
+                        .ok_or_else(|| {
+                            DataFusionError::Execution(format!(
+                                "equality delete file {} has no column with field id {}",
+                                delete_file.file_path, field_id
+                            ))
+                        })?;
+                    Ok(cast(batch.column(index), data_type)?)
+                })
+                .collect::<DFResult<Vec<ArrayRef>>>()?;
+            let rows = converter.convert_columns(&columns)?;
+            keys.extend(rows.iter().map(|row| Box::<[u8]>::from(row.as_ref())));
+        }
+
+        Ok(Arc::new(EqualityDeleteSet {
+            field_ids: delete_file.equality_ids.clone(),
+            data_types,
+            converter,
+            keys,
+        }))
+    }
+
+    /// Clears `keep` for the rows of `batch` whose values in `columns` match a deleted row.
+    fn mark_deleted(&self, batch: &RecordBatch, columns: &[usize], keep: &mut [bool]) -> DFResult<()> {
+        let columns = columns
+            .iter()
+            .zip(&self.data_types)
+            .map(|(index, data_type)| cast(batch.column(*index), data_type))
+            .collect::<Result<Vec<ArrayRef>, _>>()?;
+        let rows = self.converter.convert_columns(&columns)?;
+        for (keep, row) in keep.iter_mut().zip(rows.iter()) {
+            if self.keys.contains(row.as_ref()) {
+                *keep = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_datafusion_error(error: iceberg::Error) -> DataFusionError {
+    DataFusionError::External(Box::new(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_format::external_table::IcebergTableScan;
+    use crate::table_format::iceberg_commit::testing::{create_table, MemoryCommitter, TestDir};
+    use crate::table_format::iceberg_commit::{commit_snapshot, RemovedFiles, SnapshotUpdate};
+    use datafusion::execution::TaskContext;
+    use datafusion::parquet::arrow::ArrowWriter;
+    use datafusion::physical_plan::collect;
+    use iceberg::arrow::schema_to_arrow_schema;
+    use iceberg::spec::{
+        DataFileBuilder, NestedField, Operation, PrimitiveType, Struct, Type, UnboundPartitionSpec,
+    };
+
+    fn field(name: &str, data_type: DataType, field_id: i32) -> Field {
+        Field::new(name, data_type, false).with_metadata(HashMap::from([(
+            PARQUET_FIELD_ID_META_KEY.to_string(),
+            field_id.to_string(),
+        )]))
+    }
+
+    /// Writes `batch` to a parquet file of `content` at `path` and returns the file.
+    async fn write_file(
+        table: &Table,
+        content: DataContentType,
+        path: String,
+        batch: RecordBatch,
+        equality_ids: Vec<i32>,
+    ) -> DFResult<DataFile> {
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        let file_size = buffer.len() as u64;
+        table
+            .file_io()
+            .new_output(&path)
+            .map_err(to_datafusion_error)?
+            .write(buffer.into())
+            .await
+            .map_err(to_datafusion_error)?;
+        DataFileBuilder::default()
+            .content(content)
+            .file_path(path)
+            .file_format(DataFileFormat::Parquet)
+            .partition(Struct::empty())
+            .record_count(batch.num_rows() as u64)
+            .file_size_in_bytes(file_size)
+            .equality_ids(equality_ids)
+            .partition_spec_id(0)
+            .build()
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+
+    async fn write_data_file(table: &Table, name: &str, rows: Vec<(i64, &str)>) -> DFResult<DataFile> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            field("id", DataType::Int64, 1),
+            field("name", DataType::Utf8, 2),
+        ]));
+        let (ids, names): (Vec<i64>, Vec<&str>) = rows.into_iter().unzip();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int64Array::from(ids)), Arc::new(StringArray::from(names))],
+        )?;
+        let path = format!("{}/data/{name}.parquet", table.metadata().location());
+        write_file(table, DataContentType::Data, path, batch, vec![]).await
+    }
+
+    async fn commit(committer: &MemoryCommitter, operation: Operation, files: Vec<DataFile>) -> DFResult<Table> {
+        let update = SnapshotUpdate {
+            operation,
+            added_files: files,
+            removed_files: RemovedFiles::None,
+            referenced_files: HashSet::new(),
+            base_snapshot_id: None,
+            schema_id: 0,
+            partition_spec_id: 0,
+        };
+        commit_snapshot(committer, &update).await
+    }
+
+    async fn scan(table: &Table, projection: Option<&Vec<usize>>) -> DFResult<Vec<RecordBatch>> {
+        let schema = schema_to_arrow_schema(table.metadata().current_schema()).map_err(to_datafusion_error)?;
+        let scan = IcebergTableScan::try_new(table.clone(), None, Arc::new(schema), projection, &[], None, 2, 1024).await?;
+        collect(Arc::new(scan), Arc::new(TaskContext::default())).await
+    }
+
+    #[tokio::test]
+    async fn test_scan_with_deletes() -> DFResult<()> {
+        let table_dir = TestDir::new("iceberg-deletes");
+        let schema = Schema::builder()
+            .with_fields(vec![
+                NestedField::required(1, "id", Type::Primitive(PrimitiveType::Long)).into(),
+                NestedField::required(2, "name", Type::Primitive(PrimitiveType::String)).into(),
+            ])
+            .build()
+            .map_err(to_datafusion_error)?;
+        let (committer, table) =
+            create_table(table_dir.path(), schema, UnboundPartitionSpec::builder().build(), HashMap::new()).await?;
+
+        let a = write_data_file(&table, "a", vec![(1, "a"), (2, "b"), (3, "c")]).await?;
+        let b = write_data_file(&table, "b", vec![(4, "d"), (5, "e"), (6, "f")]).await?;
+        let (a_path, b_path) = (a.file_path().to_string(), b.file_path().to_string());
+        let table = commit(&committer, Operation::Append, vec![a, b]).await?;
+
+        // one position delete file shared by both data files, deleting ids 1 and 6
+        let position_schema = Arc::new(ArrowSchema::new(vec![
+            field("file_path", DataType::Utf8, 2147483546),
+            field("pos", DataType::Int64, 2147483545),
+        ]));
+        let batch = RecordBatch::try_new(
+            position_schema,
+            vec![
+                Arc::new(StringArray::from(vec![a_path.as_str(), b_path.as_str()])),
+                Arc::new(Int64Array::from(vec![0, 2])),
+            ],
+        )?;
+        let path = format!("{}/data/position-deletes.parquet", table_dir.path().display());
+        let position_deletes = write_file(&table, DataContentType::PositionDeletes, path, batch, vec![]).await?;
+        let table = commit(&committer, Operation::Delete, vec![position_deletes]).await?;
+
+        // an update of id 5, its equality delete must not delete the new row written with it
+        let equality_schema = Arc::new(ArrowSchema::new(vec![field("id", DataType::Int64, 1)]));
+        let batch = RecordBatch::try_new(equality_schema, vec![Arc::new(Int64Array::from(vec![2, 5]))])?;
+        let path = format!("{}/data/equality-deletes.parquet", table_dir.path().display());
+        let equality_deletes = write_file(&table, DataContentType::EqualityDeletes, path, batch, vec![1]).await?;
+        let c = write_data_file(&table, "c", vec![(5, "updated")]).await?;
+        let table = commit(&committer, Operation::Overwrite, vec![equality_deletes, c]).await?;
+
+        let mut rows = Vec::new();
+        for batch in scan(&table, None).await? {
+            let ids = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+            let names = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+            rows.extend(ids.iter().zip(names.iter()).map(|(id, name)| (id.unwrap(), name.unwrap().to_string())));
+        }
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec![(3, "c".to_string()), (4, "d".to_string()), (5, "updated".to_string())]
+        );
+
+        // the equality column is read to apply the deletes but not returned
+        let batches = scan(&table, Some(&vec![1])).await?;
+        assert!(batches.iter().all(|batch| batch.num_columns() == 1));
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 3);
+
+        let orc_deletes = DataFileBuilder::default()
+            .content(DataContentType::PositionDeletes)
+            .file_path(format!("{}/data/position-deletes.orc", table_dir.path().display()))
+            .file_format(DataFileFormat::Orc)
+            .partition(Struct::empty())
+            .record_count(1)
+            .file_size_in_bytes(1)
+            .partition_spec_id(0)
+            .build()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let table = commit(&committer, Operation::Delete, vec![orc_deletes]).await?;
+        assert!(matches!(scan(&table, None).await, Err(DataFusionError::NotImplemented(_))));
+        Ok(())
+    }
+}
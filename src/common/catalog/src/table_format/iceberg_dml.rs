@@ -0,0 +1,673 @@
+use crate::table_format::expr_to_predicate::convert_filter_to_predicate;
+use crate::table_format::external_table::IcebergTableScan;
+use crate::table_format::iceberg_commit::{commit_snapshot, IcebergTableCommitter, RemovedFiles, SnapshotUpdate};
+use crate::table_format::iceberg_sink::IcebergDataSink;
+use async_trait::async_trait;
+use datafusion::arrow::array::{Array, AsArray, BooleanArray, RecordBatch};
+use datafusion::arrow::compute::{cast, filter_record_batch};
+use datafusion::arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::{not_impl_err, plan_err};
+use datafusion::datasource::sink::DataSink;
+use datafusion::datasource::TableType;
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::execution::SendableRecordBatchStream;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::ExecutionPlan;
+use futures::StreamExt;
+use iceberg::arrow::schema_to_arrow_schema;
+use iceberg::spec::{DataContentType, FormatVersion, ManifestContentType, Operation, Struct, TableMetadata};
+use iceberg::table::Table;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Column with the path of the data file a row is read from, in scans for row-level changes.
+pub const FILE_PATH_COLUMN: &str = "_file";
+/// Column with the position of a row in its data file.
+pub const POSITION_COLUMN: &str = "_pos";
+/// Column with the [`RowOperation`] of the rows written by [`IcebergRowLevelWriter`].
+pub const OPERATION_COLUMN: &str = "_operation";
+
+/// Table properties choosing between copy-on-write and merge-on-read.
+const WRITE_DELETE_MODE: &str = "write.delete.mode";
+const WRITE_UPDATE_MODE: &str = "write.update.mode";
+const WRITE_MERGE_MODE: &str = "write.merge.mode";
+
+/// A row-level change statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowLevelCommand {
+    Delete,
+    Update,
+    Merge,
+}
+
+/// How the rows changed by a [`RowLevelCommand`] are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowLevelMode {
+    /// The data files with changed rows are rewritten without the deleted rows
+    CopyOnWrite,
+    /// Changed rows are deleted with position delete files, updated rows are written to new
+    /// data files
+    MergeOnRead,
+}
+
+impl RowLevelMode {
+    /// The mode of `command` on a table, from its `write.<command>.mode` property,
+    /// copy-on-write by default like Spark.
+    pub fn for_command(metadata: &TableMetadata, command: RowLevelCommand) -> DFResult<Self> {
+        let property = match command {
+            RowLevelCommand::Delete => WRITE_DELETE_MODE,
+            RowLevelCommand::Update => WRITE_UPDATE_MODE,
+            RowLevelCommand::Merge => WRITE_MERGE_MODE,
+        };
+        match metadata.properties().get(property).map(|mode| mode.as_str()) {
+            None | Some("copy-on-write") => Ok(RowLevelMode::CopyOnWrite),
+            Some("merge-on-read") if metadata.format_version() == FormatVersion::V1 => {
+                plan_err!("{property} merge-on-read needs format version 2, the table has version 1")
+            }
+            Some("merge-on-read") => Ok(RowLevelMode::MergeOnRead),
+            Some(mode) => plan_err!("invalid {property} {mode}"),
+        }
+    }
+}
+
+/// What happens to a row given to [`IcebergRowLevelWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowOperation {
+    /// An unchanged row of the target table, written again if its data file is rewritten
+    Keep,
+    Delete,
+    Update,
+    Insert,
+}
+
+impl RowOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RowOperation::Keep => "keep",
+            RowOperation::Delete => "delete",
+            RowOperation::Update => "update",
+            RowOperation::Insert => "insert",
+        }
+    }
+
+    fn parse(operation: &str) -> Option<Self> {
+        match operation {
+            "keep" => Some(RowOperation::Keep),
+            "delete" => Some(RowOperation::Delete),
+            "update" => Some(RowOperation::Update),
+            "insert" => Some(RowOperation::Insert),
+            _ => None,
+        }
+    }
+}
+
+/// The current snapshot of an Iceberg table with the [`FILE_PATH_COLUMN`] and
+/// [`POSITION_COLUMN`] of every row, the target of row-level changes.
+#[derive(Debug, Clone)]
+pub struct IcebergRowPositionTable {
+    table: Table,
+    /// Arrow schema of the table columns
+    table_schema: SchemaRef,
+    schema: SchemaRef,
+    /// Data files to read, all of them if `None`
+    files: Option<HashSet<String>>,
+}
+
+impl IcebergRowPositionTable {
+    pub fn try_new(table: Table, files: Option<HashSet<String>>) -> DFResult<Self> {
+        let table_schema = schema_to_arrow_schema(table.metadata().current_schema())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let mut fields = table_schema.fields().to_vec();
+        fields.push(Arc::new(Field::new(FILE_PATH_COLUMN, DataType::Utf8, false)));
+        fields.push(Arc::new(Field::new(POSITION_COLUMN, DataType::Int64, false)));
+        Ok(IcebergRowPositionTable {
+            table,
+            table_schema: Arc::new(table_schema),
+            schema: Arc::new(Schema::new(fields)),
+            files,
+        })
+    }
+
+    /// This table reading only the data files in `files`, all of them if `None`.
+    pub fn with_files(&self, files: Option<HashSet<String>>) -> Self {
+        IcebergRowPositionTable {
+            files,
+            ..self.clone()
+        }
+    }
+
+    /// Arrow schema of the table columns, without the row position columns.
+    pub fn table_schema(&self) -> SchemaRef {
+        self.table_schema.clone()
+    }
+}
+
+#[async_trait]
+impl TableProvider for IcebergRowPositionTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(&self, filters: &[&Expr]) -> DFResult<Vec<TableProviderFilterPushDown>> {
+        // filters only prune data files, rows are read from the start of the files
+        Ok(filters
+            .iter()
+            .map(|filter| match convert_filter_to_predicate(filter, &self.table_schema) {
+                Some(_) => TableProviderFilterPushDown::Inexact,
+                None => TableProviderFilterPushDown::Unsupported,
+            })
+            .collect())
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let column_count = self.table_schema.fields().len();
+        let table_projection = match projection {
+            Some(projection) => projection
+                .iter()
+                .filter(|index| **index < column_count)
+                .copied()
+                .collect::<Vec<usize>>(),
+            None => (0..column_count).collect(),
+        };
+        let config = state.config();
+        let mut scan = IcebergTableScan::try_new(
+            self.table.clone(),
+            self.table.metadata().current_snapshot_id(),
+            self.table_schema.clone(),
+            Some(&table_projection),
+            filters,
+            None,
+            config.target_partitions(),
+            config.batch_size(),
+        )
+        .await?;
+        if let Some(files) = &self.files {
+            scan = scan.with_files(files);
+        }
+        let scan = scan.with_row_positions();
+        let Some(projection) = projection else {
+            return Ok(Arc::new(scan));
+        };
+
+        // the scan returns the projected table columns followed by the position columns
+        let exprs = projection
+            .iter()
+            .map(|index| {
+                let scan_index = match table_projection.iter().position(|i| i == index) {
+                    Some(scan_index) => scan_index,
+                    None => table_projection.len() + index - column_count,
+                };
+                let name = self.schema.field(*index).name().clone();
+                (Arc::new(Column::new(&name, scan_index)) as Arc<dyn PhysicalExpr>, name)
+            })
+            .collect::<Vec<(Arc<dyn PhysicalExpr>, String)>>();
+        Ok(Arc::new(ProjectionExec::try_new(exprs, Arc::new(scan))?))
+    }
+}
+
+/// Writes the outcome of a row-level change to an Iceberg table and commits it as one
+/// snapshot, in the [`RowLevelMode`] of the change.
+#[derive(Debug)]
+pub struct IcebergRowLevelWriter {
+    table: Table,
+    committer: Arc<dyn IcebergTableCommitter>,
+    mode: RowLevelMode,
+    sink: IcebergDataSink,
+}
+
+impl IcebergRowLevelWriter {
+    pub fn try_new(
+        table: Table,
+        committer: Arc<dyn IcebergTableCommitter>,
+        mode: RowLevelMode,
+    ) -> DFResult<Self> {
+        let sink = IcebergDataSink::try_new(table.clone(), committer.clone(), false)?;
+        Ok(IcebergRowLevelWriter {
+            table,
+            committer,
+            mode,
+            sink,
+        })
+    }
+
+    /// Applies the rows of `data`, which has the [`OPERATION_COLUMN`], the table columns with
+    /// the values to write and the [`FILE_PATH_COLUMN`] and [`POSITION_COLUMN`] of the target
+    /// row. With copy-on-write the `touched_files` are rewritten, so every row of them has to
+    /// be given, with [`RowOperation::Keep`] if it is unchanged.
+    ///
+    /// Returns the number of deleted, updated and inserted rows.
+    pub async fn write(
+        &self,
+        data: SendableRecordBatchStream,
+        touched_files: &HashSet<String>,
+    ) -> DFResult<u64> {
+        let schema = data.schema();
+        let operation_index = schema.index_of(OPERATION_COLUMN)?;
+        let file_index = schema.index_of(FILE_PATH_COLUMN)?;
+        let position_index = schema.index_of(POSITION_COLUMN)?;
+        let column_indexes = self
+            .sink
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| schema.index_of(field.name()))
+            .collect::<Result<Vec<usize>, _>>()?;
+
+        let mut changes = RowChanges::default();
+        let data = data.map(|batch| -> DFResult<RecordBatch> {
+            let batch = batch?;
+            let operations = cast(batch.column(operation_index), &DataType::Utf8)?;
+            let operations = operations.as_string::<i32>();
+            let files = cast(batch.column(file_index), &DataType::Utf8)?;
+            let files = files.as_string::<i32>();
+            let positions = cast(batch.column(position_index), &DataType::Int64)?;
+            let positions = positions.as_primitive::<Int64Type>();
+
+            let mut written = Vec::with_capacity(batch.num_rows());
+            for row in 0..batch.num_rows() {
+                let operation = operations
+                    .is_valid(row)
+                    .then(|| RowOperation::parse(operations.value(row)))
+                    .flatten();
+                let target = (files.is_valid(row) && positions.is_valid(row))
+                    .then(|| (files.value(row), positions.value(row)));
+                let write = match (operation, target) {
+                    (None, _) => false,
+                    (Some(RowOperation::Insert), _) => {
+                        changes.changed_rows += 1;
+                        true
+                    }
+                    (Some(_), None) => {
+                        return Err(DataFusionError::Internal(format!(
+                            "row to change in {} has no {} and {}",
+                            self.table.identifier().name(),
+                            FILE_PATH_COLUMN,
+                            POSITION_COLUMN
+                        )));
+                    }
+                    (Some(RowOperation::Keep), Some((file, position))) => {
+                        let rewritten = self.mode == RowLevelMode::CopyOnWrite && touched_files.contains(file);
+                        if rewritten {
+                            changes.rewrite(&self.table, file, position)?;
+                        }
+                        rewritten
+                    }
+                    (Some(operation), Some((file, _)))
+                        if self.mode == RowLevelMode::CopyOnWrite && !touched_files.contains(file) =>
+                    {
+                        return Err(DataFusionError::Execution(format!(
+                            "{} of {} changes a row not found when looking for the files to \
+                             rewrite, the statement is not deterministic",
+                            operation.as_str(),
+                            self.table.identifier().name()
+                        )));
+                    }
+                    (Some(RowOperation::Delete), Some((file, position))) => {
+                        changes.delete(&self.table, file, position)?;
+                        false
+                    }
+                    (Some(RowOperation::Update), Some((file, position))) => {
+                        changes.rewrite(&self.table, file, position)?;
+                        changes.add_position_delete(file, position);
+                        changes.changed_rows += 1;
+                        true
+                    }
+                };
+                written.push(write);
+            }
+            let batch = filter_record_batch(&batch, &BooleanArray::from(written))?;
+            Ok(batch.project(&column_indexes)?)
+        });
+        let (data_files, _) = self.sink.write_data_files(data).await?;
+
+        let metadata = self.table.metadata();
+        let mut added_files = data_files;
+        let mut referenced_files = HashSet::new();
+        let removed_files = match self.mode {
+            RowLevelMode::CopyOnWrite => {
+                if touched_files.is_empty() && added_files.is_empty() {
+                    return Ok(changes.changed_rows);
+                }
+                RemovedFiles::Paths(touched_files.clone())
+            }
+            RowLevelMode::MergeOnRead => {
+                if changes.position_deletes.is_empty() && added_files.is_empty() {
+                    return Ok(changes.changed_rows);
+                }
+                for (partition, positions) in self.group_by_partition(changes.position_deletes).await? {
+                    referenced_files.extend(positions.iter().map(|(path, _)| path.clone()));
+                    added_files.push(self.sink.write_position_deletes(&partition, &positions).await?);
+                }
+                RemovedFiles::None
+            }
+        };
+        let adds_data = added_files
+            .iter()
+            .any(|file| file.content_type() == DataContentType::Data);
+        let deletes_data = !touched_files.is_empty() || !referenced_files.is_empty();
+        let operation = match (adds_data, deletes_data) {
+            (false, _) => Operation::Delete,
+            (true, false) => Operation::Append,
+            (true, true) => Operation::Overwrite,
+        };
+        let update = SnapshotUpdate {
+            operation,
+            added_files,
+            removed_files,
+            referenced_files,
+            base_snapshot_id: metadata.current_snapshot_id(),
+            schema_id: metadata.current_schema_id(),
+            partition_spec_id: metadata.default_partition_spec_id(),
+        };
+        commit_snapshot(self.committer.as_ref(), &update).await?;
+        Ok(changes.changed_rows)
+    }
+
+    /// Groups deleted positions by the partition of their data file, sorted by file and
+    /// position as position delete files require.
+    async fn group_by_partition(
+        &self,
+        position_deletes: HashMap<String, Vec<i64>>,
+    ) -> DFResult<HashMap<Struct, Vec<(String, Vec<i64>)>>> {
+        let metadata = self.table.metadata();
+        let file_io = self.table.file_io();
+        let mut partitions: HashMap<String, Struct> = HashMap::new();
+        if let Some(snapshot) = metadata.current_snapshot() {
+            let manifest_list = snapshot
+                .load_manifest_list(file_io, metadata)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            for manifest_file in manifest_list.entries() {
+                if manifest_file.content != ManifestContentType::Data {
+                    continue;
+                }
+                let manifest = manifest_file
+                    .load_manifest(file_io)
+                    .await
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                for entry in manifest.entries().iter().filter(|entry| entry.is_alive()) {
+                    if !position_deletes.contains_key(entry.file_path()) {
+                        continue;
+                    }
+                    // delete files are written with the default spec only
+                    if manifest_file.partition_spec_id != metadata.default_partition_spec_id() {
+                        return not_impl_err!(
+                            "merge-on-read changes of data files of an older partition spec are \
+                             not supported, use copy-on-write for {}",
+                            self.table.identifier().name()
+                        );
+                    }
+                    partitions.insert(entry.file_path().to_string(), entry.data_file().partition().clone());
+                }
+            }
+        }
+
+        let mut grouped: HashMap<Struct, Vec<(String, Vec<i64>)>> = HashMap::new();
+        for (path, mut positions) in position_deletes {
+            let Some(partition) = partitions.get(&path) else {
+                return Err(DataFusionError::Internal(format!("data file {path} not found in manifests")));
+            };
+            positions.sort_unstable();
+            grouped.entry(partition.clone()).or_default().push((path, positions));
+        }
+        for files in grouped.values_mut() {
+            files.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        Ok(grouped)
+    }
+}
+
+/// Rows of the target table seen by [`IcebergRowLevelWriter`], to make sure every row is
+/// changed once at most.
+#[derive(Default)]
+struct RowChanges {
+    deleted: HashMap<String, HashSet<i64>>,
+    /// Updated rows and kept rows of rewritten files
+    rewritten: HashMap<String, HashSet<i64>>,
+    position_deletes: HashMap<String, Vec<i64>>,
+    changed_rows: u64,
+}
+
+impl RowChanges {
+    /// Deleting a row once for every source row it matches is fine, doing anything else with
+    /// it as well is not.
+    fn delete(&mut self, table: &Table, file: &str, position: i64) -> DFResult<()> {
+        if contains(&self.rewritten, file, position) {
+            return multiple_matches_err(table);
+        }
+        if insert(&mut self.deleted, file, position) {
+            self.add_position_delete(file, position);
+            self.changed_rows += 1;
+        }
+        Ok(())
+    }
+
+    fn rewrite(&mut self, table: &Table, file: &str, position: i64) -> DFResult<()> {
+        if contains(&self.deleted, file, position) || !insert(&mut self.rewritten, file, position) {
+            return multiple_matches_err(table);
+        }
+        Ok(())
+    }
+
+    fn add_position_delete(&mut self, file: &str, position: i64) {
+        match self.position_deletes.get_mut(file) {
+            Some(positions) => positions.push(position),
+            None => {
+                self.position_deletes.insert(file.to_string(), vec![position]);
+            }
+        }
+    }
+}
+
+fn contains(positions: &HashMap<String, HashSet<i64>>, file: &str, position: i64) -> bool {
+    positions
+        .get(file)
+        .is_some_and(|positions| positions.contains(&position))
+}
+
+fn insert(positions: &mut HashMap<String, HashSet<i64>>, file: &str, position: i64) -> bool {
+    match positions.get_mut(file) {
+        Some(positions) => positions.insert(position),
+        None => {
+            positions.insert(file.to_string(), HashSet::from([position]));
+            true
+        }
+    }
+}
+
+fn multiple_matches_err(table: &Table) -> DFResult<()> {
+    Err(DataFusionError::Execution(format!(
+        "a row of {} matches more than one source row",
+        table.identifier().name()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_format::iceberg_commit::testing::{empty_table, MemoryCommitter, TestDir};
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::execution::TaskContext;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use datafusion::prelude::SessionContext;
+
+    /// Runs `sql` against the current table registered as `t` with its row positions.
+    async fn query(committer: &Arc<MemoryCommitter>, sql: &str) -> DFResult<(Table, Vec<RecordBatch>)> {
+        let table = committer.load_table().await?;
+        let ctx = SessionContext::new();
+        ctx.register_table("t", Arc::new(IcebergRowPositionTable::try_new(table.clone(), None)?))?;
+        let batches = ctx.sql(sql).await?.collect().await?;
+        Ok((table, batches))
+    }
+
+    /// Plans the rows `sql` selects from `t`, returning an [`IcebergRowLevelWriter`] with
+    /// the rows and the files they touch, to be written later.
+    async fn plan_change(
+        committer: &Arc<MemoryCommitter>,
+        mode: RowLevelMode,
+        sql: &str,
+    ) -> DFResult<(IcebergRowLevelWriter, SendableRecordBatchStream, HashSet<String>)> {
+        let (table, batches) = query(committer, sql).await?;
+        let mut touched_files = HashSet::new();
+        if mode == RowLevelMode::CopyOnWrite {
+            for files in query(committer, "SELECT DISTINCT _file FROM t").await?.1 {
+                touched_files.extend(files.column(0).as_string::<i32>().iter().flatten().map(str::to_string));
+            }
+        }
+        let schema = batches[0].schema();
+        let data = Box::pin(MemoryStream::try_new(batches, schema, None)?);
+        let writer = IcebergRowLevelWriter::try_new(table, committer.clone(), mode)?;
+        Ok((writer, data, touched_files))
+    }
+
+    /// Writes the rows `sql` selects from `t` with an [`IcebergRowLevelWriter`].
+    async fn change(committer: &Arc<MemoryCommitter>, mode: RowLevelMode, sql: &str) -> DFResult<u64> {
+        let (writer, data, touched_files) = plan_change(committer, mode, sql).await?;
+        writer.write(data, &touched_files).await
+    }
+
+    async fn rows(committer: &Arc<MemoryCommitter>) -> DFResult<Vec<(i64, String)>> {
+        let (_, batches) = query(committer, "SELECT id, region FROM t ORDER BY id").await?;
+        let mut rows = Vec::new();
+        for batch in batches {
+            let ids = batch.column(0).as_primitive::<Int64Type>();
+            let regions = batch.column(1).as_string::<i32>();
+            rows.extend(ids.values().iter().zip(regions.iter()).map(|(id, region)| {
+                (*id, region.unwrap_or_default().to_string())
+            }));
+        }
+        Ok(rows)
+    }
+
+    #[tokio::test]
+    async fn test_merge_on_read_and_copy_on_write() -> DFResult<()> {
+        let table_dir = TestDir::new("iceberg-dml");
+        let properties = HashMap::from([(WRITE_DELETE_MODE.to_string(), "merge-on-read".to_string())]);
+        let (committer, table) = empty_table(&table_dir, properties).await?;
+        assert_eq!(
+            RowLevelMode::for_command(table.metadata(), RowLevelCommand::Delete)?,
+            RowLevelMode::MergeOnRead
+        );
+        assert_eq!(
+            RowLevelMode::for_command(table.metadata(), RowLevelCommand::Update)?,
+            RowLevelMode::CopyOnWrite
+        );
+
+        let sink = IcebergDataSink::try_new(table, committer.clone(), false)?;
+        let schema = sink.schema().clone();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["eu", "us", "eu"])),
+            ],
+        )?;
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+        sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+
+        let deleted = change(
+            &committer,
+            RowLevelMode::MergeOnRead,
+            "SELECT CASE WHEN id = 2 THEN 'delete' END AS _operation, id, region, _file, _pos FROM t",
+        )
+        .await?;
+        assert_eq!(deleted, 1);
+        let summary = committer.load_table().await?.metadata().current_snapshot().expect("snapshot").summary().clone();
+        assert_eq!(summary.operation, Operation::Delete);
+        assert_eq!(summary.additional_properties["added-position-deletes"], "1");
+        assert_eq!(rows(&committer).await?, vec![(1, "eu".to_string()), (3, "eu".to_string())]);
+
+        let updated = change(
+            &committer,
+            RowLevelMode::CopyOnWrite,
+            "SELECT CASE WHEN id = 3 THEN 'update' ELSE 'keep' END AS _operation, id, \
+             CASE WHEN id = 3 THEN 'apac' ELSE region END AS region, _file, _pos FROM t",
+        )
+        .await?;
+        assert_eq!(updated, 1);
+        let summary = committer.load_table().await?.metadata().current_snapshot().expect("snapshot").summary().clone();
+        assert_eq!(summary.operation, Operation::Overwrite);
+        assert_eq!(rows(&committer).await?, vec![(1, "eu".to_string()), (3, "apac".to_string())]);
+
+        let result = change(
+            &committer,
+            RowLevelMode::MergeOnRead,
+            "SELECT 'delete' AS _operation, id, region, _file, _pos FROM t UNION ALL \
+             SELECT 'update' AS _operation, id, region, _file, _pos FROM t WHERE id = 1",
+        )
+        .await;
+        assert!(result.is_err_and(|e| e.to_string().contains("more than one source row")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_changes_of_the_same_rows() -> DFResult<()> {
+        let table_dir = TestDir::new("iceberg-dml-concurrent");
+        let (committer, table) = empty_table(&table_dir, HashMap::new()).await?;
+        let sink = IcebergDataSink::try_new(table, committer.clone(), false)?;
+        let schema = sink.schema().clone();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["eu", "us", "eu"])),
+            ],
+        )?;
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+        sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+        let update = "SELECT CASE WHEN id = 2 THEN 'update' ELSE 'keep' END AS _operation, id, \
+            CASE WHEN id = 2 THEN 'apac' ELSE region END AS region, _file, _pos FROM t";
+
+        // both writers update id 2 of the same snapshot, the second one fails instead of
+        // adding the updated row twice
+        let first = plan_change(&committer, RowLevelMode::MergeOnRead, update).await?;
+        let (writer, data, touched_files) = plan_change(&committer, RowLevelMode::MergeOnRead, update).await?;
+        assert_eq!(first.0.write(first.1, &first.2).await?, 1);
+        let error = writer.write(data, &touched_files).await.unwrap_err();
+        assert!(error.to_string().contains("deleted by a concurrent commit"), "{error}");
+        let expected = vec![(1, "eu".to_string()), (2, "apac".to_string()), (3, "eu".to_string())];
+        assert_eq!(rows(&committer).await?, expected);
+
+        // rewriting the file would restore id 3, deleted after it was read
+        let (writer, data, touched_files) = plan_change(&committer, RowLevelMode::CopyOnWrite, update).await?;
+        let delete = "SELECT CASE WHEN id = 3 THEN 'delete' END AS _operation, id, region, _file, _pos FROM t";
+        assert_eq!(change(&committer, RowLevelMode::MergeOnRead, delete).await?, 1);
+        let error = writer.write(data, &touched_files).await.unwrap_err();
+        assert!(error.to_string().contains("deleted by a concurrent commit"), "{error}");
+        assert_eq!(rows(&committer).await?, expected[..2].to_vec());
+
+        // appends don't conflict with a change of the rows read before them
+        let delete = delete.replace("id = 3", "id = 1");
+        let (writer, data, touched_files) = plan_change(&committer, RowLevelMode::MergeOnRead, &delete).await?;
+        let sink = IcebergDataSink::try_new(committer.load_table().await?, committer.clone(), false)?;
+        let schema = sink.schema().clone();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![4])), Arc::new(StringArray::from(vec!["us"]))],
+        )?;
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+        sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+        assert_eq!(writer.write(data, &touched_files).await?, 1);
+        assert_eq!(rows(&committer).await?, vec![(2, "apac".to_string()), (4, "us".to_string())]);
+        Ok(())
+    }
+}
@@ -0,0 +1,862 @@
+use crate::table_format::external_table::ExternalIcebergTable;
+use crate::table_format::iceberg_statistics::literal_to_scalar;
+use async_trait::async_trait;
+use datafusion::arrow::array::{
+    ArrayRef, BinaryBuilder, BooleanArray, Int32Array, Int32Builder, Int64Array, Int64Builder,
+    ListBuilder, MapBuilder, RecordBatch, StringArray, StringBuilder, StructArray,
+    TimestampMillisecondArray,
+};
+use datafusion::arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::ScalarValue;
+use datafusion::datasource::{MemTable, TableType};
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::ExecutionPlan;
+use iceberg::arrow::type_to_arrow_type;
+use iceberg::spec::{
+    DataContentType, Literal, ManifestContentType, ManifestEntryRef, ManifestFile, NestedField,
+    SnapshotReference, SnapshotRetention, Struct, StructType, TableMetadata, Type, MAIN_BRANCH,
+};
+use std::any::Any;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// Separates the table name from the metadata table, e.g. `orders$snapshots`.
+const METADATA_TABLE_SEPARATOR: char = '$';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcebergMetadataTableType {
+    Snapshots,
+    History,
+    Files,
+    Manifests,
+    Partitions,
+    Refs,
+}
+
+impl IcebergMetadataTableType {
+    pub fn try_from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "snapshots" => Some(IcebergMetadataTableType::Snapshots),
+            "history" => Some(IcebergMetadataTableType::History),
+            "files" => Some(IcebergMetadataTableType::Files),
+            "manifests" => Some(IcebergMetadataTableType::Manifests),
+            "partitions" => Some(IcebergMetadataTableType::Partitions),
+            "refs" => Some(IcebergMetadataTableType::Refs),
+            _ => None,
+        }
+    }
+
+    /// Schema of the metadata table. `partition_type` is the type of the `partition` column
+    /// of the files and partitions tables, which is left out for unpartitioned tables.
+    fn schema(&self, partition_type: Option<&DataType>) -> SchemaRef {
+        let partition = partition_type.map(|data_type| Field::new("partition", data_type.clone(), false));
+        let fields = match self {
+            IcebergMetadataTableType::Snapshots => vec![
+                Field::new("committed_at", timestamp_type(), false),
+                Field::new("snapshot_id", DataType::Int64, false),
+                Field::new("parent_id", DataType::Int64, true),
+                Field::new("operation", DataType::Utf8, true),
+                Field::new("manifest_list", DataType::Utf8, false),
+                Field::new("summary", map_type(DataType::Utf8, DataType::Utf8), true),
+            ],
+            IcebergMetadataTableType::History => vec![
+                Field::new("made_current_at", timestamp_type(), false),
+                Field::new("snapshot_id", DataType::Int64, false),
+                Field::new("parent_id", DataType::Int64, true),
+                Field::new("is_current_ancestor", DataType::Boolean, false),
+            ],
+            IcebergMetadataTableType::Files => vec![
+                Field::new("content", DataType::Int32, false),
+                Field::new("file_path", DataType::Utf8, false),
+                Field::new("file_format", DataType::Utf8, false),
+                Field::new("spec_id", DataType::Int32, false),
+            ]
+            .into_iter()
+            .chain(partition)
+            .chain([
+                Field::new("record_count", DataType::Int64, false),
+                Field::new("file_size_in_bytes", DataType::Int64, false),
+                Field::new("column_sizes", map_type(DataType::Int32, DataType::Int64), true),
+                Field::new("value_counts", map_type(DataType::Int32, DataType::Int64), true),
+                Field::new(
+                    "null_value_counts",
+                    map_type(DataType::Int32, DataType::Int64),
+                    true,
+                ),
+                Field::new(
+                    "nan_value_counts",
+                    map_type(DataType::Int32, DataType::Int64),
+                    true,
+                ),
+                Field::new("lower_bounds", map_type(DataType::Int32, DataType::Utf8), true),
+                Field::new("upper_bounds", map_type(DataType::Int32, DataType::Utf8), true),
+                Field::new("key_metadata", DataType::Binary, true),
+                Field::new("split_offsets", DataType::new_list(DataType::Int64, true), true),
+                Field::new("equality_ids", DataType::new_list(DataType::Int32, true), true),
+                Field::new("sort_order_id", DataType::Int32, true),
+            ])
+            .collect(),
+            IcebergMetadataTableType::Manifests => vec![
+                Field::new("content", DataType::Int32, false),
+                Field::new("path", DataType::Utf8, false),
+                Field::new("length", DataType::Int64, false),
+                Field::new("partition_spec_id", DataType::Int32, false),
+                Field::new("added_snapshot_id", DataType::Int64, false),
+                Field::new("added_data_files_count", DataType::Int32, false),
+                Field::new("existing_data_files_count", DataType::Int32, false),
+                Field::new("deleted_data_files_count", DataType::Int32, false),
+                Field::new("added_delete_files_count", DataType::Int32, false),
+                Field::new("existing_delete_files_count", DataType::Int32, false),
+                Field::new("deleted_delete_files_count", DataType::Int32, false),
+            ],
+            IcebergMetadataTableType::Partitions => partition.into_iter().chain([
+                Field::new("spec_id", DataType::Int32, false),
+                Field::new("record_count", DataType::Int64, false),
+                Field::new("file_count", DataType::Int32, false),
+                Field::new("total_data_file_size_in_bytes", DataType::Int64, false),
+                Field::new("position_delete_record_count", DataType::Int64, false),
+                Field::new("position_delete_file_count", DataType::Int32, false),
+                Field::new("equality_delete_record_count", DataType::Int64, false),
+                Field::new("equality_delete_file_count", DataType::Int32, false),
+                Field::new("last_updated_at", timestamp_type(), true),
+                Field::new("last_updated_snapshot_id", DataType::Int64, true),
+            ])
+            .collect(),
+            IcebergMetadataTableType::Refs => vec![
+                Field::new("name", DataType::Utf8, false),
+                Field::new("type", DataType::Utf8, false),
+                Field::new("snapshot_id", DataType::Int64, false),
+                Field::new("max_reference_age_in_ms", DataType::Int64, true),
+                Field::new("min_snapshots_to_keep", DataType::Int32, true),
+                Field::new("max_snapshot_age_in_ms", DataType::Int64, true),
+            ],
+        };
+        Arc::new(Schema::new(fields))
+    }
+}
+
+/// Splits `orders$snapshots` into `orders` and the metadata table type. Names without
+/// a known metadata table suffix are returned as is.
+pub fn parse_metadata_table_name(name: &str) -> (&str, Option<IcebergMetadataTableType>) {
+    match name.rsplit_once(METADATA_TABLE_SEPARATOR) {
+        Some((table_name, suffix)) => match IcebergMetadataTableType::try_from_name(suffix) {
+            Some(metadata_table_type) => (table_name, Some(metadata_table_type)),
+            None => (name, None),
+        },
+        None => (name, None),
+    }
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+}
+
+/// Data type produced by a default [`MapBuilder`].
+fn map_type(key: DataType, value: DataType) -> DataType {
+    DataType::Map(
+        Arc::new(Field::new(
+            "entries",
+            DataType::Struct(Fields::from(vec![
+                Field::new("keys", key, false),
+                Field::new("values", value, true),
+            ])),
+            false,
+        )),
+        false,
+    )
+}
+
+/// Read-only table exposing the metadata of an Iceberg table, built from the table
+/// metadata and the manifests of its current snapshot.
+#[derive(Debug)]
+pub struct IcebergMetadataTable {
+    table: ExternalIcebergTable,
+    metadata_table_type: IcebergMetadataTableType,
+    schema: SchemaRef,
+    /// Partition fields of all partition specs, see [`partition_type`]
+    partition_type: StructType,
+}
+
+impl IcebergMetadataTable {
+    pub fn try_new(
+        table: ExternalIcebergTable,
+        metadata_table_type: IcebergMetadataTableType,
+    ) -> Result<Self, DataFusionError> {
+        let partition_type = partition_type(table.static_table.metadata().as_ref());
+        let schema = if partition_type.fields().is_empty() {
+            metadata_table_type.schema(None)
+        } else {
+            let arrow_type = type_to_arrow_type(&Type::Struct(partition_type.clone()))
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            metadata_table_type.schema(Some(&arrow_type))
+        };
+        Ok(IcebergMetadataTable {
+            table,
+            metadata_table_type,
+            schema,
+            partition_type,
+        })
+    }
+
+    async fn build_batch(&self) -> Result<RecordBatch, DataFusionError> {
+        let columns = match self.metadata_table_type {
+            IcebergMetadataTableType::Snapshots => self.snapshots_columns()?,
+            IcebergMetadataTableType::History => self.history_columns(),
+            IcebergMetadataTableType::Files => self.files_columns().await?,
+            IcebergMetadataTableType::Manifests => self.manifests_columns().await?,
+            IcebergMetadataTableType::Partitions => self.partitions_columns().await?,
+            IcebergMetadataTableType::Refs => self.refs_columns()?,
+        };
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+
+    fn snapshots_columns(&self) -> Result<Vec<ArrayRef>, DataFusionError> {
+        let metadata = self.table.static_table.metadata();
+        let mut snapshots: Vec<_> = metadata.snapshots().collect();
+        snapshots.sort_by_key(|s| s.timestamp_ms());
+
+        let mut summary = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+        for snapshot in &snapshots {
+            let mut properties: Vec<_> = snapshot.summary().additional_properties.iter().collect();
+            properties.sort();
+            for (key, value) in properties {
+                summary.keys().append_value(key);
+                summary.values().append_value(value);
+            }
+            summary.append(true)?;
+        }
+        Ok(vec![
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    snapshots.iter().map(|s| s.timestamp_ms()),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(Int64Array::from_iter_values(
+                snapshots.iter().map(|s| s.snapshot_id()),
+            )),
+            Arc::new(Int64Array::from_iter(
+                snapshots.iter().map(|s| s.parent_snapshot_id()),
+            )),
+            Arc::new(StringArray::from_iter(
+                snapshots
+                    .iter()
+                    .map(|s| Some(s.summary().operation.as_str())),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                snapshots.iter().map(|s| s.manifest_list()),
+            )),
+            Arc::new(summary.finish()),
+        ])
+    }
+
+    fn history_columns(&self) -> Vec<ArrayRef> {
+        let metadata = self.table.static_table.metadata();
+        // snapshots reachable from the current snapshot through parent ids
+        let mut current_ancestors: HashSet<i64> = HashSet::new();
+        let mut next = metadata.current_snapshot_id();
+        while let Some(snapshot_id) = next {
+            if !current_ancestors.insert(snapshot_id) {
+                break;
+            }
+            next = metadata
+                .snapshot_by_id(snapshot_id)
+                .and_then(|s| s.parent_snapshot_id());
+        }
+
+        let history = metadata.history();
+        vec![
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    history.iter().map(|log| log.timestamp_ms),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(Int64Array::from_iter_values(
+                history.iter().map(|log| log.snapshot_id),
+            )),
+            Arc::new(Int64Array::from_iter(history.iter().map(|log| {
+                metadata
+                    .snapshot_by_id(log.snapshot_id)
+                    .and_then(|s| s.parent_snapshot_id())
+            }))),
+            Arc::new(BooleanArray::from_iter(
+                history
+                    .iter()
+                    .map(|log| Some(current_ancestors.contains(&log.snapshot_id))),
+            )),
+        ]
+    }
+
+    async fn files_columns(&self) -> Result<Vec<ArrayRef>, DataFusionError> {
+        let entries = self.load_live_entries().await?;
+        let mut content = Int32Builder::new();
+        let mut file_path = StringBuilder::new();
+        let mut file_format = StringBuilder::new();
+        let mut spec_id = Int32Builder::new();
+        let mut partitions = Vec::with_capacity(entries.len());
+        let mut record_count = Int64Builder::new();
+        let mut file_size = Int64Builder::new();
+        let mut column_sizes = MapBuilder::new(None, Int32Builder::new(), Int64Builder::new());
+        let mut value_counts = MapBuilder::new(None, Int32Builder::new(), Int64Builder::new());
+        let mut null_value_counts =
+            MapBuilder::new(None, Int32Builder::new(), Int64Builder::new());
+        let mut nan_value_counts =
+            MapBuilder::new(None, Int32Builder::new(), Int64Builder::new());
+        let mut lower_bounds = MapBuilder::new(None, Int32Builder::new(), StringBuilder::new());
+        let mut upper_bounds = MapBuilder::new(None, Int32Builder::new(), StringBuilder::new());
+        let mut key_metadata = BinaryBuilder::new();
+        let mut split_offsets = ListBuilder::new(Int64Builder::new());
+        let mut equality_ids = ListBuilder::new(Int32Builder::new());
+        let mut sort_order_id = Int32Builder::new();
+
+        for (manifest_file, entry) in &entries {
+            let data_file = entry.data_file();
+            content.append_value(content_type_id(data_file.content_type()));
+            file_path.append_value(data_file.file_path());
+            file_format.append_value(data_file.file_format().to_string());
+            spec_id.append_value(manifest_file.partition_spec_id);
+            partitions.push((manifest_file.partition_spec_id, data_file.partition()));
+            record_count.append_value(data_file.record_count() as i64);
+            file_size.append_value(data_file.file_size_in_bytes() as i64);
+            for (builder, counts) in [
+                (&mut column_sizes, data_file.column_sizes()),
+                (&mut value_counts, data_file.value_counts()),
+                (&mut null_value_counts, data_file.null_value_counts()),
+                (&mut nan_value_counts, data_file.nan_value_counts()),
+            ] {
+                let mut counts: Vec<_> = counts.iter().collect();
+                counts.sort();
+                for (field_id, count) in counts {
+                    builder.keys().append_value(*field_id);
+                    builder.values().append_value(*count as i64);
+                }
+                builder.append(true)?;
+            }
+            for (builder, bounds) in [
+                (&mut lower_bounds, data_file.lower_bounds()),
+                (&mut upper_bounds, data_file.upper_bounds()),
+            ] {
+                let mut bounds: Vec<_> = bounds.iter().collect();
+                bounds.sort_by_key(|(field_id, _)| **field_id);
+                for (field_id, bound) in bounds {
+                    builder.keys().append_value(*field_id);
+                    builder.values().append_value(bound.to_string());
+                }
+                builder.append(true)?;
+            }
+            key_metadata.append_option(data_file.key_metadata());
+            // empty lists are reported as null, as they are missing from the manifest
+            let offsets = data_file.split_offsets();
+            split_offsets.append_option((!offsets.is_empty()).then(|| offsets.iter().copied().map(Some)));
+            let ids = data_file.equality_ids();
+            equality_ids.append_option((!ids.is_empty()).then(|| ids.iter().copied().map(Some)));
+            sort_order_id.append_option(data_file.sort_order_id());
+        }
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(content.finish()),
+            Arc::new(file_path.finish()),
+            Arc::new(file_format.finish()),
+            Arc::new(spec_id.finish()),
+        ];
+        columns.extend(self.partition_array(&partitions)?);
+        columns.extend::<[ArrayRef; 12]>([
+            Arc::new(record_count.finish()),
+            Arc::new(file_size.finish()),
+            Arc::new(column_sizes.finish()),
+            Arc::new(value_counts.finish()),
+            Arc::new(null_value_counts.finish()),
+            Arc::new(nan_value_counts.finish()),
+            Arc::new(lower_bounds.finish()),
+            Arc::new(upper_bounds.finish()),
+            Arc::new(key_metadata.finish()),
+            Arc::new(split_offsets.finish()),
+            Arc::new(equality_ids.finish()),
+            Arc::new(sort_order_id.finish()),
+        ]);
+        Ok(columns)
+    }
+
+    async fn manifests_columns(&self) -> Result<Vec<ArrayRef>, DataFusionError> {
+        let manifest_files = self.load_manifest_files().await?;
+        let count = |manifest: &ManifestFile, content: ManifestContentType, value: Option<u32>| {
+            if manifest.content == content {
+                value.unwrap_or_default() as i32
+            } else {
+                0
+            }
+        };
+        let files = &manifest_files;
+        Ok(vec![
+            Arc::new(Int32Array::from_iter_values(files.iter().map(|m| {
+                match m.content {
+                    ManifestContentType::Data => 0,
+                    ManifestContentType::Deletes => 1,
+                }
+            }))),
+            Arc::new(StringArray::from_iter_values(
+                files.iter().map(|m| m.manifest_path.as_str()),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                files.iter().map(|m| m.manifest_length),
+            )),
+            Arc::new(Int32Array::from_iter_values(
+                files.iter().map(|m| m.partition_spec_id),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                files.iter().map(|m| m.added_snapshot_id),
+            )),
+            Arc::new(Int32Array::from_iter_values(files.iter().map(|m| {
+                count(m, ManifestContentType::Data, m.added_files_count)
+            }))),
+            Arc::new(Int32Array::from_iter_values(files.iter().map(|m| {
+                count(m, ManifestContentType::Data, m.existing_files_count)
+            }))),
+            Arc::new(Int32Array::from_iter_values(files.iter().map(|m| {
+                count(m, ManifestContentType::Data, m.deleted_files_count)
+            }))),
+            Arc::new(Int32Array::from_iter_values(files.iter().map(|m| {
+                count(m, ManifestContentType::Deletes, m.added_files_count)
+            }))),
+            Arc::new(Int32Array::from_iter_values(files.iter().map(|m| {
+                count(m, ManifestContentType::Deletes, m.existing_files_count)
+            }))),
+            Arc::new(Int32Array::from_iter_values(files.iter().map(|m| {
+                count(m, ManifestContentType::Deletes, m.deleted_files_count)
+            }))),
+        ])
+    }
+
+    async fn partitions_columns(&self) -> Result<Vec<ArrayRef>, DataFusionError> {
+        #[derive(Default)]
+        struct PartitionSummary {
+            record_count: i64,
+            file_count: i32,
+            total_data_file_size: i64,
+            position_delete_record_count: i64,
+            position_delete_file_count: i32,
+            equality_delete_record_count: i64,
+            equality_delete_file_count: i32,
+            last_updated_snapshot_id: Option<i64>,
+            last_updated_at: Option<i64>,
+        }
+
+        let metadata = self.table.static_table.metadata();
+        let entries = self.load_live_entries().await?;
+        // keyed by the formatted partition, which orders the partitions
+        let mut partitions: BTreeMap<(String, i32), (&Struct, PartitionSummary)> = BTreeMap::new();
+        for (manifest_file, entry) in &entries {
+            let data_file = entry.data_file();
+            let spec_id = manifest_file.partition_spec_id;
+            let key = (self.format_partition(spec_id, data_file.partition()), spec_id);
+            let (_, summary) = partitions
+                .entry(key)
+                .or_insert_with(|| (data_file.partition(), PartitionSummary::default()));
+            let record_count = data_file.record_count() as i64;
+            match data_file.content_type() {
+                DataContentType::Data => {
+                    summary.record_count += record_count;
+                    summary.file_count += 1;
+                    summary.total_data_file_size += data_file.file_size_in_bytes() as i64;
+                }
+                DataContentType::PositionDeletes => {
+                    summary.position_delete_record_count += record_count;
+                    summary.position_delete_file_count += 1;
+                }
+                DataContentType::EqualityDeletes => {
+                    summary.equality_delete_record_count += record_count;
+                    summary.equality_delete_file_count += 1;
+                }
+            }
+            let updated_at = entry
+                .snapshot_id()
+                .and_then(|id| metadata.snapshot_by_id(id))
+                .map(|s| (s.snapshot_id(), s.timestamp_ms()));
+            if let Some((snapshot_id, timestamp_ms)) = updated_at
+                && summary.last_updated_at.is_none_or(|ts| ts < timestamp_ms)
+            {
+                summary.last_updated_at = Some(timestamp_ms);
+                summary.last_updated_snapshot_id = Some(snapshot_id);
+            }
+        }
+
+        let summaries: Vec<_> = partitions.iter().map(|((_, spec_id), value)| (*spec_id, value)).collect();
+        let partitions: Vec<_> = summaries
+            .iter()
+            .map(|(spec_id, (partition, _))| (*spec_id, *partition))
+            .collect();
+        let mut columns: Vec<ArrayRef> = self.partition_array(&partitions)?.into_iter().collect();
+        columns.extend::<[ArrayRef; 10]>([
+            Arc::new(Int32Array::from_iter_values(
+                summaries.iter().map(|(spec_id, _)| *spec_id),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                summaries.iter().map(|(_, (_, s))| s.record_count),
+            )),
+            Arc::new(Int32Array::from_iter_values(
+                summaries.iter().map(|(_, (_, s))| s.file_count),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                summaries.iter().map(|(_, (_, s))| s.total_data_file_size),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                summaries.iter().map(|(_, (_, s))| s.position_delete_record_count),
+            )),
+            Arc::new(Int32Array::from_iter_values(
+                summaries.iter().map(|(_, (_, s))| s.position_delete_file_count),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                summaries.iter().map(|(_, (_, s))| s.equality_delete_record_count),
+            )),
+            Arc::new(Int32Array::from_iter_values(
+                summaries.iter().map(|(_, (_, s))| s.equality_delete_file_count),
+            )),
+            Arc::new(
+                TimestampMillisecondArray::from_iter(
+                    summaries.iter().map(|(_, (_, s))| s.last_updated_at),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(Int64Array::from_iter(
+                summaries.iter().map(|(_, (_, s))| s.last_updated_snapshot_id),
+            )),
+        ]);
+        Ok(columns)
+    }
+
+    fn refs_columns(&self) -> Result<Vec<ArrayRef>, DataFusionError> {
+        let refs = snapshot_refs(self.table.static_table.metadata().as_ref())?;
+        let max_ref_age_ms = |reference: &SnapshotReference| match reference.retention {
+            SnapshotRetention::Branch { max_ref_age_ms, .. } | SnapshotRetention::Tag { max_ref_age_ms } => {
+                max_ref_age_ms
+            }
+        };
+        let branch_retention = |reference: &SnapshotReference| match reference.retention {
+            SnapshotRetention::Branch {
+                min_snapshots_to_keep,
+                max_snapshot_age_ms,
+                ..
+            } => (min_snapshots_to_keep, max_snapshot_age_ms),
+            SnapshotRetention::Tag { .. } => (None, None),
+        };
+        Ok(vec![
+            Arc::new(StringArray::from_iter_values(refs.keys())),
+            Arc::new(StringArray::from_iter_values(refs.values().map(|r| {
+                if r.is_branch() { "BRANCH" } else { "TAG" }
+            }))),
+            Arc::new(Int64Array::from_iter_values(refs.values().map(|r| r.snapshot_id))),
+            Arc::new(Int64Array::from_iter(refs.values().map(max_ref_age_ms))),
+            Arc::new(Int32Array::from_iter(
+                refs.values().map(|r| branch_retention(r).0),
+            )),
+            Arc::new(Int64Array::from_iter(
+                refs.values().map(|r| branch_retention(r).1),
+            )),
+        ])
+    }
+
+    /// Loads the manifest list of the current snapshot.
+    async fn load_manifest_files(&self) -> Result<Vec<ManifestFile>, DataFusionError> {
+        let table = self.table.table();
+        let metadata = table.metadata();
+        let Some(snapshot) = metadata.current_snapshot() else {
+            return Ok(vec![]);
+        };
+        let manifest_list = snapshot
+            .load_manifest_list(table.file_io(), metadata)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(manifest_list.entries().to_vec())
+    }
+
+    /// Loads all live (added or existing) manifest entries of the current snapshot,
+    /// together with the manifest file they belong to.
+    async fn load_live_entries(
+        &self,
+    ) -> Result<Vec<(ManifestFile, ManifestEntryRef)>, DataFusionError> {
+        let table = self.table.table();
+        let mut entries = Vec::new();
+        for manifest_file in self.load_manifest_files().await? {
+            let manifest = manifest_file
+                .load_manifest(table.file_io())
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            for entry in manifest.entries() {
+                if entry.is_alive() {
+                    entries.push((manifest_file.clone(), entry.clone()));
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Builds the `partition` column from the partition tuples of `partitions` and the ids
+    /// of the specs they belong to. Fields missing from a spec are null. Returns `None`
+    /// for unpartitioned tables, which have no `partition` column.
+    fn partition_array(&self, partitions: &[(i32, &Struct)]) -> Result<Option<ArrayRef>, DataFusionError> {
+        let Some(DataType::Struct(fields)) = self
+            .schema
+            .column_with_name("partition")
+            .map(|(_, field)| field.data_type().clone())
+        else {
+            return Ok(None);
+        };
+        let metadata = self.table.static_table.metadata();
+        let mut columns = Vec::with_capacity(fields.len());
+        for (partition_field, field) in self.partition_type.fields().iter().zip(fields.iter()) {
+            let values = partitions.iter().map(|(spec_id, partition)| {
+                let position = metadata.partition_spec_by_id(*spec_id).and_then(|spec| {
+                    spec.fields()
+                        .iter()
+                        .position(|f| f.field_id == partition_field.id)
+                });
+                match position.and_then(|position| partition.iter().nth(position).flatten()) {
+                    Some(Literal::Primitive(literal)) => literal_to_scalar(literal, field.data_type())
+                        .ok_or_else(|| {
+                            DataFusionError::Internal(format!(
+                                "invalid value {literal:?} of partition field {}",
+                                field.name()
+                            ))
+                        }),
+                    _ => ScalarValue::try_from(field.data_type()),
+                }
+            });
+            let values = values.collect::<Result<Vec<_>, _>>()?;
+            columns.push(if values.is_empty() {
+                datafusion::arrow::array::new_empty_array(field.data_type())
+            } else {
+                ScalarValue::iter_to_array(values)?
+            });
+        }
+        Ok(Some(Arc::new(StructArray::try_new(fields, columns, None)?)))
+    }
+
+    /// Formats a partition tuple as `{field=value, ...}` using the names of its spec.
+    fn format_partition(&self, spec_id: i32, partition: &Struct) -> String {
+        let metadata = self.table.static_table.metadata();
+        let field_names: Vec<String> = metadata
+            .partition_spec_by_id(spec_id)
+            .map(|spec| spec.fields().iter().map(|f| f.name.clone()).collect())
+            .unwrap_or_default();
+        let values: Vec<String> = partition
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| {
+                let name = field_names
+                    .get(idx)
+                    .cloned()
+                    .unwrap_or_else(|| idx.to_string());
+                match value {
+                    Some(value) => format!("{name}={value:?}"),
+                    None => format!("{name}=null"),
+                }
+            })
+            .collect();
+        format!("{{{}}}", values.join(", "))
+    }
+}
+
+/// Partition type of the `partition` column of the files and partitions tables: the
+/// fields of all partition specs of the table by field id, so that files written with
+/// any spec fit. Fields of dropped source columns are left out.
+fn partition_type(metadata: &TableMetadata) -> StructType {
+    let schema = metadata.current_schema();
+    let mut fields = BTreeMap::new();
+    for spec in metadata.partition_specs_iter() {
+        for field in spec.fields() {
+            let result_type = schema
+                .field_by_id(field.source_id)
+                .and_then(|source| field.transform.result_type(&source.field_type).ok());
+            if let Some(result_type) = result_type {
+                fields.entry(field.field_id).or_insert_with(|| {
+                    Arc::new(NestedField::optional(field.field_id, &field.name, result_type))
+                });
+            }
+        }
+    }
+    StructType::new(fields.into_values().collect())
+}
+
+/// Branches and tags of the table by name. Tables without refs still have an implicit
+/// `main` branch at their current snapshot.
+pub(crate) fn snapshot_refs(
+    metadata: &TableMetadata,
+) -> Result<BTreeMap<String, SnapshotReference>, DataFusionError> {
+    // refs aren't exposed by TableMetadata, read them from its serialized form
+    let mut metadata_json =
+        serde_json::to_value(metadata).map_err(|e| DataFusionError::External(Box::new(e)))?;
+    let mut refs: BTreeMap<String, SnapshotReference> = match metadata_json.get_mut("refs") {
+        Some(refs) => serde_json::from_value(refs.take())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?,
+        None => BTreeMap::new(),
+    };
+    if refs.is_empty()
+        && let Some(snapshot_id) = metadata.current_snapshot_id()
+    {
+        refs.insert(
+            MAIN_BRANCH.to_string(),
+            SnapshotReference::new(snapshot_id, SnapshotRetention::branch(None, None, None)),
+        );
+    }
+    Ok(refs)
+}
+
+fn content_type_id(content_type: DataContentType) -> i32 {
+    match content_type {
+        DataContentType::Data => 0,
+        DataContentType::PositionDeletes => 1,
+        DataContentType::EqualityDeletes => 2,
+    }
+}
+
+#[async_trait]
+impl TableProvider for IcebergMetadataTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::View
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> datafusion::common::Result<Arc<dyn ExecutionPlan>> {
+        let batch = self.build_batch().await?;
+        let table = MemTable::try_new(self.schema.clone(), vec![vec![batch]])?;
+        table.scan(state, projection, filters, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_format::iceberg_commit::testing::{create_table, orders_schema, MemoryCommitter, TestDir};
+    use crate::table_format::iceberg_commit::{to_datafusion_error, IcebergTableCommitter};
+    use crate::table_format::iceberg_sink::IcebergDataSink;
+    use datafusion::arrow::array::{Array, AsArray};
+    use datafusion::arrow::compute::concat_batches;
+    use datafusion::arrow::datatypes::{Int32Type, Int64Type};
+    use datafusion::datasource::sink::DataSink;
+    use datafusion::execution::TaskContext;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use datafusion::prelude::SessionContext;
+    use iceberg::spec::{Transform, UnboundPartitionSpec};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_metadata_table_name() {
+        assert_eq!(
+            parse_metadata_table_name("orders$snapshots"),
+            ("orders", Some(IcebergMetadataTableType::Snapshots))
+        );
+        assert_eq!(
+            parse_metadata_table_name("orders$FILES"),
+            ("orders", Some(IcebergMetadataTableType::Files))
+        );
+        assert_eq!(parse_metadata_table_name("orders$unknown"), ("orders$unknown", None));
+        assert_eq!(parse_metadata_table_name("orders"), ("orders", None));
+    }
+
+    async fn query(
+        table: &ExternalIcebergTable,
+        metadata_table_type: IcebergMetadataTableType,
+        sql: &str,
+    ) -> Result<RecordBatch, DataFusionError> {
+        let ctx = SessionContext::new();
+        let metadata_table = IcebergMetadataTable::try_new(table.clone(), metadata_table_type)?;
+        ctx.register_table("t", Arc::new(metadata_table))?;
+        let batches = ctx.sql(sql).await?.collect().await?;
+        Ok(concat_batches(&batches[0].schema(), &batches)?)
+    }
+
+    async fn append(committer: &Arc<MemoryCommitter>, rows: Vec<(i64, &str)>) -> Result<(), DataFusionError> {
+        let sink = IcebergDataSink::try_new(committer.load_table().await?, committer.clone(), false)?;
+        let schema = sink.schema().clone();
+        let (ids, regions): (Vec<i64>, Vec<&str>) = rows.into_iter().unzip();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(ids)), Arc::new(StringArray::from(regions))],
+        )?;
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+        sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_metadata_tables() -> Result<(), DataFusionError> {
+        let table_dir = TestDir::new("iceberg-metadata");
+        let partition_spec = UnboundPartitionSpec::builder()
+            .add_partition_field(2, "region", Transform::Identity)
+            .map_err(to_datafusion_error)?
+            .build();
+        let (committer, _) = create_table(table_dir.path(), orders_schema(), partition_spec, HashMap::new()).await?;
+        append(&committer, vec![(1, "eu"), (2, "us"), (3, "eu")]).await?;
+        append(&committer, vec![(4, "us")]).await?;
+        let table = ExternalIcebergTable::from_table(committer.load_table().await?).await?;
+        let current_snapshot_id = table.static_table.metadata().current_snapshot_id().unwrap();
+
+        let snapshots = query(
+            &table,
+            IcebergMetadataTableType::Snapshots,
+            "SELECT snapshot_id, parent_id, operation, summary['added-records'] FROM t ORDER BY committed_at",
+        )
+        .await?;
+        assert_eq!(snapshots.num_rows(), 2);
+        let snapshot_ids = snapshots.column(0).as_primitive::<Int64Type>();
+        let parent_ids = snapshots.column(1).as_primitive::<Int64Type>();
+        assert_eq!(snapshot_ids.value(1), current_snapshot_id);
+        assert!(parent_ids.is_null(0));
+        assert_eq!(parent_ids.value(1), snapshot_ids.value(0));
+        assert_eq!(snapshots.column(2).as_string::<i32>().value(1), "append");
+        let added_records: Vec<_> = snapshots.column(3).as_string::<i32>().iter().collect();
+        assert_eq!(added_records, vec![Some("3"), Some("1")]);
+
+        let files = query(
+            &table,
+            IcebergMetadataTableType::Files,
+            "SELECT \"partition\"['region'], record_count, content, file_format, equality_ids, key_metadata \
+             FROM t ORDER BY \"partition\"['region'], record_count",
+        )
+        .await?;
+        let regions: Vec<_> = files.column(0).as_string::<i32>().iter().collect();
+        assert_eq!(regions, vec![Some("eu"), Some("us"), Some("us")]);
+        assert_eq!(files.column(1).as_primitive::<Int64Type>().values(), &[2, 1, 1]);
+        assert_eq!(files.column(2).as_primitive::<Int32Type>().values(), &[0, 0, 0]);
+        assert!(files.column(3).as_string::<i32>().iter().all(|format| format == Some("parquet")));
+        assert_eq!(files.column(4).null_count(), 3);
+        assert_eq!(files.column(5).null_count(), 3);
+
+        let partitions = query(
+            &table,
+            IcebergMetadataTableType::Partitions,
+            "SELECT \"partition\"['region'], record_count, file_count, last_updated_snapshot_id \
+             FROM t ORDER BY \"partition\"['region']",
+        )
+        .await?;
+        let regions: Vec<_> = partitions.column(0).as_string::<i32>().iter().collect();
+        assert_eq!(regions, vec![Some("eu"), Some("us")]);
+        assert_eq!(partitions.column(1).as_primitive::<Int64Type>().values(), &[2, 2]);
+        assert_eq!(partitions.column(2).as_primitive::<Int32Type>().values(), &[1, 2]);
+        assert_eq!(
+            partitions.column(3).as_primitive::<Int64Type>().values(),
+            &[snapshot_ids.value(0), current_snapshot_id]
+        );
+
+        let refs = query(&table, IcebergMetadataTableType::Refs, "SELECT name, type, snapshot_id FROM t").await?;
+        assert_eq!(refs.column(0).as_string::<i32>().value(0), "main");
+        assert_eq!(refs.column(1).as_string::<i32>().value(0), "BRANCH");
+        assert_eq!(refs.column(2).as_primitive::<Int64Type>().value(0), current_snapshot_id);
+        Ok(())
+    }
+}
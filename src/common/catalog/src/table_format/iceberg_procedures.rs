@@ -0,0 +1,1080 @@
+use crate::table_format::external_table::IcebergTableScan;
+use crate::table_format::iceberg_commit::{
+    commit_snapshot, commit_table_change, new_manifest_writer, partition_spec, to_datafusion_error, IcebergTableCommitter,
+    NewSnapshot, RemovedFiles, SnapshotUpdate, TableChange,
+};
+use crate::table_format::iceberg_metadata_table::snapshot_refs;
+use crate::table_format::iceberg_sink::IcebergDataSink;
+use async_trait::async_trait;
+use chrono::Utc;
+use datafusion::arrow::array::{ArrayRef, AsArray, RecordBatch, UInt64Array};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Int64Type, Schema, TimeUnit};
+use datafusion::catalog::Session;
+use datafusion::common::{plan_err, ScalarValue};
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::physical_plan::execute_stream;
+use futures::{StreamExt, TryStreamExt};
+use iceberg::arrow::schema_to_arrow_schema;
+use iceberg::spec::{
+    DataContentType, ManifestContentType, ManifestFile, Operation, SnapshotRef, Struct, TableMetadata,
+};
+use iceberg::table::Table;
+use iceberg::{TableRequirement, TableUpdate};
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Table properties with the defaults of the maintenance procedures.
+const MAX_SNAPSHOT_AGE_MS: &str = "history.expire.max-snapshot-age-ms";
+const MAX_SNAPSHOT_AGE_MS_DEFAULT: i64 = 5 * 24 * 60 * 60 * 1000;
+const MIN_SNAPSHOTS_TO_KEEP: &str = "history.expire.min-snapshots-to-keep";
+const MIN_SNAPSHOTS_TO_KEEP_DEFAULT: usize = 1;
+const WRITE_TARGET_FILE_SIZE_BYTES: &str = "write.target-file-size-bytes";
+const WRITE_TARGET_FILE_SIZE_BYTES_DEFAULT: u64 = 512 * 1024 * 1024;
+const MANIFEST_TARGET_SIZE_BYTES: &str = "commit.manifest.target-size-bytes";
+const MANIFEST_TARGET_SIZE_BYTES_DEFAULT: u64 = 8 * 1024 * 1024;
+
+/// Partitions with fewer small data files are only compacted if those add up to a full file.
+const MIN_INPUT_FILES_DEFAULT: usize = 5;
+/// Files younger than this may belong to a commit in progress and aren't orphans yet.
+const ORPHAN_FILE_MIN_AGE_MS: i64 = 3 * 24 * 60 * 60 * 1000;
+/// Number of files deleted at the same time.
+const DELETE_CONCURRENCY: usize = 16;
+
+/// A table maintenance procedure, called like Spark's Iceberg procedures with
+/// `CALL <catalog>.system.<procedure>(table => '<schema>.<table>', ...)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IcebergProcedure {
+    /// Removes the snapshots older than `older_than_ms` but the last `retain_last` ones, or
+    /// the snapshots in `snapshot_ids`, and deletes the files only they reference.
+    ExpireSnapshots {
+        older_than_ms: Option<i64>,
+        retain_last: Option<usize>,
+        snapshot_ids: Vec<i64>,
+    },
+    /// Bin-packs the small data files of every partition into files of the target size.
+    RewriteDataFiles {
+        target_file_size_bytes: Option<u64>,
+        min_input_files: Option<usize>,
+    },
+    /// Merges the small data manifests of every partition spec.
+    RewriteManifests,
+    /// Deletes the files under `location` that no metadata of the table references.
+    RemoveOrphanFiles {
+        older_than_ms: Option<i64>,
+        location: Option<String>,
+    },
+}
+
+impl IcebergProcedure {
+    /// The parameters of the procedure `name` in positional order, None if there is no
+    /// such procedure.
+    pub fn parameters(name: &str) -> Option<&'static [&'static str]> {
+        match name {
+            "expire_snapshots" => Some(&["table", "older_than", "retain_last", "snapshot_ids", "dry_run"]),
+            "rewrite_data_files" => Some(&["table", "target_file_size_bytes", "min_input_files", "dry_run"]),
+            "rewrite_manifests" => Some(&["table", "dry_run"]),
+            "remove_orphan_files" => Some(&["table", "older_than", "location", "dry_run"]),
+            _ => None,
+        }
+    }
+
+    /// Creates the procedure `name` from its arguments by parameter name, other than
+    /// `table`. Returns whether it is a dry run too.
+    pub fn try_new(name: &str, mut args: HashMap<String, ScalarValue>) -> DFResult<(Self, bool)> {
+        let dry_run = bool_arg(&mut args, "dry_run")?.unwrap_or(false);
+        let procedure = match name {
+            "expire_snapshots" => IcebergProcedure::ExpireSnapshots {
+                older_than_ms: timestamp_arg(&mut args, "older_than")?,
+                retain_last: u64_arg(&mut args, "retain_last")?.map(|n| n as usize),
+                snapshot_ids: i64_list_arg(&mut args, "snapshot_ids")?,
+            },
+            "rewrite_data_files" => IcebergProcedure::RewriteDataFiles {
+                target_file_size_bytes: u64_arg(&mut args, "target_file_size_bytes")?,
+                min_input_files: u64_arg(&mut args, "min_input_files")?.map(|n| n as usize),
+            },
+            "rewrite_manifests" => IcebergProcedure::RewriteManifests,
+            "remove_orphan_files" => IcebergProcedure::RemoveOrphanFiles {
+                older_than_ms: timestamp_arg(&mut args, "older_than")?,
+                location: string_arg(&mut args, "location")?,
+            },
+            _ => return plan_err!("unknown procedure {name}"),
+        };
+        if let Some(parameter) = args.keys().next() {
+            return plan_err!("unknown parameter {parameter} of procedure {name}");
+        }
+        Ok((procedure, dry_run))
+    }
+
+    /// Schema of the single row of counts the procedure returns.
+    pub fn schema(&self) -> Schema {
+        let columns: &[&str] = match self {
+            IcebergProcedure::ExpireSnapshots { .. } => &[
+                "expired_snapshots_count",
+                "deleted_data_files_count",
+                "deleted_position_delete_files_count",
+                "deleted_equality_delete_files_count",
+                "deleted_manifest_files_count",
+                "deleted_manifest_lists_count",
+                "deleted_statistics_files_count",
+                "deleted_bytes_count",
+                "failed_deletes_count",
+            ],
+            IcebergProcedure::RewriteDataFiles { .. } => {
+                &["rewritten_data_files_count", "added_data_files_count", "rewritten_bytes_count"]
+            }
+            IcebergProcedure::RewriteManifests => {
+                &["rewritten_manifests_count", "added_manifests_count", "rewritten_bytes_count"]
+            }
+            IcebergProcedure::RemoveOrphanFiles { .. } => {
+                &["orphan_files_count", "orphan_bytes_count", "failed_deletes_count"]
+            }
+        };
+        Schema::new(
+            columns
+                .iter()
+                .map(|name| Field::new(*name, DataType::UInt64, false))
+                .collect::<Vec<Field>>(),
+        )
+    }
+}
+
+/// Runs `procedure` on the current table of `committer`, only reporting what it would do
+/// if `dry_run`. Returns a single row with the counts of the changed files.
+///
+/// `object_store` lists the files under the table location to find orphan files.
+pub async fn call_procedure(
+    state: &dyn Session,
+    procedure: &IcebergProcedure,
+    dry_run: bool,
+    committer: Arc<dyn IcebergTableCommitter>,
+    object_store: Arc<dyn ObjectStore>,
+) -> DFResult<RecordBatch> {
+    let table = committer.load_table().await?;
+    let counts = match procedure {
+        IcebergProcedure::ExpireSnapshots {
+            older_than_ms,
+            retain_last,
+            snapshot_ids,
+        } => {
+            expire_snapshots(&table, committer.as_ref(), *older_than_ms, *retain_last, snapshot_ids, dry_run)
+                .await?
+        }
+        IcebergProcedure::RewriteDataFiles {
+            target_file_size_bytes,
+            min_input_files,
+        } => {
+            rewrite_data_files(state, &table, committer, *target_file_size_bytes, *min_input_files, dry_run)
+                .await?
+        }
+        IcebergProcedure::RewriteManifests => rewrite_manifests(&table, committer.as_ref(), dry_run).await?,
+        IcebergProcedure::RemoveOrphanFiles {
+            older_than_ms,
+            location,
+        } => remove_orphan_files(&table, object_store, *older_than_ms, location.as_deref(), dry_run).await?,
+    };
+
+    let schema = procedure.schema();
+    debug_assert!(counts
+        .iter()
+        .map(|(name, _)| *name)
+        .eq(schema.fields().iter().map(|field| field.name().as_str())));
+    let columns = counts
+        .iter()
+        .map(|(_, count)| Arc::new(UInt64Array::from(vec![*count])) as ArrayRef)
+        .collect();
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+async fn expire_snapshots(
+    table: &Table,
+    committer: &dyn IcebergTableCommitter,
+    older_than_ms: Option<i64>,
+    retain_last: Option<usize>,
+    snapshot_ids: &[i64],
+    dry_run: bool,
+) -> DFResult<Vec<(&'static str, u64)>> {
+    let metadata = table.metadata();
+    let table_name = table.identifier().name();
+    let ref_heads = ref_snapshot_ids(metadata)?;
+    let expired: HashSet<i64> = if snapshot_ids.is_empty() {
+        let older_than_ms = older_than_ms.unwrap_or_else(|| {
+            Utc::now().timestamp_millis() - property(metadata, MAX_SNAPSHOT_AGE_MS, MAX_SNAPSHOT_AGE_MS_DEFAULT)
+        });
+        let retain_last = retain_last
+            .unwrap_or_else(|| property(metadata, MIN_SNAPSHOTS_TO_KEEP, MIN_SNAPSHOTS_TO_KEEP_DEFAULT))
+            .max(1);
+        let mut retained = ref_heads.clone();
+        let mut ancestor = metadata.current_snapshot();
+        for _ in 0..retain_last {
+            let Some(snapshot) = ancestor else { break };
+            retained.insert(snapshot.snapshot_id());
+            ancestor = snapshot
+                .parent_snapshot_id()
+                .and_then(|parent_id| metadata.snapshot_by_id(parent_id));
+        }
+        metadata
+            .snapshots()
+            .filter(|snapshot| snapshot.timestamp_ms() < older_than_ms && !retained.contains(&snapshot.snapshot_id()))
+            .map(|snapshot| snapshot.snapshot_id())
+            .collect()
+    } else {
+        for snapshot_id in snapshot_ids {
+            if metadata.snapshot_by_id(*snapshot_id).is_none() {
+                return plan_err!("snapshot {snapshot_id} of {table_name} not found");
+            }
+            if ref_heads.contains(snapshot_id) {
+                return plan_err!("cannot expire snapshot {snapshot_id} of {table_name}, a branch or tag references it");
+            }
+        }
+        snapshot_ids.iter().copied().collect()
+    };
+
+    let mut reachable_files = ReachableFiles::default();
+    let mut deleted_files = HashMap::new();
+    if !expired.is_empty() {
+        let before = reachable_files.reachable_from(table, metadata.snapshots().cloned().collect()).await?;
+        let after = if dry_run {
+            let retained = metadata
+                .snapshots()
+                .filter(|snapshot| !expired.contains(&snapshot.snapshot_id()))
+                .cloned()
+                .collect();
+            reachable_files.reachable_from(table, retained).await?
+        } else {
+            let expiration = SnapshotExpiration {
+                snapshot_ids: expired.clone(),
+            };
+            let committed = commit_table_change(committer, &expiration).await?;
+            let retained = committed.metadata().snapshots().cloned().collect();
+            reachable_files.reachable_from(&committed, retained).await?
+        };
+        deleted_files = before;
+        deleted_files.retain(|path, _| !after.contains_key(path));
+    }
+
+    // manifest lists don't record their size
+    let file_io = table.file_io();
+    for (path, (kind, size)) in deleted_files.iter_mut() {
+        if *kind == FileKind::ManifestList {
+            let input = file_io.new_input(path.as_str()).map_err(to_datafusion_error)?;
+            *size = input.metadata().await.map(|metadata| metadata.size).unwrap_or(0);
+        }
+    }
+    println!(
+        "expire {} snapshots of table {}, {} files unreachable{}",
+        expired.len(),
+        table_name,
+        deleted_files.len(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+    let mut failed_deletes = HashSet::new();
+    if !dry_run {
+        let deletes = deleted_files.keys().cloned().map(|path| {
+            let file_io = file_io.clone();
+            async move { file_io.delete(&path).await.err().map(|_| path) }
+        });
+        failed_deletes = futures::stream::iter(deletes)
+            .buffer_unordered(DELETE_CONCURRENCY)
+            .collect::<Vec<Option<String>>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        deleted_files.retain(|path, _| !failed_deletes.contains(path));
+    }
+
+    let count = |kind: FileKind| deleted_files.values().filter(|(k, _)| *k == kind).count() as u64;
+    Ok(vec![
+        ("expired_snapshots_count", expired.len() as u64),
+        ("deleted_data_files_count", count(FileKind::Data)),
+        ("deleted_position_delete_files_count", count(FileKind::PositionDeletes)),
+        ("deleted_equality_delete_files_count", count(FileKind::EqualityDeletes)),
+        ("deleted_manifest_files_count", count(FileKind::Manifest)),
+        ("deleted_manifest_lists_count", count(FileKind::ManifestList)),
+        ("deleted_statistics_files_count", count(FileKind::Statistics)),
+        ("deleted_bytes_count", deleted_files.values().map(|(_, size)| size).sum()),
+        ("failed_deletes_count", failed_deletes.len() as u64),
+    ])
+}
+
+/// Removes snapshots with their statistics files.
+struct SnapshotExpiration {
+    snapshot_ids: HashSet<i64>,
+}
+
+#[async_trait]
+impl TableChange for SnapshotExpiration {
+    async fn produce(&self, base: &Table) -> DFResult<(Vec<TableUpdate>, Vec<TableRequirement>)> {
+        let metadata = base.metadata();
+        let ref_heads = ref_snapshot_ids(metadata)?;
+        let mut snapshot_ids = Vec::new();
+        let mut updates = Vec::new();
+        for snapshot_id in self.snapshot_ids.iter().copied() {
+            if ref_heads.contains(&snapshot_id) {
+                return Err(DataFusionError::Execution(format!(
+                    "snapshot {} of {} was referenced by a concurrent commit",
+                    snapshot_id,
+                    base.identifier().name()
+                )));
+            }
+            if metadata.snapshot_by_id(snapshot_id).is_some() {
+                snapshot_ids.push(snapshot_id);
+            }
+            if metadata.statistics_for_snapshot(snapshot_id).is_some() {
+                updates.push(TableUpdate::RemoveStatistics { snapshot_id });
+            }
+            if metadata.partition_statistics_for_snapshot(snapshot_id).is_some() {
+                updates.push(TableUpdate::RemovePartitionStatistics { snapshot_id });
+            }
+        }
+        updates.insert(0, TableUpdate::RemoveSnapshots { snapshot_ids });
+        let requirements = vec![TableRequirement::UuidMatch { uuid: metadata.uuid() }];
+        Ok((updates, requirements))
+    }
+}
+
+/// Ids of the snapshots at the head of a branch or tag, including the implicit main
+/// branch of tables without refs.
+fn ref_snapshot_ids(metadata: &TableMetadata) -> DFResult<HashSet<i64>> {
+    Ok(snapshot_refs(metadata)?
+        .values()
+        .map(|reference| reference.snapshot_id)
+        .collect())
+}
+
+/// Kinds of the files a snapshot references.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    ManifestList,
+    Manifest,
+    Data,
+    PositionDeletes,
+    EqualityDeletes,
+    Statistics,
+}
+
+/// Live content files of a manifest with their kind and size.
+type ManifestContent = Arc<Vec<(String, FileKind, u64)>>;
+
+/// Collects the files reachable from snapshots, reading every manifest once.
+#[derive(Default)]
+struct ReachableFiles {
+    /// Files of the manifests read so far, by manifest path
+    manifests: HashMap<String, ManifestContent>,
+}
+
+impl ReachableFiles {
+    /// Every file reachable from `snapshots` of `table` with its kind and size. The size of
+    /// manifest lists is unknown and 0.
+    async fn reachable_from(
+        &mut self,
+        table: &Table,
+        snapshots: Vec<SnapshotRef>,
+    ) -> DFResult<HashMap<String, (FileKind, u64)>> {
+        let metadata = table.metadata();
+        let file_io = table.file_io();
+        let mut files = HashMap::new();
+        for snapshot in snapshots {
+            files.insert(snapshot.manifest_list().to_string(), (FileKind::ManifestList, 0));
+            if let Some(statistics) = metadata.statistics_for_snapshot(snapshot.snapshot_id()) {
+                let size = statistics.file_size_in_bytes.max(0) as u64;
+                files.insert(statistics.statistics_path.clone(), (FileKind::Statistics, size));
+            }
+            if let Some(statistics) = metadata.partition_statistics_for_snapshot(snapshot.snapshot_id()) {
+                let size = statistics.file_size_in_bytes.max(0) as u64;
+                files.insert(statistics.statistics_path.clone(), (FileKind::Statistics, size));
+            }
+
+            let manifest_list = snapshot
+                .load_manifest_list(file_io, metadata)
+                .await
+                .map_err(to_datafusion_error)?;
+            for manifest_file in manifest_list.entries() {
+                let size = manifest_file.manifest_length.max(0) as u64;
+                files.insert(manifest_file.manifest_path.clone(), (FileKind::Manifest, size));
+                let content_files = match self.manifests.get(&manifest_file.manifest_path) {
+                    Some(content_files) => content_files.clone(),
+                    None => {
+                        let manifest = manifest_file
+                            .load_manifest(file_io)
+                            .await
+                            .map_err(to_datafusion_error)?;
+                        let content_files = manifest
+                            .entries()
+                            .iter()
+                            .filter(|entry| entry.is_alive())
+                            .map(|entry| {
+                                let kind = match entry.content_type() {
+                                    DataContentType::Data => FileKind::Data,
+                                    DataContentType::PositionDeletes => FileKind::PositionDeletes,
+                                    DataContentType::EqualityDeletes => FileKind::EqualityDeletes,
+                                };
+                                let size = entry.data_file().file_size_in_bytes();
+                                (entry.file_path().to_string(), kind, size)
+                            })
+                            .collect::<Vec<_>>();
+                        let content_files = Arc::new(content_files);
+                        self.manifests
+                            .insert(manifest_file.manifest_path.clone(), content_files.clone());
+                        content_files
+                    }
+                };
+                for (path, kind, size) in content_files.iter() {
+                    files.insert(path.clone(), (*kind, *size));
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+async fn rewrite_data_files(
+    state: &dyn Session,
+    table: &Table,
+    committer: Arc<dyn IcebergTableCommitter>,
+    target_file_size_bytes: Option<u64>,
+    min_input_files: Option<usize>,
+    dry_run: bool,
+) -> DFResult<Vec<(&'static str, u64)>> {
+    let metadata = table.metadata();
+    let target_size = target_file_size_bytes
+        .unwrap_or_else(|| property(metadata, WRITE_TARGET_FILE_SIZE_BYTES, WRITE_TARGET_FILE_SIZE_BYTES_DEFAULT))
+        .max(1);
+    let min_input_files = min_input_files.unwrap_or(MIN_INPUT_FILES_DEFAULT).max(1);
+
+    // data files well below the target size, by partition
+    let mut small_files: HashMap<(i32, Struct), Vec<(String, u64)>> = HashMap::new();
+    let current_snapshot = metadata.current_snapshot();
+    if let Some(snapshot) = current_snapshot {
+        let manifest_list = snapshot
+            .load_manifest_list(table.file_io(), metadata)
+            .await
+            .map_err(to_datafusion_error)?;
+        for manifest_file in manifest_list.entries() {
+            if manifest_file.content != ManifestContentType::Data {
+                continue;
+            }
+            let manifest = manifest_file
+                .load_manifest(table.file_io())
+                .await
+                .map_err(to_datafusion_error)?;
+            for entry in manifest.entries().iter().filter(|entry| entry.is_alive()) {
+                let size = entry.data_file().file_size_in_bytes();
+                if size < target_size / 4 * 3 {
+                    small_files
+                        .entry((manifest_file.partition_spec_id, entry.data_file().partition().clone()))
+                        .or_default()
+                        .push((entry.file_path().to_string(), size));
+                }
+            }
+        }
+    }
+    let mut bins: Vec<Vec<(String, u64)>> = Vec::new();
+    for files in small_files.into_values() {
+        let total_size: u64 = files.iter().map(|(_, size)| size).sum();
+        if files.len() < 2 || (files.len() < min_input_files && total_size < target_size) {
+            continue;
+        }
+        bins.extend(bin_pack(files, target_size).into_iter().filter(|bin| bin.len() > 1));
+    }
+
+    let rewritten_files: HashSet<String> = bins.iter().flatten().map(|(path, _)| path.clone()).collect();
+    let rewritten_bytes: u64 = bins.iter().flatten().map(|(_, size)| size).sum();
+    let mut added_files_count: u64 = bins
+        .iter()
+        .map(|bin| bin.iter().map(|(_, size)| size).sum::<u64>().div_ceil(target_size))
+        .sum();
+    println!(
+        "rewrite {} data files of table {} into {} bins{}",
+        rewritten_files.len(),
+        table.identifier().name(),
+        bins.len(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+    if let Some(snapshot) = current_snapshot.filter(|_| !dry_run && !bins.is_empty()) {
+        let schema = Arc::new(
+            schema_to_arrow_schema(metadata.current_schema())
+                .map_err(|e| DataFusionError::External(Box::new(e)))?,
+        );
+        let scan = IcebergTableScan::try_new(
+            table.clone(),
+            Some(snapshot.snapshot_id()),
+            schema,
+            None,
+            &[],
+            None,
+            1,
+            state.config().batch_size(),
+        )
+        .await?;
+        let sink = IcebergDataSink::try_new(table.clone(), committer.clone(), false)?;
+        let mut added_files = Vec::new();
+        for bin in &bins {
+            let paths = bin.iter().map(|(path, _)| path.clone()).collect();
+            let bin_scan = scan.clone().with_files(&paths);
+            let data = execute_stream(Arc::new(bin_scan), state.task_ctx())?;
+            let (files, _) = sink.write_data_files(data).await?;
+            added_files.extend(files);
+        }
+        added_files_count = added_files.len() as u64;
+
+        // delete files committed since the rows were read would not apply to the rewritten
+        // files, the commit fails on those
+        let update = SnapshotUpdate {
+            operation: Operation::Replace,
+            added_files,
+            removed_files: RemovedFiles::Paths(rewritten_files.clone()),
+            referenced_files: HashSet::new(),
+            base_snapshot_id: Some(snapshot.snapshot_id()),
+            schema_id: metadata.current_schema_id(),
+            partition_spec_id: metadata.default_partition_spec_id(),
+        };
+        commit_snapshot(committer.as_ref(), &update).await?;
+    }
+
+    Ok(vec![
+        ("rewritten_data_files_count", rewritten_files.len() as u64),
+        ("added_data_files_count", added_files_count),
+        ("rewritten_bytes_count", rewritten_bytes),
+    ])
+}
+
+/// Packs `files` into bins of at most `target_size` bytes, first fit by decreasing size.
+fn bin_pack(mut files: Vec<(String, u64)>, target_size: u64) -> Vec<Vec<(String, u64)>> {
+    files.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    let mut bins: Vec<(u64, Vec<(String, u64)>)> = Vec::new();
+    for file in files {
+        match bins.iter_mut().find(|(size, _)| size + file.1 <= target_size) {
+            Some((size, bin)) => {
+                *size += file.1;
+                bin.push(file);
+            }
+            None => bins.push((file.1, vec![file])),
+        }
+    }
+    bins.into_iter().map(|(_, bin)| bin).collect()
+}
+
+async fn rewrite_manifests(
+    table: &Table,
+    committer: &dyn IcebergTableCommitter,
+    dry_run: bool,
+) -> DFResult<Vec<(&'static str, u64)>> {
+    let metadata = table.metadata();
+    let target_size = property(metadata, MANIFEST_TARGET_SIZE_BYTES, MANIFEST_TARGET_SIZE_BYTES_DEFAULT).max(1);
+    let mut outcome = ManifestRewriteOutcome::default();
+    if let Some(snapshot) = metadata.current_snapshot() {
+        let manifest_list = snapshot
+            .load_manifest_list(table.file_io(), metadata)
+            .await
+            .map_err(to_datafusion_error)?;
+        let bins = plan_manifest_bins(manifest_list.entries(), target_size);
+        outcome = ManifestRewriteOutcome::of(&bins);
+        if !dry_run && !bins.is_empty() {
+            let rewrite = ManifestRewrite {
+                target_size,
+                outcome: Mutex::new(ManifestRewriteOutcome::default()),
+            };
+            commit_table_change(committer, &rewrite).await?;
+            outcome = rewrite.outcome.into_inner().unwrap();
+        }
+    }
+    println!(
+        "rewrite {} manifests of table {} into {}{}",
+        outcome.rewritten_manifests,
+        table.identifier().name(),
+        outcome.added_manifests,
+        if dry_run { " (dry run)" } else { "" }
+    );
+    Ok(vec![
+        ("rewritten_manifests_count", outcome.rewritten_manifests),
+        ("added_manifests_count", outcome.added_manifests),
+        ("rewritten_bytes_count", outcome.rewritten_bytes),
+    ])
+}
+
+/// Groups the data manifests smaller than `target_size` by partition spec into bins of
+/// about `target_size` bytes. Bins of a single manifest are left out.
+fn plan_manifest_bins(manifests: &[ManifestFile], target_size: u64) -> Vec<Vec<ManifestFile>> {
+    let mut by_spec: BTreeMap<i32, Vec<&ManifestFile>> = BTreeMap::new();
+    for manifest_file in manifests {
+        if manifest_file.content == ManifestContentType::Data && (manifest_file.manifest_length as u64) < target_size {
+            by_spec.entry(manifest_file.partition_spec_id).or_default().push(manifest_file);
+        }
+    }
+    let mut bins = Vec::new();
+    for manifest_files in by_spec.into_values() {
+        let mut bin: Vec<ManifestFile> = Vec::new();
+        let mut bin_size = 0;
+        for manifest_file in manifest_files {
+            bin.push(manifest_file.clone());
+            bin_size += manifest_file.manifest_length.max(0) as u64;
+            if bin_size >= target_size {
+                bins.push(std::mem::take(&mut bin));
+                bin_size = 0;
+            }
+        }
+        bins.push(bin);
+    }
+    bins.retain(|bin| bin.len() > 1);
+    bins
+}
+
+#[derive(Debug, Default)]
+struct ManifestRewriteOutcome {
+    rewritten_manifests: u64,
+    added_manifests: u64,
+    rewritten_bytes: u64,
+}
+
+impl ManifestRewriteOutcome {
+    fn of(bins: &[Vec<ManifestFile>]) -> Self {
+        ManifestRewriteOutcome {
+            rewritten_manifests: bins.iter().map(|bin| bin.len() as u64).sum(),
+            added_manifests: bins.len() as u64,
+            rewritten_bytes: bins
+                .iter()
+                .flatten()
+                .map(|manifest_file| manifest_file.manifest_length.max(0) as u64)
+                .sum(),
+        }
+    }
+}
+
+/// Rewrites the small data manifests of the current snapshot into fewer manifests, keeping
+/// the snapshot and sequence numbers of their entries.
+struct ManifestRewrite {
+    target_size: u64,
+    /// Outcome of the last produced change
+    outcome: Mutex<ManifestRewriteOutcome>,
+}
+
+#[async_trait]
+impl TableChange for ManifestRewrite {
+    async fn produce(&self, base: &Table) -> DFResult<(Vec<TableUpdate>, Vec<TableRequirement>)> {
+        let metadata = base.metadata();
+        let file_io = base.file_io();
+        let mut new_snapshot = NewSnapshot::new(metadata);
+        let mut manifests = Vec::new();
+        let mut bins = Vec::new();
+        if let Some(snapshot) = metadata.current_snapshot() {
+            let manifest_list = snapshot
+                .load_manifest_list(file_io, metadata)
+                .await
+                .map_err(to_datafusion_error)?;
+            bins = plan_manifest_bins(manifest_list.entries(), self.target_size);
+            let rewritten: HashSet<&str> = bins
+                .iter()
+                .flatten()
+                .map(|manifest_file| manifest_file.manifest_path.as_str())
+                .collect();
+            manifests.extend(
+                manifest_list
+                    .entries()
+                    .iter()
+                    .filter(|manifest_file| !rewritten.contains(manifest_file.manifest_path.as_str()))
+                    .cloned(),
+            );
+        }
+        let kept_manifests = manifests.len();
+
+        let mut entries_processed = 0;
+        for bin in &bins {
+            let spec = partition_spec(metadata, bin[0].partition_spec_id)?;
+            let mut writer = new_manifest_writer(
+                base,
+                new_snapshot.next_manifest_path(metadata),
+                new_snapshot.snapshot_id,
+                ManifestContentType::Data,
+                &spec,
+            )?;
+            for manifest_file in bin {
+                let manifest = manifest_file
+                    .load_manifest(file_io)
+                    .await
+                    .map_err(to_datafusion_error)?;
+                for entry in manifest.entries().iter().filter(|entry| entry.is_alive()) {
+                    writer
+                        .add_existing_file(
+                            entry.data_file().clone(),
+                            entry.snapshot_id().unwrap_or(manifest_file.added_snapshot_id),
+                            entry.sequence_number().unwrap_or(manifest_file.sequence_number),
+                            entry.file_sequence_number,
+                        )
+                        .map_err(to_datafusion_error)?;
+                    entries_processed += 1;
+                }
+            }
+            manifests.push(writer.write_manifest_file().await.map_err(to_datafusion_error)?);
+        }
+
+        let outcome = ManifestRewriteOutcome::of(&bins);
+        let summary_properties = HashMap::from([
+            ("manifests-created".to_string(), outcome.added_manifests.to_string()),
+            ("manifests-replaced".to_string(), outcome.rewritten_manifests.to_string()),
+            ("manifests-kept".to_string(), kept_manifests.to_string()),
+            ("entries-processed".to_string(), entries_processed.to_string()),
+        ]);
+        *self.outcome.lock().unwrap() = outcome;
+        new_snapshot
+            .add(base, manifests, Operation::Replace, summary_properties, false)
+            .await
+    }
+}
+
+async fn remove_orphan_files(
+    table: &Table,
+    object_store: Arc<dyn ObjectStore>,
+    older_than_ms: Option<i64>,
+    location: Option<&str>,
+    dry_run: bool,
+) -> DFResult<Vec<(&'static str, u64)>> {
+    let metadata = table.metadata();
+    let older_than_ms = older_than_ms.unwrap_or_else(|| Utc::now().timestamp_millis() - ORPHAN_FILE_MIN_AGE_MS);
+    let table_location = FileLocation::parse(metadata.location());
+    let prefix = location.map_or_else(|| table_location.clone(), FileLocation::parse);
+    if !table_location.contains(&prefix) {
+        return plan_err!(
+            "cannot remove orphan files of {} outside of its location {}",
+            table.identifier().name(),
+            metadata.location()
+        );
+    }
+
+    let mut valid_locations: HashSet<FileLocation> = ReachableFiles::default()
+        .reachable_from(table, metadata.snapshots().cloned().collect())
+        .await?
+        .into_keys()
+        .map(|path| FileLocation::parse(&path))
+        .collect();
+    valid_locations.extend(
+        metadata
+            .statistics_iter()
+            .map(|statistics| FileLocation::parse(&statistics.statistics_path)),
+    );
+    valid_locations.extend(
+        metadata
+            .partition_statistics_iter()
+            .map(|statistics| FileLocation::parse(&statistics.statistics_path)),
+    );
+    valid_locations.extend(metadata.metadata_log().iter().map(|log| FileLocation::parse(&log.metadata_file)));
+    valid_locations.extend(table.metadata_location().map(FileLocation::parse));
+    valid_locations.insert(table_location.child("metadata/version-hint.text"));
+
+    let mut orphans = Vec::new();
+    let mut listing = object_store.list(Some(&prefix.path));
+    while let Some(meta) = listing.try_next().await? {
+        let listed = FileLocation {
+            path: meta.location.clone(),
+            ..table_location.clone()
+        };
+        if meta.last_modified.timestamp_millis() < older_than_ms && !valid_locations.contains(&listed) {
+            orphans.push(meta);
+        }
+    }
+    println!(
+        "remove {} orphan files of table {}{}",
+        orphans.len(),
+        table.identifier().name(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+    let mut failed_deletes = 0;
+    if !dry_run {
+        let locations: Vec<ObjectPath> = orphans.iter().map(|meta| meta.location.clone()).collect();
+        let deletes = locations.into_iter().map(|location| {
+            let object_store = object_store.clone();
+            async move { object_store.delete(&location).await.is_err() }
+        });
+        failed_deletes = futures::stream::iter(deletes)
+            .buffer_unordered(DELETE_CONCURRENCY)
+            .collect::<Vec<bool>>()
+            .await
+            .into_iter()
+            .filter(|failed| *failed)
+            .count() as u64;
+    }
+
+    Ok(vec![
+        ("orphan_files_count", orphans.len() as u64),
+        ("orphan_bytes_count", orphans.iter().map(|meta| meta.size).sum()),
+        ("failed_deletes_count", failed_deletes),
+    ])
+}
+
+/// A file location split into its scheme, bucket and path in the bucket. Locations
+/// without a scheme are local paths, the Hadoop S3 schemes are the same as `s3`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FileLocation {
+    scheme: String,
+    bucket: String,
+    path: ObjectPath,
+}
+
+impl FileLocation {
+    fn parse(location: &str) -> Self {
+        match Url::parse(location) {
+            Ok(url) => FileLocation {
+                scheme: match url.scheme() {
+                    "s3a" | "s3n" => "s3".to_string(),
+                    scheme => scheme.to_string(),
+                },
+                bucket: url.host_str().unwrap_or_default().to_string(),
+                path: ObjectPath::from_url_path(url.path()).unwrap_or_else(|_| ObjectPath::from(url.path())),
+            },
+            Err(_) => FileLocation {
+                scheme: "file".to_string(),
+                bucket: String::new(),
+                path: ObjectPath::from(location),
+            },
+        }
+    }
+
+    /// Whether `other` is this location or under it, comparing whole path segments.
+    fn contains(&self, other: &FileLocation) -> bool {
+        self.scheme == other.scheme && self.bucket == other.bucket && other.path.prefix_matches(&self.path)
+    }
+
+    fn child(&self, relative_path: &str) -> Self {
+        FileLocation {
+            path: ObjectPath::from(format!("{}/{relative_path}", self.path)),
+            ..self.clone()
+        }
+    }
+}
+
+/// The value of a table property, `default` if unset or invalid.
+fn property<T: FromStr>(metadata: &TableMetadata, key: &str, default: T) -> T {
+    metadata
+        .properties()
+        .get(key)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Takes the argument `name`, None if it wasn't given or is NULL.
+fn take_arg(args: &mut HashMap<String, ScalarValue>, name: &str) -> Option<ScalarValue> {
+    args.remove(name).filter(|value| !value.is_null())
+}
+
+fn bool_arg(args: &mut HashMap<String, ScalarValue>, name: &str) -> DFResult<Option<bool>> {
+    match take_arg(args, name) {
+        None => Ok(None),
+        Some(ScalarValue::Boolean(value)) => Ok(value),
+        Some(value) => plan_err!("{name} must be a boolean, found {value}"),
+    }
+}
+
+fn u64_arg(args: &mut HashMap<String, ScalarValue>, name: &str) -> DFResult<Option<u64>> {
+    let Some(value) = take_arg(args, name) else {
+        return Ok(None);
+    };
+    match value.cast_to(&DataType::UInt64) {
+        Ok(ScalarValue::UInt64(value)) => Ok(value),
+        _ => plan_err!("{name} must be a non-negative integer, found {value}"),
+    }
+}
+
+/// A timestamp argument in milliseconds since the epoch.
+fn timestamp_arg(args: &mut HashMap<String, ScalarValue>, name: &str) -> DFResult<Option<i64>> {
+    let Some(value) = take_arg(args, name) else {
+        return Ok(None);
+    };
+    match value.cast_to(&DataType::Timestamp(TimeUnit::Millisecond, None)) {
+        Ok(ScalarValue::TimestampMillisecond(value, _)) => Ok(value),
+        _ => plan_err!("{name} must be a timestamp, found {value}"),
+    }
+}
+
+/// An argument with a list of integers, or a single integer.
+fn i64_list_arg(args: &mut HashMap<String, ScalarValue>, name: &str) -> DFResult<Vec<i64>> {
+    match take_arg(args, name) {
+        None => Ok(vec![]),
+        Some(ScalarValue::List(list)) => {
+            let values = cast(list.values(), &DataType::Int64)?;
+            Ok(values.as_primitive::<Int64Type>().iter().flatten().collect())
+        }
+        Some(value) => match value.cast_to(&DataType::Int64) {
+            Ok(ScalarValue::Int64(Some(value))) => Ok(vec![value]),
+            _ => plan_err!("{name} must be an array of integers, found {value}"),
+        },
+    }
+}
+
+fn string_arg(args: &mut HashMap<String, ScalarValue>, name: &str) -> DFResult<Option<String>> {
+    match take_arg(args, name) {
+        None => Ok(None),
+        Some(ScalarValue::Utf8(value) | ScalarValue::LargeUtf8(value) | ScalarValue::Utf8View(value)) => {
+            Ok(value)
+        }
+        Some(value) => plan_err!("{name} must be a string, found {value}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_format::iceberg_commit::testing::{empty_table, MemoryCommitter, TestDir};
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::arrow::datatypes::UInt64Type;
+    use datafusion::execution::TaskContext;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use datafusion::datasource::sink::DataSink;
+    use datafusion::prelude::SessionContext;
+    use object_store::local::LocalFileSystem;
+    use std::fs;
+
+    #[test]
+    fn test_bin_pack() {
+        let files = [("a", 60), ("b", 50), ("c", 40), ("d", 30), ("e", 10)]
+            .into_iter()
+            .map(|(path, size)| (path.to_string(), size))
+            .collect();
+        let bins = bin_pack(files, 100);
+        let bins: Vec<Vec<&str>> = bins
+            .iter()
+            .map(|bin| bin.iter().map(|(path, _)| path.as_str()).collect())
+            .collect();
+        assert_eq!(bins, vec![vec!["a", "c"], vec!["b", "d", "e"]]);
+    }
+
+    #[test]
+    fn test_procedure_arguments() -> DFResult<()> {
+        let args = HashMap::from([
+            ("older_than".to_string(), ScalarValue::Utf8(Some("2024-01-01T00:00:00".to_string()))),
+            ("retain_last".to_string(), ScalarValue::Int64(Some(3))),
+            ("dry_run".to_string(), ScalarValue::Boolean(Some(true))),
+        ]);
+        let (procedure, dry_run) = IcebergProcedure::try_new("expire_snapshots", args)?;
+        assert_eq!(
+            procedure,
+            IcebergProcedure::ExpireSnapshots {
+                older_than_ms: Some(1704067200000),
+                retain_last: Some(3),
+                snapshot_ids: vec![],
+            }
+        );
+        assert!(dry_run);
+
+        let args = HashMap::from([("retain_last".to_string(), ScalarValue::Int64(Some(-1)))]);
+        assert!(IcebergProcedure::try_new("expire_snapshots", args).is_err());
+        let args = HashMap::from([("location".to_string(), ScalarValue::Utf8(None))]);
+        assert!(IcebergProcedure::try_new("rewrite_manifests", args).is_err());
+        Ok(())
+    }
+
+    /// Runs the procedure `name` with `args` on the table of `committer`, returns the
+    /// counts it reports by name.
+    async fn call(
+        committer: &Arc<MemoryCommitter>,
+        name: &str,
+        args: Vec<(&str, ScalarValue)>,
+    ) -> DFResult<HashMap<String, u64>> {
+        let args = args.into_iter().map(|(name, value)| (name.to_string(), value)).collect();
+        let (procedure, dry_run) = IcebergProcedure::try_new(name, args)?;
+        let ctx = SessionContext::new();
+        let batch = call_procedure(
+            &ctx.state(),
+            &procedure,
+            dry_run,
+            committer.clone(),
+            Arc::new(LocalFileSystem::new()),
+        )
+        .await?;
+        Ok(batch
+            .schema()
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, column)| (field.name().clone(), column.as_primitive::<UInt64Type>().value(0)))
+            .collect())
+    }
+
+    async fn data_file_paths(committer: &Arc<MemoryCommitter>) -> DFResult<Vec<String>> {
+        let table = committer.load_table().await?;
+        let files = ReachableFiles::default()
+            .reachable_from(&table, table.metadata().current_snapshot().into_iter().cloned().collect())
+            .await?;
+        let mut paths = files
+            .into_iter()
+            .filter(|(_, (kind, _))| *kind == FileKind::Data)
+            .map(|(path, _)| path)
+            .collect::<Vec<String>>();
+        paths.sort();
+        Ok(paths)
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_procedures() -> DFResult<()> {
+        let table_dir = TestDir::new("iceberg-procedures");
+        let (committer, _) = empty_table(&table_dir, HashMap::new()).await?;
+
+        // three appends, each with its own data file and manifest
+        for id in 1..=3 {
+            let sink = IcebergDataSink::try_new(committer.load_table().await?, committer.clone(), false)?;
+            let schema = sink.schema().clone();
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int64Array::from(vec![id])), Arc::new(StringArray::from(vec!["eu"]))],
+            )?;
+            let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+            sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+        }
+        let appended_files = data_file_paths(&committer).await?;
+        assert_eq!(appended_files.len(), 3);
+        let dry_run = ("dry_run", ScalarValue::Boolean(Some(true)));
+
+        let planned = call(&committer, "rewrite_manifests", vec![dry_run.clone()]).await?;
+        assert_eq!(planned["rewritten_manifests_count"], 3);
+        assert_eq!(committer.load_table().await?.metadata().snapshots().count(), 3);
+        let rewritten = call(&committer, "rewrite_manifests", vec![]).await?;
+        assert_eq!((rewritten["rewritten_manifests_count"], rewritten["added_manifests_count"]), (3, 1));
+
+        let min_input_files = ("min_input_files", ScalarValue::Int64(Some(2)));
+        let planned = call(&committer, "rewrite_data_files", vec![min_input_files.clone(), dry_run.clone()]).await?;
+        assert_eq!(planned["rewritten_data_files_count"], 3);
+        assert_eq!(data_file_paths(&committer).await?, appended_files);
+        let rewritten = call(&committer, "rewrite_data_files", vec![min_input_files]).await?;
+        assert_eq!((rewritten["rewritten_data_files_count"], rewritten["added_data_files_count"]), (3, 1));
+        let compacted_files = data_file_paths(&committer).await?;
+        assert_eq!(compacted_files.len(), 1);
+
+        let expire_args = |dry_run: bool| {
+            vec![
+                ("older_than", ScalarValue::TimestampMillisecond(Some(Utc::now().timestamp_millis() + 1000), None)),
+                ("retain_last", ScalarValue::Int64(Some(1))),
+                ("dry_run", ScalarValue::Boolean(Some(dry_run))),
+            ]
+        };
+        let planned = call(&committer, "expire_snapshots", expire_args(true)).await?;
+        assert_eq!((planned["expired_snapshots_count"], planned["deleted_data_files_count"]), (4, 3));
+        assert!(appended_files.iter().all(|path| fs::exists(path).unwrap()));
+        let expired = call(&committer, "expire_snapshots", expire_args(false)).await?;
+        assert_eq!((expired["deleted_data_files_count"], expired["failed_deletes_count"]), (3, 0));
+        assert!(appended_files.iter().all(|path| !fs::exists(path).unwrap()));
+        assert_eq!(committer.load_table().await?.metadata().snapshots().count(), 1);
+
+        let orphan = table_dir.path().join("data").join("orphan.parquet");
+        fs::write(&orphan, "")?;
+        let orphan_args = |dry_run: bool| {
+            vec![
+                ("older_than", ScalarValue::TimestampMillisecond(Some(Utc::now().timestamp_millis() + 1000), None)),
+                ("dry_run", ScalarValue::Boolean(Some(dry_run))),
+            ]
+        };
+        assert_eq!(call(&committer, "remove_orphan_files", orphan_args(true)).await?["orphan_files_count"], 1);
+        assert!(orphan.exists());
+        let removed = call(&committer, "remove_orphan_files", orphan_args(false)).await?;
+        assert_eq!((removed["orphan_files_count"], removed["failed_deletes_count"]), (1, 0));
+        assert!(!orphan.exists());
+        assert_eq!(data_file_paths(&committer).await?, compacted_files);
+        // a sibling directory sharing the name of the table as a prefix is outside of it
+        let sibling = ("location", ScalarValue::Utf8(Some(format!("{}-backup", table_dir.path().display()))));
+        assert!(call(&committer, "remove_orphan_files", vec![sibling]).await.is_err());
+        Ok(())
+    }
+}
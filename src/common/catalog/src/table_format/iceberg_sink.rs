@@ -0,0 +1,511 @@
+use crate::table_format::iceberg_commit::{commit_snapshot, IcebergTableCommitter, RemovedFiles, SnapshotUpdate};
+use async_trait::async_trait;
+use datafusion::arrow::array::{ArrayRef, Int64Array, RecordBatch, StringArray, StructArray, UInt32Array};
+use datafusion::arrow::compute::{take_record_batch, SortOptions};
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::common::not_impl_err;
+use datafusion::datasource::sink::{DataSink, DataSinkExec};
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::dml::InsertOp;
+use datafusion::parquet::file::properties::WriterProperties;
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::{LexOrdering, LexRequirement, PhysicalSortExpr};
+use datafusion::physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan};
+use futures::{Stream, TryStreamExt};
+use iceberg::arrow::{arrow_struct_to_literal, schema_to_arrow_schema, type_to_arrow_type};
+use iceberg::spec::{
+    DataContentType, DataFile, DataFileFormat, Literal, NestedField, NullOrder, Operation,
+    PartitionSpecRef, PrimitiveType, Schema, SortDirection, Struct, StructType,
+    Transform, Type,
+};
+use iceberg::table::Table;
+use iceberg::transform::{create_transform_function, BoxedTransformFunction};
+use iceberg::writer::base_writer::data_file_writer::{DataFileWriter, DataFileWriterBuilder};
+use iceberg::writer::file_writer::location_generator::{DefaultFileNameGenerator, LocationGenerator};
+use iceberg::writer::file_writer::{FileWriter, FileWriterBuilder, ParquetWriterBuilder};
+use iceberg::writer::{IcebergWriter, IcebergWriterBuilder};
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::pin::pin;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Table properties overriding where data files are written, `<location>/data` by default.
+const WRITE_DATA_PATH: &str = "write.data.path";
+const WRITE_FOLDER_STORAGE_PATH: &str = "write.folder-storage.path";
+
+type PartitionWriter = DataFileWriter<ParquetWriterBuilder<DataLocationGenerator, DefaultFileNameGenerator>>;
+
+/// Plans INSERT INTO (append) and INSERT OVERWRITE (replace all rows) of `table`. Rows are
+/// sorted by the identity fields of the table's sort order before they are written.
+pub fn plan_insert(
+    table: Table,
+    committer: Arc<dyn IcebergTableCommitter>,
+    input: Arc<dyn ExecutionPlan>,
+    insert_op: InsertOp,
+) -> DFResult<Arc<dyn ExecutionPlan>> {
+    let overwrite = match insert_op {
+        InsertOp::Append => false,
+        InsertOp::Overwrite => true,
+        InsertOp::Replace => return not_impl_err!("REPLACE INTO is not supported for iceberg tables"),
+    };
+    let sink = IcebergDataSink::try_new(table, committer, overwrite)?;
+    let sort_requirement = sink.sort_requirement()?;
+    Ok(Arc::new(DataSinkExec::new(input, Arc::new(sink), sort_requirement)))
+}
+
+/// Writes rows to Parquet data files of an Iceberg table, one open file per partition of
+/// the default partition spec, and commits the files as a new snapshot once all rows are
+/// written.
+pub struct IcebergDataSink {
+    table: Table,
+    committer: Arc<dyn IcebergTableCommitter>,
+    /// Arrow schema of the current table schema, with field ids
+    schema: SchemaRef,
+    partitioner: Partitioner,
+    overwrite: bool,
+}
+
+impl IcebergDataSink {
+    pub fn try_new(
+        table: Table,
+        committer: Arc<dyn IcebergTableCommitter>,
+        overwrite: bool,
+    ) -> DFResult<Self> {
+        let schema = Arc::new(
+            schema_to_arrow_schema(table.metadata().current_schema())
+                .map_err(|e| DataFusionError::External(Box::new(e)))?,
+        );
+        let partitioner = Partitioner::try_new(&table, &schema)?;
+        Ok(IcebergDataSink {
+            table,
+            committer,
+            schema,
+            partitioner,
+            overwrite,
+        })
+    }
+
+    /// The input ordering for the table's sort order, up to its first non-identity field
+    /// which DataFusion cannot sort by.
+    fn sort_requirement(&self) -> DFResult<Option<LexRequirement>> {
+        let metadata = self.table.metadata();
+        let iceberg_schema = metadata.current_schema();
+        let mut sort_exprs: Vec<PhysicalSortExpr> = Vec::new();
+        for field in &metadata.default_sort_order().fields {
+            if field.transform != Transform::Identity {
+                break;
+            }
+            let Some(name) = iceberg_schema.name_by_field_id(field.source_id) else {
+                break;
+            };
+            let Ok(idx) = self.schema.index_of(name) else {
+                break;
+            };
+            let options = SortOptions {
+                descending: field.direction == SortDirection::Descending,
+                nulls_first: field.null_order == NullOrder::First,
+            };
+            sort_exprs.push(PhysicalSortExpr::new(Arc::new(Column::new(name, idx)), options));
+        }
+        if sort_exprs.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(LexRequirement::from(LexOrdering::new(sort_exprs))))
+    }
+
+    async fn new_writer(
+        &self,
+        partition: &Struct,
+        file_name_generator: &DefaultFileNameGenerator,
+    ) -> DFResult<PartitionWriter> {
+        let metadata = self.table.metadata();
+        let parquet_writer_builder = ParquetWriterBuilder::new(
+            WriterProperties::builder().build(),
+            metadata.current_schema().clone(),
+            self.table.file_io().clone(),
+            DataLocationGenerator {
+                dir_path: self.data_dir_path(partition),
+            },
+            file_name_generator.clone(),
+        );
+        let partition_value = (!partition.fields().is_empty()).then(|| partition.clone());
+        DataFileWriterBuilder::new(
+            parquet_writer_builder,
+            partition_value,
+            metadata.default_partition_spec_id(),
+        )
+        .build()
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+}
+
+impl IcebergDataSink {
+    /// Writes the rows of `data` to data files without committing them, returning the
+    /// files and the number of rows written.
+    pub(crate) async fn write_data_files<S>(&self, data: S) -> DFResult<(Vec<DataFile>, u64)>
+    where
+        S: Stream<Item = DFResult<RecordBatch>>,
+    {
+        let mut data = pin!(data);
+        let file_name_generator =
+            DefaultFileNameGenerator::new(Uuid::new_v4().to_string(), None, DataFileFormat::Parquet);
+        let mut writers: HashMap<Struct, PartitionWriter> = HashMap::new();
+        let mut row_count: u64 = 0;
+        while let Some(batch) = data.try_next().await? {
+            // the Parquet writer takes the field ids from the batch schema
+            let batch = RecordBatch::try_new(self.schema.clone(), batch.columns().to_vec())?;
+            row_count += batch.num_rows() as u64;
+            for (partition, batch) in self.partitioner.split(&batch)? {
+                if !writers.contains_key(&partition) {
+                    let writer = self.new_writer(&partition, &file_name_generator).await?;
+                    writers.insert(partition.clone(), writer);
+                }
+                if let Some(writer) = writers.get_mut(&partition) {
+                    writer
+                        .write(batch)
+                        .await
+                        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                }
+            }
+        }
+
+        let mut data_files = Vec::new();
+        for (_, mut writer) in writers {
+            let files = writer
+                .close()
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            data_files.extend(files);
+        }
+        Ok((data_files, row_count))
+    }
+
+    /// Writes a position delete file of a partition of the default partition spec, deleting
+    /// the rows at `positions` of every data file path. Positions must be sorted.
+    pub(crate) async fn write_position_deletes(
+        &self,
+        partition: &Struct,
+        positions: &[(String, Vec<i64>)],
+    ) -> DFResult<DataFile> {
+        let metadata = self.table.metadata();
+        let schema = position_delete_schema()?;
+        let arrow_schema = Arc::new(
+            schema_to_arrow_schema(&schema).map_err(|e| DataFusionError::External(Box::new(e)))?,
+        );
+        let file_paths = StringArray::from(
+            positions
+                .iter()
+                .flat_map(|(path, positions)| std::iter::repeat_n(path.as_str(), positions.len()))
+                .collect::<Vec<&str>>(),
+        );
+        let positions = Int64Array::from_iter_values(
+            positions.iter().flat_map(|(_, positions)| positions.iter().copied()),
+        );
+        let batch = RecordBatch::try_new(arrow_schema, vec![Arc::new(file_paths), Arc::new(positions)])?;
+
+        let file_name_generator = DefaultFileNameGenerator::new(
+            format!("{}-deletes", Uuid::new_v4()),
+            None,
+            DataFileFormat::Parquet,
+        );
+        let mut writer = ParquetWriterBuilder::new(
+            WriterProperties::builder().build(),
+            Arc::new(schema),
+            self.table.file_io().clone(),
+            DataLocationGenerator {
+                dir_path: self.data_dir_path(partition),
+            },
+            file_name_generator,
+        )
+        .build()
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        writer
+            .write(&batch)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let mut files = writer
+            .close()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let Some(mut file) = files.pop() else {
+            return Err(DataFusionError::Internal("no position delete file written".to_string()));
+        };
+        file.content(DataContentType::PositionDeletes)
+            .partition(partition.clone())
+            .partition_spec_id(metadata.default_partition_spec_id())
+            .build()
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+
+    /// Directory of the data files of a partition of the default partition spec.
+    fn data_dir_path(&self, partition: &Struct) -> String {
+        let properties = self.table.metadata().properties();
+        let data_path = properties
+            .get(WRITE_DATA_PATH)
+            .or_else(|| properties.get(WRITE_FOLDER_STORAGE_PATH))
+            .cloned()
+            .unwrap_or_else(|| format!("{}/data", self.table.metadata().location()));
+        match self.partitioner.partition_path(partition) {
+            Some(partition_path) => format!("{data_path}/{partition_path}"),
+            None => data_path,
+        }
+    }
+}
+
+/// Schema of position delete files, with the reserved field ids of the spec.
+fn position_delete_schema() -> DFResult<Schema> {
+    Schema::builder()
+        .with_fields(vec![
+            NestedField::required(2147483546, "file_path", Type::Primitive(PrimitiveType::String)).into(),
+            NestedField::required(2147483545, "pos", Type::Primitive(PrimitiveType::Long)).into(),
+        ])
+        .build()
+        .map_err(|e| DataFusionError::External(Box::new(e)))
+}
+
+impl Debug for IcebergDataSink {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IcebergDataSink")
+            .field("table", self.table.identifier())
+            .field("overwrite", &self.overwrite)
+            .finish()
+    }
+}
+
+impl DisplayAs for IcebergDataSink {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        let operation = if self.overwrite { "overwrite" } else { "append" };
+        write!(f, "IcebergDataSink: table={}, {}", self.table.identifier().name(), operation)
+    }
+}
+
+#[async_trait]
+impl DataSink for IcebergDataSink {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    async fn write_all(
+        &self,
+        data: SendableRecordBatchStream,
+        _context: &Arc<TaskContext>,
+    ) -> DFResult<u64> {
+        let (data_files, row_count) = self.write_data_files(data).await?;
+        if data_files.is_empty() && !self.overwrite {
+            return Ok(0);
+        }
+        let metadata = self.table.metadata();
+        let update = SnapshotUpdate {
+            operation: if self.overwrite {
+                Operation::Overwrite
+            } else {
+                Operation::Append
+            },
+            added_files: data_files,
+            removed_files: if self.overwrite {
+                RemovedFiles::All
+            } else {
+                RemovedFiles::None
+            },
+            referenced_files: HashSet::new(),
+            base_snapshot_id: metadata.current_snapshot_id(),
+            schema_id: metadata.current_schema_id(),
+            partition_spec_id: metadata.default_partition_spec_id(),
+        };
+        commit_snapshot(self.committer.as_ref(), &update).await?;
+        Ok(row_count)
+    }
+}
+
+/// Writes data files to a directory, the data directory of the table or a partition in it.
+#[derive(Debug, Clone)]
+struct DataLocationGenerator {
+    dir_path: String,
+}
+
+impl LocationGenerator for DataLocationGenerator {
+    fn generate_location(&self, file_name: &str) -> String {
+        format!("{}/{}", self.dir_path, file_name)
+    }
+}
+
+/// Splits record batches by the partition values of the default partition spec.
+struct Partitioner {
+    partition_spec: PartitionSpecRef,
+    partition_type: StructType,
+    /// Arrow type of the partition struct
+    arrow_partition_type: DataType,
+    /// Batch column of the source field and the transform of every partition field
+    fields: Vec<(usize, BoxedTransformFunction)>,
+}
+
+impl Partitioner {
+    fn try_new(table: &Table, schema: &SchemaRef) -> DFResult<Self> {
+        let metadata = table.metadata();
+        let iceberg_schema = metadata.current_schema();
+        let partition_spec = metadata.default_partition_spec().clone();
+        let partition_type = partition_spec
+            .partition_type(iceberg_schema)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let arrow_partition_type = type_to_arrow_type(&Type::Struct(partition_type.clone()))
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let mut fields = Vec::with_capacity(partition_spec.fields().len());
+        for field in partition_spec.fields() {
+            // partitioning by nested fields would need the struct columns to be walked
+            let Some(idx) = iceberg_schema
+                .name_by_field_id(field.source_id)
+                .and_then(|name| schema.index_of(name).ok())
+            else {
+                return not_impl_err!(
+                    "writing tables partitioned by nested field {} is not supported",
+                    field.name
+                );
+            };
+            let transform = create_transform_function(&field.transform)
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            fields.push((idx, transform));
+        }
+        Ok(Partitioner {
+            partition_spec,
+            partition_type,
+            arrow_partition_type,
+            fields,
+        })
+    }
+
+    /// Splits `batch` into the rows of every partition, in order of first appearance.
+    fn split(&self, batch: &RecordBatch) -> DFResult<Vec<(Struct, RecordBatch)>> {
+        if self.fields.is_empty() {
+            return Ok(vec![(Struct::empty(), batch.clone())]);
+        }
+        let DataType::Struct(arrow_fields) = &self.arrow_partition_type else {
+            return Err(DataFusionError::Internal("partition type is not a struct".to_string()));
+        };
+        let columns = self
+            .fields
+            .iter()
+            .map(|(idx, transform)| transform.transform(batch.column(*idx).clone()))
+            .collect::<Result<Vec<ArrayRef>, _>>()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let partition_array: ArrayRef = Arc::new(StructArray::try_new(arrow_fields.clone(), columns, None)?);
+        let partition_values = arrow_struct_to_literal(&partition_array, &self.partition_type)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+        let mut partition_rows: Vec<(Struct, Vec<u32>)> = Vec::new();
+        let mut partition_index: HashMap<Struct, usize> = HashMap::new();
+        for (row, value) in partition_values.into_iter().enumerate() {
+            let Some(Literal::Struct(partition)) = value else {
+                return Err(DataFusionError::Internal(format!(
+                    "invalid partition value of row {row}"
+                )));
+            };
+            let idx = *partition_index.entry(partition.clone()).or_insert_with(|| {
+                partition_rows.push((partition, Vec::new()));
+                partition_rows.len() - 1
+            });
+            partition_rows[idx].1.push(row as u32);
+        }
+        if partition_rows.len() == 1 {
+            return Ok(partition_rows
+                .into_iter()
+                .map(|(partition, _)| (partition, batch.clone()))
+                .collect());
+        }
+        partition_rows
+            .into_iter()
+            .map(|(partition, rows)| {
+                let batch = take_record_batch(batch, &UInt32Array::from(rows))?;
+                Ok((partition, batch))
+            })
+            .collect()
+    }
+
+    /// The `<field>=<value>/...` directory of a partition, `None` if the table is not
+    /// partitioned.
+    fn partition_path(&self, partition: &Struct) -> Option<String> {
+        if self.fields.is_empty() {
+            return None;
+        }
+        let path = self
+            .partition_spec
+            .fields()
+            .iter()
+            .zip(self.partition_type.fields())
+            .zip(partition.iter())
+            .map(|((field, partition_field), value)| {
+                // the single-value JSON form of the spec, e.g. `2024-01-31` for dates
+                let value = match value.map(|value| value.clone().try_into_json(&partition_field.field_type)) {
+                    Some(Ok(serde_json::Value::String(value))) => value,
+                    Some(Ok(value)) => value.to_string(),
+                    _ => "null".to_string(),
+                };
+                let value: String = url::form_urlencoded::byte_serialize(value.as_bytes()).collect();
+                format!("{}={}", field.name, value)
+            })
+            .collect::<Vec<String>>()
+            .join("/");
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{Field, Schema};
+    use datafusion::physical_plan::memory::MemoryStream;
+    use crate::table_format::iceberg_commit::testing::{create_table, orders_schema, MemoryCommitter, TestDir};
+    use crate::table_format::iceberg_commit::to_datafusion_error;
+    use iceberg::spec::UnboundPartitionSpec;
+
+    async fn write(committer: &Arc<MemoryCommitter>, rows: Vec<(i64, &str)>, overwrite: bool) -> DFResult<Table> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("region", DataType::Utf8, true),
+        ]));
+        let (ids, regions): (Vec<i64>, Vec<&str>) = rows.into_iter().unzip();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(ids)), Arc::new(StringArray::from(regions))],
+        )?;
+        let sink = IcebergDataSink::try_new(committer.load_table().await?, committer.clone(), overwrite)?;
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+        sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+        committer.load_table().await
+    }
+
+    #[tokio::test]
+    async fn test_insert_and_overwrite() -> DFResult<()> {
+        let table_dir = TestDir::new("iceberg-sink");
+        let partition_spec = UnboundPartitionSpec::builder()
+            .add_partition_field(2, "region", Transform::Identity)
+            .map_err(to_datafusion_error)?
+            .build();
+        let (committer, _) = create_table(table_dir.path(), orders_schema(), partition_spec, HashMap::new()).await?;
+
+        let table = write(&committer, vec![(1, "eu"), (2, "us"), (3, "eu")], false).await?;
+        assert!(table.metadata_location().is_some_and(|location| location.contains("/00001-")));
+        let summary = table.metadata().current_snapshot().expect("snapshot").summary();
+        assert_eq!(summary.operation, Operation::Append);
+        assert_eq!(summary.additional_properties["total-records"], "3");
+        assert_eq!(summary.additional_properties["total-data-files"], "2");
+        assert!(table_dir.path().join("data").join("region=eu").is_dir());
+
+        let table = write(&committer, vec![(4, "us")], true).await?;
+        let summary = table.metadata().current_snapshot().expect("snapshot").summary();
+        assert_eq!(summary.operation, Operation::Overwrite);
+        assert_eq!(summary.additional_properties["total-records"], "1");
+        assert_eq!(summary.additional_properties["deleted-data-files"], "2");
+        assert_eq!(table.metadata().snapshots().len(), 2);
+        Ok(())
+    }
+}
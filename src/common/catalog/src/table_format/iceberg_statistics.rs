@@ -0,0 +1,422 @@
+use crate::table_format::iceberg_commit::to_datafusion_error;
+use crate::table_format::iceberg_deletes::MANIFEST_CONCURRENCY;
+use datafusion::arrow::datatypes::{DataType, Schema, TimeUnit};
+use datafusion::common::stats::Precision;
+use datafusion::common::{ColumnStatistics, ScalarValue, Statistics};
+use datafusion::error::Result as DFResult;
+use futures::{StreamExt, TryStreamExt};
+use iceberg::scan::FileScanTask;
+use iceberg::spec::{
+    DataContentType, DataFile, Datum, ManifestContentType, PrimitiveLiteral, SnapshotRef, TableMetadata,
+};
+use iceberg::table::Table;
+use std::collections::{HashMap, HashSet};
+
+/// Puffin blob type of Apache DataSketches Theta sketches, as written by Spark and Trino.
+pub const THETA_SKETCH_BLOB_TYPE: &str = "apache-datasketches-theta-v1";
+/// Blob metadata property with the number of distinct values a sketch estimates.
+pub const NDV_PROPERTY: &str = "ndv";
+
+/// Statistics of a snapshot from its summary and Puffin statistics, without reading its
+/// manifests. Row counts are exact unless the snapshot has delete files.
+pub fn snapshot_statistics(
+    metadata: &TableMetadata,
+    snapshot_id: Option<i64>,
+    schema: &Schema,
+) -> Statistics {
+    let Some(snapshot) = snapshot(metadata, snapshot_id) else {
+        return Statistics::new_unknown(schema).with_num_rows(Precision::Exact(0));
+    };
+    let exact = total(snapshot, "total-delete-files") == Some(0);
+    let num_rows = match total(snapshot, "total-records") {
+        Some(records) if exact => Precision::Exact(records as usize),
+        Some(records) => Precision::Inexact(records as usize),
+        None => Precision::Absent,
+    };
+    let total_byte_size = match total(snapshot, "total-files-size") {
+        Some(size) => Precision::Inexact(size as usize),
+        None => Precision::Absent,
+    };
+    let mut statistics = Statistics::new_unknown(schema)
+        .with_num_rows(num_rows)
+        .with_total_byte_size(total_byte_size);
+    set_distinct_counts(&mut statistics, &distinct_counts(metadata, snapshot, schema));
+    statistics
+}
+
+/// Statistics of the data files a scan reads, from the metrics of their manifest entries,
+/// with the numbers of distinct values of the Puffin statistics of the snapshot.
+#[derive(Debug, Default)]
+pub(crate) struct ScanStatistics {
+    /// Statistics of the scanned columns by data file path
+    files: HashMap<String, Statistics>,
+    distinct_counts: Vec<Option<usize>>,
+}
+
+impl ScanStatistics {
+    /// Reads the metrics of the data files of `tasks` in `snapshot_id` for the columns of
+    /// `schema`. Row counts are exact if no rows are deleted or `filtered` out of the files.
+    pub(crate) async fn try_new<'a>(
+        table: &Table,
+        snapshot_id: Option<i64>,
+        schema: &Schema,
+        tasks: impl IntoIterator<Item = &'a FileScanTask>,
+        filtered: bool,
+    ) -> DFResult<Self> {
+        let metadata = table.metadata();
+        let Some(snapshot) = snapshot(metadata, snapshot_id) else {
+            return Ok(ScanStatistics::default());
+        };
+        let exact = !filtered && total(snapshot, "total-delete-files") == Some(0);
+        let iceberg_schema = snapshot
+            .schema_id()
+            .and_then(|schema_id| metadata.schema_by_id(schema_id))
+            .unwrap_or_else(|| metadata.current_schema());
+        // field ids of the columns, None for columns not in the table such as `_pos`
+        let field_ids = schema
+            .fields()
+            .iter()
+            .map(|field| iceberg_schema.field_by_name(field.name()).map(|f| f.id))
+            .collect::<Vec<Option<i32>>>();
+
+        let paths = tasks
+            .into_iter()
+            .map(|task| task.data_file_path.as_str())
+            .collect::<HashSet<&str>>();
+        let mut files = HashMap::new();
+        let manifest_list = snapshot
+            .load_manifest_list(table.file_io(), metadata)
+            .await
+            .map_err(to_datafusion_error)?;
+        // only data manifests with live files can hold planned files, they are read at once
+        // until all planned files are found
+        let loads = manifest_list
+            .entries()
+            .iter()
+            .filter(|manifest_file| {
+                manifest_file.content == ManifestContentType::Data
+                    && (manifest_file.has_added_files() || manifest_file.has_existing_files())
+            })
+            .map(|manifest_file| manifest_file.load_manifest(table.file_io()))
+            .collect::<Vec<_>>();
+        let mut manifests = futures::stream::iter(loads).buffer_unordered(MANIFEST_CONCURRENCY);
+        while files.len() < paths.len() {
+            let Some(manifest) = manifests.try_next().await.map_err(to_datafusion_error)? else {
+                break;
+            };
+            for entry in manifest.entries() {
+                if entry.is_alive()
+                    && entry.content_type() == DataContentType::Data
+                    && paths.contains(entry.file_path())
+                {
+                    let statistics = file_statistics(entry.data_file(), schema, &field_ids, exact);
+                    files.insert(entry.file_path().to_string(), statistics);
+                }
+            }
+        }
+
+        let distinct_counts = distinct_counts(metadata, snapshot, schema);
+        Ok(ScanStatistics {
+            files,
+            distinct_counts,
+        })
+    }
+
+    /// Statistics of the rows read from `tasks`, with unknown statistics for the columns of
+    /// `schema` past the scanned columns.
+    pub(crate) fn statistics<'a>(
+        &self,
+        tasks: impl IntoIterator<Item = &'a FileScanTask>,
+        schema: &Schema,
+    ) -> DFResult<Statistics> {
+        let mut paths = HashSet::new();
+        let mut file_statistics = Vec::new();
+        for task in tasks {
+            if !paths.insert(task.data_file_path.as_str()) {
+                continue;
+            }
+            match self.files.get(&task.data_file_path) {
+                Some(statistics) => file_statistics.push(statistics),
+                None => return Ok(Statistics::new_unknown(schema)),
+            }
+        }
+        let mut statistics = if file_statistics.is_empty() {
+            Statistics::new_unknown(schema)
+                .with_num_rows(Precision::Exact(0))
+                .with_total_byte_size(Precision::Exact(0))
+        } else {
+            Statistics::try_merge_iter(file_statistics, schema)?
+        };
+        statistics
+            .column_statistics
+            .resize(schema.fields().len(), ColumnStatistics::new_unknown());
+        set_distinct_counts(&mut statistics, &self.distinct_counts);
+        Ok(statistics)
+    }
+}
+
+/// Statistics of the columns of `schema` in a data file. Bounds may be truncated, so
+/// minimums and maximums are never exact.
+fn file_statistics(
+    data_file: &DataFile,
+    schema: &Schema,
+    field_ids: &[Option<i32>],
+    exact: bool,
+) -> Statistics {
+    let precision = |value: usize| {
+        if exact {
+            Precision::Exact(value)
+        } else {
+            Precision::Inexact(value)
+        }
+    };
+    // the bytes of the scanned columns, or of the whole file if the writer didn't
+    // record column sizes
+    let column_sizes = field_ids
+        .iter()
+        .flatten()
+        .map(|field_id| data_file.column_sizes().get(field_id))
+        .collect::<Option<Vec<&u64>>>();
+    let byte_size = match column_sizes {
+        Some(sizes) if !sizes.is_empty() => sizes.into_iter().sum(),
+        _ => data_file.file_size_in_bytes(),
+    };
+
+    let mut statistics = Statistics::default()
+        .with_num_rows(precision(data_file.record_count() as usize))
+        .with_total_byte_size(Precision::Inexact(byte_size as usize));
+    for (field, field_id) in schema.fields().iter().zip(field_ids) {
+        let mut column = ColumnStatistics::new_unknown();
+        if let Some(field_id) = field_id {
+            if let Some(null_count) = data_file.null_value_counts().get(field_id) {
+                column = column.with_null_count(precision(*null_count as usize));
+            }
+            let bound = |bounds: &HashMap<i32, Datum>| {
+                bounds
+                    .get(field_id)
+                    .and_then(|datum| literal_to_scalar(datum.literal(), field.data_type()))
+                    .map(Precision::Inexact)
+                    .unwrap_or(Precision::Absent)
+            };
+            column = column
+                .with_min_value(bound(data_file.lower_bounds()))
+                .with_max_value(bound(data_file.upper_bounds()));
+        }
+        statistics = statistics.add_column_statistics(column);
+    }
+    statistics
+}
+
+/// Converts an Iceberg value, e.g. a bound or a partition value, into a value of `data_type`.
+pub(crate) fn literal_to_scalar(literal: &PrimitiveLiteral, data_type: &DataType) -> Option<ScalarValue> {
+    let value = match (literal, data_type) {
+        (PrimitiveLiteral::Boolean(v), _) => ScalarValue::Boolean(Some(*v)),
+        (PrimitiveLiteral::Int(v), DataType::Date32) => ScalarValue::Date32(Some(*v)),
+        (PrimitiveLiteral::Int(v), _) => ScalarValue::Int32(Some(*v)),
+        (PrimitiveLiteral::Long(v), DataType::Timestamp(TimeUnit::Microsecond, tz)) => {
+            ScalarValue::TimestampMicrosecond(Some(*v), tz.clone())
+        }
+        (PrimitiveLiteral::Long(v), DataType::Timestamp(TimeUnit::Nanosecond, tz)) => {
+            ScalarValue::TimestampNanosecond(Some(*v), tz.clone())
+        }
+        (PrimitiveLiteral::Long(v), DataType::Time64(TimeUnit::Microsecond)) => {
+            ScalarValue::Time64Microsecond(Some(*v))
+        }
+        (PrimitiveLiteral::Long(v), _) => ScalarValue::Int64(Some(*v)),
+        (PrimitiveLiteral::Float(v), _) => ScalarValue::Float32(Some(v.0)),
+        (PrimitiveLiteral::Double(v), _) => ScalarValue::Float64(Some(v.0)),
+        (PrimitiveLiteral::String(v), _) => ScalarValue::Utf8(Some(v.clone())),
+        (PrimitiveLiteral::Binary(v), _) => ScalarValue::Binary(Some(v.clone())),
+        (PrimitiveLiteral::Int128(v), DataType::Decimal128(precision, scale)) => {
+            ScalarValue::Decimal128(Some(*v), *precision, *scale)
+        }
+        _ => return None,
+    };
+    if value.data_type() == *data_type {
+        Some(value)
+    } else {
+        value.cast_to(data_type).ok()
+    }
+}
+
+/// Numbers of distinct values of the columns of `schema` from the Theta sketches in the
+/// Puffin statistics of `snapshot`.
+fn distinct_counts(metadata: &TableMetadata, snapshot: &SnapshotRef, schema: &Schema) -> Vec<Option<usize>> {
+    let mut by_field_id: HashMap<i32, usize> = HashMap::new();
+    if let Some(statistics_file) = metadata.statistics_for_snapshot(snapshot.snapshot_id()) {
+        for blob in &statistics_file.blob_metadata {
+            if let (THETA_SKETCH_BLOB_TYPE, [field_id], Some(ndv)) = (
+                blob.r#type.as_str(),
+                blob.fields.as_slice(),
+                blob.properties.get(NDV_PROPERTY).and_then(|ndv| ndv.parse().ok()),
+            ) {
+                by_field_id.insert(*field_id, ndv);
+            }
+        }
+    }
+    let iceberg_schema = metadata.current_schema();
+    schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let field_id = iceberg_schema.field_by_name(field.name())?.id;
+            by_field_id.get(&field_id).copied()
+        })
+        .collect()
+}
+
+/// Sets the estimated distinct counts of the columns, at most the number of rows.
+fn set_distinct_counts(statistics: &mut Statistics, distinct_counts: &[Option<usize>]) {
+    let num_rows = statistics.num_rows.get_value().copied();
+    for (column, distinct_count) in statistics.column_statistics.iter_mut().zip(distinct_counts) {
+        if let Some(distinct_count) = distinct_count {
+            let distinct_count = num_rows.map_or(*distinct_count, |rows| rows.min(*distinct_count));
+            column.distinct_count = Precision::Inexact(distinct_count);
+        }
+    }
+}
+
+fn snapshot(metadata: &TableMetadata, snapshot_id: Option<i64>) -> Option<&SnapshotRef> {
+    match snapshot_id {
+        Some(snapshot_id) => metadata.snapshot_by_id(snapshot_id),
+        None => metadata.current_snapshot(),
+    }
+}
+
+/// A count of the summary of `snapshot`, such as `total-records`.
+fn total(snapshot: &SnapshotRef, key: &str) -> Option<u64> {
+    snapshot
+        .summary()
+        .additional_properties
+        .get(key)
+        .and_then(|value| value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_format::external_table::IcebergTableScan;
+    use crate::table_format::iceberg_analyze::analyze_table;
+    use crate::table_format::iceberg_commit::testing::{empty_table, TestDir};
+    use crate::table_format::iceberg_commit::IcebergTableCommitter;
+    use crate::table_format::iceberg_dml::{IcebergRowLevelWriter, IcebergRowPositionTable, RowLevelMode};
+    use crate::table_format::iceberg_sink::IcebergDataSink;
+    use datafusion::arrow::array::{Int64Array, RecordBatch, StringArray};
+    use datafusion::datasource::sink::DataSink;
+    use datafusion::execution::TaskContext;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion::prelude::SessionContext;
+    use iceberg::arrow::schema_to_arrow_schema;
+    use iceberg::spec::PrimitiveType;
+    use std::sync::Arc;
+
+    /// Statistics of a scan of the whole current snapshot, as reported to the optimizer.
+    async fn scan_statistics(table: &Table) -> DFResult<Statistics> {
+        let schema = Arc::new(schema_to_arrow_schema(table.metadata().current_schema()).map_err(to_datafusion_error)?);
+        let scan = IcebergTableScan::try_new(table.clone(), None, schema, None, &[], None, 2, 8192)
+            .await?
+            .with_statistics()
+            .await?;
+        scan.partition_statistics(None)
+    }
+
+    #[tokio::test]
+    async fn test_table_statistics() -> DFResult<()> {
+        let table_dir = TestDir::new("iceberg-statistics");
+        let (committer, table) = empty_table(&table_dir, HashMap::new()).await?;
+        let arrow_schema = schema_to_arrow_schema(table.metadata().current_schema()).map_err(to_datafusion_error)?;
+        let statistics = snapshot_statistics(table.metadata(), None, &arrow_schema);
+        assert_eq!(statistics.num_rows, Precision::Exact(0));
+
+        let sink = IcebergDataSink::try_new(table, committer.clone(), false)?;
+        let schema = sink.schema().clone();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![3, 1, 2, 1])),
+                Arc::new(StringArray::from(vec![Some("eu"), None, Some("us"), Some("eu")])),
+            ],
+        )?;
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+        sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+
+        // without manifests, from the snapshot summary
+        let table = committer.load_table().await?;
+        let statistics = snapshot_statistics(table.metadata(), None, &arrow_schema);
+        assert_eq!(statistics.num_rows, Precision::Exact(4));
+        assert!(matches!(statistics.total_byte_size, Precision::Inexact(size) if size > 0));
+        assert_eq!(statistics.column_statistics[0].distinct_count, Precision::Absent);
+
+        // from the metrics of the data files
+        let statistics = scan_statistics(&table).await?;
+        assert_eq!(statistics.num_rows, Precision::Exact(4));
+        assert!(matches!(statistics.total_byte_size, Precision::Inexact(size) if size > 0));
+        let [id, region] = statistics.column_statistics.as_slice() else {
+            panic!("expected 2 columns, got {:?}", statistics.column_statistics);
+        };
+        assert_eq!(id.min_value, Precision::Inexact(ScalarValue::Int64(Some(1))));
+        assert_eq!(id.max_value, Precision::Inexact(ScalarValue::Int64(Some(3))));
+        assert_eq!(id.null_count, Precision::Exact(0));
+        assert_eq!(region.min_value, Precision::Inexact(ScalarValue::Utf8(Some("eu".to_string()))));
+        assert_eq!(region.max_value, Precision::Inexact(ScalarValue::Utf8(Some("us".to_string()))));
+        assert_eq!(region.null_count, Precision::Exact(1));
+        assert_eq!(id.distinct_count, Precision::Absent);
+
+        // with the numbers of distinct values of the Puffin statistics of the snapshot
+        let ctx = SessionContext::new();
+        analyze_table(&ctx.state(), committer.clone(), &[]).await?;
+        let table = committer.load_table().await?;
+        let statistics = snapshot_statistics(table.metadata(), None, &arrow_schema);
+        assert_eq!(statistics.column_statistics[0].distinct_count, Precision::Inexact(3));
+        assert_eq!(statistics.column_statistics[1].distinct_count, Precision::Inexact(2));
+        let statistics = scan_statistics(&table).await?;
+        assert_eq!(statistics.column_statistics[0].distinct_count, Precision::Inexact(3));
+        assert_eq!(statistics.column_statistics[1].distinct_count, Precision::Inexact(2));
+
+        // deleted rows are still counted by the data files, so counts are estimates
+        ctx.register_table("t", Arc::new(IcebergRowPositionTable::try_new(table.clone(), None)?))?;
+        let deleted = ctx
+            .sql("SELECT 'delete' AS _operation, id, region, _file, _pos FROM t WHERE id = 2")
+            .await?
+            .execute_stream()
+            .await?;
+        let writer = IcebergRowLevelWriter::try_new(table, committer.clone(), RowLevelMode::MergeOnRead)?;
+        assert_eq!(writer.write(deleted, &HashSet::new()).await?, 1);
+        let table = committer.load_table().await?;
+        let statistics = snapshot_statistics(table.metadata(), None, &arrow_schema);
+        assert_eq!(statistics.num_rows, Precision::Inexact(4));
+        let statistics = scan_statistics(&table).await?;
+        assert_eq!(statistics.num_rows, Precision::Inexact(4));
+        assert_eq!(statistics.column_statistics[1].null_count, Precision::Inexact(1));
+        // the Puffin statistics are of the previous snapshot
+        assert_eq!(statistics.column_statistics[0].distinct_count, Precision::Absent);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_literal_to_scalar() {
+        let utc = Some(Arc::from("+00:00"));
+        assert_eq!(
+            literal_to_scalar(Datum::timestamptz_micros(1_000_000).literal(), &DataType::Timestamp(TimeUnit::Microsecond, utc.clone())),
+            Some(ScalarValue::TimestampMicrosecond(Some(1_000_000), utc))
+        );
+        assert_eq!(
+            literal_to_scalar(Datum::date(19_000).literal(), &DataType::Date32),
+            Some(ScalarValue::Date32(Some(19_000)))
+        );
+        assert_eq!(
+            literal_to_scalar(Datum::int(7).literal(), &DataType::Int64),
+            Some(ScalarValue::Int64(Some(7)))
+        );
+        // 123.45 as big-endian unscaled bytes
+        let decimal = Datum::try_from_bytes(&[0x30, 0x39], PrimitiveType::Decimal { precision: 10, scale: 2 })
+            .unwrap();
+        assert_eq!(
+            literal_to_scalar(decimal.literal(), &DataType::Decimal128(10, 2)),
+            Some(ScalarValue::Decimal128(Some(12345), 10, 2))
+        );
+        assert_eq!(literal_to_scalar(Datum::string("a").literal(), &DataType::Boolean), None);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::table_format::expr_to_predicate::convert_filter_to_predicate;
+use crate::table_format::external_table::{ExternalIcebergTable, IcebergTableScan};
+use crate::table_format::iceberg_metadata_table::{IcebergMetadataTable, IcebergMetadataTableType};
+use crate::table_format::iceberg_statistics::snapshot_statistics;
+use crate::table_format::table::TableVersion;
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::common::Statistics;
+use datafusion::datasource::TableType;
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::ExecutionPlan;
+use iceberg::arrow::schema_to_arrow_schema;
+use iceberg::table::Table;
+use std::any::Any;
+use std::sync::Arc;
+
+/// An Iceberg table loaded through an Iceberg catalog (REST, HMS, ...), and the reads of
+/// Iceberg tables in Glue, which [`GlueTable`] delegates to.
+///
+/// [`GlueTable`]: crate::glue_table::GlueTable
+#[derive(Debug, Clone)]
+pub struct IcebergTable {
+    schema: SchemaRef,
+    /// Snapshot to read, the current one unless the table is read as of a [`TableVersion`]
+    snapshot_id: Option<i64>,
+    table: ExternalIcebergTable,
+}
+
+impl IcebergTable {
+    pub async fn try_new(table: Table) -> Result<Self, DataFusionError> {
+        Self::try_from_external(ExternalIcebergTable::from_table(table).await?)
+    }
+
+    /// Wraps a table whose metadata file was located through a metastore, e.g. HMS.
+    pub fn try_from_external(table: ExternalIcebergTable) -> Result<Self, DataFusionError> {
+        let metadata = table.static_table.metadata();
+        let schema = schema_to_arrow_schema(metadata.current_schema())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(IcebergTable {
+            schema: Arc::new(schema),
+            snapshot_id: metadata.current_snapshot_id(),
+            table,
+        })
+    }
+
+    /// Returns this table as of a historical `version`, with the schema of that version.
+    pub fn with_version(&self, version: &TableVersion) -> Result<IcebergTable, DataFusionError> {
+        let (snapshot_id, schema) = self.table.resolve_version(version)?;
+        Ok(IcebergTable {
+            schema,
+            snapshot_id: Some(snapshot_id),
+            table: self.table.clone(),
+        })
+    }
+
+    /// Whether this is the current version of the table, older versions can't be written to.
+    pub fn is_current_version(&self) -> bool {
+        self.snapshot_id == self.table.static_table.metadata().current_snapshot_id()
+    }
+
+    /// The current Iceberg table, e.g. to commit to it.
+    pub fn table(&self) -> Table {
+        self.table.table()
+    }
+
+    /// Returns the metadata table of this table, e.g. `orders$snapshots`.
+    pub fn metadata_table(
+        &self,
+        metadata_table_type: IcebergMetadataTableType,
+    ) -> Result<IcebergMetadataTable, DataFusionError> {
+        IcebergMetadataTable::try_new(self.table.clone(), metadata_table_type)
+    }
+}
+
+#[async_trait]
+impl TableProvider for IcebergTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        Some(snapshot_statistics(
+            &self.table.static_table.metadata(),
+            self.snapshot_id,
+            &self.schema,
+        ))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> datafusion::common::Result<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|filter| {
+                if convert_filter_to_predicate(filter, &self.schema).is_some() {
+                    TableProviderFilterPushDown::Inexact
+                } else {
+                    TableProviderFilterPushDown::Unsupported
+                }
+            })
+            .collect())
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> datafusion::common::Result<Arc<dyn ExecutionPlan>> {
+        let config = state.config();
+        let mut scan = IcebergTableScan::try_new(
+            self.table.table(),
+            self.snapshot_id,
+            self.schema.clone(),
+            projection,
+            filters,
+            limit,
+            config.target_partitions(),
+            config.batch_size(),
+        )
+        .await?;
+        if config.collect_statistics() {
+            scan = scan.with_statistics().await?;
+        }
+        Ok(Arc::new(scan))
+    }
+}
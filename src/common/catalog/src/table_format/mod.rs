@@ -1,2 +1,18 @@
 pub mod table;
-pub mod external_table;
\ No newline at end of file
+pub mod external_table;
+pub mod expr_to_predicate;
+pub mod iceberg_metadata_table;
+pub mod iceberg_table;
+pub mod iceberg_commit;
+pub mod iceberg_ddl;
+pub mod iceberg_sink;
+pub mod iceberg_deletes;
+pub mod iceberg_dml;
+pub mod iceberg_procedures;
+pub mod iceberg_statistics;
+pub mod iceberg_analyze;
+pub mod hive_type;
+pub mod partition_pruning;
+pub mod delta_table;
+pub mod hudi_table;
+pub mod orc_format;
@@ -0,0 +1,257 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::arrow::error::ArrowError;
+use datafusion::catalog::Session;
+use datafusion::common::Statistics;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::datasource::physical_plan::{
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileScanConfigBuilder, FileSource,
+};
+use datafusion::datasource::schema_adapter::{DefaultSchemaAdapterFactory, SchemaAdapterFactory};
+use datafusion::datasource::source::DataSourceExec;
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use orc_rust::ArrowReaderBuilder;
+use orc_rust::projection::ProjectionMask;
+use orc_rust::reader::AsyncChunkReader;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Reads ORC files, e.g. the data files of Hive tables stored as ORC, through orc-rust.
+/// Columns are matched by name, columns missing from a file are read as nulls.
+#[derive(Debug, Default)]
+pub struct OrcFormat;
+
+#[async_trait]
+impl FileFormat for OrcFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_ext(&self) -> String {
+        "orc".to_string()
+    }
+
+    fn get_ext_with_compression(&self, _compression: &FileCompressionType) -> DFResult<String> {
+        // ORC compresses streams within the file, never the whole file
+        Ok(self.get_ext())
+    }
+
+    async fn infer_schema(
+        &self,
+        _state: &dyn Session,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> DFResult<SchemaRef> {
+        let mut schemas = Vec::with_capacity(objects.len());
+        for object in objects {
+            let reader = ObjectStoreReader::new(store.clone(), object);
+            let builder = ArrowReaderBuilder::try_new_async(reader)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            schemas.push(builder.schema().as_ref().clone());
+        }
+        Ok(Arc::new(Schema::try_merge(schemas)?))
+    }
+
+    async fn infer_stats(
+        &self,
+        _state: &dyn Session,
+        _store: &Arc<dyn ObjectStore>,
+        table_schema: SchemaRef,
+        _object: &ObjectMeta,
+    ) -> DFResult<Statistics> {
+        Ok(Statistics::new_unknown(&table_schema))
+    }
+
+    async fn create_physical_plan(
+        &self,
+        _state: &dyn Session,
+        conf: FileScanConfig,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let conf = FileScanConfigBuilder::from(conf)
+            .with_source(self.file_source())
+            .build();
+        Ok(DataSourceExec::from_data_source(conf))
+    }
+
+    fn file_source(&self) -> Arc<dyn FileSource> {
+        Arc::new(OrcSource::default())
+    }
+}
+
+/// [`FileSource`] of [`OrcFormat`].
+#[derive(Debug, Clone, Default)]
+pub struct OrcSource {
+    batch_size: Option<usize>,
+    metrics: ExecutionPlanMetricsSet,
+    projected_statistics: Option<Statistics>,
+    schema_adapter_factory: Option<Arc<dyn SchemaAdapterFactory>>,
+}
+
+impl FileSource for OrcSource {
+    fn create_file_opener(
+        &self,
+        object_store: Arc<dyn ObjectStore>,
+        base_config: &FileScanConfig,
+        _partition: usize,
+    ) -> Arc<dyn FileOpener> {
+        let projected_schema = base_config.projected_file_schema();
+        let schema_adapter_factory = self
+            .schema_adapter_factory
+            .clone()
+            .unwrap_or_else(|| Arc::new(DefaultSchemaAdapterFactory));
+        Arc::new(OrcOpener {
+            batch_size: self.batch_size.unwrap_or(8192),
+            projected_schema: projected_schema.clone(),
+            table_schema: base_config.file_schema.clone(),
+            schema_adapter_factory,
+            object_store,
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn with_batch_size(&self, batch_size: usize) -> Arc<dyn FileSource> {
+        Arc::new(OrcSource {
+            batch_size: Some(batch_size),
+            ..self.clone()
+        })
+    }
+
+    fn with_schema(&self, _schema: SchemaRef) -> Arc<dyn FileSource> {
+        Arc::new(self.clone())
+    }
+
+    fn with_projection(&self, _config: &FileScanConfig) -> Arc<dyn FileSource> {
+        Arc::new(self.clone())
+    }
+
+    fn with_statistics(&self, statistics: Statistics) -> Arc<dyn FileSource> {
+        Arc::new(OrcSource {
+            projected_statistics: Some(statistics),
+            ..self.clone()
+        })
+    }
+
+    fn metrics(&self) -> &ExecutionPlanMetricsSet {
+        &self.metrics
+    }
+
+    fn statistics(&self) -> DFResult<Statistics> {
+        self.projected_statistics
+            .clone()
+            .ok_or_else(|| DataFusionError::Internal("orc source statistics are not set".to_string()))
+    }
+
+    fn file_type(&self) -> &str {
+        "orc"
+    }
+
+    fn with_schema_adapter_factory(
+        &self,
+        schema_adapter_factory: Arc<dyn SchemaAdapterFactory>,
+    ) -> DFResult<Arc<dyn FileSource>> {
+        Ok(Arc::new(OrcSource {
+            schema_adapter_factory: Some(schema_adapter_factory),
+            ..self.clone()
+        }))
+    }
+
+    fn schema_adapter_factory(&self) -> Option<Arc<dyn SchemaAdapterFactory>> {
+        self.schema_adapter_factory.clone()
+    }
+}
+
+struct OrcOpener {
+    batch_size: usize,
+    /// Projected columns of the table, the batches read are mapped to this schema
+    projected_schema: SchemaRef,
+    table_schema: SchemaRef,
+    schema_adapter_factory: Arc<dyn SchemaAdapterFactory>,
+    object_store: Arc<dyn ObjectStore>,
+}
+
+impl FileOpener for OrcOpener {
+    /// Reads the stripes of the file, only those starting in `file_meta.range` if set.
+    fn open(&self, file_meta: FileMeta) -> DFResult<FileOpenFuture> {
+        let reader = ObjectStoreReader::new(self.object_store.clone(), &file_meta.object_meta);
+        let batch_size = self.batch_size;
+        let schema_adapter = self
+            .schema_adapter_factory
+            .create(self.projected_schema.clone(), self.table_schema.clone());
+
+        Ok(Box::pin(async move {
+            let builder = ArrowReaderBuilder::try_new_async(reader)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            let file_schema = builder.schema();
+            let (schema_mapper, projection) = schema_adapter.map_schema(&file_schema)?;
+            let projected_names = projection
+                .iter()
+                .map(|idx| file_schema.field(*idx).name().as_str())
+                .collect::<Vec<&str>>();
+            let projection_mask =
+                ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &projected_names);
+            let mut builder = builder
+                .with_batch_size(batch_size)
+                .with_projection(projection_mask);
+            if let Some(range) = &file_meta.range {
+                builder = builder.with_file_byte_range(range.start as usize..range.end as usize);
+            }
+            let stream = builder
+                .build_async()
+                .and_then(move |batch| {
+                    futures::future::ready(
+                        schema_mapper
+                            .map_batch(batch)
+                            .map_err(|e| ArrowError::ExternalError(Box::new(e))),
+                    )
+                });
+            Ok(stream.boxed())
+        }))
+    }
+}
+
+/// Reads byte ranges of an ORC file from an object store.
+struct ObjectStoreReader {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    size: u64,
+}
+
+impl ObjectStoreReader {
+    fn new(store: Arc<dyn ObjectStore>, object: &ObjectMeta) -> Self {
+        ObjectStoreReader {
+            store,
+            location: object.location.clone(),
+            size: object.size,
+        }
+    }
+}
+
+impl AsyncChunkReader for ObjectStoreReader {
+    fn len(&mut self) -> BoxFuture<'_, std::io::Result<u64>> {
+        futures::future::ready(Ok(self.size)).boxed()
+    }
+
+    fn get_bytes(&mut self, offset_from_start: u64, length: u64) -> BoxFuture<'_, std::io::Result<Bytes>> {
+        let range = offset_from_start..offset_from_start + length;
+        async move {
+            self.store
+                .get_range(&self.location, range)
+                .await
+                .map_err(std::io::Error::other)
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,255 @@
+use datafusion::arrow::array::{Array, ArrayRef, AsArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::{DFSchema, ScalarValue};
+use datafusion::error::DataFusionError;
+use datafusion::execution::context::ExecutionProps;
+use datafusion::logical_expr::expr::InList;
+use datafusion::logical_expr::{Between, BinaryExpr, Expr, Like, Operator};
+use datafusion::physical_expr::create_physical_expr;
+use std::sync::Arc;
+
+/// Returns whether `filter` only references partition columns, so it can be used to
+/// prune partitions.
+pub fn is_partition_filter(filter: &Expr, partition_columns: &[Field]) -> bool {
+    let columns = filter.column_refs();
+    !columns.is_empty()
+        && columns
+            .iter()
+            .all(|column| partition_columns.iter().any(|f| f.name() == &column.name))
+}
+
+/// Converts partition filters into a Glue `get_partitions` expression by `AND`ing
+/// every filter that can be (at least partially) translated, e.g.
+/// `(dt >= '2026-10-01') AND (region IN ('eu', 'us'))`.
+///
+/// Like [`convert_filters_to_predicate`](super::expr_to_predicate::convert_filters_to_predicate),
+/// the expression may match more partitions than the filters do, so the listed
+/// partitions still need to be pruned with [`prune_partitions`].
+pub fn convert_filters_to_glue_expression(
+    filters: &[Expr],
+    partition_columns: &[Field],
+) -> Option<String> {
+    filters
+        .iter()
+        .filter_map(|filter| to_glue_expression(filter, partition_columns))
+        .reduce(|left, right| format!("({left}) AND ({right})"))
+}
+
+/// Untranslatable conjuncts of an `AND` are dropped, which is always safe as `NOT` is
+/// never translated.
+fn to_glue_expression(expr: &Expr, partition_columns: &[Field]) -> Option<String> {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
+            Operator::And => {
+                let left = to_glue_expression(left, partition_columns);
+                let right = to_glue_expression(right, partition_columns);
+                match (left, right) {
+                    (Some(left), Some(right)) => Some(format!("({left}) AND ({right})")),
+                    (Some(left), None) => Some(left),
+                    (None, Some(right)) => Some(right),
+                    (None, None) => None,
+                }
+            }
+            Operator::Or => {
+                let left = to_glue_expression(left, partition_columns)?;
+                let right = to_glue_expression(right, partition_columns)?;
+                Some(format!("({left}) OR ({right})"))
+            }
+            _ => comparison_to_glue_expression(left, *op, right, partition_columns),
+        },
+        Expr::InList(InList {
+            expr,
+            list,
+            negated: false,
+        }) => {
+            let (name, data_type) = to_partition_column(expr, partition_columns)?;
+            let values = list
+                .iter()
+                .map(|e| to_glue_literal(e, data_type))
+                .collect::<Option<Vec<String>>>()?;
+            Some(format!("{name} IN ({})", values.join(", ")))
+        }
+        Expr::Between(Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        }) => {
+            let (name, data_type) = to_partition_column(expr, partition_columns)?;
+            let low = to_glue_literal(low, data_type)?;
+            let high = to_glue_literal(high, data_type)?;
+            Some(format!("{name} BETWEEN {low} AND {high}"))
+        }
+        Expr::Like(Like {
+            negated: false,
+            expr,
+            pattern,
+            escape_char: None,
+            case_insensitive: false,
+        }) => {
+            let (name, data_type) = to_partition_column(expr, partition_columns)?;
+            if !matches!(data_type, DataType::Utf8) {
+                return None;
+            }
+            Some(format!("{name} LIKE {}", to_glue_literal(pattern, data_type)?))
+        }
+        _ => None,
+    }
+}
+
+fn comparison_to_glue_expression(
+    left: &Expr,
+    op: Operator,
+    right: &Expr,
+    partition_columns: &[Field],
+) -> Option<String> {
+    // normalize to `column op literal`
+    let (column, op, literal) = match (
+        to_partition_column(left, partition_columns),
+        to_partition_column(right, partition_columns),
+    ) {
+        (Some(column), None) => (column, op, right),
+        (None, Some(column)) => (column, op.swap()?, left),
+        _ => return None,
+    };
+    let (name, data_type) = column;
+    let op = match op {
+        Operator::Eq => "=",
+        Operator::NotEq => "<>",
+        Operator::Lt => "<",
+        Operator::LtEq => "<=",
+        Operator::Gt => ">",
+        Operator::GtEq => ">=",
+        _ => return None,
+    };
+    Some(format!("{name} {op} {}", to_glue_literal(literal, data_type)?))
+}
+
+fn to_partition_column<'a>(
+    expr: &Expr,
+    partition_columns: &'a [Field],
+) -> Option<(&'a str, &'a DataType)> {
+    let Expr::Column(column) = expr else {
+        return None;
+    };
+    partition_columns
+        .iter()
+        .find(|f| f.name() == &column.name)
+        .map(|f| (f.name().as_str(), f.data_type()))
+}
+
+/// Formats a literal the way Glue compares it against a partition column of `data_type`.
+/// Glue only understands string, date and integer partition keys, so literals of other
+/// types, or that would need a lossy cast, are not translated.
+fn to_glue_literal(expr: &Expr, data_type: &DataType) -> Option<String> {
+    let Expr::Literal(value, _) = expr else {
+        return None;
+    };
+    if value.is_null() {
+        return None;
+    }
+    match data_type {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64
+            if value.data_type().is_integer() =>
+        {
+            Some(value.cast_to(&DataType::Int64).ok()?.to_string())
+        }
+        DataType::Utf8 => match value {
+            ScalarValue::Utf8(Some(s))
+            | ScalarValue::LargeUtf8(Some(s))
+            | ScalarValue::Utf8View(Some(s)) => Some(quote(s)),
+            _ => None,
+        },
+        DataType::Date32 => match value {
+            ScalarValue::Date32(_) => Some(quote(&value.to_string())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Evaluates partition `filters` against the values of each partition and returns
+/// whether the partition may contain matching rows.
+///
+/// `filters` must only reference partition columns, see [`is_partition_filter`], a filter
+/// that fails to evaluate on the partition values is an error.
+pub fn prune_partitions(
+    partition_columns: &[Field],
+    partition_values: &[Vec<ScalarValue>],
+    filters: &[Expr],
+) -> Result<Vec<bool>, DataFusionError> {
+    let mut matches = vec![true; partition_values.len()];
+    if partition_values.is_empty() || filters.is_empty() {
+        return Ok(matches);
+    }
+
+    let schema = Arc::new(Schema::new(partition_columns.to_vec()));
+    let columns = (0..partition_columns.len())
+        .map(|idx| ScalarValue::iter_to_array(partition_values.iter().map(|v| v[idx].clone())))
+        .collect::<Result<Vec<ArrayRef>, DataFusionError>>()?;
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    let df_schema = DFSchema::try_from(schema.as_ref().clone())?;
+    let props = ExecutionProps::new();
+
+    for filter in filters {
+        let array = create_physical_expr(filter, &df_schema, &props)
+            .and_then(|expr| expr.evaluate(&batch))
+            .and_then(|value| value.into_array(batch.num_rows()))
+            .map_err(|e| e.context(format!("failed to prune partitions with filter {filter}")))?;
+        let Some(array) = array.as_boolean_opt() else {
+            return Err(DataFusionError::Plan(format!(
+                "partition filter {filter} is not a predicate, got {}",
+                array.data_type()
+            )));
+        };
+        for (idx, matched) in matches.iter_mut().enumerate() {
+            // null means the filter is not satisfied
+            *matched &= array.is_valid(idx) && array.value(idx);
+        }
+    }
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::prelude::{col, lit};
+
+    #[test]
+    fn test_partition_pruning() -> Result<(), DataFusionError> {
+        let partition_columns = vec![
+            Field::new("dt", DataType::Utf8, true),
+            Field::new("hour", DataType::Int32, true),
+        ];
+        let filters = vec![
+            col("dt").gt_eq(lit("2026-10-01")),
+            col("hour").in_list(vec![lit(1), lit(2)], false),
+            // not translatable, only evaluated locally
+            col("dt").not_like(lit("2026-10-02%")),
+        ];
+        assert!(filters.iter().all(|f| is_partition_filter(f, &partition_columns)));
+        assert!(!is_partition_filter(&col("id").eq(lit(1)), &partition_columns));
+        assert_eq!(
+            convert_filters_to_glue_expression(&filters, &partition_columns),
+            Some("(dt >= '2026-10-01') AND (hour IN (1, 2))".to_string())
+        );
+
+        let partition_values = vec![
+            vec![ScalarValue::from("2026-09-30"), ScalarValue::Int32(Some(1))],
+            vec![ScalarValue::from("2026-10-01"), ScalarValue::Int32(Some(1))],
+            vec![ScalarValue::from("2026-10-01"), ScalarValue::Int32(Some(3))],
+            vec![ScalarValue::from("2026-10-02"), ScalarValue::Int32(Some(2))],
+            vec![ScalarValue::from("2026-10-03"), ScalarValue::Int32(None)],
+        ];
+        assert_eq!(
+            prune_partitions(&partition_columns, &partition_values, &filters)?,
+            vec![false, true, false, false, false]
+        );
+        Ok(())
+    }
+}
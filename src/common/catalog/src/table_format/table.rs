@@ -1,5 +1,15 @@
+use crate::glue_table::GlueTable;
+use crate::table_format::iceberg_metadata_table::parse_metadata_table_name;
+use crate::table_format::iceberg_table::IcebergTable;
+use datafusion::arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
+use datafusion::catalog::TableProvider;
+use datafusion::common::not_impl_err;
+use datafusion::error::DataFusionError;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::Arc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TableIdentifier {
     pub namespace: Vec<String>,
     pub name: String
@@ -12,4 +22,90 @@ impl TableIdentifier {
             name: tbl_name.to_string()
         }
     }
-}
\ No newline at end of file
+}
+
+/// A historical version of a table to read instead of its current one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableVersion {
+    /// `FOR SYSTEM_VERSION AS OF <snapshot id>`
+    SnapshotId(i64),
+    /// `FOR SYSTEM_TIME AS OF '<timestamp>'`, milliseconds since epoch
+    Timestamp(i64),
+    /// `FOR SYSTEM_VERSION AS OF '<branch or tag>'`
+    Ref(String),
+    /// `FOR SYSTEM_TIME BETWEEN '<begin>' AND '<end>'`, the changes committed after
+    /// `begin` up to and including `end`, milliseconds since epoch
+    Incremental(i64, i64),
+}
+
+impl TableVersion {
+    /// Parses a timestamp literal like `2026-10-01 00:00:00` into milliseconds since epoch.
+    pub fn parse_timestamp(timestamp: &str) -> Result<i64, DataFusionError> {
+        string_to_timestamp_nanos(timestamp)
+            .map(|nanos| nanos / 1_000_000)
+            .map_err(|e| DataFusionError::Plan(format!("invalid timestamp '{timestamp}': {e}")))
+    }
+}
+
+impl Display for TableVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableVersion::SnapshotId(id) => write!(f, "snapshot={id}"),
+            TableVersion::Timestamp(ts) => write!(f, "timestamp={ts}"),
+            TableVersion::Ref(name) => write!(f, "ref={name}"),
+            TableVersion::Incremental(begin, end) => write!(f, "changes={begin}..{end}"),
+        }
+    }
+}
+
+/// Looks up `name` in a schema, either a table loaded by `load` from its name or one of
+/// the metadata tables of an Iceberg table, e.g. `orders$snapshots`.
+pub async fn lookup_table<'a, F, Fut>(
+    name: &'a str,
+    load: F,
+) -> Result<Option<Arc<dyn TableProvider>>, DataFusionError>
+where
+    F: FnOnce(&'a str) -> Fut,
+    Fut: Future<Output = Result<Option<Arc<dyn TableProvider>>, DataFusionError>>,
+{
+    let (table_name, metadata_table_type) = parse_metadata_table_name(name);
+    let Some(table) = load(table_name).await? else {
+        return Ok(None);
+    };
+    let Some(metadata_table_type) = metadata_table_type else {
+        return Ok(Some(table));
+    };
+    if let Some(table) = table.as_any().downcast_ref::<IcebergTable>() {
+        Ok(Some(Arc::new(table.metadata_table(metadata_table_type)?)))
+    } else if let Some(table) = table.as_any().downcast_ref::<GlueTable>() {
+        Ok(Some(Arc::new(table.metadata_table(metadata_table_type)?)))
+    } else {
+        not_impl_err!("metadata tables are only supported for iceberg tables")
+    }
+}
+
+/// Returns `table` as of a historical `version`, for the formats supporting time travel.
+pub async fn table_as_of(
+    table: &Arc<dyn TableProvider>,
+    version: &TableVersion,
+) -> Result<Arc<dyn TableProvider>, DataFusionError> {
+    if let Some(table) = table.as_any().downcast_ref::<IcebergTable>() {
+        Ok(Arc::new(table.with_version(version)?))
+    } else if let Some(table) = table.as_any().downcast_ref::<GlueTable>() {
+        Ok(Arc::new(table.with_version(version).await?))
+    } else {
+        not_impl_err!("time travel is only supported for iceberg, delta and hudi tables")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() -> Result<(), DataFusionError> {
+        assert_eq!(TableVersion::parse_timestamp("1970-01-01 00:00:01")?, 1000);
+        assert!(TableVersion::parse_timestamp("yesterday").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A key-value cache whose entries expire `ttl` after being inserted.
+///
+/// A zero `ttl` disables caching.
+#[derive(Debug)]
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    pub fn new(ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cached value if it hasn't expired yet.
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((inserted_at, value)) if inserted_at.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), value));
+    }
+
+    pub fn invalidate(&self, key: &K) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+/// A single cached value which expires `ttl` after being set.
+///
+/// Unlike [`TtlCache`], an expired value is kept around so callers can fall back
+/// to it when refreshing fails.
+#[derive(Debug)]
+pub struct TtlCell<V> {
+    ttl: Duration,
+    value: Mutex<Option<(Instant, V)>>,
+}
+
+impl<V: Clone> TtlCell<V> {
+    pub fn new(ttl: Duration) -> Self {
+        TtlCell {
+            ttl,
+            value: Mutex::new(None),
+        }
+    }
+
+    /// Returns the value if it hasn't expired yet.
+    pub fn get(&self) -> Option<V> {
+        match self.value.lock().unwrap().as_ref() {
+            Some((set_at, value)) if set_at.elapsed() < self.ttl => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the last value, even if it has expired.
+    pub fn get_stale(&self) -> Option<V> {
+        self.value
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn set(&self, value: V) {
+        *self.value.lock().unwrap() = Some((Instant::now(), value));
+    }
+
+    pub fn invalidate(&self) {
+        *self.value.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttl_cache_expire() {
+        let cache: TtlCache<String, i32> = TtlCache::new(Duration::from_millis(50));
+        cache.insert("a".to_string(), 1);
+        assert_eq!(cache.get(&"a".to_string()), Some(1));
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get(&"a".to_string()), None);
+
+        let disabled: TtlCache<String, i32> = TtlCache::new(Duration::ZERO);
+        disabled.insert("a".to_string(), 1);
+        assert_eq!(disabled.get(&"a".to_string()), None);
+    }
+
+    #[test]
+    fn test_ttl_cell_stale() {
+        let cell: TtlCell<Vec<String>> = TtlCell::new(Duration::ZERO);
+        cell.set(vec!["db".to_string()]);
+        assert_eq!(cell.get(), None);
+        assert_eq!(cell.get_stale(), Some(vec!["db".to_string()]));
+    }
+}
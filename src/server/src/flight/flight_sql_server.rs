@@ -1,20 +1,95 @@
-use arrow_array::{RecordBatch, StringArray};
-use std::sync::{Arc, LazyLock};
+use arrow_array::cast::AsArray;
+use arrow_array::types::UInt64Type;
+use arrow_array::{RecordBatch, Scalar, StringArray};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
 use tonic::{Request, Response, Status};
 
 use arrow_flight::encode::FlightDataEncoderBuilder;
-use arrow_flight::sql::server::FlightSqlService;
-use arrow_flight::sql::{CommandGetCatalogs, ProstMessageExt, SqlInfo};
+use arrow_flight::error::FlightError;
+use arrow_flight::sql::server::{FlightSqlService, PeekableFlightDataStream};
+use arrow_flight::sql::{
+    CommandGetCatalogs, CommandGetDbSchemas, CommandGetSqlInfo, CommandGetTableTypes,
+    CommandGetTables, CommandStatementQuery, CommandStatementUpdate, ProstMessageExt, SqlInfo,
+    TicketStatementQuery,
+};
 use arrow_flight::{
-    flight_service_server::FlightService, FlightDescriptor, FlightEndpoint, FlightInfo, Ticket,
+    flight_service_server::FlightService, FlightData, FlightDescriptor, FlightEndpoint,
+    FlightInfo, Ticket,
 };
-use arrow_schema::{DataType, Field, Schema};
-use futures::TryStreamExt;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use futures::future::join_all;
+use futures::{Stream, TryStreamExt};
 use prost::Message;
+use std::pin::Pin;
 use tonic::codegen::Bytes;
+use datafusion::arrow::compute::like;
+use datafusion::catalog::CatalogProviderList;
+use datafusion::datasource::TableType;
+use datafusion::error::DataFusionError;
+use uuid::Uuid;
+use crate::flight::sql_info::DOBBYDB_SQL_INFO;
+use crate::session::dobbydb_session::{DobbyDBSession, QueryPlan};
+
+/// How long a planned statement waits for its `do_get` before it is dropped.
+const STATEMENT_TTL: Duration = Duration::from_secs(300);
 
 #[derive(Clone)]
-pub struct DobbyDBFlightService {}
+pub struct DobbyDBFlightService {
+    session: DobbyDBSession,
+    /// Planned statements waiting for their `do_get` with the time they were planned,
+    /// keyed by statement handle
+    statements: Arc<Mutex<HashMap<String, (Instant, QueryPlan)>>>,
+}
+
+impl DobbyDBFlightService {
+    pub fn new(session: DobbyDBSession) -> Self {
+        DobbyDBFlightService {
+            session,
+            statements: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Keeps `plan` for its `do_get` under a new opaque handle, dropping the plans whose
+    /// tickets were never fetched.
+    fn register_statement(&self, plan: QueryPlan) -> String {
+        let handle = Uuid::new_v4().to_string();
+        let mut statements = self.statements.lock().unwrap();
+        statements.retain(|_, (planned_at, _)| planned_at.elapsed() < STATEMENT_TTL);
+        statements.insert(handle.clone(), (Instant::now(), plan));
+        handle
+    }
+
+    /// Removes the planned statement, so a retried `do_get` can't run a statement with
+    /// side effects twice. Statement tickets are one-shot: fetching a ticket again, or
+    /// after [`STATEMENT_TTL`], fails with NOT_FOUND and the client has to call
+    /// `get_flight_info` again for a new ticket.
+    #[allow(clippy::result_large_err)]
+    fn take_statement(&self, handle: &[u8]) -> Result<QueryPlan, Status> {
+        let handle = std::str::from_utf8(handle)
+            .map_err(|e| Status::invalid_argument(format!("invalid statement handle: {e}")))?;
+        match self.statements.lock().unwrap().remove(handle) {
+            Some((planned_at, plan)) if planned_at.elapsed() < STATEMENT_TTL => Ok(plan),
+            _ => Err(Status::not_found(format!(
+                "statement {handle} was already fetched or has expired, statement tickets can \
+                 only be fetched once within {}s of GetFlightInfo, plan the query again to get a \
+                 new ticket",
+                STATEMENT_TTL.as_secs()
+            ))),
+        }
+    }
+}
+
+fn to_status(error: DataFusionError) -> Status {
+    match error {
+        DataFusionError::SQL(..) | DataFusionError::Plan(_) | DataFusionError::SchemaError(..) => {
+            Status::invalid_argument(error.to_string())
+        }
+        DataFusionError::NotImplemented(_) => Status::unimplemented(error.to_string()),
+        _ => Status::internal(error.to_string()),
+    }
+}
 
 static FLIGHT_CATALOG_SCHEMA: LazyLock<Schema> = LazyLock::new(|| {
     Schema::new(vec![
@@ -23,6 +98,69 @@ static FLIGHT_CATALOG_SCHEMA: LazyLock<Schema> = LazyLock::new(|| {
     ])
 });
 
+static FLIGHT_TABLE_TYPES_SCHEMA: LazyLock<Schema> = LazyLock::new(|| {
+    Schema::new(vec![Field::new("table_type", DataType::Utf8, false)])
+});
+
+const TABLE_TYPES: [&str; 3] = ["TABLE", "VIEW", "LOCAL TEMPORARY"];
+
+fn table_type_name(table_type: TableType) -> &'static str {
+    match table_type {
+        TableType::Base => TABLE_TYPES[0],
+        TableType::View => TABLE_TYPES[1],
+        TableType::Temporary => TABLE_TYPES[2],
+    }
+}
+
+/// Builds a [`FlightInfo`] with a single endpoint whose ticket is the encoded `command`.
+#[allow(clippy::result_large_err)]
+fn build_flight_info(
+    command: impl ProstMessageExt,
+    schema: &Schema,
+    descriptor: FlightDescriptor,
+) -> Result<FlightInfo, Status> {
+    let ticket = Ticket {
+        ticket: Bytes::from(command.as_any().encode_to_vec()),
+    };
+    let endpoint = FlightEndpoint::new().with_ticket(ticket);
+    FlightInfo::new()
+        .with_total_bytes(-1)
+        .with_total_records(-1)
+        .with_ordered(false)
+        .with_endpoint(endpoint)
+        .with_descriptor(descriptor)
+        .try_with_schema(schema)
+        .map_err(|err| Status::internal(format!("{err:?}")))
+}
+
+/// Rows changed by a statement, from the `count` column DML statements return. -1, an
+/// unknown count in Flight SQL, for other statements.
+fn affected_rows(batches: &[RecordBatch]) -> i64 {
+    let counts = batches
+        .iter()
+        .map(|batch| batch.column_by_name("count")?.as_primitive_opt::<UInt64Type>())
+        .collect::<Option<Vec<_>>>();
+    match counts {
+        Some(counts) if !counts.is_empty() => counts
+            .iter()
+            .flat_map(|count| count.iter().flatten())
+            .sum::<u64>() as i64,
+        _ => -1,
+    }
+}
+
+/// Encodes a single [`RecordBatch`] into a `DoGet` stream.
+fn build_batch_stream(
+    schema: SchemaRef,
+    batch: Result<RecordBatch, FlightError>,
+) -> Pin<Box<dyn Stream<Item = Result<FlightData, Status>> + Send + 'static>> {
+    let stream = FlightDataEncoderBuilder::new()
+        .with_schema(schema)
+        .build(futures::stream::once(async { batch }))
+        .map_err(|e| Status::internal(e.to_string()));
+    Box::pin(stream)
+}
+
 #[tonic::async_trait]
 impl FlightSqlService for DobbyDBFlightService {
     type FlightService = Self;
@@ -51,24 +189,14 @@ impl FlightSqlService for DobbyDBFlightService {
     async fn do_get_catalogs(
         &self,
         _query: CommandGetCatalogs,
-        request: Request<Ticket>,
+        _request: Request<Ticket>,
     ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
-        println!("ticket is {}", request.into_inner().to_string());
-        let catalog_names: Vec<String> = Vec::new();
-        let catalog_types: Vec<String> = Vec::new();
-        // let catalog_definitions = &CATALOG_MANAGER.lock().unwrap().catalog_definitions;
-        // for catalog_definition in catalog_definitions {
-        //     match catalog_definition {
-        //         CatalogDefinition::Iceberg(each) => {
-        //             catalog_names.push(each.name.clone());
-        //             catalog_types.push("Iceberg".to_string());
-        //         }
-        //         CatalogDefinition::Hive(each) => {
-        //             catalog_names.push(each.name.clone());
-        //             catalog_types.push("Hive".to_string());
-        //         }
-        //     }
-        // }
+        let mut catalog_names: Vec<String> = Vec::new();
+        let mut catalog_types: Vec<String> = Vec::new();
+        for (name, catalog_type) in self.session.catalog_manager().catalog_definitions() {
+            catalog_names.push(name);
+            catalog_types.push(catalog_type.to_string());
+        }
         let batch = RecordBatch::try_new(
             Arc::new(FLIGHT_CATALOG_SCHEMA.clone()),
             vec![
@@ -85,7 +213,478 @@ impl FlightSqlService for DobbyDBFlightService {
         Ok(Response::new(Box::pin(stream)))
     }
 
-    async fn register_sql_info(&self, id: i32, _result: &SqlInfo) {
-        println!("{}", id);
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let plan = self.session.plan_sql(&query.query).await.map_err(to_status)?;
+        let schema = plan.schema();
+        let handle = self.register_statement(plan);
+
+        let ticket = TicketStatementQuery {
+            statement_handle: Bytes::from(handle),
+        };
+        let flight_info = build_flight_info(ticket, schema.as_ref(), request.into_inner())?;
+        Ok(Response::new(flight_info))
+    }
+
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let plan = self.take_statement(&ticket.statement_handle)?;
+        let schema = plan.schema();
+        let batches = self.session.execute(plan).await.map_err(to_status)?;
+
+        let stream = FlightDataEncoderBuilder::new()
+            .with_schema(schema)
+            .build(batches.map_err(|e| FlightError::ExternalError(Box::new(e))))
+            .map_err(|e| Status::internal(e.to_string()));
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn do_put_statement_update(
+        &self,
+        ticket: CommandStatementUpdate,
+        _request: Request<PeekableFlightDataStream>,
+    ) -> Result<i64, Status> {
+        let plan = self.session.plan_sql(&ticket.query).await.map_err(to_status)?;
+        let batches: Vec<RecordBatch> = self
+            .session
+            .execute(plan)
+            .await
+            .map_err(to_status)?
+            .try_collect()
+            .await
+            .map_err(to_status)?;
+        Ok(affected_rows(&batches))
+    }
+
+    async fn get_flight_info_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder().schema();
+        let flight_info = build_flight_info(query, schema.as_ref(), request.into_inner())?;
+        Ok(Response::new(flight_info))
+    }
+
+    async fn do_get_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let catalog_manager = self.session.catalog_manager();
+        let catalog_filter = query.catalog.clone();
+        let mut builder = query.into_builder();
+        for catalog_name in catalog_manager.catalog_names() {
+            if catalog_filter.as_ref().is_some_and(|c| c != &catalog_name) {
+                continue;
+            }
+            let Some(catalog) = catalog_manager.catalog(&catalog_name) else {
+                continue;
+            };
+            catalog_manager.refresh_catalog(&catalog_name, false).await;
+            for schema_name in catalog.schema_names() {
+                builder.append(&catalog_name, schema_name);
+            }
+        }
+        let schema = builder.schema();
+        Ok(Response::new(build_batch_stream(schema, builder.build())))
+    }
+
+    async fn get_flight_info_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder().schema();
+        let flight_info = build_flight_info(query, schema.as_ref(), request.into_inner())?;
+        Ok(Response::new(flight_info))
+    }
+
+    async fn do_get_tables(
+        &self,
+        query: CommandGetTables,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let catalog_manager = self.session.catalog_manager();
+        let catalog_filter = query.catalog.clone();
+        let schema_pattern = query.db_schema_filter_pattern.clone();
+        let table_pattern = query.table_name_filter_pattern.clone();
+        // loading a table can be expensive, only do it when its schema or type is required
+        let load_table = query.include_schema || !query.table_types.is_empty();
+        let mut builder = query.into_builder();
+        let empty_schema = Schema::empty();
+        for catalog_name in catalog_manager.catalog_names() {
+            if catalog_filter.as_ref().is_some_and(|c| c != &catalog_name) {
+                continue;
+            }
+            let Some(catalog) = catalog_manager.catalog(&catalog_name) else {
+                continue;
+            };
+            // only list and load the tables of the schemas and tables the request is about
+            catalog_manager.refresh_catalog(&catalog_name, false).await;
+            let schema_names = filter_by_pattern(catalog.schema_names(), schema_pattern.as_deref())
+                .map_err(|e| Status::internal(e.to_string()))?;
+            join_all(schema_names.iter().map(|schema_name| {
+                catalog_manager.refresh_schema(&catalog_name, schema_name, true)
+            }))
+            .await;
+            for schema_name in schema_names {
+                let Some(schema) = catalog.schema(&schema_name) else {
+                    continue;
+                };
+                let table_names = filter_by_pattern(schema.table_names(), table_pattern.as_deref())
+                    .map_err(|e| Status::internal(e.to_string()))?;
+                for table_name in table_names {
+                    if !load_table {
+                        builder
+                            .append(
+                                &catalog_name,
+                                &schema_name,
+                                &table_name,
+                                TABLE_TYPES[0],
+                                &empty_schema,
+                            )
+                            .map_err(|e| Status::internal(e.to_string()))?;
+                        continue;
+                    }
+                    // one broken table must not fail the listing of all others
+                    let table = match schema.table(&table_name).await {
+                        Ok(Some(table)) => table,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            println!(
+                                "failed to load table {}.{}.{}, skip it: {}",
+                                catalog_name, schema_name, table_name, e
+                            );
+                            continue;
+                        }
+                    };
+                    builder
+                        .append(
+                            &catalog_name,
+                            &schema_name,
+                            &table_name,
+                            table_type_name(table.table_type()),
+                            table.schema().as_ref(),
+                        )
+                        .map_err(|e| Status::internal(e.to_string()))?;
+                }
+            }
+        }
+        let schema = builder.schema();
+        Ok(Response::new(build_batch_stream(schema, builder.build())))
+    }
+
+    async fn get_flight_info_table_types(
+        &self,
+        query: CommandGetTableTypes,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let flight_info =
+            build_flight_info(query, &FLIGHT_TABLE_TYPES_SCHEMA, request.into_inner())?;
+        Ok(Response::new(flight_info))
+    }
+
+    async fn do_get_table_types(
+        &self,
+        _query: CommandGetTableTypes,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let schema = Arc::new(FLIGHT_TABLE_TYPES_SCHEMA.clone());
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(TABLE_TYPES.to_vec()))],
+        )
+        .map_err(FlightError::from);
+        Ok(Response::new(build_batch_stream(schema, batch)))
+    }
+
+    async fn get_flight_info_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder(&DOBBYDB_SQL_INFO).schema();
+        let flight_info = build_flight_info(query, schema.as_ref(), request.into_inner())?;
+        Ok(Response::new(flight_info))
+    }
+
+    async fn do_get_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let builder = query.into_builder(&DOBBYDB_SQL_INFO);
+        let schema = builder.schema();
+        Ok(Response::new(build_batch_stream(schema, builder.build())))
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {
+        // all supported SqlInfo values are registered statically in DOBBYDB_SQL_INFO
+    }
+}
+
+/// Keeps the `names` matching the SQL `LIKE` filter pattern of a metadata request, all of
+/// them without a pattern.
+fn filter_by_pattern(names: Vec<String>, pattern: Option<&str>) -> Result<Vec<String>, ArrowError> {
+    let Some(pattern) = pattern else {
+        return Ok(names);
+    };
+    let matches = like(&StringArray::from(names.clone()), &Scalar::new(StringArray::from(vec![pattern])))?;
+    Ok(names
+        .into_iter()
+        .zip(matches.iter())
+        .filter_map(|(name, matches)| matches.unwrap_or(false).then_some(name))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::UInt32Type;
+    use arrow_flight::utils::flight_data_to_batches;
+    use datafusion::catalog::{
+        CatalogProvider, MemoryCatalogProvider, MemorySchemaProvider, SchemaProvider, TableProvider,
+    };
+    use datafusion::common::exec_err;
+    use datafusion::datasource::MemTable;
+    use dobbydb_common_catalog::catalog::{CatalogType, DobbyCatalogManager};
+    use std::any::Any;
+    use std::sync::Mutex;
+
+    /// A schema with a table `orders` and a table `broken` failing to load.
+    #[derive(Debug)]
+    struct BrokenTableSchema {
+        orders: Arc<dyn TableProvider>,
+        /// Names of the tables loaded so far
+        loaded: Mutex<Vec<String>>,
+    }
+
+    #[tonic::async_trait]
+    impl SchemaProvider for BrokenTableSchema {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn table_names(&self) -> Vec<String> {
+            vec!["broken".to_string(), "orders".to_string()]
+        }
+
+        async fn table(&self, name: &str) -> Result<Option<Arc<dyn TableProvider>>, DataFusionError> {
+            self.loaded.lock().unwrap().push(name.to_string());
+            match name {
+                "orders" => Ok(Some(self.orders.clone())),
+                "broken" => exec_err!("metadata of table broken not found"),
+                _ => Ok(None),
+            }
+        }
+
+        fn table_exist(&self, name: &str) -> bool {
+            self.table_names().iter().any(|table_name| table_name == name)
+        }
+    }
+
+    /// A service over the catalogs `sales` with the schemas `eu` and `us`, and `hr` with
+    /// the schema `people`.
+    fn mock_service() -> Result<DobbyDBFlightService, DataFusionError> {
+        let orders_schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let orders: Arc<dyn TableProvider> = Arc::new(MemTable::try_new(orders_schema, vec![vec![]])?);
+        let sales = MemoryCatalogProvider::new();
+        sales.register_schema("eu", Arc::new(BrokenTableSchema {
+            orders: orders.clone(),
+            loaded: Mutex::new(vec![]),
+        }))?;
+        let us = MemorySchemaProvider::new();
+        us.register_table("orders".to_string(), orders)?;
+        sales.register_schema("us", Arc::new(us))?;
+        let hr = MemoryCatalogProvider::new();
+        hr.register_schema("people", Arc::new(MemorySchemaProvider::new()))?;
+
+        let mut catalog_manager = DobbyCatalogManager::new();
+        catalog_manager.add_catalog("sales", CatalogType::Glue, Arc::new(sales));
+        catalog_manager.add_catalog("hr", CatalogType::Rest, Arc::new(hr));
+        let session = DobbyDBSession::new(Arc::new(catalog_manager));
+        Ok(DobbyDBFlightService::new(session))
+    }
+
+    async fn collect_batches(
+        response: Result<Response<<DobbyDBFlightService as FlightService>::DoGetStream>, Status>,
+    ) -> Vec<RecordBatch> {
+        let flight_data: Vec<FlightData> = response.unwrap().into_inner().try_collect().await.unwrap();
+        flight_data_to_batches(&flight_data).unwrap()
+    }
+
+    fn ticket() -> Request<Ticket> {
+        Request::new(Ticket { ticket: Bytes::new() })
+    }
+
+    fn strings(batches: &[RecordBatch], column: &str) -> Vec<String> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let values = batch.column_by_name(column).unwrap().as_string::<i32>();
+                values.iter().map(|value| value.unwrap_or_default().to_string()).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_get_catalogs_and_schemas() -> Result<(), DataFusionError> {
+        let service = mock_service()?;
+        let batches = collect_batches(service.do_get_catalogs(CommandGetCatalogs {}, ticket()).await).await;
+        assert_eq!(strings(&batches, "catalog_name"), vec!["hr", "sales"]);
+        assert_eq!(strings(&batches, "catalog_type"), vec!["rest", "glue"]);
+
+        let query = CommandGetDbSchemas {
+            catalog: None,
+            db_schema_filter_pattern: None,
+        };
+        let batches = collect_batches(service.do_get_schemas(query, ticket()).await).await;
+        assert_eq!(strings(&batches, "catalog_name"), vec!["hr", "sales", "sales"]);
+        assert_eq!(strings(&batches, "db_schema_name"), vec!["people", "eu", "us"]);
+
+        let query = CommandGetDbSchemas {
+            catalog: Some("sales".to_string()),
+            db_schema_filter_pattern: Some("u%".to_string()),
+        };
+        let batches = collect_batches(service.do_get_schemas(query, ticket()).await).await;
+        assert_eq!(strings(&batches, "db_schema_name"), vec!["us"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_tables() -> Result<(), DataFusionError> {
+        let service = mock_service()?;
+        let query = CommandGetTables {
+            catalog: Some("sales".to_string()),
+            db_schema_filter_pattern: None,
+            table_name_filter_pattern: None,
+            table_types: vec![],
+            include_schema: false,
+        };
+        let batches = collect_batches(service.do_get_tables(query, ticket()).await).await;
+        assert_eq!(strings(&batches, "db_schema_name"), vec!["eu", "eu", "us"]);
+        assert_eq!(strings(&batches, "table_name"), vec!["broken", "orders", "orders"]);
+
+        // loading the tables for their schemas skips the broken one
+        let query = CommandGetTables {
+            catalog: None,
+            db_schema_filter_pattern: None,
+            table_name_filter_pattern: Some("%r%".to_string()),
+            table_types: vec!["TABLE".to_string()],
+            include_schema: true,
+        };
+        let batches = collect_batches(service.do_get_tables(query, ticket()).await).await;
+        assert_eq!(strings(&batches, "db_schema_name"), vec!["eu", "us"]);
+        assert_eq!(strings(&batches, "table_name"), vec!["orders", "orders"]);
+        assert_eq!(strings(&batches, "table_type"), vec!["TABLE", "TABLE"]);
+        let table_schema = batches[0].column_by_name("table_schema").unwrap().as_binary::<i32>();
+        let table_schema = arrow_flight::IpcMessage(Bytes::copy_from_slice(table_schema.value(0)));
+        let table_schema = Schema::try_from(table_schema).unwrap();
+        assert_eq!(table_schema.field(0).name(), "id");
+
+        // tables not matching the filters are never loaded
+        let eu = service.session.catalog_manager().catalog("sales").unwrap().schema("eu").unwrap();
+        let eu = eu.as_any().downcast_ref::<BrokenTableSchema>().unwrap();
+        eu.loaded.lock().unwrap().clear();
+        let query = CommandGetTables {
+            catalog: None,
+            db_schema_filter_pattern: Some("e_".to_string()),
+            table_name_filter_pattern: Some("ord%".to_string()),
+            table_types: vec![],
+            include_schema: true,
+        };
+        let batches = collect_batches(service.do_get_tables(query, ticket()).await).await;
+        assert_eq!(strings(&batches, "catalog_name"), vec!["sales"]);
+        assert_eq!(strings(&batches, "db_schema_name"), vec!["eu"]);
+        assert_eq!(strings(&batches, "table_name"), vec!["orders"]);
+        assert_eq!(*eu.loaded.lock().unwrap(), vec!["orders".to_string()]);
+        Ok(())
+    }
+
+    /// Plans `sql` with GetFlightInfo, returning the schema it announces and its ticket.
+    async fn statement_ticket(
+        service: &DobbyDBFlightService,
+        sql: &str,
+    ) -> Result<(Schema, TicketStatementQuery), Status> {
+        let query = CommandStatementQuery {
+            query: sql.to_string(),
+            transaction_id: None,
+        };
+        let request = Request::new(FlightDescriptor::new_cmd(query.as_any().encode_to_vec()));
+        let flight_info = service.get_flight_info_statement(query, request).await?.into_inner();
+        let schema = flight_info.clone().try_decode_schema().unwrap();
+        let ticket = flight_info.endpoint[0].ticket.clone().unwrap();
+        let ticket = arrow_flight::sql::Any::decode(ticket.ticket)
+            .unwrap()
+            .unpack::<TicketStatementQuery>()
+            .unwrap()
+            .unwrap();
+        Ok((schema, ticket))
+    }
+
+    #[tokio::test]
+    async fn test_statement() -> Result<(), DataFusionError> {
+        let service = mock_service()?;
+        let (schema, insert) = statement_ticket(&service, "INSERT INTO sales.us.orders VALUES (1), (2)")
+            .await
+            .unwrap();
+        assert_eq!(schema.field(0).name(), "count");
+        let (schema, select) = statement_ticket(&service, "SELECT id FROM sales.us.orders")
+            .await
+            .unwrap();
+        assert_eq!(schema.field(0).name(), "id");
+
+        // the insert only runs once its ticket is fetched, and only once
+        let batches = collect_batches(service.do_get_statement(insert.clone(), ticket()).await).await;
+        assert_eq!(affected_rows(&batches), 2);
+        let error = service.do_get_statement(insert, ticket()).await.err().unwrap();
+        assert_eq!(error.code(), tonic::Code::NotFound);
+        assert!(error.message().contains("can only be fetched once"), "{}", error.message());
+
+        // the select was planned before the insert and sees its rows once fetched
+        let batches = collect_batches(service.do_get_statement(select, ticket()).await).await;
+        assert_eq!(batches[0].schema().as_ref(), &schema);
+        let ids = batches
+            .iter()
+            .flat_map(|batch| batch.column(0).as_primitive::<arrow_array::types::Int64Type>().values().to_vec())
+            .collect::<Vec<i64>>();
+        assert_eq!(ids, vec![1, 2]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_sql_info() -> Result<(), DataFusionError> {
+        let service = mock_service()?;
+        let query = CommandGetSqlInfo {
+            info: vec![
+                SqlInfo::FlightSqlServerName as u32,
+                SqlInfo::SqlDdlSchema as u32,
+                SqlInfo::SqlDdlTable as u32,
+            ],
+        };
+        let batches = collect_batches(service.do_get_sql_info(query, ticket()).await).await;
+        let batch = &batches[0];
+        let names = batch.column(0).as_primitive::<UInt32Type>();
+        assert_eq!(
+            names.values().to_vec(),
+            vec![
+                SqlInfo::FlightSqlServerName as u32,
+                SqlInfo::SqlDdlSchema as u32,
+                SqlInfo::SqlDdlTable as u32,
+            ]
+        );
+        let values = batch.column(1).as_union();
+        assert_eq!(values.value(0).as_string::<i32>().value(0), "DobbyDB");
+        assert!(values.value(1).as_boolean().value(0));
+        assert!(values.value(2).as_boolean().value(0));
+        Ok(())
     }
 }
@@ -1 +1,2 @@
-pub mod flight_sql_server;
\ No newline at end of file
+pub mod flight_sql_server;
+pub mod sql_info;
@@ -1,9 +1,12 @@
 use arrow_flight::flight_service_server::FlightServiceServer;
 use clap::Parser;
+use dobbydb_common_catalog::catalog::DobbyCatalogManager;
+use std::sync::Arc;
 use tonic::transport::Server;
 
 mod flight;
 mod parser;
+mod session;
 
 #[derive(Parser, Debug)]
 #[command(version, about)]
@@ -13,22 +16,31 @@ struct DobbyDBServerConfig {
 }
 
 struct DobbyDBServer {
+    catalog_manager: Arc<DobbyCatalogManager>,
 }
 
 impl DobbyDBServer {
     pub fn new() -> Self {
         DobbyDBServer {
+            catalog_manager: Arc::new(DobbyCatalogManager::new()),
         }
     }
 
-    pub fn init(&mut self, config: DobbyDBServerConfig) -> Result<(), Box<dyn std::error::Error>> {
-        // CATALOG_MANAGER.lock().unwrap().init(&config.config_path)?;
+    pub async fn init(&mut self, config: DobbyDBServerConfig) -> Result<(), Box<dyn std::error::Error>> {
+        let mut catalog_manager = DobbyCatalogManager::new();
+        catalog_manager.init_from_path(&config.config_path).await?;
+        self.catalog_manager = Arc::new(catalog_manager);
         Ok(())
     }
     
+    /// Creates a session with all catalogs loaded by [`DobbyDBServer::init`] registered.
+    fn session(&self) -> session::dobbydb_session::DobbyDBSession {
+        session::dobbydb_session::DobbyDBSession::new(self.catalog_manager.clone())
+    }
+
     pub async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let addr = "[::]:8081".parse()?;
-        let service = flight::flight_sql_server::DobbyDBFlightService {};
+        let service = flight::flight_sql_server::DobbyDBFlightService::new(self.session());
 
         let svc = FlightServiceServer::new(service);
 
@@ -41,7 +53,70 @@ impl DobbyDBServer {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let server_config = DobbyDBServerConfig::parse();
     let mut dobbydb_server = DobbyDBServer::new();
-    dobbydb_server.init(server_config)?;
+    dobbydb_server.init(server_config).await?;
     dobbydb_server.run().await?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{RecordBatch, StringArray};
+    use dobbydb_common_catalog::table_format::iceberg_commit::testing::TestDir;
+    use futures::TryStreamExt;
+    use std::fs;
+
+    async fn query_strings(
+        session: &session::dobbydb_session::DobbyDBSession,
+        sql: &str,
+    ) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+        let plan = session.plan_sql(sql).await?;
+        let batches: Vec<RecordBatch> = session.execute(plan).await?.try_collect().await?;
+        let mut rows = vec![];
+        for batch in &batches {
+            for row in 0..batch.num_rows() {
+                let values = batch
+                    .columns()
+                    .iter()
+                    .map(|column| column.as_any().downcast_ref::<StringArray>().unwrap().value(row).to_string())
+                    .collect();
+                rows.push(values);
+            }
+        }
+        Ok(rows)
+    }
+
+    #[tokio::test]
+    async fn test_init_registers_catalogs_in_session() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TestDir::new("server-init");
+        let warehouse = dir.path().join("warehouse");
+        fs::create_dir_all(warehouse.join("hr"))?;
+        let config_path = dir.path().join("catalog.toml");
+        fs::write(&config_path, format!("[[filesystem]]\nname = \"lake\"\nwarehouse = \"{}\"\n", warehouse.display()))?;
+
+        let mut server = DobbyDBServer::new();
+        server
+            .init(DobbyDBServerConfig {
+                config_path: config_path.to_str().unwrap().to_string(),
+            })
+            .await?;
+        let session = server.session();
+        assert_eq!(
+            query_strings(&session, "SHOW CATALOGS").await?,
+            vec![vec!["lake".to_string(), "filesystem".to_string()]]
+        );
+        let sql = "SELECT schema_name FROM information_schema.schemata \
+            WHERE catalog_name = 'lake' AND schema_name <> 'information_schema'";
+        assert_eq!(query_strings(&session, sql).await?, vec![vec!["hr".to_string()]]);
+
+        // a missing config fails the startup
+        let error = server
+            .init(DobbyDBServerConfig {
+                config_path: dir.path().join("missing.toml").to_str().unwrap().to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("No such file"), "{error}");
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use datafusion::logical_expr::sqlparser::dialect::{Dialect, GenericDialect};
+use std::any::TypeId;
+
+/// Forwards the boolean `supports_*` style methods overridden by [`GenericDialect`].
+macro_rules! delegate_to_generic {
+    ($($method:ident),* $(,)?) => {
+        $(
+            fn $method(&self) -> bool {
+                self.0.$method()
+            }
+        )*
+    };
+}
+
+/// The [`GenericDialect`] with table versions, so `FOR SYSTEM_TIME AS OF` and `AT(...)`
+/// after a table name are parsed into its [`TableFactor::Table`] version.
+///
+/// [`TableFactor::Table`]: datafusion::logical_expr::sqlparser::ast::TableFactor::Table
+#[derive(Debug, Default)]
+pub struct DobbyDBDialect(GenericDialect);
+
+impl DobbyDBDialect {
+    pub const fn new() -> Self {
+        DobbyDBDialect(GenericDialect {})
+    }
+}
+
+impl Dialect for DobbyDBDialect {
+    fn dialect(&self) -> TypeId {
+        self.0.dialect()
+    }
+
+    fn is_delimited_identifier_start(&self, ch: char) -> bool {
+        self.0.is_delimited_identifier_start(ch)
+    }
+
+    fn is_identifier_start(&self, ch: char) -> bool {
+        self.0.is_identifier_start(ch)
+    }
+
+    fn is_identifier_part(&self, ch: char) -> bool {
+        self.0.is_identifier_part(ch)
+    }
+
+    fn supports_timestamp_versioning(&self) -> bool {
+        true
+    }
+
+    delegate_to_generic!(
+        supports_unicode_string_literal,
+        supports_group_by_expr,
+        supports_group_by_with_modifier,
+        supports_connect_by,
+        supports_match_recognize,
+        supports_start_transaction_modifier,
+        supports_window_function_null_treatment_arg,
+        supports_dictionary_syntax,
+        supports_window_clause_named_window_reference,
+        supports_parenthesized_set_variables,
+        supports_select_wildcard_except,
+        support_map_literal_syntax,
+        allow_extract_custom,
+        allow_extract_single_quotes,
+        supports_create_index_with_clause,
+        supports_explain_with_utility_options,
+        supports_limit_comma,
+        supports_asc_desc_in_column_definition,
+        supports_try_convert,
+        supports_comment_on,
+        supports_load_extension,
+        supports_named_fn_args_with_assignment_operator,
+        supports_struct_literal,
+        supports_empty_projections,
+        supports_nested_comments,
+        supports_user_host_grantee,
+        supports_string_escape_constant,
+        supports_array_typedef_with_brackets,
+        supports_match_against,
+    );
+}
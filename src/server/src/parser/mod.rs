@@ -1,2 +1,4 @@
+pub mod dialect;
+#[allow(clippy::module_inception)]
 pub mod parser;
 pub mod statement;
\ No newline at end of file
@@ -1,12 +1,22 @@
 use std::collections::VecDeque;
-use datafusion::common::{Diagnostic, Span};
+use datafusion::common::{not_impl_err, plan_err, Diagnostic, Span};
 use datafusion::config::SqlParserOptions;
 use datafusion::error::DataFusionError;
-use datafusion::logical_expr::sqlparser::dialect::{Dialect, GenericDialect};
+use datafusion::logical_expr::sqlparser::ast::{
+    self, Expr, FunctionArg, FunctionArgExpr, FunctionArguments, Value,
+};
+use datafusion::logical_expr::sqlparser::dialect::Dialect;
 use datafusion::logical_expr::sqlparser::keywords::Keyword;
 use datafusion::logical_expr::sqlparser::parser::{Parser, ParserError};
 use datafusion::logical_expr::sqlparser::tokenizer::{Token, TokenWithSpan, Tokenizer};
-use crate::parser::statement::{ShowCatalogsStatement, Statement};
+use datafusion::sql::planner::IdentNormalizer;
+use dobbydb_common_catalog::table_format::iceberg_ddl::PartitionTransform;
+use dobbydb_common_catalog::table_format::table::TableVersion;
+use crate::parser::dialect::DobbyDBDialect;
+use crate::parser::statement::{
+    AnalyzeTableStatement, CallStatement, CreateSchemaStatement, CreateTableStatement, DropSchemaStatement,
+    DropTableStatement, ProcedureArgument, ShowCatalogsStatement, Statement,
+};
 
 // Use `Parser::expected` instead, if possible
 macro_rules! parser_err {
@@ -21,34 +31,45 @@ macro_rules! parser_err {
 }
 
 const DEFAULT_RECURSION_LIMIT: usize = 50;
-const DEFAULT_DIALECT: GenericDialect = GenericDialect {};
+const DEFAULT_DIALECT: DobbyDBDialect = DobbyDBDialect::new();
 
 pub struct DobbyDBParserBuilder<'a> {
     /// The SQL string to parse
     sql: &'a str,
-    /// The Dialect to use (defaults to [`GenericDialect`]
+    /// The Dialect to use (defaults to [`DobbyDBDialect`]
     dialect: &'a dyn Dialect,
     /// The recursion limit while parsing
     recursion_limit: usize,
+    /// Whether unquoted identifiers are lowercased (defaults to true)
+    enable_ident_normalization: bool,
 }
 
 impl<'a> DobbyDBParserBuilder<'a> {
     /// Create a new parser builder for the specified tokens using the
-    /// [`GenericDialect`].
+    /// [`DobbyDBDialect`].
     pub fn new(sql: &'a str) -> Self {
         Self {
             sql,
             dialect: &DEFAULT_DIALECT,
             recursion_limit: DEFAULT_RECURSION_LIMIT,
+            enable_ident_normalization: true,
         }
     }
 
+    /// Sets whether unquoted identifiers are lowercased, following the session's
+    /// `datafusion.sql_parser.enable_ident_normalization` setting.
+    pub fn with_ident_normalization(mut self, enable_ident_normalization: bool) -> Self {
+        self.enable_ident_normalization = enable_ident_normalization;
+        self
+    }
+
     pub fn build(self) -> Result<DobbyDBParser<'a>, DataFusionError> {
         let mut tokenizer = Tokenizer::new(self.dialect, self.sql);
         // Convert TokenizerError -> ParserError
         let tokens = tokenizer
             .tokenize_with_location()
             .map_err(ParserError::from)?;
+        let tokens = rewrite_table_versions(tokens);
 
         Ok(DobbyDBParser {
             parser: Parser::new(self.dialect)
@@ -56,18 +77,268 @@ impl<'a> DobbyDBParserBuilder<'a> {
                 .with_recursion_limit(self.recursion_limit),
             options: SqlParserOptions {
                 recursion_limit: self.recursion_limit,
+                enable_ident_normalization: self.enable_ident_normalization,
                 ..Default::default()
             },
         })
     }
 }
 
-struct DobbyDBParser<'a> {
+/// Rewrites the time travel clauses sqlparser doesn't parse into the Snowflake style
+/// `AT(...)` it parses into the version of a table (see [`table_version`]):
+///
+/// - `FOR SYSTEM_VERSION AS OF <snapshot id | 'ref'>` into `AT(VERSION => ...)`
+/// - `FOR TIMESTAMP AS OF <timestamp>` into `AT(TIMESTAMP => ...)`
+/// - `FOR SYSTEM_TIME BETWEEN <begin> AND <end>` into `AT(CHANGES => (<begin>, <end>))`
+///
+/// `FOR VERSION AS OF` is accepted as an alias of `FOR SYSTEM_VERSION AS OF`, while
+/// `FOR SYSTEM_TIME AS OF` is parsed by sqlparser itself.
+fn rewrite_table_versions(tokens: Vec<TokenWithSpan>) -> Vec<TokenWithSpan> {
+    let mut rewritten: Vec<TokenWithSpan> = Vec::with_capacity(tokens.len());
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match match_table_version(&tokens[idx..]) {
+            Some((version, consumed)) => {
+                let span = tokens[idx].span;
+                rewritten.extend(version.into_iter().map(|token| TokenWithSpan::new(token, span)));
+                idx += consumed;
+            }
+            None => {
+                rewritten.push(tokens[idx].clone());
+                idx += 1;
+            }
+        }
+    }
+    rewritten
+}
+
+/// Matches a time travel clause to rewrite at the start of `tokens`, returning the
+/// `AT(...)` tokens replacing it and the number of tokens (including whitespace) it spans.
+fn match_table_version(tokens: &[TokenWithSpan]) -> Option<(Vec<Token>, usize)> {
+    let mut words: Vec<(usize, &Token)> = Vec::with_capacity(6);
+    for (idx, token) in tokens.iter().enumerate() {
+        if matches!(token.token, Token::Whitespace(_)) {
+            continue;
+        }
+        words.push((idx, &token.token));
+        if words.len() == 6 {
+            break;
+        }
+    }
+    let is_keyword = |token: &Token, keyword: Keyword| {
+        matches!(token, Token::Word(w) if w.keyword == keyword && w.quote_style.is_none())
+    };
+    let is_value = |token: &Token| matches!(token, Token::SingleQuotedString(_) | Token::Number(_, _));
+    let at = |name: &str, value: Vec<Token>| {
+        let mut tokens = vec![
+            Token::make_keyword("AT"),
+            Token::LParen,
+            Token::make_word(name, None),
+            Token::RArrow,
+        ];
+        tokens.extend(value);
+        tokens.push(Token::RParen);
+        tokens
+    };
+    if let [(_, first), (_, kind), (_, between), (_, begin), (_, and), (last_idx, end)] = words[..]
+        && is_keyword(first, Keyword::FOR)
+        && is_keyword(kind, Keyword::SYSTEM_TIME)
+        && is_keyword(between, Keyword::BETWEEN)
+        && is_value(begin)
+        && is_keyword(and, Keyword::AND)
+        && is_value(end)
+    {
+        let range = vec![Token::LParen, begin.clone(), Token::Comma, end.clone(), Token::RParen];
+        return Some((at("CHANGES", range), last_idx + 1));
+    }
+
+    let [(_, first), (_, Token::Word(kind)), (_, as_token), (_, of_token), (last_idx, value), ..] =
+        words[..]
+    else {
+        return None;
+    };
+    if !is_keyword(first, Keyword::FOR)
+        || !is_keyword(as_token, Keyword::AS)
+        || !is_keyword(of_token, Keyword::OF)
+        || !is_value(value)
+    {
+        return None;
+    }
+    let name = match kind.value.to_ascii_uppercase().as_str() {
+        "SYSTEM_VERSION" | "VERSION" => "VERSION",
+        "TIMESTAMP" => "TIMESTAMP",
+        _ => return None,
+    };
+    Some((at(name, vec![value.clone()]), last_idx + 1))
+}
+
+/// Converts the version of a table parsed with [`DobbyDBDialect`], e.g.
+/// `FOR SYSTEM_TIME AS OF '2026-10-01'` or `AT(VERSION => 123)`, into the version the
+/// table is read as of.
+pub fn table_version(version: &ast::TableVersion) -> Result<TableVersion, DataFusionError> {
+    let function = match version {
+        ast::TableVersion::ForSystemTimeAsOf(timestamp) => {
+            return Ok(TableVersion::Timestamp(parse_timestamp(timestamp)?));
+        }
+        ast::TableVersion::Function(Expr::Function(function))
+            if function.name.to_string().eq_ignore_ascii_case("AT") =>
+        {
+            function
+        }
+        ast::TableVersion::Function(function) => {
+            return not_impl_err!("unsupported table version {function}");
+        }
+    };
+    let args = match &function.args {
+        FunctionArguments::List(args) => &args.args[..],
+        _ => &[],
+    };
+    let [FunctionArg::Named { name, arg: FunctionArgExpr::Expr(arg), .. }] = args else {
+        return plan_err!("expected a single named argument in table version {function}");
+    };
+    match (name.value.to_ascii_uppercase().as_str(), arg) {
+        ("VERSION", Expr::Value(value)) => match &value.value {
+            Value::Number(snapshot_id, _) => snapshot_id
+                .parse::<i64>()
+                .map(TableVersion::SnapshotId)
+                .map_err(|e| DataFusionError::Plan(format!("invalid snapshot id {snapshot_id}: {e}"))),
+            Value::SingleQuotedString(ref_name) => Ok(TableVersion::Ref(ref_name.clone())),
+            _ => plan_err!("expected a snapshot id or a branch or tag name, found {value}"),
+        },
+        ("TIMESTAMP", timestamp) => Ok(TableVersion::Timestamp(parse_timestamp(timestamp)?)),
+        ("CHANGES", Expr::Tuple(range)) if range.len() == 2 => Ok(TableVersion::Incremental(
+            parse_timestamp(&range[0])?,
+            parse_timestamp(&range[1])?,
+        )),
+        _ => plan_err!("unsupported table version {function}"),
+    }
+}
+
+/// Parses a `'<timestamp>'` literal or milliseconds since epoch.
+fn parse_timestamp(expr: &Expr) -> Result<i64, DataFusionError> {
+    match expr {
+        Expr::Value(value) => match &value.value {
+            Value::SingleQuotedString(timestamp) => TableVersion::parse_timestamp(timestamp),
+            Value::Number(timestamp_ms, _) => timestamp_ms.parse::<i64>().map_err(|e| {
+                DataFusionError::Plan(format!("invalid timestamp {timestamp_ms}: {e}"))
+            }),
+            _ => plan_err!("expected a timestamp, found {expr}"),
+        },
+        _ => plan_err!("expected a timestamp, found {expr}"),
+    }
+}
+
+/// Parses a `PARTITIONED BY` item: a column, partitioned by identity, or a Spark style
+/// transform such as `bucket(16, id)`, `truncate(4, name)` or `days(ts)`.
+fn parse_partition_transform(
+    parser: &mut Parser,
+    normalizer: &IdentNormalizer,
+) -> Result<PartitionTransform, ParserError> {
+    let name = normalizer.normalize(parser.parse_identifier()?);
+    if !parser.consume_token(&Token::LParen) {
+        return Ok(PartitionTransform {
+            column: name,
+            transform: "identity".to_string(),
+        });
+    }
+    let mut column = None;
+    let mut width = None;
+    loop {
+        let token = parser.next_token();
+        match token.token {
+            Token::Number(n, _) if width.is_none() => width = Some(n),
+            Token::Word(w) if column.is_none() => {
+                column = Some(normalizer.normalize(w.into_ident(token.span)))
+            }
+            token => {
+                return Err(ParserError::ParserError(format!(
+                    "Expected: transform argument, found: {token}"
+                )))
+            }
+        }
+        if !parser.consume_token(&Token::Comma) {
+            parser.expect_token(&Token::RParen)?;
+            break;
+        }
+    }
+    let transform = match (name.as_str(), width) {
+        ("bucket", Some(width)) => format!("bucket[{width}]"),
+        ("truncate", Some(width)) => format!("truncate[{width}]"),
+        ("identity", None) => "identity".to_string(),
+        ("years" | "year", None) => "year".to_string(),
+        ("months" | "month", None) => "month".to_string(),
+        ("days" | "day" | "date", None) => "day".to_string(),
+        ("hours" | "hour" | "date_hour", None) => "hour".to_string(),
+        _ => {
+            return Err(ParserError::ParserError(format!(
+                "invalid partition transform {name}"
+            )))
+        }
+    };
+    let column = column.ok_or_else(|| {
+        ParserError::ParserError(format!("missing column of partition transform {name}"))
+    })?;
+    Ok(PartitionTransform { column, transform })
+}
+
+/// Parses a `<key> = <value>` table property. Keys may be quoted or not, unquoted ones
+/// such as `format-version` or `write.parquet.compression-codec` span several tokens.
+fn parse_table_property(parser: &mut Parser) -> Result<(String, String), ParserError> {
+    let mut key = String::new();
+    if let Token::SingleQuotedString(quoted) = parser.peek_token().token {
+        parser.next_token();
+        key = quoted;
+        parser.expect_token(&Token::Eq)?;
+    } else {
+        loop {
+            match parser.next_token().token {
+                Token::Eq if !key.is_empty() => break,
+                Token::Word(w) => key.push_str(&w.value),
+                Token::Number(n, _) => key.push_str(&n),
+                Token::Minus => key.push('-'),
+                Token::Period => key.push('.'),
+                token => {
+                    return Err(ParserError::ParserError(format!(
+                        "Expected: table property, found: {token}"
+                    )))
+                }
+            }
+        }
+    }
+    let value = match parser.next_token().token {
+        Token::SingleQuotedString(value) | Token::Number(value, _) => value,
+        Token::Word(w) => w.value,
+        token => {
+            return Err(ParserError::ParserError(format!(
+                "Expected: value of table property {key}, found: {token}"
+            )))
+        }
+    };
+    Ok((key, value))
+}
+
+/// Parses a `<parameter> => <value>` or a positional `<value>` procedure argument.
+fn parse_procedure_argument(parser: &mut Parser) -> Result<ProcedureArgument, ParserError> {
+    let name = match (parser.peek_token().token, parser.peek_nth_token(1).token) {
+        (Token::Word(_), Token::RArrow) => {
+            let name = parser.parse_identifier()?;
+            parser.expect_token(&Token::RArrow)?;
+            Some(name)
+        }
+        _ => None,
+    };
+    let value = parser.parse_expr()?;
+    Ok(ProcedureArgument { name, value })
+}
+
+pub struct DobbyDBParser<'a> {
     pub parser: Parser<'a>,
     options: SqlParserOptions,
 }
 
 impl<'a> DobbyDBParser<'a> {
+    /// Parses `sql` with the default options, see [`DobbyDBParserBuilder`] to customize them.
+    #[cfg(test)]
     pub fn parse_sql(sql: &str) -> Result<VecDeque<Statement>, DataFusionError> {
         let mut parser = DobbyDBParserBuilder::new(sql).build()?;
         parser.parse_statements()
@@ -122,10 +393,22 @@ impl<'a> DobbyDBParser<'a> {
                         self.parser.next_token();
                         self.parse_show()
                     }
-                    // Keyword::CREATE => {
-                    //     self.parser.next_token(); // CREATE
-                    //     self.parse_create()
-                    // }
+                    Keyword::CREATE => {
+                        self.parser.next_token(); // CREATE
+                        self.parse_create()
+                    }
+                    Keyword::DROP => {
+                        self.parser.next_token(); // DROP
+                        self.parse_drop()
+                    }
+                    Keyword::CALL => {
+                        self.parser.next_token(); // CALL
+                        self.parse_call()
+                    }
+                    Keyword::ANALYZE => {
+                        self.parser.next_token(); // ANALYZE
+                        self.parse_analyze()
+                    }
                     // Keyword::COPY => {
                     //     if let Token::Word(w) = self.parser.peek_nth_token(1).token {
                     //         // use native parser for COPY INTO
@@ -154,19 +437,183 @@ impl<'a> DobbyDBParser<'a> {
     }
 
     fn parse_show(&mut self) -> Result<Statement, DataFusionError> {
-        if let token = self.parser.peek_token() {
-            match &token.token {
-                Token::Word(w) => {
-                    let val = w.value.to_ascii_uppercase();
-                    if val == "CATALOGS" {
-                        self.parser.next_token();
-                        return Ok(Statement::ShowCatalogsStatement(ShowCatalogsStatement{}));
-                    }
-                },
-                _ => {}
+        if let Token::Word(w) = &self.parser.peek_token().token
+            && w.value.eq_ignore_ascii_case("CATALOGS")
+        {
+            self.parser.next_token();
+            return Ok(Statement::ShowCatalogsStatement(ShowCatalogsStatement{}));
+        }
+        // not a DobbyDB specific SHOW statement, rewind and let sqlparser-rs handle it
+        self.parser.prev_token();
+        self.parse_and_handle_statement()
+    }
+
+    fn parse_create(&mut self) -> Result<Statement, DataFusionError> {
+        if self.parser.parse_keyword(Keyword::TABLE) {
+            return self.parse_create_table();
+        }
+        if self
+            .parser
+            .parse_one_of_keywords(&[Keyword::SCHEMA, Keyword::DATABASE])
+            .is_some()
+        {
+            return self.parse_create_schema();
+        }
+        // not a DobbyDB specific CREATE statement, rewind and let sqlparser-rs handle it
+        self.parser.prev_token();
+        self.parse_and_handle_statement()
+    }
+
+    fn parse_create_table(&mut self) -> Result<Statement, DataFusionError> {
+        let if_not_exists = self
+            .parser
+            .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_object_name(false)?;
+        let mut columns = Vec::new();
+        if self.parser.consume_token(&Token::LParen) {
+            columns = self.parser.parse_comma_separated(Parser::parse_column_def)?;
+            self.parser.expect_token(&Token::RParen)?;
+        }
+
+        let mut partition_by = Vec::new();
+        let mut properties = Vec::new();
+        let mut location = None;
+        loop {
+            if self.parser.parse_keyword(Keyword::USING) {
+                let format = self.parser.parse_identifier()?;
+                if !format.value.eq_ignore_ascii_case("iceberg") {
+                    return parser_err!(format!(
+                        "only iceberg tables can be created, found: USING {format}"
+                    ));
+                }
+            } else if self.parser.parse_keywords(&[Keyword::PARTITIONED, Keyword::BY]) {
+                self.parser.expect_token(&Token::LParen)?;
+                let normalizer = IdentNormalizer::new(self.options.enable_ident_normalization);
+                partition_by = self
+                    .parser
+                    .parse_comma_separated(|parser| parse_partition_transform(parser, &normalizer))?;
+                self.parser.expect_token(&Token::RParen)?;
+            } else if self.parser.parse_keyword(Keyword::WITH)
+                || self.parser.parse_keyword(Keyword::TBLPROPERTIES)
+            {
+                self.parser.expect_token(&Token::LParen)?;
+                properties = self.parser.parse_comma_separated(parse_table_property)?;
+                self.parser.expect_token(&Token::RParen)?;
+            } else if self.parser.parse_keyword(Keyword::LOCATION) {
+                location = Some(self.parser.parse_literal_string()?);
+            } else {
+                break;
+            }
+        }
+
+        let query = if self.parser.parse_keyword(Keyword::AS) {
+            Some(self.parser.parse_query()?)
+        } else {
+            None
+        };
+        if columns.is_empty() && query.is_none() {
+            return self.expected("column definitions or AS <query>", self.parser.peek_token());
+        }
+        Ok(Statement::CreateTableStatement(CreateTableStatement {
+            name,
+            if_not_exists,
+            columns,
+            partition_by,
+            properties,
+            location,
+            query,
+        }))
+    }
+
+    fn parse_create_schema(&mut self) -> Result<Statement, DataFusionError> {
+        let if_not_exists = self
+            .parser
+            .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_object_name(false)?;
+        let mut location = None;
+        let mut properties = Vec::new();
+        loop {
+            if self.parser.parse_keyword(Keyword::LOCATION) {
+                location = Some(self.parser.parse_literal_string()?);
+            } else if self.parser.parse_keyword(Keyword::WITH) {
+                self.parser.expect_token(&Token::LParen)?;
+                properties = self.parser.parse_comma_separated(parse_table_property)?;
+                self.parser.expect_token(&Token::RParen)?;
+            } else {
+                break;
             }
         }
-        Ok(Statement::Statement(Box::from(self.parser.parse_create()?)))
+        Ok(Statement::CreateSchemaStatement(CreateSchemaStatement {
+            name,
+            if_not_exists,
+            location,
+            properties,
+        }))
+    }
+
+    fn parse_drop(&mut self) -> Result<Statement, DataFusionError> {
+        if self.parser.parse_keyword(Keyword::TABLE) {
+            let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let name = self.parser.parse_object_name(false)?;
+            let purge = self.parser.parse_keyword(Keyword::PURGE);
+            return Ok(Statement::DropTableStatement(DropTableStatement {
+                name,
+                if_exists,
+                purge,
+            }));
+        }
+        if self
+            .parser
+            .parse_one_of_keywords(&[Keyword::SCHEMA, Keyword::DATABASE])
+            .is_some()
+        {
+            let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let name = self.parser.parse_object_name(false)?;
+            let cascade = matches!(
+                self.parser
+                    .parse_one_of_keywords(&[Keyword::CASCADE, Keyword::RESTRICT]),
+                Some(Keyword::CASCADE)
+            );
+            return Ok(Statement::DropSchemaStatement(DropSchemaStatement {
+                name,
+                if_exists,
+                cascade,
+            }));
+        }
+        // not a DobbyDB specific DROP statement, rewind and let sqlparser-rs handle it
+        self.parser.prev_token();
+        self.parse_and_handle_statement()
+    }
+
+    fn parse_call(&mut self) -> Result<Statement, DataFusionError> {
+        let name = self.parser.parse_object_name(false)?;
+        self.parser.expect_token(&Token::LParen)?;
+        let mut args = Vec::new();
+        if !self.parser.consume_token(&Token::RParen) {
+            args = self.parser.parse_comma_separated(parse_procedure_argument)?;
+            self.parser.expect_token(&Token::RParen)?;
+        }
+        Ok(Statement::CallStatement(CallStatement { name, args }))
+    }
+
+    fn parse_analyze(&mut self) -> Result<Statement, DataFusionError> {
+        if !self.parser.parse_keyword(Keyword::TABLE) {
+            // not a DobbyDB specific ANALYZE statement, rewind and let sqlparser-rs handle it
+            self.parser.prev_token();
+            return self.parse_and_handle_statement();
+        }
+        let name = self.parser.parse_object_name(false)?;
+        let _ = self.parser.parse_keywords(&[Keyword::COMPUTE, Keyword::STATISTICS]);
+        let mut columns = Vec::new();
+        if self.parser.parse_keyword(Keyword::FOR)
+            && !self.parser.parse_keywords(&[Keyword::ALL, Keyword::COLUMNS])
+        {
+            self.parser.expect_keyword_is(Keyword::COLUMNS)?;
+            columns = self
+                .parser
+                .parse_comma_separated(|parser| parser.parse_identifier())?;
+        }
+        Ok(Statement::AnalyzeTableStatement(AnalyzeTableStatement { name, columns }))
     }
 
     /// Helper method to parse a statement and handle errors consistently, especially for recursion limits
@@ -197,4 +644,211 @@ mod tests {
         println!("{:?}", statement);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_show_fallback_to_sqlparser() -> Result<(), DataFusionError> {
+        let mut statements = DobbyDBParser::parse_sql("show tables")?;
+        assert_eq!(statements.len(), 1);
+        assert!(matches!(
+            statements.pop_front().unwrap(),
+            Statement::Statement(_)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_and_drop_table() -> Result<(), DataFusionError> {
+        let sql = "create table if not exists glue.sales.Orders (id bigint not null, ts timestamp, name varchar) \
+            partitioned by (bucket(16, id), days(ts), Name) \
+            with (format-version=2, 'write.parquet.compression-codec'='zstd')";
+        let Statement::CreateTableStatement(create) = DobbyDBParser::parse_sql(sql)?.pop_front().unwrap()
+        else {
+            panic!("expected a create table statement");
+        };
+        assert!(create.if_not_exists);
+        assert_eq!(create.name.to_string(), "glue.sales.Orders");
+        assert_eq!(create.columns.len(), 3);
+        let partition_by = create
+            .partition_by
+            .iter()
+            .map(|p| (p.column.as_str(), p.transform.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            partition_by,
+            vec![("id", "bucket[16]"), ("ts", "day"), ("name", "identity")]
+        );
+        assert_eq!(
+            create.properties,
+            vec![
+                ("format-version".to_string(), "2".to_string()),
+                ("write.parquet.compression-codec".to_string(), "zstd".to_string()),
+            ]
+        );
+        assert!(create.query.is_none());
+
+        let sql = "create table sales.top_orders using iceberg as select * from sales.orders limit 10";
+        let Statement::CreateTableStatement(create) = DobbyDBParser::parse_sql(sql)?.pop_front().unwrap()
+        else {
+            panic!("expected a create table statement");
+        };
+        assert!(create.columns.is_empty());
+        assert!(create.query.is_some());
+        assert!(DobbyDBParser::parse_sql("create table t partitioned by (days(ts))").is_err());
+        assert!(DobbyDBParser::parse_sql("create table t (id int) using delta").is_err());
+
+        let Statement::DropTableStatement(drop) =
+            DobbyDBParser::parse_sql("drop table if exists sales.orders purge")?.pop_front().unwrap()
+        else {
+            panic!("expected a drop table statement");
+        };
+        assert!(drop.if_exists && drop.purge);
+        assert!(matches!(
+            DobbyDBParser::parse_sql("drop view v")?.pop_front().unwrap(),
+            Statement::Statement(_)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_and_drop_schema() -> Result<(), DataFusionError> {
+        let sql = "create database if not exists glue.sales location 's3://bucket/sales' with (owner = 'data')";
+        let Statement::CreateSchemaStatement(create) = DobbyDBParser::parse_sql(sql)?.pop_front().unwrap()
+        else {
+            panic!("expected a create schema statement");
+        };
+        assert!(create.if_not_exists);
+        assert_eq!(create.name.to_string(), "glue.sales");
+        assert_eq!(create.location.as_deref(), Some("s3://bucket/sales"));
+        assert_eq!(create.properties, vec![("owner".to_string(), "data".to_string())]);
+
+        let Statement::DropSchemaStatement(drop) =
+            DobbyDBParser::parse_sql("drop schema glue.sales cascade")?.pop_front().unwrap()
+        else {
+            panic!("expected a drop schema statement");
+        };
+        assert!(!drop.if_exists && drop.cascade);
+        Ok(())
+    }
+
+    #[test]
+    fn test_call() -> Result<(), DataFusionError> {
+        let sql = "call glue.system.expire_snapshots('sales.orders', retain_last => 2, dry_run => true)";
+        let Statement::CallStatement(call) = DobbyDBParser::parse_sql(sql)?.pop_front().unwrap() else {
+            panic!("expected a call statement");
+        };
+        assert_eq!(call.name.to_string(), "glue.system.expire_snapshots");
+        let args = call
+            .args
+            .iter()
+            .map(|arg| (arg.name.as_ref().map(|name| name.value.as_str()), arg.value.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            args,
+            vec![
+                (None, "'sales.orders'".to_string()),
+                (Some("retain_last"), "2".to_string()),
+                (Some("dry_run"), "true".to_string()),
+            ]
+        );
+        assert!(DobbyDBParser::parse_sql("call system.rewrite_manifests").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyze_table() -> Result<(), DataFusionError> {
+        let sql = "analyze table glue.sales.orders compute statistics for columns id, \"Name\"";
+        let Statement::AnalyzeTableStatement(analyze) = DobbyDBParser::parse_sql(sql)?.pop_front().unwrap()
+        else {
+            panic!("expected an analyze table statement");
+        };
+        assert_eq!(analyze.name.to_string(), "glue.sales.orders");
+        let columns = analyze.columns.iter().map(|c| c.value.as_str()).collect::<Vec<_>>();
+        assert_eq!(columns, vec!["id", "Name"]);
+
+        for sql in ["analyze table sales.orders", "analyze table sales.orders for all columns"] {
+            let Statement::AnalyzeTableStatement(analyze) = DobbyDBParser::parse_sql(sql)?.pop_front().unwrap()
+            else {
+                panic!("expected an analyze table statement");
+            };
+            assert!(analyze.columns.is_empty());
+        }
+        assert!(DobbyDBParser::parse_sql("analyze table sales.orders for id").is_err());
+        Ok(())
+    }
+
+    /// Returns the name, alias and version of the single table `sql` selects from.
+    fn versioned_table(sql: &str) -> Result<(String, Option<String>, Option<TableVersion>), DataFusionError> {
+        let Statement::Statement(statement) = DobbyDBParser::parse_sql(sql)?.pop_front().unwrap() else {
+            panic!("expected a sqlparser statement");
+        };
+        let ast::Statement::Query(query) = *statement else {
+            panic!("expected a query");
+        };
+        let ast::SetExpr::Select(select) = *query.body else {
+            panic!("expected a select");
+        };
+        let ast::TableFactor::Table { name, alias, version, .. } = &select.from[0].relation else {
+            panic!("expected a table");
+        };
+        Ok((
+            name.to_string(),
+            alias.as_ref().map(|alias| alias.to_string()),
+            version.as_ref().map(table_version).transpose()?,
+        ))
+    }
+
+    #[test]
+    fn test_time_travel() -> Result<(), DataFusionError> {
+        let cases = [
+            (
+                "select * from db.Orders FOR SYSTEM_VERSION AS OF 123 where id = 1",
+                ("db.Orders", None, TableVersion::SnapshotId(123)),
+            ),
+            (
+                "select * from db.orders for system_time as of '1970-01-01 00:00:01' o",
+                ("db.orders", Some("o"), TableVersion::Timestamp(1000)),
+            ),
+            (
+                "select * from \"Orders\" for version as of 'audit'",
+                ("\"Orders\"", None, TableVersion::Ref("audit".to_string())),
+            ),
+            (
+                "select * from orders for system_time between 1000 and '1970-01-01 00:00:02'",
+                ("orders", None, TableVersion::Incremental(1000, 2000)),
+            ),
+            (
+                "select * from cat.db.\"orders@2026\" for timestamp as of 1000 as o",
+                ("cat.db.\"orders@2026\"", Some("o"), TableVersion::Timestamp(1000)),
+            ),
+            (
+                "select * from orders at(timestamp => '1970-01-01 00:00:01')",
+                ("orders", None, TableVersion::Timestamp(1000)),
+            ),
+        ];
+        for (sql, (name, alias, version)) in cases {
+            assert_eq!(
+                versioned_table(sql)?,
+                (name.to_string(), alias.map(str::to_string), Some(version))
+            );
+        }
+        assert_eq!(versioned_table("select * from orders")?, ("orders".to_string(), None, None));
+        assert!(versioned_table("select * from orders for system_version as of 1.5").is_err());
+        assert!(versioned_table("select * from orders before(statement => 'id')").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ident_normalization_disabled() -> Result<(), DataFusionError> {
+        let mut statements = DobbyDBParserBuilder::new(
+            "create table db.t (Id int) partitioned by (bucket(4, Id))",
+        )
+        .with_ident_normalization(false)
+        .build()?
+        .parse_statements()?;
+        let Statement::CreateTableStatement(create) = statements.pop_front().unwrap() else {
+            panic!("expected a create table statement");
+        };
+        assert_eq!(create.partition_by[0].column, "Id");
+        Ok(())
+    }
+}
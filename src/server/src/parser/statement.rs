@@ -1,14 +1,95 @@
- use sqlparser::ast::Statement as SQLStatement;
+use dobbydb_common_catalog::table_format::iceberg_ddl::PartitionTransform;
+use sqlparser::ast::{ColumnDef, Expr, Ident, ObjectName, Query, Statement as SQLStatement};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Statement {
     /// ANSI SQL AST node (from sqlparser-rs)
     Statement(Box<SQLStatement>),
 
-    ShowCatalogsStatement(ShowCatalogsStatement)
+    ShowCatalogsStatement(ShowCatalogsStatement),
+
+    CreateTableStatement(CreateTableStatement),
+
+    DropTableStatement(DropTableStatement),
+
+    CreateSchemaStatement(CreateSchemaStatement),
+
+    DropSchemaStatement(DropSchemaStatement),
+
+    CallStatement(CallStatement),
+
+    AnalyzeTableStatement(AnalyzeTableStatement),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ShowCatalogsStatement {
 
-}
\ No newline at end of file
+}
+
+/// `CREATE TABLE [IF NOT EXISTS] <name> [(<columns>)] [USING iceberg]
+/// [PARTITIONED BY (<transforms>)] [WITH (<key> = <value>, ...)] [LOCATION '<location>']
+/// [AS <query>]`, creating an Iceberg table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateTableStatement {
+    pub name: ObjectName,
+    pub if_not_exists: bool,
+    /// Empty for `CREATE TABLE ... AS SELECT`, the query gives the columns then
+    pub columns: Vec<ColumnDef>,
+    pub partition_by: Vec<PartitionTransform>,
+    pub properties: Vec<(String, String)>,
+    pub location: Option<String>,
+    pub query: Option<Box<Query>>,
+}
+
+/// `DROP TABLE [IF EXISTS] <name> [PURGE]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DropTableStatement {
+    pub name: ObjectName,
+    pub if_exists: bool,
+    /// Whether the table files are deleted too
+    pub purge: bool,
+}
+
+/// `CREATE {SCHEMA | DATABASE} [IF NOT EXISTS] <name> [LOCATION '<location>']
+/// [WITH (<key> = <value>, ...)]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateSchemaStatement {
+    pub name: ObjectName,
+    pub if_not_exists: bool,
+    pub location: Option<String>,
+    pub properties: Vec<(String, String)>,
+}
+
+/// `DROP {SCHEMA | DATABASE} [IF EXISTS] <name> [CASCADE | RESTRICT]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DropSchemaStatement {
+    pub name: ObjectName,
+    pub if_exists: bool,
+    /// Whether the tables of the schema are dropped too, otherwise only an empty schema is
+    pub cascade: bool,
+}
+
+/// `CALL <name>([<parameter> =>] <value>, ...)`, running a table maintenance procedure such
+/// as `system.expire_snapshots`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallStatement {
+    pub name: ObjectName,
+    pub args: Vec<ProcedureArgument>,
+}
+
+/// An argument of a [`CallStatement`], by name or by position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcedureArgument {
+    pub name: Option<Ident>,
+    pub value: Expr,
+}
+
+/// `ANALYZE TABLE <name> [COMPUTE STATISTICS] [FOR COLUMNS <column>, ... | FOR ALL COLUMNS]`,
+/// computing the number of distinct values of columns of an Iceberg table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyzeTableStatement {
+    pub name: ObjectName,
+    /// Empty for all columns
+    pub columns: Vec<Ident>,
+}
@@ -0,0 +1,738 @@
+use crate::parser::parser::{table_version, DobbyDBParserBuilder};
+use crate::parser::statement::{
+    AnalyzeTableStatement, CallStatement, CreateSchemaStatement, CreateTableStatement, DropSchemaStatement,
+    DropTableStatement, Statement,
+};
+use crate::session::row_level::{execute_row_level, RowLevelStatement};
+use arrow_array::{RecordBatch, RecordBatchOptions, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use datafusion::catalog::{
+    CatalogProvider, CatalogProviderList, MemoryCatalogProvider, MemoryCatalogProviderList, MemorySchemaProvider,
+    SchemaProvider,
+};
+use datafusion::common::{
+    internal_err, not_impl_err, plan_err, DFSchema, ResolvedTableReference, ScalarValue, TableReference,
+};
+use datafusion::datasource::provider_as_source;
+use datafusion::error::DataFusionError;
+use datafusion::execution::{SendableRecordBatchStream, SessionState, SessionStateBuilder};
+use datafusion::logical_expr::dml::InsertOp;
+use datafusion::logical_expr::{
+    cast, ColumnarValue, DdlStatement, Expr, LogicalPlan, LogicalPlanBuilder,
+};
+use datafusion::physical_plan::memory::MemoryStream;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use datafusion::sql::parser::Statement as DFStatement;
+use datafusion::sql::planner::{object_name_to_table_reference, IdentNormalizer};
+use dobbydb_common_catalog::catalog::DobbyCatalogManager;
+use dobbydb_common_catalog::glue_catalog::GlueCatalog;
+use dobbydb_common_catalog::glue_schema::GlueDatabase;
+use dobbydb_common_catalog::glue_table::GlueTable;
+use dobbydb_common_catalog::table_format::iceberg_analyze::analyze_result_schema;
+use dobbydb_common_catalog::table_format::iceberg_procedures::IcebergProcedure;
+use dobbydb_common_catalog::table_format::table::table_as_of;
+use sqlparser::ast::helpers::stmt_create_table::CreateTableBuilder;
+use sqlparser::ast::{
+    ColumnDef, HiveFormat, Ident, ObjectName, Statement as SQLStatement, TableAlias, TableFactor, TableVersion,
+    Visit, Visitor, VisitorMut, VisitMut,
+};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+/// Catalog the tables read as of a version are planned from, see
+/// [`DobbyDBSession::statement_to_plan`].
+const TABLE_VERSIONS_CATALOG: &str = "dobbydb_table_versions";
+const TABLE_VERSIONS_SCHEMA: &str = "versions";
+
+/// A planned query, ready to be executed.
+#[derive(Debug, Clone)]
+pub enum QueryPlan {
+    /// Plan produced by DataFusion for standard SQL statements
+    Logical(Box<LogicalPlan>),
+    /// Result computed eagerly for DobbyDB specific statements
+    Batches(SchemaRef, Vec<RecordBatch>),
+    /// DobbyDB statement changing tables or catalogs, only run once the plan is executed
+    Deferred(SchemaRef, Box<Statement>),
+}
+
+impl QueryPlan {
+    /// Arrow schema of the batches produced by this plan.
+    pub fn schema(&self) -> SchemaRef {
+        match self {
+            QueryPlan::Logical(plan) => Arc::new(plan.schema().as_arrow().clone()),
+            QueryPlan::Batches(schema, _) | QueryPlan::Deferred(schema, _) => schema.clone(),
+        }
+    }
+}
+
+/// Wraps a DataFusion [`SessionContext`] with all DobbyDB catalogs registered.
+#[derive(Clone)]
+pub struct DobbyDBSession {
+    ctx: SessionContext,
+    catalog_manager: Arc<DobbyCatalogManager>,
+}
+
+impl DobbyDBSession {
+    pub fn new(catalog_manager: Arc<DobbyCatalogManager>) -> Self {
+        let config = SessionConfig::new().with_information_schema(true);
+        let ctx = SessionContext::new_with_config(config);
+        ctx.register_catalog_list(catalog_manager.clone());
+        DobbyDBSession {
+            ctx,
+            catalog_manager,
+        }
+    }
+
+    pub fn catalog_manager(&self) -> &Arc<DobbyCatalogManager> {
+        &self.catalog_manager
+    }
+
+    /// Parses the sql with [`DobbyDBParser`] and plans it.
+    ///
+    /// Only a single statement is accepted.
+    pub async fn plan_sql(&self, sql: &str) -> Result<QueryPlan, DataFusionError> {
+        let enable_ident_normalization = self
+            .ctx
+            .state()
+            .config_options()
+            .sql_parser
+            .enable_ident_normalization;
+        let mut statements = DobbyDBParserBuilder::new(sql)
+            .with_ident_normalization(enable_ident_normalization)
+            .build()?
+            .parse_statements()?;
+        if statements.len() != 1 {
+            return Err(DataFusionError::Plan(format!(
+                "expected exactly one statement, found {}",
+                statements.len()
+            )));
+        }
+        let statement = statements.pop_front().unwrap();
+        self.plan_statement(statement).await
+    }
+
+    /// Plans `statement` without running it. Statements changing tables or catalogs are
+    /// deferred to [`DobbyDBSession::execute`].
+    pub async fn plan_statement(&self, statement: Statement) -> Result<QueryPlan, DataFusionError> {
+        self.refresh_catalogs(&statement).await?;
+        let schema = match &statement {
+            Statement::Statement(stmt) => match RowLevelStatement::try_from_statement(stmt)? {
+                Some(_) if reads_table_versions(stmt) => {
+                    return not_impl_err!("time travel is not supported in DELETE, UPDATE and MERGE");
+                }
+                // DataFusion plans DELETE and UPDATE but cannot run them
+                Some(_) => count_schema(),
+                None => {
+                    let plan = self.statement_to_plan(*stmt.clone()).await?;
+                    return Ok(QueryPlan::Logical(Box::new(plan)));
+                }
+            },
+            Statement::ShowCatalogsStatement(_) => return self.show_catalogs(),
+            Statement::CreateTableStatement(create) if create.query.is_some() => count_schema(),
+            Statement::CreateTableStatement(_)
+            | Statement::DropTableStatement(_)
+            | Statement::CreateSchemaStatement(_)
+            | Statement::DropSchemaStatement(_) => Arc::new(Schema::empty()),
+            Statement::CallStatement(call) => {
+                let (_, procedure, _) = self.resolve_procedure(call)?;
+                Arc::new(procedure.schema())
+            }
+            Statement::AnalyzeTableStatement(_) => analyze_result_schema(),
+        };
+        Ok(QueryPlan::Deferred(schema, Box::new(statement)))
+    }
+
+    /// Refreshes the cached schema and table names the tables of `statement` are resolved
+    /// with, the sync catalog methods DataFusion plans with don't load them.
+    async fn refresh_catalogs(&self, statement: &Statement) -> Result<(), DataFusionError> {
+        let state = self.ctx.state();
+        let options = state.config_options();
+        let table_name = |name: &ObjectName| {
+            object_name_to_table_reference(name.clone(), options.sql_parser.enable_ident_normalization)
+        };
+        let table_refs = match statement {
+            Statement::Statement(stmt) => {
+                state.resolve_table_references(&DFStatement::Statement(stmt.clone()))?
+            }
+            Statement::CreateTableStatement(create) => {
+                let mut table_refs = vec![table_name(&create.name)?];
+                if let Some(query) = &create.query {
+                    let query = DFStatement::Statement(Box::new(SQLStatement::Query(query.clone())));
+                    table_refs.extend(state.resolve_table_references(&query)?);
+                }
+                table_refs
+            }
+            Statement::DropTableStatement(drop) => vec![table_name(&drop.name)?],
+            Statement::AnalyzeTableStatement(analyze) => vec![table_name(&analyze.name)?],
+            Statement::CallStatement(call) => vec![self.resolve_procedure(call)?.0],
+            Statement::ShowCatalogsStatement(_)
+            | Statement::CreateSchemaStatement(_)
+            | Statement::DropSchemaStatement(_) => vec![],
+        };
+        let table_refs = table_refs
+            .into_iter()
+            .map(|table_ref| {
+                table_ref.resolve(&options.catalog.default_catalog, &options.catalog.default_schema)
+            })
+            .collect::<Vec<_>>();
+        self.catalog_manager.refresh_table_references(&table_refs).await;
+        Ok(())
+    }
+
+    /// Plans a standard SQL statement with DataFusion, which ignores table versions. Tables
+    /// read as of a version are swapped for the versioned tables, registered in a catalog
+    /// of their own while the statement is planned.
+    async fn statement_to_plan(&self, mut statement: SQLStatement) -> Result<LogicalPlan, DataFusionError> {
+        let state = self.ctx.state();
+        let mut versioned_tables = VersionedTables::default();
+        let _ = VisitMut::visit(&mut statement, &mut versioned_tables);
+        if versioned_tables.tables.is_empty() {
+            return state.statement_to_plan(DFStatement::Statement(Box::new(statement))).await;
+        }
+
+        let enable_ident_normalization = state.config_options().sql_parser.enable_ident_normalization;
+        let versions = MemorySchemaProvider::new();
+        for (name, versioned_name, version) in versioned_tables.tables {
+            let table_ref = object_name_to_table_reference(name, enable_ident_normalization)?;
+            let table = self.ctx.table_provider(table_ref).await?;
+            versions.register_table(versioned_name, table_as_of(&table, &table_version(&version)?).await?)?;
+        }
+        let versions_catalog = MemoryCatalogProvider::new();
+        versions_catalog.register_schema(TABLE_VERSIONS_SCHEMA, Arc::new(versions))?;
+        let catalogs = MemoryCatalogProviderList::new();
+        for catalog_name in self.catalog_manager.catalog_names() {
+            if let Some(catalog) = self.catalog_manager.catalog(&catalog_name) {
+                catalogs.register_catalog(catalog_name, catalog);
+            }
+        }
+        catalogs.register_catalog(TABLE_VERSIONS_CATALOG.to_string(), Arc::new(versions_catalog));
+        SessionStateBuilder::new_from_existing(state)
+            .with_catalog_list(Arc::new(catalogs))
+            .build()
+            .statement_to_plan(DFStatement::Statement(Box::new(statement)))
+            .await
+    }
+
+    /// Executes a plan returned by [`DobbyDBSession::plan_sql`].
+    pub async fn execute(&self, plan: QueryPlan) -> Result<SendableRecordBatchStream, DataFusionError> {
+        match plan {
+            QueryPlan::Logical(plan) => {
+                let df = self.ctx.execute_logical_plan(*plan).await?;
+                df.execute_stream().await
+            }
+            QueryPlan::Batches(schema, batches) => {
+                Ok(Box::pin(MemoryStream::try_new(batches, schema, None)?))
+            }
+            QueryPlan::Deferred(schema, statement) => {
+                let batches = self.run_statement(*statement).await?;
+                Ok(Box::pin(MemoryStream::try_new(batches, schema, None)?))
+            }
+        }
+    }
+
+    /// Runs a statement deferred by [`DobbyDBSession::plan_statement`].
+    async fn run_statement(&self, statement: Statement) -> Result<Vec<RecordBatch>, DataFusionError> {
+        match statement {
+            Statement::Statement(stmt) => match RowLevelStatement::try_from_statement(&stmt)? {
+                Some(row_level) => execute_row_level(&self.ctx, row_level).await,
+                None => internal_err!("statement {} is planned by DataFusion", stmt),
+            },
+            Statement::ShowCatalogsStatement(_) => internal_err!("SHOW CATALOGS is computed eagerly"),
+            Statement::CreateTableStatement(create) => self.create_table(create).await,
+            Statement::DropTableStatement(drop) => self.drop_table(drop).await,
+            Statement::CreateSchemaStatement(create) => self.create_schema(create).await,
+            Statement::DropSchemaStatement(drop) => self.drop_schema(drop).await,
+            Statement::CallStatement(call) => self.call_procedure(call).await,
+            Statement::AnalyzeTableStatement(analyze) => self.analyze_table(analyze).await,
+        }
+    }
+
+    fn show_catalogs(&self) -> Result<QueryPlan, DataFusionError> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("catalog_name", DataType::Utf8, false),
+            Field::new("catalog_type", DataType::Utf8, false),
+        ]));
+        let mut catalog_names: Vec<String> = Vec::new();
+        let mut catalog_types: Vec<String> = Vec::new();
+        for (name, catalog_type) in self.catalog_manager.catalog_definitions() {
+            catalog_names.push(name);
+            catalog_types.push(catalog_type.to_string());
+        }
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(catalog_names)),
+                Arc::new(StringArray::from(catalog_types)),
+            ],
+        )?;
+        Ok(QueryPlan::Batches(schema, vec![batch]))
+    }
+
+    /// Creates an Iceberg table in Glue. `CREATE TABLE ... AS SELECT` runs the query into
+    /// the new table right away and drops the table again if the query fails.
+    async fn create_table(&self, create: CreateTableStatement) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let (table_ref, schema) = self.resolve_schema(&create.name)?;
+        let database = glue_database(&schema)?;
+        let input = match create.query {
+            Some(_) if !create.columns.is_empty() => {
+                return plan_err!("CREATE TABLE ... AS SELECT takes no column definitions");
+            }
+            Some(query) => Some(self.statement_to_plan(SQLStatement::Query(query)).await?),
+            None => None,
+        };
+        let table_schema = match &input {
+            Some(input) => input.schema().as_arrow().clone(),
+            None => self.columns_schema(create.name, create.columns).await?,
+        };
+
+        let created = database
+            .create_iceberg_table(
+                &table_ref.table,
+                &table_schema,
+                &create.partition_by,
+                create.properties.into_iter().collect(),
+                create.location,
+                create.if_not_exists,
+            )
+            .await?;
+        let Some(input) = input.filter(|_| created) else {
+            return Ok(vec![]);
+        };
+        match self.insert_into(&table_ref, input).await {
+            Ok(batches) => Ok(batches),
+            Err(e) => {
+                if let Err(drop_error) = database.drop_table(&table_ref.table, true).await {
+                    println!("failed to drop table {} after a failed CTAS: {}", table_ref, drop_error);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn drop_table(&self, drop: DropTableStatement) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let (table_ref, schema) = self.resolve_schema(&drop.name)?;
+        let database = glue_database(&schema)?;
+        if !database.drop_table(&table_ref.table, drop.purge).await? && !drop.if_exists {
+            return plan_err!("table {} not found", table_ref);
+        }
+        Ok(vec![])
+    }
+
+    /// Creates a database in a Glue catalog.
+    async fn create_schema(&self, create: CreateSchemaStatement) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let (catalog, schema_name) = self.resolve_catalog(&create.name)?;
+        glue_catalog(&catalog)?
+            .create_database(
+                &schema_name,
+                create.location,
+                create.properties.into_iter().collect(),
+                create.if_not_exists,
+            )
+            .await?;
+        Ok(vec![])
+    }
+
+    async fn drop_schema(&self, drop: DropSchemaStatement) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let (catalog, schema_name) = self.resolve_catalog(&drop.name)?;
+        if !glue_catalog(&catalog)?
+            .drop_database(&schema_name, drop.cascade)
+            .await?
+            && !drop.if_exists
+        {
+            return plan_err!("schema {} not found", drop.name);
+        }
+        Ok(vec![])
+    }
+
+    /// Runs a maintenance procedure `[catalog.]system.<procedure>(table => '<schema>.<table>',
+    /// ...)` on an Iceberg table in a Glue catalog.
+    async fn call_procedure(&self, call: CallStatement) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let (table_ref, procedure, dry_run) = self.resolve_procedure(&call)?;
+        let provider = self.ctx.table_provider(table_ref).await?;
+        let Some(glue_table) = provider.as_any().downcast_ref::<GlueTable>() else {
+            return not_impl_err!("procedures are only supported for tables in glue catalogs");
+        };
+        let batch = glue_table.call_procedure(&self.ctx.state(), &procedure, dry_run).await?;
+        Ok(vec![batch])
+    }
+
+    /// Resolves the procedure of `call` and the table it runs on, returning whether it is
+    /// a dry run too.
+    fn resolve_procedure(
+        &self,
+        call: &CallStatement,
+    ) -> Result<(TableReference, IcebergProcedure, bool), DataFusionError> {
+        let state = self.ctx.state();
+        let options = state.config_options();
+        let normalizer = IdentNormalizer::new(options.sql_parser.enable_ident_normalization);
+        let parts = call
+            .name
+            .0
+            .iter()
+            .map(|part| part.as_ident().cloned().map(|ident| normalizer.normalize(ident)))
+            .collect::<Option<Vec<String>>>();
+        let (catalog_name, procedure_name) = match parts.as_deref() {
+            Some([namespace, procedure]) if namespace == "system" => {
+                (options.catalog.default_catalog.clone(), procedure.clone())
+            }
+            Some([catalog, namespace, procedure]) if namespace == "system" => {
+                (catalog.clone(), procedure.clone())
+            }
+            _ => return plan_err!("invalid procedure name {}", call.name),
+        };
+        let Some(parameters) = IcebergProcedure::parameters(&procedure_name) else {
+            return plan_err!("procedure {} not found", call.name);
+        };
+
+        let mut args: HashMap<String, ScalarValue> = HashMap::new();
+        for (position, arg) in call.args.iter().enumerate() {
+            let name = match &arg.name {
+                Some(name) => normalizer.normalize(name.clone()),
+                None => match parameters.get(position) {
+                    Some(name) => name.to_string(),
+                    None => return plan_err!("too many arguments for procedure {}", call.name),
+                },
+            };
+            if !parameters.contains(&name.as_str()) {
+                return plan_err!("unknown parameter {} of procedure {}", name, call.name);
+            }
+            let value = evaluate_constant(&state, &arg.value.to_string())?;
+            if args.insert(name.clone(), value).is_some() {
+                return plan_err!("parameter {} of procedure {} given twice", name, call.name);
+            }
+        }
+        let table_name = match args.remove("table") {
+            Some(ScalarValue::Utf8(Some(table_name))) => table_name,
+            _ => return plan_err!("procedure {} requires the table name as a string", call.name),
+        };
+        let (procedure, dry_run) = IcebergProcedure::try_new(&procedure_name, args)?;
+
+        let table_ref = TableReference::parse_str(&table_name)
+            .resolve(&catalog_name, &options.catalog.default_schema);
+        if table_ref.catalog.as_ref() != catalog_name {
+            return plan_err!("table {} is not in catalog {}", table_name, catalog_name);
+        }
+        let table_ref = TableReference::full(table_ref.catalog, table_ref.schema, table_ref.table);
+        Ok((table_ref, procedure, dry_run))
+    }
+
+    /// Computes the number of distinct values of columns of an Iceberg table in a Glue
+    /// catalog, stored in a Puffin statistics file of its current snapshot.
+    async fn analyze_table(&self, analyze: AnalyzeTableStatement) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let state = self.ctx.state();
+        let normalizer = IdentNormalizer::new(state.config_options().sql_parser.enable_ident_normalization);
+        let columns = analyze
+            .columns
+            .into_iter()
+            .map(|column| normalizer.normalize(column))
+            .collect::<Vec<String>>();
+        let (table_ref, _) = self.resolve_schema(&analyze.name)?;
+        let provider = self
+            .ctx
+            .table_provider(TableReference::full(
+                table_ref.catalog.clone(),
+                table_ref.schema.clone(),
+                table_ref.table.clone(),
+            ))
+            .await?;
+        let Some(glue_table) = provider.as_any().downcast_ref::<GlueTable>() else {
+            return not_impl_err!("ANALYZE TABLE is only supported for tables in glue catalogs");
+        };
+        let batch = glue_table.analyze(&state, &columns).await?;
+        Ok(vec![batch])
+    }
+
+    /// Resolves a `[catalog.]schema` name against the default catalog, returning the
+    /// catalog provider and the schema name.
+    fn resolve_catalog(
+        &self,
+        name: &ObjectName,
+    ) -> Result<(Arc<dyn CatalogProvider>, String), DataFusionError> {
+        let state = self.ctx.state();
+        let options = state.config_options();
+        let normalizer = IdentNormalizer::new(options.sql_parser.enable_ident_normalization);
+        let parts = name
+            .0
+            .iter()
+            .map(|part| part.as_ident().cloned().map(|ident| normalizer.normalize(ident)))
+            .collect::<Option<Vec<String>>>();
+        let (catalog_name, schema_name) = match parts.as_deref() {
+            Some([schema]) => (options.catalog.default_catalog.clone(), schema.clone()),
+            Some([catalog, schema]) => (catalog.clone(), schema.clone()),
+            _ => return plan_err!("invalid schema name {}", name),
+        };
+        match self.ctx.catalog(&catalog_name) {
+            Some(catalog) => Ok((catalog, schema_name)),
+            None => plan_err!("catalog {} not found", catalog_name),
+        }
+    }
+
+    /// Resolves `name` against the default catalog and schema, returning the schema
+    /// provider holding the table.
+    fn resolve_schema(
+        &self,
+        name: &ObjectName,
+    ) -> Result<(ResolvedTableReference, Arc<dyn SchemaProvider>), DataFusionError> {
+        let state = self.ctx.state();
+        let options = state.config_options();
+        let table_ref = object_name_to_table_reference(
+            name.clone(),
+            options.sql_parser.enable_ident_normalization,
+        )?
+        .resolve(&options.catalog.default_catalog, &options.catalog.default_schema);
+        let schema = self
+            .ctx
+            .catalog(&table_ref.catalog)
+            .and_then(|catalog| catalog.schema(&table_ref.schema));
+        match schema {
+            Some(schema) => Ok((table_ref, schema)),
+            None => plan_err!("schema {}.{} not found", table_ref.catalog, table_ref.schema),
+        }
+    }
+
+    /// Arrow schema of column definitions, DataFusion maps their SQL types when planning
+    /// a plain `CREATE TABLE` with them.
+    async fn columns_schema(
+        &self,
+        name: ObjectName,
+        columns: Vec<ColumnDef>,
+    ) -> Result<Schema, DataFusionError> {
+        let statement = CreateTableBuilder::new(name)
+            .columns(columns)
+            .hive_formats(Some(HiveFormat::default()))
+            .build();
+        let plan = self
+            .ctx
+            .state()
+            .statement_to_plan(DFStatement::Statement(Box::new(statement)))
+            .await?;
+        match plan {
+            LogicalPlan::Ddl(DdlStatement::CreateMemoryTable(create)) => {
+                Ok(create.input.schema().as_arrow().clone())
+            }
+            plan => internal_err!("unexpected plan of column definitions: {}", plan.display()),
+        }
+    }
+
+    /// Inserts the result of `input` into the table, casting its columns by position to
+    /// the table types, and returns the inserted row count.
+    async fn insert_into(
+        &self,
+        table_ref: &ResolvedTableReference,
+        input: LogicalPlan,
+    ) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let table_ref = TableReference::full(
+            table_ref.catalog.clone(),
+            table_ref.schema.clone(),
+            table_ref.table.clone(),
+        );
+        let table = self.ctx.table_provider(table_ref.clone()).await?;
+        let exprs = input
+            .schema()
+            .columns()
+            .into_iter()
+            .zip(table.schema().fields())
+            .map(|(column, field)| {
+                cast(Expr::Column(column), field.data_type().clone()).alias(field.name())
+            })
+            .collect::<Vec<Expr>>();
+        let input = LogicalPlanBuilder::from(input).project(exprs)?.build()?;
+        let plan = LogicalPlanBuilder::insert_into(
+            input,
+            table_ref,
+            provider_as_source(table),
+            InsertOp::Append,
+        )?
+        .build()?;
+        self.ctx.execute_logical_plan(plan).await?.collect().await
+    }
+}
+
+/// Collects the tables read as of a version, renaming each to a table of
+/// [`TABLE_VERSIONS_CATALOG`] aliased with its own name.
+#[derive(Default)]
+struct VersionedTables {
+    /// Name of each table, its name in [`TABLE_VERSIONS_CATALOG`] and its version
+    tables: Vec<(ObjectName, String, TableVersion)>,
+}
+
+impl VisitorMut for VersionedTables {
+    type Break = ();
+
+    fn pre_visit_table_factor(&mut self, table_factor: &mut TableFactor) -> ControlFlow<Self::Break> {
+        if let TableFactor::Table { name, alias, version, .. } = table_factor
+            && let Some(version) = version.take()
+        {
+            let versioned_name = format!("table_{}", self.tables.len());
+            let versioned = ObjectName::from(vec![
+                Ident::with_quote('"', TABLE_VERSIONS_CATALOG),
+                Ident::with_quote('"', TABLE_VERSIONS_SCHEMA),
+                Ident::with_quote('"', &versioned_name),
+            ]);
+            let name = std::mem::replace(name, versioned);
+            if alias.is_none() {
+                *alias = name.0.last().and_then(|part| part.as_ident()).map(|ident| TableAlias {
+                    name: ident.clone(),
+                    columns: vec![],
+                });
+            }
+            self.tables.push((name, versioned_name, version));
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Whether `statement` reads a table as of a version.
+fn reads_table_versions(statement: &SQLStatement) -> bool {
+    struct FindVersion;
+    impl Visitor for FindVersion {
+        type Break = ();
+
+        fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<Self::Break> {
+            match table_factor {
+                TableFactor::Table { version: Some(_), .. } => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+    }
+    Visit::visit(statement, &mut FindVersion).is_break()
+}
+
+/// Schema of the row count DML statements return, like DataFusion's `INSERT INTO`.
+pub(crate) fn count_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![Field::new("count", DataType::UInt64, false)]))
+}
+
+/// Evaluates a constant SQL expression such as a procedure argument, e.g.
+/// `TIMESTAMP '2024-01-01 00:00:00'` or `array(1, 2)`.
+fn evaluate_constant(state: &SessionState, sql: &str) -> Result<ScalarValue, DataFusionError> {
+    let schema = DFSchema::empty();
+    let expr = state.create_logical_expr(sql, &schema)?;
+    let physical_expr = state.create_physical_expr(expr, &schema)?;
+    let batch = RecordBatch::try_new_with_options(
+        Arc::new(Schema::empty()),
+        vec![],
+        &RecordBatchOptions::new().with_row_count(Some(1)),
+    )?;
+    match physical_expr.evaluate(&batch)? {
+        ColumnarValue::Scalar(value) => Ok(value),
+        ColumnarValue::Array(array) => ScalarValue::try_from_array(&array, 0),
+    }
+}
+
+/// DDL is supported for Glue catalogs only.
+fn glue_catalog(catalog: &Arc<dyn CatalogProvider>) -> Result<&GlueCatalog, DataFusionError> {
+    match catalog.as_any().downcast_ref::<GlueCatalog>() {
+        Some(catalog) => Ok(catalog),
+        None => not_impl_err!("creating and dropping schemas is only supported in glue catalogs"),
+    }
+}
+
+fn glue_database(schema: &Arc<dyn SchemaProvider>) -> Result<&GlueDatabase, DataFusionError> {
+    match schema.as_any().downcast_ref::<GlueDatabase>() {
+        Some(database) => Ok(database),
+        None => not_impl_err!("creating and dropping tables is only supported in glue catalogs"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, Int64Array};
+    use datafusion::datasource::sink::DataSink;
+    use datafusion::datasource::MemTable;
+    use datafusion::execution::TaskContext;
+    use dobbydb_common_catalog::catalog::CatalogType;
+    use dobbydb_common_catalog::table_format::iceberg_commit::testing::{empty_table, TestDir};
+    use dobbydb_common_catalog::table_format::iceberg_commit::IcebergTableCommitter;
+    use dobbydb_common_catalog::table_format::iceberg_sink::IcebergDataSink;
+    use dobbydb_common_catalog::table_format::iceberg_table::IcebergTable;
+    use futures::TryStreamExt;
+
+    #[tokio::test]
+    async fn test_statements_with_side_effects_are_deferred() -> Result<(), DataFusionError> {
+        let session = DobbyDBSession::new(Arc::new(DobbyCatalogManager::new()));
+        let delete = session.plan_sql("DELETE FROM t WHERE id = 1").await?;
+        assert_eq!(delete.schema(), count_schema());
+        let plan = session.plan_sql("DROP SCHEMA IF EXISTS missing.db").await?;
+        assert!(matches!(plan, QueryPlan::Deferred(..)));
+
+        // the statement only runs, and fails on the missing catalog, once executed
+        let error = session.execute(plan).await.err().unwrap();
+        assert!(error.to_string().contains("catalog missing not found"), "{error}");
+        Ok(())
+    }
+
+    /// Creates an Iceberg table with the ids 1, 2 in its first snapshot and 3 in its second
+    /// one, returning it with the id of the first snapshot.
+    async fn create_versioned_table(dir: &TestDir) -> Result<(IcebergTable, i64), DataFusionError> {
+        let (committer, _) = empty_table(dir, HashMap::new()).await?;
+
+        let mut first_snapshot_id = None;
+        for ids in [vec![1, 2], vec![3]] {
+            let sink = IcebergDataSink::try_new(committer.load_table().await?, committer.clone(), false)?;
+            let schema = sink.schema().clone();
+            let regions = StringArray::from(vec!["eu"; ids.len()]);
+            let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids)), Arc::new(regions)])?;
+            let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+            sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+            first_snapshot_id = first_snapshot_id.or(committer.load_table().await?.metadata().current_snapshot_id());
+        }
+        let table = IcebergTable::try_new(committer.load_table().await?).await?;
+        Ok((table, first_snapshot_id.unwrap()))
+    }
+
+    async fn query_ids(session: &DobbyDBSession, sql: &str) -> Result<Vec<i64>, DataFusionError> {
+        let plan = session.plan_sql(sql).await?;
+        let batches: Vec<RecordBatch> = session.execute(plan).await?.try_collect().await?;
+        let mut ids = batches
+            .iter()
+            .flat_map(|batch| {
+                let ids = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                (0..ids.len()).map(|i| ids.value(i)).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        Ok(ids)
+    }
+
+    #[tokio::test]
+    async fn test_time_travel() -> Result<(), DataFusionError> {
+        let table_dir = TestDir::new("time-travel");
+        let (table, first_snapshot_id) = create_versioned_table(&table_dir).await?;
+        let schema = MemorySchemaProvider::new();
+        schema.register_table("orders@eu".to_string(), Arc::new(table))?;
+        let plain = Arc::new(MemTable::try_new(Arc::new(Schema::empty()), vec![vec![]])?);
+        schema.register_table("plain".to_string(), plain)?;
+        let sales = MemoryCatalogProvider::new();
+        sales.register_schema("us", Arc::new(schema))?;
+        let mut catalog_manager = DobbyCatalogManager::new();
+        catalog_manager.add_catalog("sales", CatalogType::Glue, Arc::new(sales));
+        let session = DobbyDBSession::new(Arc::new(catalog_manager));
+
+        assert_eq!(query_ids(&session, "SELECT id FROM sales.us.\"orders@eu\"").await?, vec![1, 2, 3]);
+        let sql = format!("SELECT id FROM sales.us.\"orders@eu\" FOR SYSTEM_VERSION AS OF {first_snapshot_id}");
+        assert_eq!(query_ids(&session, &sql).await?, vec![1, 2]);
+        // the versioned table keeps its name as alias, next to the current table
+        let sql = format!(
+            "SELECT id FROM sales.us.\"orders@eu\" WHERE id NOT IN \
+            (SELECT \"orders@eu\".id FROM sales.us.\"orders@eu\" FOR VERSION AS OF {first_snapshot_id})"
+        );
+        assert_eq!(query_ids(&session, &sql).await?, vec![3]);
+
+        let error = session
+            .plan_sql("SELECT * FROM sales.us.plain FOR SYSTEM_VERSION AS OF 1")
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("time travel is only supported"), "{error}");
+        let sql = format!(
+            "DELETE FROM sales.us.\"orders@eu\" WHERE id IN \
+            (SELECT id FROM sales.us.\"orders@eu\" FOR VERSION AS OF {first_snapshot_id})"
+        );
+        let error = session.plan_sql(&sql).await.err().unwrap();
+        assert!(error.to_string().contains("time travel is not supported"), "{error}");
+        Ok(())
+    }
+}
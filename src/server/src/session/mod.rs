@@ -0,0 +1,2 @@
+pub mod dobbydb_session;
+pub mod row_level;
@@ -0,0 +1,640 @@
+use crate::session::dobbydb_session::count_schema;
+use arrow_array::cast::AsArray;
+use arrow_array::{RecordBatch, UInt64Array};
+use arrow_schema::Schema;
+use datafusion::common::{internal_err, not_impl_err, plan_err, Column, DFSchema, ScalarValue, TableReference};
+use datafusion::datasource::provider_as_source;
+use datafusion::error::DataFusionError;
+use datafusion::execution::SessionState;
+use datafusion::logical_expr::conditional_expressions::CaseBuilder;
+use datafusion::logical_expr::{cast, col, lit, when, Expr, JoinType, LogicalPlan, LogicalPlanBuilder};
+use datafusion::prelude::SessionContext;
+use datafusion::sql::planner::{object_name_to_table_reference, IdentNormalizer};
+use dobbydb_common_catalog::glue_table::GlueTable;
+use dobbydb_common_catalog::table_format::iceberg_commit::IcebergTableCommitter;
+use dobbydb_common_catalog::table_format::iceberg_dml::{
+    IcebergRowLevelWriter, IcebergRowPositionTable, RowLevelCommand, RowLevelMode, RowOperation,
+    FILE_PATH_COLUMN, OPERATION_COLUMN, POSITION_COLUMN,
+};
+use iceberg::table::Table;
+use sqlparser::ast::{
+    Assignment, AssignmentTarget, Expr as SQLExpr, FromTable, Ident, MergeAction, MergeClause,
+    MergeClauseKind, MergeInsertKind, ObjectName, Statement as SQLStatement, TableFactor,
+    TableWithJoins, UpdateTableFromKind,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Marks the rows of the target table in the joined rows.
+const TARGET_COLUMN: &str = "__target";
+/// Marks the rows of the source in the joined rows.
+const SOURCE_COLUMN: &str = "__source";
+/// Index of the first clause matching a joined row.
+const CLAUSE_COLUMN: &str = "__clause";
+
+/// A `DELETE`, `UPDATE` or `MERGE INTO` statement, as a merge of a source into the target
+/// table. `DELETE` and `UPDATE` have a single `WHEN MATCHED` clause, and a source only with
+/// `USING` or `FROM`.
+#[derive(Debug, Clone)]
+pub struct RowLevelStatement {
+    pub command: RowLevelCommand,
+    pub target: ObjectName,
+    pub target_alias: Option<Ident>,
+    /// SQL of the tables joined with the target
+    pub source: Option<String>,
+    /// Join condition of the source and the target
+    pub on: Option<SQLExpr>,
+    pub clauses: Vec<MergeClause>,
+}
+
+impl RowLevelStatement {
+    /// Returns `None` for statements other than `DELETE`, `UPDATE` and `MERGE`.
+    pub fn try_from_statement(statement: &SQLStatement) -> Result<Option<Self>, DataFusionError> {
+        match statement {
+            SQLStatement::Delete(delete) => {
+                if !delete.tables.is_empty()
+                    || delete.returning.is_some()
+                    || !delete.order_by.is_empty()
+                    || delete.limit.is_some()
+                {
+                    return not_impl_err!("DELETE only supports a single table and a WHERE clause");
+                }
+                let (FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from)) = &delete.from;
+                let (target, target_alias) = single_table(from)?;
+                Ok(Some(Self::matched(
+                    RowLevelCommand::Delete,
+                    target,
+                    target_alias,
+                    delete.using.as_deref(),
+                    delete.selection.clone(),
+                    MergeAction::Delete,
+                )))
+            }
+            SQLStatement::Update {
+                table,
+                assignments,
+                from,
+                selection,
+                returning,
+                or,
+            } => {
+                if returning.is_some() || or.is_some() {
+                    return not_impl_err!("UPDATE does not support RETURNING and OR clauses");
+                }
+                let (target, target_alias) = single_table(std::slice::from_ref(table))?;
+                let from = from.as_ref().map(|from| match from {
+                    UpdateTableFromKind::BeforeSet(from) | UpdateTableFromKind::AfterSet(from) => {
+                        from.as_slice()
+                    }
+                });
+                Ok(Some(Self::matched(
+                    RowLevelCommand::Update,
+                    target,
+                    target_alias,
+                    from,
+                    selection.clone(),
+                    MergeAction::Update {
+                        assignments: assignments.clone(),
+                    },
+                )))
+            }
+            SQLStatement::Merge {
+                table,
+                source,
+                on,
+                clauses,
+                ..
+            } => {
+                let (target, target_alias) = table_name(table)?;
+                if clauses.is_empty() {
+                    return plan_err!("MERGE needs at least one WHEN clause");
+                }
+                for clause in clauses {
+                    let valid = match clause.action {
+                        MergeAction::Insert(_) => matches!(
+                            clause.clause_kind,
+                            MergeClauseKind::NotMatched | MergeClauseKind::NotMatchedByTarget
+                        ),
+                        MergeAction::Update { .. } | MergeAction::Delete => matches!(
+                            clause.clause_kind,
+                            MergeClauseKind::Matched | MergeClauseKind::NotMatchedBySource
+                        ),
+                    };
+                    if !valid {
+                        return plan_err!("invalid MERGE clause WHEN {} THEN {}", clause.clause_kind, clause.action);
+                    }
+                }
+                Ok(Some(RowLevelStatement {
+                    command: RowLevelCommand::Merge,
+                    target,
+                    target_alias,
+                    source: Some(source.to_string()),
+                    on: Some(on.as_ref().clone()),
+                    clauses: clauses.clone(),
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// A `DELETE` or `UPDATE` with `action`. With a source `selection` joins it with the
+    /// target, otherwise it selects the rows to change.
+    fn matched(
+        command: RowLevelCommand,
+        target: ObjectName,
+        target_alias: Option<Ident>,
+        source: Option<&[TableWithJoins]>,
+        selection: Option<SQLExpr>,
+        action: MergeAction,
+    ) -> Self {
+        let (source, on, predicate) = match source {
+            Some(source) => {
+                let source = source.iter().map(|table| table.to_string()).collect::<Vec<String>>();
+                (Some(source.join(", ")), selection, None)
+            }
+            None => (None, None, selection),
+        };
+        RowLevelStatement {
+            command,
+            target,
+            target_alias,
+            source,
+            on,
+            clauses: vec![MergeClause {
+                clause_kind: MergeClauseKind::Matched,
+                predicate,
+                action,
+            }],
+        }
+    }
+
+    fn has_inserts(&self) -> bool {
+        self.clauses
+            .iter()
+            .any(|clause| matches!(clause.action, MergeAction::Insert(_)))
+    }
+}
+
+fn single_table(tables: &[TableWithJoins]) -> Result<(ObjectName, Option<Ident>), DataFusionError> {
+    match tables {
+        [TableWithJoins { relation, joins }] if joins.is_empty() => table_name(relation),
+        _ => not_impl_err!("DELETE and UPDATE only support a single target table"),
+    }
+}
+
+fn table_name(table: &TableFactor) -> Result<(ObjectName, Option<Ident>), DataFusionError> {
+    match table {
+        TableFactor::Table {
+            name,
+            alias,
+            args: None,
+            ..
+        } => Ok((name.clone(), alias.as_ref().map(|alias| alias.name.clone()))),
+        _ => plan_err!("the target of {} must be a table", table),
+    }
+}
+
+/// Runs a `DELETE`, `UPDATE` or `MERGE INTO` on an Iceberg table in a Glue catalog and
+/// commits the changes as a single snapshot, returning the number of changed rows.
+pub async fn execute_row_level(
+    ctx: &SessionContext,
+    statement: RowLevelStatement,
+) -> Result<Vec<RecordBatch>, DataFusionError> {
+    let state = ctx.state();
+    let options = state.config_options();
+    let table_ref = object_name_to_table_reference(
+        statement.target.clone(),
+        options.sql_parser.enable_ident_normalization,
+    )?;
+    let resolved = table_ref
+        .clone()
+        .resolve(&options.catalog.default_catalog, &options.catalog.default_schema);
+    let provider = ctx
+        .table_provider(TableReference::full(resolved.catalog, resolved.schema, resolved.table))
+        .await?;
+    let Some(glue_table) = provider.as_any().downcast_ref::<GlueTable>() else {
+        return not_impl_err!("DELETE, UPDATE and MERGE are only supported on tables in glue catalogs");
+    };
+    let (table, committer) = glue_table.iceberg_writer()?;
+    let changed_rows = change_rows(ctx, &statement, table_ref, table, committer).await?;
+
+    let batch = RecordBatch::try_new(count_schema(), vec![Arc::new(UInt64Array::from(vec![changed_rows]))])?;
+    Ok(vec![batch])
+}
+
+/// Changes the rows of `table`, named `table_ref` in the statement, and commits them
+/// through `committer`.
+///
+/// With copy-on-write the statement is run twice: the first run finds the data files with
+/// rows to change, the second one reads all rows of them to rewrite the files.
+async fn change_rows(
+    ctx: &SessionContext,
+    statement: &RowLevelStatement,
+    table_ref: TableReference,
+    table: Table,
+    committer: Arc<dyn IcebergTableCommitter>,
+) -> Result<u64, DataFusionError> {
+    let state = ctx.state();
+    let normalizer = IdentNormalizer::new(state.config_options().sql_parser.enable_ident_normalization);
+    let mode = RowLevelMode::for_command(table.metadata(), statement.command)?;
+    let qualifier = match &statement.target_alias {
+        Some(alias) => TableReference::bare(normalizer.normalize(alias.clone())),
+        None => table_ref,
+    };
+    let planner = RowLevelPlanner {
+        state: &state,
+        target: IcebergRowPositionTable::try_new(table.clone(), None)?,
+        qualifier,
+        normalizer,
+        statement,
+    };
+
+    let touched_files = match mode {
+        RowLevelMode::CopyOnWrite => {
+            let plan = LogicalPlanBuilder::from(planner.plan(None, false).await?)
+                .filter(col(FILE_PATH_COLUMN).is_not_null())?
+                .aggregate(vec![col(FILE_PATH_COLUMN)], Vec::<Expr>::new())?
+                .build()?;
+            let mut files = HashSet::new();
+            for batch in ctx.execute_logical_plan(plan).await?.collect().await? {
+                let Some(paths) = batch.column(0).as_string_opt::<i32>() else {
+                    return internal_err!("unexpected type of {}", FILE_PATH_COLUMN);
+                };
+                files.extend(paths.iter().flatten().map(|path| path.to_string()));
+            }
+            files
+        }
+        RowLevelMode::MergeOnRead => HashSet::new(),
+    };
+
+    if mode == RowLevelMode::CopyOnWrite && touched_files.is_empty() && !statement.has_inserts() {
+        return Ok(0);
+    }
+    // inserted rows are only right when all rows of the target are joined with the source
+    let files = (mode == RowLevelMode::CopyOnWrite && !statement.has_inserts())
+        .then(|| touched_files.clone());
+    let plan = planner.plan(files, mode == RowLevelMode::CopyOnWrite).await?;
+    let data = ctx.execute_logical_plan(plan).await?.execute_stream().await?;
+    let writer = IcebergRowLevelWriter::try_new(table, committer, mode)?;
+    writer.write(data, &touched_files).await
+}
+
+/// Plans the rows a [`RowLevelStatement`] gives to [`IcebergRowLevelWriter`].
+struct RowLevelPlanner<'a> {
+    state: &'a SessionState,
+    /// The target table, reading all data files
+    target: IcebergRowPositionTable,
+    /// Qualifier of the target columns in the statement
+    qualifier: TableReference,
+    normalizer: IdentNormalizer,
+    statement: &'a RowLevelStatement,
+}
+
+impl RowLevelPlanner<'_> {
+    /// Joins the target with the source and computes the [`OPERATION_COLUMN`], the values of
+    /// the table columns and the position of the target row of every joined row a clause
+    /// applies to. With `keep_rows` the other target rows are returned too, with
+    /// [`RowOperation::Keep`]. Only the data files in `files` are read, if given.
+    async fn plan(
+        &self,
+        files: Option<HashSet<String>>,
+        keep_rows: bool,
+    ) -> Result<LogicalPlan, DataFusionError> {
+        let target_table = self.target.with_files(files);
+        let table_schema = target_table.table_schema();
+        let mut target = LogicalPlanBuilder::scan(
+            self.qualifier.clone(),
+            provider_as_source(Arc::new(target_table)),
+            None,
+        )?;
+        if let (None, false, [MergeClause { predicate: Some(predicate), .. }]) =
+            (&self.statement.source, keep_rows, self.statement.clauses.as_slice())
+        {
+            // the other rows are dropped anyway, filtering them first prunes data files
+            let predicate = self.expr(predicate, target.schema())?;
+            target = target.filter(predicate)?;
+        }
+        let target = with_marker(target, TARGET_COLUMN)?;
+
+        let joined = match &self.statement.source {
+            Some(source) => {
+                let source = self.state.create_logical_plan(&format!("SELECT * FROM {source}")).await?;
+                let source = with_marker(LogicalPlanBuilder::from(source), SOURCE_COLUMN)?.build()?;
+                let on = match &self.statement.on {
+                    Some(on) => self.expr(on, &target.schema().join(source.schema())?)?,
+                    None => lit(true),
+                };
+                let by_target = self.statement.clauses.iter().any(|clause| {
+                    matches!(
+                        clause.clause_kind,
+                        MergeClauseKind::NotMatched | MergeClauseKind::NotMatchedByTarget
+                    )
+                });
+                let by_source = keep_rows
+                    || self
+                        .statement
+                        .clauses
+                        .iter()
+                        .any(|clause| clause.clause_kind == MergeClauseKind::NotMatchedBySource);
+                let join_type = match (by_source, by_target) {
+                    (false, false) => JoinType::Inner,
+                    (true, false) => JoinType::Left,
+                    (false, true) => JoinType::Right,
+                    (true, true) => JoinType::Full,
+                };
+                target.join_on(source, join_type, vec![on])?
+            }
+            None => target,
+        };
+
+        // the first clause a row matches applies to it
+        let mut clause_index: Option<CaseBuilder> = None;
+        for (index, clause) in self.statement.clauses.iter().enumerate() {
+            let mut condition = self.clause_condition(&clause.clause_kind);
+            if let Some(predicate) = &clause.predicate {
+                condition = condition.and(self.expr(predicate, joined.schema())?);
+            }
+            clause_index = Some(match clause_index {
+                Some(mut case) => case.when(condition, lit(index as i64)),
+                None => when(condition, lit(index as i64)),
+            });
+        }
+        let Some(clause_index) = clause_index else {
+            return plan_err!("no WHEN clause");
+        };
+        let mut exprs = joined.schema().columns().into_iter().map(Expr::Column).collect::<Vec<Expr>>();
+        exprs.push(clause_index.end()?.alias(CLAUSE_COLUMN));
+        let values = self.clause_values(&table_schema, joined.schema())?;
+        let joined = joined.project(exprs)?;
+
+        let clause = col(CLAUSE_COLUMN);
+        let mut operation: Option<CaseBuilder> = None;
+        for (index, statement_clause) in self.statement.clauses.iter().enumerate() {
+            let row_operation = match statement_clause.action {
+                MergeAction::Insert(_) => RowOperation::Insert,
+                MergeAction::Update { .. } => RowOperation::Update,
+                MergeAction::Delete => RowOperation::Delete,
+            };
+            let condition = clause.clone().eq(lit(index as i64));
+            let then = lit(row_operation.as_str());
+            operation = Some(match operation {
+                Some(mut case) => case.when(condition, then),
+                None => when(condition, then),
+            });
+        }
+        let Some(mut operation) = operation else {
+            return internal_err!("no WHEN clause");
+        };
+        if keep_rows {
+            operation = operation.when(col(TARGET_COLUMN).is_not_null(), lit(RowOperation::Keep.as_str()));
+        }
+        let mut exprs = vec![operation.end()?.alias(OPERATION_COLUMN)];
+        for field in table_schema.fields() {
+            let target_value = self.target_column(field.name());
+            let mut value: Option<CaseBuilder> = None;
+            for (index, clause_values) in values.iter().enumerate() {
+                let Some(clause_value) = clause_values.get(field.name()) else {
+                    continue;
+                };
+                let condition = clause.clone().eq(lit(index as i64));
+                value = Some(match value {
+                    Some(mut case) => case.when(condition, clause_value.clone()),
+                    None => when(condition, clause_value.clone()),
+                });
+            }
+            let value = match value {
+                Some(mut value) => value.otherwise(target_value)?,
+                None => target_value,
+            };
+            exprs.push(cast(value, field.data_type().clone()).alias(field.name()));
+        }
+        exprs.push(self.target_column(FILE_PATH_COLUMN).alias(FILE_PATH_COLUMN));
+        exprs.push(self.target_column(POSITION_COLUMN).alias(POSITION_COLUMN));
+
+        joined
+            .project(exprs)?
+            .filter(col(OPERATION_COLUMN).is_not_null())?
+            .build()
+    }
+
+    /// Whether a joined row is of the kind of rows a clause applies to.
+    fn clause_condition(&self, clause_kind: &MergeClauseKind) -> Expr {
+        if self.statement.source.is_none() {
+            return lit(true);
+        }
+        match clause_kind {
+            MergeClauseKind::Matched => col(TARGET_COLUMN)
+                .is_not_null()
+                .and(col(SOURCE_COLUMN).is_not_null()),
+            MergeClauseKind::NotMatched | MergeClauseKind::NotMatchedByTarget => {
+                col(TARGET_COLUMN).is_null()
+            }
+            MergeClauseKind::NotMatchedBySource => col(SOURCE_COLUMN).is_null(),
+        }
+    }
+
+    /// The values every clause writes to the table columns by column name, columns an
+    /// update does not set keep their value.
+    fn clause_values(
+        &self,
+        table_schema: &Schema,
+        schema: &DFSchema,
+    ) -> Result<Vec<HashMap<String, Expr>>, DataFusionError> {
+        let mut clause_values = Vec::with_capacity(self.statement.clauses.len());
+        for clause in &self.statement.clauses {
+            let mut values: HashMap<String, Expr> = HashMap::new();
+            match &clause.action {
+                MergeAction::Update { assignments } => {
+                    for Assignment { target, value } in assignments {
+                        let AssignmentTarget::ColumnName(name) = target else {
+                            return not_impl_err!("assignments to a tuple of columns are not supported");
+                        };
+                        let Some(column) = name.0.last().and_then(|part| part.as_ident()) else {
+                            return plan_err!("invalid column {}", name);
+                        };
+                        let column = self.table_column(table_schema, column)?;
+                        if values.insert(column.clone(), self.expr(value, schema)?).is_some() {
+                            return plan_err!("column {} is assigned more than once", column);
+                        }
+                    }
+                }
+                MergeAction::Insert(insert) => {
+                    let MergeInsertKind::Values(insert_values) = &insert.kind else {
+                        return not_impl_err!("INSERT ROW is not supported in MERGE");
+                    };
+                    let [row] = insert_values.rows.as_slice() else {
+                        return plan_err!("INSERT in MERGE takes a single row of values");
+                    };
+                    let columns = match insert.columns.is_empty() {
+                        true => table_schema.fields().iter().map(|field| field.name().clone()).collect(),
+                        false => insert
+                            .columns
+                            .iter()
+                            .map(|column| self.table_column(table_schema, column))
+                            .collect::<Result<Vec<String>, DataFusionError>>()?,
+                    };
+                    if columns.len() != row.len() {
+                        return plan_err!("INSERT in MERGE has {} columns and {} values", columns.len(), row.len());
+                    }
+                    for field in table_schema.fields() {
+                        values.insert(field.name().clone(), lit(ScalarValue::Null));
+                    }
+                    for (column, value) in columns.into_iter().zip(row) {
+                        values.insert(column, self.expr(value, schema)?);
+                    }
+                }
+                MergeAction::Delete => {}
+            }
+            clause_values.push(values);
+        }
+        Ok(clause_values)
+    }
+
+    fn table_column(&self, table_schema: &Schema, column: &Ident) -> Result<String, DataFusionError> {
+        let column = self.normalizer.normalize(column.clone());
+        match table_schema.field_with_name(&column) {
+            Ok(_) => Ok(column),
+            Err(_) => plan_err!("column {} not found in {}", column, self.qualifier),
+        }
+    }
+
+    fn target_column(&self, name: &str) -> Expr {
+        Expr::Column(Column::new(Some(self.qualifier.clone()), name))
+    }
+
+    fn expr(&self, expr: &SQLExpr, schema: &DFSchema) -> Result<Expr, DataFusionError> {
+        self.state.create_logical_expr(&expr.to_string(), schema)
+    }
+}
+
+/// Appends a column of `true` named `marker`, telling after an outer join whether the
+/// joined row has a row of this side.
+fn with_marker(plan: LogicalPlanBuilder, marker: &str) -> Result<LogicalPlanBuilder, DataFusionError> {
+    let mut exprs = plan.schema().columns().into_iter().map(Expr::Column).collect::<Vec<Expr>>();
+    exprs.push(lit(true).alias(marker));
+    plan.project(exprs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Int64Array, StringArray};
+    use arrow_array::types::Int64Type;
+    use arrow_schema::{DataType, Field};
+    use datafusion::datasource::MemTable;
+    use datafusion::datasource::sink::DataSink;
+    use datafusion::execution::TaskContext;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use dobbydb_common_catalog::table_format::iceberg_commit::testing::{empty_table, MemoryCommitter, TestDir};
+    use dobbydb_common_catalog::table_format::iceberg_sink::IcebergDataSink;
+    use sqlparser::dialect::GenericDialect;
+    use sqlparser::parser::Parser;
+
+    /// Creates a table with the rows `(1, eu), (2, us), (3, eu), (4, apac)` whose deletes,
+    /// updates and merges all use `mode`.
+    async fn create_table(dir: &TestDir, mode: &str) -> Result<Arc<MemoryCommitter>, DataFusionError> {
+        let properties = ["write.delete.mode", "write.update.mode", "write.merge.mode"]
+            .into_iter()
+            .map(|property| (property.to_string(), mode.to_string()))
+            .collect();
+        let (committer, table) = empty_table(dir, properties).await?;
+
+        let sink = IcebergDataSink::try_new(table, committer.clone(), false)?;
+        let schema = sink.schema().clone();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec!["eu", "us", "eu", "apac"])),
+            ],
+        )?;
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None)?);
+        sink.write_all(stream, &Arc::new(TaskContext::default())).await?;
+        Ok(committer)
+    }
+
+    /// Runs a row-level statement on the table `t` of `committer`, with the source table
+    /// `src` holding the rows `(3, emea), (4, latam), (5, na)`.
+    async fn execute(committer: &Arc<MemoryCommitter>, sql: &str) -> Result<u64, DataFusionError> {
+        let ctx = SessionContext::new();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("region", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![3, 4, 5])),
+                Arc::new(StringArray::from(vec!["emea", "latam", "na"])),
+            ],
+        )?;
+        ctx.register_table("src", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+        let statements = Parser::parse_sql(&GenericDialect {}, sql)?;
+        let Some(statement) = RowLevelStatement::try_from_statement(&statements[0])? else {
+            return plan_err!("not a row-level statement: {sql}");
+        };
+        let table_ref = object_name_to_table_reference(statement.target.clone(), true)?;
+        let table = committer.load_table().await?;
+        change_rows(&ctx, &statement, table_ref, table, committer.clone()).await
+    }
+
+    async fn rows(committer: &Arc<MemoryCommitter>) -> Result<Vec<(i64, String)>, DataFusionError> {
+        let ctx = SessionContext::new();
+        let table = IcebergRowPositionTable::try_new(committer.load_table().await?, None)?;
+        ctx.register_table("t", Arc::new(table))?;
+        let mut rows = Vec::new();
+        for batch in ctx.sql("SELECT id, region FROM t ORDER BY id").await?.collect().await? {
+            let ids = batch.column(0).as_primitive::<Int64Type>();
+            let regions = batch.column(1).as_string::<i32>();
+            rows.extend(ids.values().iter().zip(regions.iter()).map(|(id, region)| {
+                (*id, region.unwrap_or_default().to_string())
+            }));
+        }
+        Ok(rows)
+    }
+
+    fn expected(rows: &[(i64, &str)]) -> Vec<(i64, String)> {
+        rows.iter().map(|(id, region)| (*id, region.to_string())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_delete_update_and_merge() -> Result<(), DataFusionError> {
+        for mode in ["copy-on-write", "merge-on-read"] {
+            let table_dir = TestDir::new(&format!("row-level-{mode}"));
+            let committer = create_table(&table_dir, mode).await?;
+
+            assert_eq!(execute(&committer, "DELETE FROM t WHERE region = 'us'").await?, 1, "{mode}");
+            assert_eq!(rows(&committer).await?, expected(&[(1, "eu"), (3, "eu"), (4, "apac")]), "{mode}");
+            // copy-on-write rewrites the data file, merge-on-read adds a position delete file
+            let table = committer.load_table().await?;
+            let summary = table.metadata().current_snapshot().expect("snapshot").summary();
+            assert_eq!(
+                summary.additional_properties.contains_key("added-position-deletes"),
+                mode == "merge-on-read",
+                "{mode}: {summary:?}"
+            );
+
+            let updated = execute(&committer, "UPDATE t SET region = s.region FROM src AS s WHERE t.id = s.id").await?;
+            assert_eq!(updated, 2, "{mode}");
+            assert_eq!(rows(&committer).await?, expected(&[(1, "eu"), (3, "emea"), (4, "latam")]), "{mode}");
+
+            let merged = execute(
+                &committer,
+                "MERGE INTO t USING src AS s ON t.id = s.id \
+                 WHEN MATCHED AND s.id = 3 THEN DELETE \
+                 WHEN MATCHED THEN UPDATE SET region = 'matched' \
+                 WHEN NOT MATCHED THEN INSERT VALUES (s.id, s.region) \
+                 WHEN NOT MATCHED BY SOURCE THEN UPDATE SET region = 'unmatched'",
+            )
+            .await?;
+            assert_eq!(merged, 4, "{mode}");
+            assert_eq!(
+                rows(&committer).await?,
+                expected(&[(1, "unmatched"), (4, "matched"), (5, "na")]),
+                "{mode}"
+            );
+        }
+        Ok(())
+    }
+}
@@ -61,5 +61,5 @@ async fn run_from_command() -> Result<(), DataFusionError> {
         maxrows: args.maxrows,
         color: args.color,
     };
-    Ok(exec::exec_from_repl(&ctx, &mut print_options).await.map_err(|e| DataFusionError::External(Box::new(e)))?)
+    exec::exec_from_repl(&ctx, &mut print_options).await.map_err(|e| DataFusionError::External(Box::new(e)))
 }